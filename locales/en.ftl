@@ -0,0 +1,886 @@
+# DockerOps messages, English (reference catalog)
+# Placeables: { $name } is replaced by the value given by the code, and
+# { $count -> [one] ... *[other] ... } picks the variant of its plural category
+
+using-profile = Using profile: { $profile }
+using-context = Using context: { $context }
+
+## Deployments
+stack-deployed = ✅ Stack '{ $stack }' deployed
+stack-not-deployed = Stack '{ $stack }' was not deployed (status: { $status })
+stack-adopted = ✅ Stack '{ $stack }' adopted (hash: { $hash })
+stack-adopted-hint = It is redeployed by the next sync only if its compose file changes in the repository
+stack-removed = ✅ Stack '{ $stack }' removed, it can be brought back with 'restore-stack' (see 'trash')
+stack-restored = ✅ Stack '{ $stack }' restored
+stack-restored-hint = Add it back to stacks.yaml to keep it managed from the repository
+stack-rolled-back = ✅ Stack '{ $stack }' rolled back to revision { $revision }
+stack-rolled-back-hint = Its current compose file is not redeployed until the repository changes it (or with 'deploy')
+stack-exported = ✅ Stack '{ $stack }' exported to { $path }
+stack-imported = ✅ Stack '{ $stack }' imported
+stack-restarted = ✅ Stack '{ $stack }' restarted ({ $count ->
+        [one] { $count } service
+       *[other] { $count } services
+    })
+repository-unwatched = ✅ Repository { $url } is no longer watched
+repository-unwatched-hint = Its stacks can be brought back with 'restore-stack' (see 'trash')
+repository-sync-failed = ❌ Failed to sync repository { $url }: { $error }
+no-repositories-to-poll = No repositories in cache, nothing to poll. Use 'watch' to add one.
+
+## Status
+no-deployments = No deployment recorded
+no-stacks-deployed = No stacks deployed
+trash-empty = Trash is empty
+trash-count = { $count ->
+        [one] Found { $count } stack in the trash:
+       *[other] Found { $count } stacks in the trash:
+    }
+no-conflicts = No conflicts between manual changes and git
+conflict-resolved = ✅ Conflict of stack '{ $stack }' resolved with { $resolution }
+unhealthy = DockerOps is unhealthy
+
+## Drift
+drift-nothing-to-check = No deployed stack to check
+drift-none = { $count ->
+        [one] ✅ { $count } stack matches its last deployment
+       *[other] ✅ { $count } stacks match their last deployment
+    }
+drift-found = { $drifted ->
+        [one] { $drifted } of { $count } stacks drifted from its last deployment (use --fix to deploy it again)
+       *[other] { $drifted } of { $count } stacks drifted from their last deployment (use --fix to deploy them again)
+    }
+drift-reapplied = ✅ Stack '{ $stack }' re-applied
+drift-reapply-failed = ❌ Could not re-apply stack '{ $stack }': { $error }
+
+## Failure issues opened in the watched repository
+issue-title = DockerOps: stack '{ $stack }' fails to deploy
+issue-body = DockerOps could not deploy stack `{ $stack }` { $failures } times in a row (commit `{ $commit }`). It is not retried until its compose file changes.
+issue-error = **Error:**
+issue-changes = **Changes to `{ $directory }/` since the last successful run (`{ $commit }`):**
+issue-footer = This issue is closed automatically once the stack deploys again.
+issue-closed = Stack `{ $stack }` deployed successfully, closing.
+
+## Webhook notifications
+notification-deploy-failed = ❌ DockerOps: stack '{ $stack }' failed to deploy { $failures } times in a row ({ $url }): { $error }
+notification-deploy-recovered = ✅ DockerOps: stack '{ $stack }' deployed again after { $failures ->
+        [one] { $failures } failure
+       *[other] { $failures } failures
+    } ({ $url })
+notifications-undelivered = { $count ->
+        [one] ⚠️  { $count } webhook notification could not be delivered:
+       *[other] ⚠️  { $count } webhook notifications could not be delivered:
+    }
+webhook-answered = Webhook answered { $status }: { $body }
+
+## Commands
+github-app-authentication-disabled = ⚠️  GitHub App authentication disabled: { $error }
+local-repository-refs-unsupported = --branch, --tag and --commit are not supported for local repositories, their working tree is deployed as is
+local-repository-not-found = Local repository { $path } not found: { $error }
+watching-repository = Watching repository: { $url }
+repository-already-watched-use-force = Repository '{ $url }' is already being watched (last watch: { $last_watch }), use --force to redeploy its stacks
+repository-watch-options-kept = ⚠️  The repository keeps the branch, tag, commit, path and namespace it was first watched with
+repository-already-watched-redeploying = Repository already watched, redeploying its stacks (--force)
+repository-working-copy = Repository working copy: { $repo_path }
+watch-dry-run = Dry run, the repository is not watched
+repository-pinned-to-commit = Repository pinned to commit { $commit }
+repository-added-to-cache = Repository added to cache
+could-not-read = Could not read { $path }: { $error }
+invalid-repository-list = Invalid repository list { $path }: { $error }
+invalid-namespace = Invalid namespace '{ $namespace }' (letters, digits, '-' and '_' only)
+repository-list-tag-conflict = Repository '{ $url }': 'tag' can't be combined with 'branch' or 'commit'
+bootstrap-started = { $count ->
+        [one] Bootstrapping { $count } repository from { $path } ({ $parallel } at a time)
+       *[other] Bootstrapping { $count } repositories from { $path } ({ $parallel } at a time)
+    }
+bootstrap-repository-skipped = Repository { $url } is already watched, skipping
+bootstrap-summary = Bootstrap summary:
+already-watched = ⏭️  { $url } (already watched)
+bootstrap-failures = { $failures } of { $total } repositories could not be watched
+bootstrap-succeeded = All repositories are watched
+reconciling-database = Reconciling database...
+no-repositories-in-cache = No repositories found in cache. Please run 'watch' command first.
+repository-not-watched = Repository '{ $url }' is not being watched
+found-repositories-in-cache = { $count ->
+        [one] Found { $count } repository in cache:
+       *[other] Found { $count } repositories in cache:
+    }
+found-stacks-in-database = { $count ->
+        [one] Found { $count } stack in database:
+       *[other] Found { $count } stacks in database:
+    }
+repository-stack-line = - { $stack } ({ $app }status: { $status }, hash: { $hash }{ $staleness })
+repository-drift-line = ⚠️  { $object_type } { $object_name } got '{ $action }' outside DockerOps ({ $time })
+found-images-in-database = { $count ->
+        [one] Found { $count } image in database:
+       *[other] Found { $count } images in database:
+    }
+cached-image-line = { $reference_count ->
+        [one] - { $name } (referenced once)
+       *[other] - { $name } (referenced { $reference_count } times)
+    }
+starting-reconciliation-process = Starting reconciliation process...
+reconcile-forced = ⚠️  Force mode enabled - will redeploy all stacks regardless of changes
+reconciling-repository = Reconciling repository: { $url }
+reconciliation-completed = Reconciliation completed!
+planning-repository = Planning repository: { $url }
+plan-no-changes = No changes, nothing to apply
+planned-changes = Planned changes:
+plan-saved = Plan saved to { $output }, run 'dockerops apply { $output }' to carry it out
+plan-applying = Applying the plan made by { $created_by } on { $time }
+plan-empty = The plan has no changes
+plan-repository-unwatched = Repository '{ $url }' is no longer watched, run plan again
+plan-stack-changed = Stack '{ $stack }' changed since the plan was made ({ $current } instead of { $previous_hash }), run plan again
+plan-applying-changes = { $count ->
+        [one] Applying { $count } change to { $url }
+       *[other] Applying { $count } changes to { $url }
+    }
+plan-changes-not-applied = { $count ->
+        [one] { $count } planned change was not applied:
+       *[other] { $count } planned changes were not applied:
+    }
+    {"  "}{ $details }
+plan-applied = ✅ Plan applied
+repository-no-longer-watched = Repository { $url } is not watched anymore
+reconciling-stack = Reconciling stack '{ $stack }' from { $url } (status: { $status })
+reconcile-stack-forced = ⚠️  Force mode enabled - will redeploy the stack regardless of changes
+stack-reconciliation-completed = Reconciliation of stack '{ $stack }' completed (status: { $status })
+deploying-stack-from = Deploying stack '{ $stack }' from { $url }
+stack-was-not-deployed = Stack '{ $stack }' was not deployed
+stack-already-managed = Stack '{ $stack }' is already managed from { $url }
+repository-not-watched-run-watch = Repository '{ $url }' is not being watched, run 'watch' first
+stack-not-running-use-deploy = Stack '{ $stack }' is not running in Swarm, use 'deploy' to deploy it
+adopting-stack-from = Adopting stack '{ $stack }' from { $url }
+stack-was-not-adopted = Stack '{ $stack }' was not adopted
+settings-global-header = Global settings (config.yaml), overridden by dockerops.yaml and stacks.yaml:
+settings-stack-header = Effective settings of stack '{ $stack }' ({ $url }):
+settings-no-stacks-yaml = No stacks.yaml in the working copy of { $url }, run 'sync' first
+settings-stack-not-declared = Stack '{ $stack }' is not declared in the stacks.yaml of { $url }
+
+## Daemon, mirror and webhooks
+task-history-max-age = task_history.max_age: { $error }
+
+## Daemon, mirror and webhooks
+daemon-started = Starting DockerOps daemon (polling every { $interval }s, press Ctrl+C to stop)
+
+## Daemon, mirror and webhooks
+docker-event-failed = ⚠️  Could not process Docker event: { $error }
+
+## Daemon, mirror and webhooks
+docker-events-ended = ⚠️  Docker events stream ended, drift alerts resume after the next poll
+
+## Daemon, mirror and webhooks
+daemon-interrupted = Received interrupt signal, stopping daemon
+docker-events-watching = Watching Docker events for changes made outside DockerOps
+docker-events-unavailable = ⚠️  Could not watch Docker events, drift alerts are disabled: { $error }
+drift-event = ⚠️  [{ $time }] Drift on stack '{ $stack }': { $object_type } { $object_name } got '{ $action }' outside DockerOps
+self-deploy-webhook-secret-missing = Set 'webhook_secret' in { $file_path } before deploying DockerOps as a service
+self-deploy-context-unsupported = Docker context '{ $context }' can't be used from a service, set a tcp:// docker_host or use the manager's socket
+self-deploy-file-setting = ⚠️  '{ $setting }' points to a file that must also exist in the service's container
+self-deploy-creating-secret = Creating secret { $secret_name } from { $config_path }
+docker-has-no-stdin = docker has no stdin
+self-deploy-secret-failed = Failed to create secret { $secret_name }: { $error }
+self-deploy-deploying = Deploying stack '{ $stack }' running { $image }
+failed-to-deploy-stack = Failed to deploy stack '{ $stack }': { $error }
+self-deploy-done = ✅ DockerOps runs as stack '{ $stack }', webhooks on port { $port }
+self-deploy-watch-hint = Watch the repositories from the service: docker exec $(docker ps -qf label=com.docker.swarm.service.name={ $stack }_dockerops) dockerops watch <url>
+self-deploy-update-hint = To update DockerOps from git, commit { $compose_path } as { $stack }/docker-compose.yml of a watched repository
+mirror-target-is-database = The mirror target is the database itself
+invalid-mirror-target = Invalid mirror target { $target }: { $error }
+unsupported-mirror-target = Mirror targets with the { $scheme }:// scheme are not supported, use a file path, a sqlite: URL or ssh://[user@]host[:port]/path
+mirror-started = Mirroring state to { $target } every { $interval }s (press Ctrl+C to stop)
+mirror-failed = ❌ Failed to mirror state to { $target }: { $error }
+mirror-interrupted = Received interrupt signal, stopping mirror
+mirror-done = [{ $time }] State mirrored to { $target }
+could-not-read-repository-cache = ❌ Could not read repository cache: { $error }
+polling-repositories = { $count ->
+        [one] [{ $time }] Polling { $count } repository...
+       *[other] [{ $time }] Polling { $count } repositories...
+    }
+webhook-deliveries-not-recorded = ⚠️  Could not record the webhook deliveries of { $url }: { $error }
+webhook-deliveries-unreadable = ⚠️  Could not read the webhook deliveries: { $error }
+repository-unreadable = ⚠️  Could not read the repository { $url }: { $error }
+webhook-replaying-pushes = { $pushes ->
+        [one] Replaying { $pushes } missed push to { $url }, received at { $received_at }
+       *[other] Replaying { $pushes } missed pushes to { $url }, the last one received at { $received_at }
+    }
+webhook-pushes-replayed = ✅ Replayed the pushes to { $url }
+webhook-replay-failed = ❌ Replay failed for { $url }: { $error }
+
+## Repository synchronization
+remote-tip-already-processed = Remote tip { $tip } was already processed, nothing to fetch
+no-newer-tag = No tag newer than { $deployed } matches '{ $pattern }', nothing to deploy
+commit-already-processed = Commit { $head } was already processed, nothing to deploy
+signed-commits-unsupported-locally = Signed commits are required but local working trees have no commit to verify
+stack-ref-needs-repository = Stacks declared with a ref need a git repository
+stack-ref-needs-working-copy = Stacks declared with a ref need a git working copy
+stack-ref-not-found = Ref '{ $git_ref }' of stack '{ $stack }' not found: { $error }
+commit-signed-by = ✅ { $signer } (stack { $stack })
+stack-checked-out = Stack '{ $stack }' checked out from { $git_ref } ({ $id })
+unwatch-removing-stacks = Removing the stacks of repository: { $url }
+found-stacks-to-remove = { $count ->
+        [one] Found { $count } stack to remove
+       *[other] Found { $count } stacks to remove
+    }
+removing-stack = Removing stack: { $stack }
+removing-stack-of-repository = Removing stack '{ $stack }' of { $url }
+removed-stack-still-declared = ⚠️  Stack '{ $stack }' is still declared in the stacks.yaml of { $url }, the next synchronization deploys it again
+cleanup-started = Stopping DockerOps and cleaning up all resources...
+found-images-to-remove = { $count ->
+        [one] Found { $count } image to remove
+       *[other] Found { $count } images to remove
+    }
+cleanup-keeping-image = ⏸️  Keeping image { $name }, used by { $users }
+removing-image = Removing image: { $name }
+cleanup-images-kept = { $kept ->
+        [one] ⚠️  { $kept } image kept because containers or services not managed by DockerOps use it
+       *[other] ⚠️  { $kept } images kept because containers or services not managed by DockerOps use them
+    }
+cleaning-up-database = Cleaning up database...
+cleanup-working-copies-not-removed = Warning: Could not remove repository working copies: { $error }
+cleanup-cache-not-empty = { $count ->
+        [one] Warning: Repository cache still contains { $count } entry, forcing cleanup...
+       *[other] Warning: Repository cache still contains { $count } entries, forcing cleanup...
+    }
+cleanup-cache-failed = { $count ->
+        [one] ❌ Cache cleanup failed! Still contains { $count } entry
+       *[other] ❌ Cache cleanup failed! Still contains { $count } entries
+    }
+cleanup-cache-cleared = ✅ Cache successfully cleared
+cleanup-done = All stacks and images have been removed.
+cleanup-restore-hint = Removed stacks can be brought back with 'restore-stack' (see 'trash').
+cleanup-database-closing = Database connection will be closed.
+
+## Setup and contexts
+cli-version = DockerOps CLI v{ $version }
+
+## Setup and contexts
+cli-description = A Docker Swarm stack manager for git repositories
+
+## Setup and contexts
+cli-repository = Repository: https://github.com/TomBedinoVT/DockerOps
+schema-unknown-file = Unknown file '{ $file }' (expected { $list })
+setup-started = DockerOps setup, writing { $path }
+setup-ask-update-config = A configuration file exists, update it?
+setup-ask-database = SQLite database file
+setup-ask-docker-host = DOCKER_HOST (e.g. ssh://admin@manager01, empty for the local daemon)
+setup-ask-another-endpoint = Try another endpoint?
+setup-ask-github-token = GitHub token (empty for none or GITHUB_TOKEN)
+setup-ask-ssh-key = SSH key for ssh:// and git@ remotes (empty for the ssh-agent)
+setup-ask-nfs = Directory where the NFS share for binding volumes is mounted (empty to skip)
+setup-ask-failure-issues = Open a GitHub issue in the repository when a stack keeps failing to deploy?
+setup-ask-failure-threshold = Consecutive failures before a stack is reported and no longer retried
+setup-ask-repository = Git repository to watch (empty to skip)
+setup-ask-path = Directory holding stacks.yaml in the repository (empty for the root)
+setup-step-database = 1. Database
+setup-database-not-writable = ❌ { $db_path } cannot be created or written
+setup-step-docker = 2. Docker endpoint
+setup-docker-reached = ✅ Docker { $version } reached
+setup-not-swarm-manager = ⚠️  This daemon is not a Swarm manager, stacks cannot be deployed through it
+setup-docker-failed = ❌ Could not run docker: { $error }
+setup-step-git = 3. Git credentials
+setup-github-token-valid = ✅ Token of GitHub user { $login }
+setup-github-token-rejected = ❌ GitHub rejected the token: { $error }
+setup-key-missing = ❌ { $key } does not exist
+setup-step-nfs = 4. NFS share (optional)
+setup-nfs-hint = ✅ Declare it in the nfs.yaml of your repositories:
+setup-nfs-not-a-directory = ❌ { $answer } is not a directory, mount the share first
+setup-step-notifications = 5. Notifications
+setup-issues-need-token = ⚠️  Issues need a GitHub token (or a GitHub App) with write access to issues
+setup-expected-positive-number = ❌ Expected a number greater than 0
+setup-config-written = ✅ Configuration written to { $path }
+setup-step-repository = 6. First repository
+setup-watch-hint = Run 'dockerops watch <url>' to add a repository
+compose-file-not-found = Compose file not found: { $compose_file }
+import-stack-name-unknown = Could not infer a stack name, use --name
+import-started = Importing compose project '{ $stack }' into { $repo }
+import-directory-exists = Stack directory already exists: { $stack_dir }
+import-stack-already-declared = Stack '{ $stack }' is already declared in stacks.yaml
+import-copied-directory = Copied directory { $host_path } -> { $destination }
+import-copied-file = Copied file { $host_path } -> { $destination }
+import-bind-source-missing = Warning: Bind source { $host_path } does not exist, binding created empty
+import-kept-host-bind-mount = { $service_name }: { $host_path } is not a file or directory, the service keeps binding it from the host
+skipped-special-file = ⚠️  Skipped { $path }: sockets, FIFOs and devices are not copied
+import-unsupported-volume-type = Warning: Skipping unsupported volume type '{ $other }' in service { $service_name }
+import-service-volume = Service { $service_name }: volume { $original_source } -> { $reference }
+import-volume-ids-already-defined = Warning: Volume ids already defined in volumes.yaml, keeping existing definitions: { $conflicting }
+import-wrote = Wrote { $stack_compose_path }
+import-added-to = Added '{ $stack }' to { $stacks_file_path }
+import-added-volumes = { $count ->
+        [one] Added { $count } volume definition to { $volumes_file_path }
+       *[other] Added { $count } volume definitions to { $volumes_file_path }
+    }
+import-bindings-need-nfs = Note: bindings require an nfs.yaml at the repository root (see VOLUMES.md)
+import-completed = Import completed, review the generated files and commit them to your repository.
+contexts-configured = Contexts from config.yaml:
+list-none = (none)
+docker-contexts = Docker contexts:
+context-none-docker-host = No context selected, using docker_host { $host }
+context-none-docker-defaults = No context selected, using the docker CLI defaults
+context-selection-cleared = ✅ Context selection cleared
+unknown-context = Unknown context '{ $name }' (see 'contexts')
+context-selected = ✅ Now using context '{ $name }'
+context-state-kept-in = Its database, working copies and trash are kept in { $dir }
+cannot-switch-context-shared-database = { $count ->
+        [one] The database { $path } set with db_path or DOCKEROPS_DB_PATH is shared by all the contexts and watches { $count } repository: unwatch it or give each context its own database before switching
+       *[other] The database { $path } set with db_path or DOCKEROPS_DB_PATH is shared by all the contexts and watches { $count } repositories: unwatch them or give each context its own database before switching
+    }
+
+## Inspection and maintenance
+debug-checking-repository-cache = Debug: Checking repository cache...
+history-line = { $status } { $time } { $stack_name } ({ $kind }) in { $seconds }s: hash { $hash }{ $commit }{ $canary }{ $operator }
+history-from = from { $url }
+trash-line = - { $stack } from { $url } (removed: { $time }, reason: { $reason }{ $deleted_by }){ $restorable }
+trash-stack-not-found = Stack '{ $stack }' is not in the trash
+trash-stack-ambiguous = Stack '{ $stack }' was trashed from several repositories, use --repo to select one
+restore-stack-already-deployed = Stack '{ $stack }' is already deployed from { $url }
+restore-no-snapshot = No compose snapshot was kept for stack '{ $stack }'
+restore-started = Restoring stack '{ $stack }' from { $url } (removed: { $time })
+restore-directory-gone = ⚠️  The copy of the stack directory is gone, deploying from the repository
+gc-none-recorded = No garbage collection recorded yet
+gc-nothing-enabled = Nothing to collect: enable gc.dangling_images, gc.stopped_containers or gc.build_cache in { $file_path }
+restore-directory-gone-no-secrets = ⚠️  Stack directory is gone from the repository, deploying without secrets
+restore-repository-unwatched-no-secrets = ⚠️  Repository is no longer watched, deploying without secrets
+restore-directory-cleanup-failed = Warning: Could not clean up restore directory: { $error }
+restore-compose-cleanup-failed = Warning: Could not clean up restore compose file: { $error }
+rollback-no-previous-revision = { $reason }, no previous revision to roll back to
+rollback-after-unhealthy-update = Rolling stack '{ $stack }' back to revision { $revision } after its unhealthy update
+rollback-done = { $reason }, rolled back to revision { $revision }
+rollback-failed = { $reason }, rolling back to revision { $revision } failed too: { $error }
+rollback-no-revision = No revision of stack '{ $stack }' was recorded yet
+rollback-unknown-revision = Stack '{ $stack }' has no revision { $number } (kept: { $list })
+rollback-no-older-revision = Stack '{ $stack }' has no revision older than the deployed one
+rollback-revision-already-deployed = Revision { $revision } of stack '{ $stack }' is already deployed
+rollback-started = Rolling stack '{ $stack }' back to revision { $revision } (deployed: { $time })
+export-no-snapshot = No compose snapshot was kept for stack '{ $stack }', deploy it first
+export-images-not-pinned = ⚠️  No running service of stack '{ $stack }' reports an image digest, the images are not pinned
+import-bundle-started = Importing stack '{ $stack }' exported from { $url } ({ $time }{ $commit })
+import-bundle-volumes = ⚠️  The stack uses these volumes, they must exist on this cluster:
+failed-to-inspect-services-of-stack = Failed to inspect services of stack '{ $stack }': { $error }
+drill-started = Starting drill on stack '{ $stack }' from { $url } (timeout: { $timeout }s)
+drill-nothing-to-disrupt = Stack '{ $stack }' has no running services, nothing to drill
+drill-stack-unhealthy = Stack '{ $stack }' is not healthy before the drill ({ $name } has { $running }/{ $desired } replicas), fix it first
+drill-stopping-services = Stopping services of stack '{ $stack }'...
+drill-skipping-global-service = Skipping global service { $name } (cannot be scaled)
+drill-scale-down-failed = Failed to scale down { $name }: { $error }
+drill-scaled-down = Scaled { $name } to 0
+drill-waiting-for-detection = Waiting for DockerOps to detect the drift...
+drill-drift-not-detected = Drill failed: drift on stack '{ $stack }' was not detected within { $timeout }s, is the daemon running?
+drill-drift-detected = Drift detected after { $seconds }s, waiting for the reconciliation to restore the stack...
+drill-stack-not-restored = Drill failed: stack '{ $stack }' was not restored within { $timeout }s
+drill-restoring-replicas = Restoring the replicas of stack '{ $stack }'
+drill-replicas-not-restored = ⚠️  Could not restore the replicas of { $name }: { $error }
+drill-report = Drill report for stack '{ $stack }':
+drill-services-disrupted = Services disrupted:  { $count }
+drill-detection-latency = Detection latency:   { $seconds }s
+drill-recovery-latency = Recovery latency:    { $recovery }s (repair took { $repair }s)
+drill-passed = Result:              ✅ PASS (recovered within { $timeout }s)
+stack-not-managed = Stack '{ $stack }' is not managed by DockerOps
+stack-ambiguous = Stack '{ $stack }' exists in several repositories ({ $list }), use --repo to select one
+app-has-no-stacks = No stack belongs to application '{ $name }'
+status-stack-line = { $desired ->
+        [one] { $stack } (status: { $status }): { $health } - { $running }/{ $desired } replica running
+       *[other] { $stack } (status: { $status }): { $health } - { $running }/{ $desired } replicas running
+    }
+notification-line = { $attempts ->
+        [one] - { $time } to { $url } ({ $attempts } attempt, { $state }): { $text }
+       *[other] - { $time } to { $url } ({ $attempts } attempts, { $state }): { $text }
+    }
+stack-line = { $stack } ({ $url }, status: { $status }, hash: { $hash })
+no-service-running-in-swarm = ⚠️  No service running in Swarm
+state-at = State at { $time }
+deployed-stack-line = { $stack_name } ({ $url }, hash: { $hash }{ $commit })
+deployed-line = Deployed { $time } ({ $kind })
+deployed-images-unknown = ⚠️  Images unknown, the revision of this hash is no longer kept
+deployed-images-not-pinned = ⚠️  Images as written in the compose file, their digests were not recorded
+restarting-service = Restarting service '{ $name }'
+failed-to-restart-service = Failed to restart service '{ $name }': { $error }
+service-not-running = Service '{ $service }' is not running in stack '{ $stack }'
+stack-has-no-running-service = Stack '{ $stack }' has no service running in Swarm
+running-in-container = Running in { $name } on { $hostname } (container { $container })
+docker-could-not-run = Could not run docker: { $error }
+failed-to-list-tasks-of-service = Failed to list tasks of service '{ $service }': { $error }
+service-has-no-task = Service '{ $service }' has no task meant to run
+replica-not-running = Replica { $slot } of service '{ $service }' is not running
+service-has-no-running-task = No task of service '{ $service }' is running
+task-node-endpoint-missing = The task runs on node '{ $hostname }', declare its Docker endpoint in node_endpoints of config.yaml (e.g. { $hostname }: ssh://root@{ $hostname }) or create a docker context named '{ $hostname }'
+failed-to-list-tasks-of-stack = Failed to list tasks of stack '{ $stack }': { $error }
+no-task-in-swarm = ⚠️  No task in Swarm
+image-freshness-check-failed-for = ⚠️  Could not check image freshness for { $url }: { $error }
+image-digest-check-failed = ⚠️  Could not check the digest of { $image }: { $error }
+no-deployed-services-found = No deployed services found
+image-report-stack = Stack { $stack_name } ({ $url }):
+digest-not-checked = Digest: not checked
+digest-outdated = ⚠️  Digest outdated since { $since } (deployed { $deployed }, latest { $latest })
+digest-up-to-date = ✅ Digest up to date
+newer-tags-available = { $newer_tags ->
+        [one] ⚠️  { $newer_tags } newer tag available (latest: { $latest_tag })
+       *[other] ⚠️  { $newer_tags } newer tags available (latest: { $latest_tag })
+    }
+
+## Git working copies
+fetching-repository = Fetching repository updates from: { $clone_url }
+
+## Git working copies
+working-copy-update-failed = ⚠️  Could not update the working copy ({ $error }), cloning again
+
+## Git working copies
+cloning-repository-branch = Cloning repository from: { $clone_url } (branch: { $branch })
+
+## Git working copies
+cloning-repository = Cloning repository from: { $clone_url }
+
+## Git working copies
+shallow-clone = { $depth ->
+        [one] Shallow clone with the last commit
+       *[other] Shallow clone with the last { $depth } commits
+    }
+
+## Git working copies
+failed-to-clone-repository = Failed to clone repository: { $error }
+
+## Git working copies
+sparse-checkout-done = Checked out stacks.yaml, the root files and the declared stack directories
+updating-submodule-from = Updating submodule { $path } from { $url }
+failed-to-update-submodule = Failed to update submodule { $path }: { $error }
+copying-local-working-tree-from = Copying local working tree from: { $source }
+local-repository-is-not-a-directory = Local repository { $source } is not a directory
+working-copy-not-removed = Warning: Could not remove repository working copy: { $error }
+full-history-needed = the full history is needed and the working copy is shallow
+branch-not-found = Branch '{ $target }' not found: { $error }
+working-copy-updated-to = Working copy updated to { $id }
+remote-refs-unavailable = ⚠️  Could not list the remote refs ({ $error }), fetching
+ref-not-found-on-remote = '{ $target }' not found on the remote
+auth-github-app = Using GitHub App installation token for authentication
+auth-token = Using { $name } token for authentication
+auth-no-token = No { $name } token found. Trying to access the repository without authentication...
+auth-no-token-hint = If this fails, set the { $token_variable } environment variable
+
+## Stack processing
+processing-stacks-from-repository = Processing stacks from repository...
+
+## Stack processing
+stacks-yaml-not-found-in-repository = stacks.yaml not found in repository
+
+## Stack processing
+found-stack-definitions = { $count ->
+        [one] Found { $count } stack definition:
+       *[other] Found { $count } stack definitions:
+    }
+
+## Stack processing
+stack-not-declared = Stack '{ $only_stack }' is not declared in stacks.yaml
+
+## Stack processing
+deployment-order = Deployment order: { $list }
+
+## Stack processing
+invalid-prerequisites-for-stack = Invalid prerequisites for stack '{ $stack }': { $error }
+
+## Stack processing
+invalid-hooks-for-stack = Invalid hooks for stack '{ $stack }': { $error }
+
+## Stack processing
+stack-teardown-needs-disabled = Invalid stack '{ $stack }': 'teardown' only applies to stacks with 'enabled: false'
+
+## Stack processing
+deploy-parallelism = { $max_parallel ->
+        [one] Deploying up to { $max_parallel } stack at a time
+       *[other] Deploying up to { $max_parallel } stacks at a time
+    }
+
+## Stack processing
+changed-only-nothing-changed = No stack changed, volumes and images left as they are (--changed-only)
+
+## Stack processing
+processing-images = Processing images...
+
+## Stack processing
+image-freshness-check-failed = ⚠️  Could not check image freshness: { $error }
+skipping-stack-filtered = Skipping stack { $stack } (outside of --only / --exclude)
+skipping-stack-disabled = Skipping stack { $stack } (disabled in stacks.yaml)
+skipping-stack-unchanged = Skipping stack { $stack } (unchanged, --changed-only)
+processing-stack = Processing stack: { $stack }
+stack-directory-not-found = Warning: Stack directory '{ $directory }' not found
+stack-compose-file-not-found = Warning: No docker-compose file found in stack directory '{ $directory }'
+render-cache-hit = Inputs unchanged since the last rendering, using the cached compose file
+output-not-published = Stack '{ $stack }' references output '{ $output }' of stack '{ $stack2 }', which has not been published
+compose-deprecated-syntax = ⚠️  { $directory }/{ $file_name } uses deprecated compose syntax:
+processing-compose-volumes = Processing volumes in docker-compose file...
+volume-processing-completed = Volume processing completed
+enforcing-encrypted-overlay-networks = Enforcing encrypted overlay networks...
+processing-secrets = Processing secrets...
+secret-processing-completed = Secret processing completed
+compose-file-updated = Updated docker-compose file with processed volumes at { $compose_path }
+plan-stack-render-changed = Stack '{ $stack }' renders to { $compose_hash } instead of the planned { $planned }, its inputs changed since the plan (values, outputs, refs...), run plan again
+stack-failures-not-retrying = ⚠️  Stack '{ $stack }' failed to deploy { $failures } times in a row, not retrying until its compose file changes (use --force to retry)
+stack-rolled-back-not-redeploying = ⏸️  Stack '{ $stack }' was rolled back from this compose file, not redeploying until it changes (use --force to redeploy)
+stack-changed = Stack '{ $stack }' has changed (hash: { $hash } -> { $compose_hash })
+stack-unchanged-forced = Stack '{ $stack }' unchanged but force mode enabled, redeploying
+stack-retrying = Stack '{ $stack }' failed to deploy last time, retrying
+stack-modified-in-swarm = Stack '{ $stack }' unchanged in git but modified in Swarm, redeploying
+stack-unchanged = Stack '{ $stack }' unchanged
+stopping-existing-stack = Stopping existing stack '{ $stack }'
+deploying-updated-stack = Deploying updated stack '{ $stack }'
+stack-owned-by-other-repository = ❌ Stack '{ $stack }' is already deployed from { $url }, skipping
+stack-adopting-running = Adopting stack '{ $stack }' running in Swarm (hash: { $compose_hash })
+stack-new = New stack '{ $stack }' found, deploying
+stack-has-no-service = ⚠️  Stack '{ $stack }' has no service in Swarm
+changed-outside-dockerops = ⚠️  Changed outside DockerOps: { $list }
+conflict-deploying-resolution = Deploying with the '{ $resolution }' resolution of manual changes
+conflict-inspect-failed = ⚠️  Could not inspect stack '{ $stack }' for manual changes: { $error }
+conflict-detected = ⚠️  Stack '{ $stack }' was changed manually and git changes it too:
+override-line = { $service } { $field }: deployed { $deployed }, running { $live }, git { $git }
+conflict-resolve-hint = Not deploying it until `dockerops resolve { $stack } --keep-override|--take-git|--merge`
+stack-drifted = ⚠️  Stack '{ $stack }' ({ $url }) drifted:
+drift-no-snapshot = ❌ No compose snapshot was kept for stack '{ $stack }', run 'deploy { $stack }' instead
+re-applying-stack = Re-applying stack '{ $stack }'
+drift-reapply-failures = { $failed ->
+        [one] { $failed } drifted stack could not be re-applied
+       *[other] { $failed } drifted stacks could not be re-applied
+    }
+failed-to-list-stacks = Failed to list stacks: { $error }
+conflict-waiting-line = ⚠️  { $stack_name } ({ $url }) - waiting since { $time }
+conflict-line = { $stack_name } ({ $url }) - resolved with { $resolution }{ $operator }
+conflict-override-line = [{ $marker }] { $service } { $field }: deployed { $deployed }, running { $live }, git { $git }
+conflicts-resolve-hint = Resolve with: dockerops resolve <stack> --keep-override | --take-git | --merge
+conflict-none-for-stack = Stack '{ $stack }' has no conflict with manual changes
+conflict-keeping-value = Keeping { $service } { $field } = { $live }
+traffic-delay-expired = Update of stack '{ $stack }' was delayed for { $waited }s, deploying regardless of the load
+traffic-load-unavailable = ⚠️  Could not query the load of stack '{ $stack }' ({ $error }), deploying
+traffic-update-delayed = ⏸️  Stack '{ $stack }' is busy (load { $load } > { $threshold }), delaying its update to a quieter period
+env-example-keys-missing = Keys listed in .env.example are not provided by values.yaml, secrets.yaml or the environment:
+    { $details }
+dry-run-message = [dry run] { $message }
+dry-run-would-pull = [dry run] Would pull: { $list }
+dry-run-image-kept-referenced = [dry run] Image { $image } would be kept, another repository still references it
+dry-run-image-kept-in-use = [dry run] Image { $image } would be kept, { $users } still uses it
+dry-run-would-remove-unused-image = [dry run] Would remove unused image: { $image }
+prune-stack-undeclared-line = { $status ->
+        [restored] ⚠️  Restored stack '{ $stack }' is still missing from stacks.yaml
+       *[other] ⚠️  Imported stack '{ $stack }' is still missing from stacks.yaml
+    }
+prune-stack-kept = ⏸️  Stack '{ $stack }' is no longer declared in stacks.yaml, keeping it (remove it with 'rm', --prune or prune_stacks)
+dry-run-would-trash-stack = [dry run] Would remove stack '{ $stack }', { $reason }, and move it to the trash
+stack-trashed = Stack '{ $stack }' was { $reason }, moving it to the trash
+
+## Deploy results and notifications
+canary-started = Canary analysis of stack '{ $stack }' for { $duration }s
+
+## Deploy results and notifications
+canary-passed = ✅ Canary analysis of stack '{ $stack }' passed
+
+## Deploy results and notifications
+canary-failed = ❌ Canary analysis of stack '{ $stack }' failed, rolling back
+
+## Deploy results and notifications
+canary-service-rolled-back = Rolled back { $service }
+
+## Deploy results and notifications
+canary-rollback-failed = ⚠️  Could not roll back { $service }: { $error }
+
+## Deploy results and notifications
+canary-failure-reason = Canary analysis failed, the update was rolled back:
+    { $summary }
+issue-close-failed = ⚠️  Could not close issue #{ $number }: { $error }
+stack-failing = ❌ Stack '{ $stack }' failed to deploy { $failures } times in a row
+issue-report-failed = ⚠️  Could not report the failure on GitHub: { $error }
+notification-queue-failed = ⚠️  Could not queue notification for { $url }: { $error }
+outbox-unreadable = ⚠️  Could not read the notification outbox: { $error }
+notification-given-up = ❌ Giving up on notification to { $url } after { $attempts } attempts: { $error }
+notification-retrying = ⚠️  Could not notify { $url } (attempt { $attempts }), retrying at { $next_attempt }: { $error }
+outbox-update-failed = ⚠️  Could not update the notification outbox: { $error }
+outbox-purge-failed = ⚠️  Could not purge the notification outbox: { $error }
+not-a-github-repository = { $url } is not a GitHub repository
+issue-opened = Opened issue #{ $number } in { $owner }/{ $repo }
+issue-updated = Updated issue #{ $number } in { $owner }/{ $repo }
+issue-closed-log = Closed issue #{ $number } in { $owner }/{ $repo }
+
+## Garbage collection
+gc-failed = ⚠️  Garbage collection failed: { $error }
+gc-skipped = Garbage collection skipped: { $reclaimable } reclaimable, below gc.min_reclaimable ({ $threshold })
+gc-started = Collecting garbage older than { $older_than }s on the manager...
+gc-dangling-images-failed = ⚠️  Could not prune dangling images: { $error }
+gc-stopped-containers-failed = ⚠️  Could not remove stopped containers: { $error }
+gc-build-cache-failed = ⚠️  Could not prune the build cache: { $error }
+docker-image-ls-failed = docker image ls failed: { $error }
+gc-pruning-dangling-images = Pruning dangling images...
+docker-container-ls-failed = docker container ls failed: { $error }
+gc-removing-containers = Removing stopped containers of the managed stacks...
+gc-containers-not-removed = ⚠️  Some containers could not be removed: { $error }
+docker-system-df-failed = docker system df failed: { $error }
+gc-pruning-build-cache = Pruning the build cache...
+task-history-limit-failed = ⚠️  Could not set the Swarm task history limit: { $error }
+task-prune-failed = ⚠️  Could not prune stopped tasks: { $error }
+task-prune-stack-failed = ⚠️  Could not prune the stopped tasks of stack '{ $stack }': { $error }
+task-prune-nodes-unreachable = ⚠️  Stopped tasks left on { $list }, declare their Docker endpoints in node_endpoints of config.yaml
+task-history-limit-set = Swarm task history limit set to { $limit } (was { $current })
+tasks-pruned = { $removed ->
+        [one] Pruned { $removed } stopped task of stack '{ $stack }'
+       *[other] Pruned { $removed } stopped tasks of stack '{ $stack }'
+    }
+trash-purged = { $purged ->
+        [one] Purged { $purged } expired stack from the trash
+       *[other] Purged { $purged } expired stacks from the trash
+    }
+
+## Variables and registries
+github-variables-failed = Could not fetch GitHub variables of { $owner }/{ $repo }: { $error }
+
+## Variables and registries
+github-variables-loaded = { $count ->
+        [one] Loaded { $count } GitHub Actions variable
+       *[other] Loaded { $count } GitHub Actions variables
+    }
+
+## Variables and registries
+github-variables-not-github = ⚠️  GitHub variables are enabled but { $url } is not a GitHub repository
+invalid-registries-file = Invalid registries file { $path }: { $error }
+registries-sops-failed = registries.yaml is SOPS-encrypted but sops could not be run: { $error }
+registries-decrypt-failed = Could not decrypt registries.yaml: { $error }
+registries-not-encrypted = ⚠️  registries.yaml is not encrypted, credentials are stored in clear text in the repository
+registries-loaded = { $count ->
+        [one] Loaded credentials for { $count } registry
+       *[other] Loaded credentials for { $count } registries
+    }
+
+## Stack deployment
+yaml-parse-failed = Warning: Could not parse YAML file { $file_path }: { $error }
+
+## Stack deployment
+images-found-in-file = { $count ->
+        [one] Found { $count } image in { $file_path }: { $images_found }
+       *[other] Found { $count } images in { $file_path }: { $images_found }
+    }
+stack-deploying = Deploying stack '{ $stack }' with docker stack deploy
+self-update-warning = ⚠️  This is the stack running DockerOps, the service restarts once the update is applied
+extracting-images-from-compose-file = Extracting images from compose file...
+images-pulling = { $count ->
+        [one] Found { $count } image, pulling before deployment: { $images_found }
+       *[other] Found { $count } images, pulling before deployment: { $images_found }
+    }
+all-images-pulled-successfully = All images pulled successfully
+no-images-found-in-compose-file = No images found in compose file
+secret-env-added = Added environment variable: { $env_name } (secret)
+successfully-deployed-stack = Successfully deployed stack '{ $stack }'
+error-deploying-stack = Error deploying stack '{ $stack }': { $error }
+deploy-failed = Failed to deploy stack: { $error }
+hook-running = Running { $phase } hook { $index } of '{ $stack }': { $describe }
+hook-failed = { $phase } hook { $index } of stack '{ $stack }' ({ $describe }) failed: { $error }
+hooks-succeeded = ✅ { $phase } hooks of '{ $stack }' succeeded
+timed-out = timed out after { $timeout }s
+job-start-failed = could not start the job: { $error }
+prerequisites-checking = Checking host prerequisites of '{ $stack }'
+failed-to-list-the-swarm-nodes = Failed to list the Swarm nodes: { $error }
+failed-to-inspect-the-swarm-nodes = Failed to inspect the Swarm nodes: { $error }
+prerequisites-not-met = { $nodes ->
+        [one] Host prerequisites of stack '{ $stack }' are not met on { $failed } of { $nodes } node:
+       *[other] Host prerequisites of stack '{ $stack }' are not met on { $failed } of { $nodes } nodes:
+    }
+    {"  "}{ $details }
+prerequisites-met = { $count ->
+        [one] ✅ Host prerequisites met on { $count } node
+       *[other] ✅ Host prerequisites met on { $count } nodes
+    }
+prerequisites-probe-failed = Failed to start the prerequisites probe: { $error }
+convergence-waiting = Waiting up to { $timeout }s for the services of '{ $stack }' to converge
+convergence-done = ✅ All services of '{ $stack }' converged
+convergence-failed = No replica of stack '{ $stack }' is running after { $timeout }s: { $list }
+convergence-degraded = ⚠️  Stack '{ $stack }' is degraded after { $timeout }s: { $list }
+dependencies-not-ready = ⏸️  Stack '{ $stack }' not deployed, its dependencies are not ready: { $list }
+dependencies-waiting = Waiting for the dependencies of '{ $stack }' to converge: { $list }
+registry-auth-disabled = ⚠️  with_registry_auth is disabled, worker nodes may fail to pull the private images of registries.yaml
+registry-auth-docker-cli = Sending the registry credentials of the docker CLI to the nodes
+registry-auth-sending = Sending registry credentials to the nodes ({ $list })
+updating-service = Updating service '{ $stack }_{ $name }'{ $image }{ $replicas }
+failed-to-update-service = Failed to update service '{ $stack }_{ $name }': { $error }
+services-updated = { $count ->
+        [one] Successfully updated { $count } service of stack '{ $stack }'
+       *[other] Successfully updated { $count } services of stack '{ $stack }'
+    }
+trash-copy-failed = Warning: Could not copy the directory of stack '{ $stack }' to the trash: { $error }
+stack-stopping = Stopping stack '{ $stack }' with docker stack rm
+successfully-stopped-stack = Successfully stopped stack '{ $stack }'
+stack-stop-failed = Warning: Error stopping stack '{ $stack }': { $error }
+
+## Images
+images-used-by-repository = { $count ->
+        [one] Found { $count } image used by the repository
+       *[other] Found { $count } images used by the repository
+    }
+
+## Images
+images-checking-changed = Checking { $count } of them, the others belong to unchanged stacks
+
+## Images
+processing-image = Processing image: { $image }
+image-kept-referenced = Image { $image } is still referenced by another repository, keeping it
+image-kept-in-use = ⏸️  Image { $image } is no longer used by DockerOps but { $users } still uses it, keeping it
+removing-unused-image = Removing unused image: { $image }
+image-users-unknown-containers = ⚠️  Could not list containers, keeping all images: { $error }
+image-users-unknown-services = ⚠️  Could not inspect services, keeping all images
+image-digest-mismatch = SHA mismatch for { $image }: local={ $local }, remote={ $remote }
+image-replacing = Removing old image and pulling new version
+image-is-up-to-date = Image { $image } is up to date
+image-missing-locally = Image { $image } not found locally, pulling
+image-remote-digest-unknown = Could not get remote SHA for { $image }
+removing-image-indented = Removing image: { $image }
+successfully-removed-image = Successfully removed image: { $image }
+image-remove-failed = Warning: Error removing image { $image }: { $error }
+provenance-unverified = Provenance of { $image } could not be verified: { $error }
+provenance-verified = ✅ Provenance verified: built by { $workflow } from { $source }
+pulling-image = Pulling image: { $image }
+successfully-pulled-image = Successfully pulled image: { $image }
+error-pulling-image = Error pulling image { $image }: { $error }
+pull-failed = Failed to pull image: { $error }
+pull-trying-mirror = ⚠️  Pull from { $registry_name } failed, trying mirror { $mirror }: { $error }
+mirror-tag-failed = Could not tag { $mirrored } as { $image }: { $error }
+pulled-from-mirror = Pulled { $image } from mirror { $mirror }
+bandwidth-limit-ignored = ⚠️  Bandwidth limit ignored: the Docker daemon is remote and can't reach the local pull proxy
+bandwidth-limited-fetch = Fetching layers of { $image } at up to { $limit } KB/s
+bandwidth-limited-pull-failed = ⚠️  Rate limited pull failed, pulling directly: { $error }
+
+## Secrets and volumes
+checking-for-secrets-yaml-file = Checking for secrets.yaml file...
+
+## Secrets and volumes
+secrets-file-missing = No secrets.yaml file found, skipping secret processing
+
+## Secrets and volumes
+secrets-file-found = Found secrets.yaml file, reading secrets...
+
+## Secrets and volumes
+found-secret-definitions = { $count ->
+        [one] Found { $count } secret definition
+       *[other] Found { $count } secret definitions
+    }
+
+## Secrets and volumes
+using-secrets-path = Using secrets path: { $secrets_base_path }
+
+## Secrets and volumes
+processing-secret = Processing secret: { $id } -> { $env }
+
+## Secrets and volumes
+secret-file-not-found = Secret file not found: { $secret_path }
+
+## Secrets and volumes
+secret-value-loaded-from = Secret value loaded from: { $secret_path }
+
+## Secrets and volumes
+successfully-loaded-secrets = { $count ->
+        [one] Successfully loaded { $count } secret
+       *[other] Successfully loaded { $count } secrets
+    }
+nfs-yaml-not-found-at = nfs.yaml not found at: { $nfs_file_path }
+reading-nfs-yaml-from = Reading nfs.yaml from: { $nfs_file_path }
+nfs-config = NFS config: { $config }
+looking-for-volumes-yaml-in = Looking for volumes.yaml in: { $repo_path }
+volumes-file-missing = No volumes.yaml found at { $volumes_file_path }, skipping volume processing
+found-volumes-yaml-at = Found volumes.yaml at: { $volumes_file_path }
+volumes-file-read = { $count ->
+        [one] Read volumes.yaml content ({ $count } character)
+       *[other] Read volumes.yaml content ({ $count } characters)
+    }
+volumes-file-parsed = { $count ->
+        [one] Parsed { $count } volume definition from volumes.yaml
+       *[other] Parsed { $count } volume definitions from volumes.yaml
+    }
+found-nfs-yaml-at = Found nfs.yaml at: { $nfs_file_path }
+nfs-file-missing = No nfs.yaml found at { $nfs_file_path }, NFS bindings will be skipped
+processing-volume-definitions = { $count ->
+        [one] Processing { $count } volume definition
+       *[other] Processing { $count } volume definitions
+    }
+processing-volume-definition = Processing volume definition: { $volume_def }
+processing-volume = Processing volume: { $id } (type: volume, path: { $path })
+processing-binding = Processing binding: { $id } (type: binding, path: { $path })
+binding-skipped-no-nfs = Warning: No NFS configuration found, skipping binding volume
+finished-processing-all-volume-definitions = Finished processing all volume definitions
+binding-source-missing = Warning: Local path does not exist: { $local_path }
+copying-to-nfs = Copying { $local_path } to NFS: { $nfs_dest_path }
+removing-existing-directory-on-nfs = Removing existing directory on NFS: { $nfs_dest_path }
+removing-existing-file-on-nfs = Removing existing file on NFS: { $nfs_dest_path }
+successfully-copied-to-nfs = Successfully copied to NFS: { $nfs_dest_path }
+source-is-not-a-directory = Source is not a directory: { $src }
+fixing-permissions-for-docker-compatibility = Fixing permissions for Docker compatibility...
+permissions-directories-set = Successfully set directory permissions to 755
+permissions-directories-failed = Warning: Failed to set directory permissions: { $error }
+permissions-files-set = Successfully set file permissions to 644
+permissions-files-failed = Warning: Failed to set file permissions: { $error }
+ownership-changed = Successfully changed ownership to { $current_user }
+ownership-change-failed = Warning: Failed to change ownership: { $error }
+compose-not-a-mapping = docker-compose content is not a mapping
+compose-networks-not-a-mapping = 'networks' section of stack '{ $stack }' is not a mapping
+network-encryption-violation = ⚠️  Policy violation: external network '{ $external_name }' used by stack '{ $stack }' is not encrypted
+enabled-encryption-on-network = Enabled encryption on network '{ $name }'
+external-network-does-not-exist = ⚠️  External network '{ $network_name }' does not exist
+parsing-docker-compose-content = Parsing docker-compose content...
+successfully-parsed-yaml-content = Successfully parsed YAML content
+processing-services = { $count ->
+        [one] Found services section, processing { $count } service
+       *[other] Found services section, processing { $count } services
+    }
+processing-service = Processing service: { $service_name_str }
+found-volumes-section-in-service = Found volumes section in service { $service_name_str }
+no-volumes-section-found-in-service = No volumes section found in service { $service_name_str }
+compose-has-no-services = No services section found in docker-compose
+converting-modified-yaml-back-to-string = Converting modified YAML back to string...
+compose-serialized = { $count ->
+        [one] Successfully converted YAML to string ({ $count } character)
+       *[other] Successfully converted YAML to string ({ $count } characters)
+    }
+processing-service-volumes = Processing service volumes...
+found-volume-entries = { $count ->
+        [one] Found { $count } volume entry
+       *[other] Found { $count } volume entries
+    }
+processing-volume-entry = Processing volume entry { $index }: { $volume }
+volume-string = Volume string: '{ $volume_str }'
+split-into-parts = { $count ->
+        [one] Split into { $count } part: { $parts }
+       *[other] Split into { $count } parts: { $parts }
+    }
+volume-string-parts = Volume ID: '{ $volume_id }', Container path: '{ $container_path }', Options: '{ $options }'
+found-volume-definition = Found volume definition: { $volume_def }
+replacing-volume = Replacing Docker volume { $volume_id } with: { $volume_path }
+nfs-config-path = NFS config path: { $path }
+volume-path = Volume path: { $path }
+full-nfs-path = Full NFS path: { $full_nfs_path }
+dry-run-would-create-nfs-directory = [dry run] Would create NFS directory: { $full_nfs_path }
+creating-nfs-directory = Creating NFS directory: { $full_nfs_path }
+successfully-created-nfs-directory = Successfully created NFS directory
+nfs-directory-already-exists = NFS directory already exists: { $full_nfs_path }
+replacing-binding = Replacing binding volume { $volume_id } with NFS path: { $nfs_path }
+volume-definition-missing = Warning: Volume definition not found for ID: '{ $volume_id }'
+available-volume-definitions = Available volume definitions: { $ids }
+volume-string-invalid-parts = Volume string does not have 2 or 3 parts, skipping
+volume-string-without-colon = Volume string does not contain ':', skipping
+volume-entry-is-not-a-string = Volume entry is not a string, skipping
+volume-format-is-not-a-sequence = Volume format is not a sequence, skipping
+finished-processing-service-volumes = Finished processing service volumes
+adding-volumes-section = Adding volumes section to docker-compose...
+created-new-volumes-section = Created new volumes section
+adding-volume-to-volumes-section = Adding volume '{ $id }' to volumes section
+volumes-section-skipping-binding = Skipping binding '{ $id }' in volumes section (handled in service volumes)
+volumes-section-updated = Volumes section updated
+
+## Repository layout and helpers
+trash-copy-not-removed = Warning: Could not remove the trashed copy of stack '{ $stack }': { $error }
+invalid-tag-pattern = Invalid tag pattern '{ $pattern }': { $error }
+no-tag-matches = No tag matches '{ $pattern }'
+newest-tag-matching = Newest tag matching '{ $pattern }': { $tag }
+revision-not-found = Revision '{ $revision }' not found in repository (was it removed by a force-push?): { $error }
+checked-out-commit = Checked out commit { $id }
+directory-not-found-in-commit = Directory '{ $prefix }' not found in commit { $id }
+invalid-stacks-yaml-in-commit = Invalid stacks.yaml in commit { $id }: { $error }
+path-outside-repository = --path must be a directory inside the repository (got '{ $path }')
+directory-not-found-in-repository = Directory '{ $path }' not found in repository
+failed-to-list-docker-contexts = Failed to list docker contexts: { $error }
+invalid-values-file = Invalid values file { $path }: { $error }
+invalid-value-type = Value '{ $key }' in { $path } must be a string, number or boolean
+dependency-not-declared = Stack '{ $stack }' depends on '{ $dependency }', which is not declared in stacks.yaml
+stack-depends-on-itself = Stack '{ $stack }' depends on itself
+dependency-cycle = Dependencies and output references form a cycle between stacks: { $list }
+gc-reclaimable = Could be reclaimed: { $total } (the build cache regardless of gc.older_than)
+gc-reclaimed = ✅ Reclaimed { $total }
+setup-aborted = Setup aborted, no more input
+setup-answer-y-or-n = Please answer y or n
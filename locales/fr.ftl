@@ -0,0 +1,886 @@
+# Messages de DockerOps, français
+# Les messages absents sont affichés en anglais (en.ftl)
+# En français, 0 et 1 prennent la variante [one]
+
+using-profile = Profil utilisé : { $profile }
+using-context = Contexte utilisé : { $context }
+
+## Déploiements
+stack-deployed = ✅ Stack '{ $stack }' déployée
+stack-not-deployed = La stack '{ $stack }' n'a pas été déployée (statut : { $status })
+stack-adopted = ✅ Stack '{ $stack }' reprise (hash : { $hash })
+stack-adopted-hint = Elle n'est redéployée par une synchronisation que si son docker-compose change dans le répertoire
+stack-removed = ✅ Stack '{ $stack }' supprimée, elle peut être restaurée avec 'restore-stack' (voir 'trash')
+stack-restored = ✅ Stack '{ $stack }' restaurée
+stack-restored-hint = Rajoutez-la dans stacks.yaml pour qu'elle reste gérée depuis le répertoire
+stack-rolled-back = ✅ Stack '{ $stack }' ramenée à la révision { $revision }
+stack-rolled-back-hint = Son docker-compose actuel n'est plus redéployé tant que le répertoire ne le modifie pas (ou avec 'deploy')
+stack-exported = ✅ Stack '{ $stack }' exportée dans { $path }
+stack-restarted = ✅ Stack '{ $stack }' redémarrée ({ $count ->
+        [one] { $count } service
+       *[other] { $count } services
+    })
+stack-imported = ✅ Stack '{ $stack }' importée
+repository-unwatched = ✅ Le répertoire { $url } n'est plus surveillé
+repository-unwatched-hint = Ses stacks peuvent être restaurées avec 'restore-stack' (voir 'trash')
+repository-sync-failed = ❌ Échec de la synchronisation du répertoire { $url } : { $error }
+no-repositories-to-poll = Aucun répertoire en cache, rien à surveiller. Utilisez 'watch' pour en ajouter un.
+
+## État
+no-deployments = Aucun déploiement enregistré
+no-stacks-deployed = Aucune stack déployée
+trash-empty = La corbeille est vide
+trash-count = { $count ->
+        [one] { $count } stack dans la corbeille :
+       *[other] { $count } stacks dans la corbeille :
+    }
+no-conflicts = Aucun conflit entre modifications manuelles et git
+conflict-resolved = ✅ Conflit de la stack '{ $stack }' résolu avec { $resolution }
+unhealthy = DockerOps est en mauvaise santé
+
+## Écarts
+drift-nothing-to-check = Aucune stack déployée à vérifier
+drift-none = { $count ->
+        [one] ✅ { $count } stack correspond à son dernier déploiement
+       *[other] ✅ { $count } stacks correspondent à leur dernier déploiement
+    }
+drift-found = { $drifted ->
+        [one] { $drifted } stack sur { $count } s'écarte de son dernier déploiement (--fix pour la redéployer)
+       *[other] { $drifted } stacks sur { $count } s'écartent de leur dernier déploiement (--fix pour les redéployer)
+    }
+drift-reapplied = ✅ Stack '{ $stack }' réappliquée
+drift-reapply-failed = ❌ Impossible de réappliquer la stack '{ $stack }' : { $error }
+
+## Issues d'échec ouvertes dans le répertoire surveillé
+issue-title = DockerOps : la stack '{ $stack }' ne se déploie pas
+issue-body = DockerOps n'a pas pu déployer la stack `{ $stack }` { $failures } fois de suite (commit `{ $commit }`). Elle n'est plus retentée tant que son docker-compose ne change pas.
+issue-error = **Erreur :**
+issue-changes = **Modifications de `{ $directory }/` depuis la dernière synchronisation réussie (`{ $commit }`) :**
+issue-footer = Cette issue est fermée automatiquement dès que la stack se déploie de nouveau.
+issue-closed = Stack `{ $stack }` déployée avec succès, fermeture.
+
+## Notifications par webhook
+notification-deploy-failed = ❌ DockerOps : la stack '{ $stack }' n'a pas pu être déployée { $failures } fois de suite ({ $url }) : { $error }
+notification-deploy-recovered = ✅ DockerOps : la stack '{ $stack }' est de nouveau déployée après { $failures ->
+        [one] { $failures } échec
+       *[other] { $failures } échecs
+    } ({ $url })
+notifications-undelivered = { $count ->
+        [one] ⚠️  { $count } notification par webhook n'a pas pu être envoyée :
+       *[other] ⚠️  { $count } notifications par webhook n'ont pas pu être envoyées :
+    }
+webhook-answered = Le webhook a répondu { $status } : { $body }
+
+## Commandes
+github-app-authentication-disabled = ⚠️  Authentification par GitHub App désactivée : { $error }
+local-repository-refs-unsupported = --branch, --tag et --commit ne sont pas pris en charge pour les dépôts locaux, leur arbre de travail est déployé tel quel
+local-repository-not-found = Dépôt local { $path } introuvable : { $error }
+watching-repository = Surveillance du dépôt : { $url }
+repository-already-watched-use-force = Le dépôt '{ $url }' est déjà surveillé (dernière surveillance : { $last_watch }), utilisez --force pour redéployer ses stacks
+repository-watch-options-kept = ⚠️  Le dépôt garde la branche, le tag, le commit, le chemin et l'espace de noms de sa première surveillance
+repository-already-watched-redeploying = Dépôt déjà surveillé, redéploiement de ses stacks (--force)
+repository-working-copy = Copie de travail du dépôt : { $repo_path }
+watch-dry-run = Simulation, le dépôt n'est pas surveillé
+repository-pinned-to-commit = Dépôt épinglé au commit { $commit }
+repository-added-to-cache = Dépôt ajouté au cache
+could-not-read = Impossible de lire { $path } : { $error }
+invalid-repository-list = Liste de dépôts invalide { $path } : { $error }
+invalid-namespace = Espace de noms '{ $namespace }' invalide (lettres, chiffres, '-' et '_' uniquement)
+repository-list-tag-conflict = Dépôt '{ $url }' : 'tag' ne peut pas être combiné avec 'branch' ou 'commit'
+bootstrap-started = { $count ->
+        [one] Initialisation de { $count } dépôt depuis { $path } ({ $parallel } à la fois)
+       *[other] Initialisation de { $count } dépôts depuis { $path } ({ $parallel } à la fois)
+    }
+bootstrap-repository-skipped = Le dépôt { $url } est déjà surveillé, ignoré
+bootstrap-summary = Bilan de l'initialisation :
+already-watched = ⏭️  { $url } (déjà surveillé)
+bootstrap-failures = { $failures ->
+        [one] { $failures } dépôt sur { $total } n'a pas pu être surveillé
+       *[other] { $failures } dépôts sur { $total } n'ont pas pu être surveillés
+    }
+bootstrap-succeeded = Tous les dépôts sont surveillés
+reconciling-database = Réconciliation de la base de données...
+no-repositories-in-cache = Aucun dépôt dans le cache. Lancez d'abord la commande 'watch'.
+repository-not-watched = Le dépôt '{ $url }' n'est pas surveillé
+found-repositories-in-cache = { $count ->
+        [one] { $count } dépôt dans le cache :
+       *[other] { $count } dépôts dans le cache :
+    }
+found-stacks-in-database = { $count ->
+        [one] { $count } stack dans la base de données :
+       *[other] { $count } stacks dans la base de données :
+    }
+repository-stack-line = - { $stack } ({ $app }statut : { $status }, empreinte : { $hash }{ $staleness })
+repository-drift-line = ⚠️  { $object_type } { $object_name } a subi '{ $action }' hors de DockerOps ({ $time })
+found-images-in-database = { $count ->
+        [one] { $count } image dans la base de données :
+       *[other] { $count } images dans la base de données :
+    }
+cached-image-line = - { $name } (référencée { $reference_count } fois)
+starting-reconciliation-process = Démarrage de la réconciliation...
+reconcile-forced = ⚠️  Mode forcé activé - toutes les stacks seront redéployées quels que soient les changements
+reconciling-repository = Réconciliation du dépôt : { $url }
+reconciliation-completed = Réconciliation terminée !
+planning-repository = Planification du dépôt : { $url }
+plan-no-changes = Aucun changement, rien à appliquer
+planned-changes = Changements prévus :
+plan-saved = Plan enregistré dans { $output }, lancez 'dockerops apply { $output }' pour l'exécuter
+plan-applying = Application du plan fait par { $created_by } le { $time }
+plan-empty = Le plan ne contient aucun changement
+plan-repository-unwatched = Le dépôt '{ $url }' n'est plus surveillé, relancez plan
+plan-stack-changed = La stack '{ $stack }' a changé depuis le plan ({ $current } au lieu de { $previous_hash }), relancez plan
+plan-applying-changes = { $count ->
+        [one] Application de { $count } changement à { $url }
+       *[other] Application de { $count } changements à { $url }
+    }
+plan-changes-not-applied = { $count ->
+        [one] { $count } changement prévu n'a pas été appliqué :
+       *[other] { $count } changements prévus n'ont pas été appliqués :
+    }
+    {"  "}{ $details }
+plan-applied = ✅ Plan appliqué
+repository-no-longer-watched = Le dépôt { $url } n'est plus surveillé
+reconciling-stack = Réconciliation de la stack '{ $stack }' de { $url } (statut : { $status })
+reconcile-stack-forced = ⚠️  Mode forcé activé - la stack sera redéployée quels que soient les changements
+stack-reconciliation-completed = Réconciliation de la stack '{ $stack }' terminée (statut : { $status })
+deploying-stack-from = Déploiement de la stack '{ $stack }' depuis { $url }
+stack-was-not-deployed = La stack '{ $stack }' n'a pas été déployée
+stack-already-managed = La stack '{ $stack }' est déjà gérée depuis { $url }
+repository-not-watched-run-watch = Le dépôt '{ $url }' n'est pas surveillé, lancez d'abord 'watch'
+stack-not-running-use-deploy = La stack '{ $stack }' ne tourne pas dans Swarm, utilisez 'deploy' pour la déployer
+adopting-stack-from = Adoption de la stack '{ $stack }' depuis { $url }
+stack-was-not-adopted = La stack '{ $stack }' n'a pas été adoptée
+settings-global-header = Réglages globaux (config.yaml), surchargés par dockerops.yaml et stacks.yaml :
+settings-stack-header = Réglages effectifs de la stack '{ $stack }' ({ $url }) :
+settings-no-stacks-yaml = Pas de stacks.yaml dans la copie de travail de { $url }, lancez d'abord 'sync'
+settings-stack-not-declared = La stack '{ $stack }' n'est pas déclarée dans le stacks.yaml de { $url }
+
+## Démon, miroir et webhooks
+task-history-max-age = task_history.max_age : { $error }
+
+## Démon, miroir et webhooks
+daemon-started = Démarrage du démon DockerOps (interrogation toutes les { $interval }s, Ctrl+C pour arrêter)
+
+## Démon, miroir et webhooks
+docker-event-failed = ⚠️  Impossible de traiter l'événement Docker : { $error }
+
+## Démon, miroir et webhooks
+docker-events-ended = ⚠️  Le flux d'événements Docker s'est arrêté, les alertes de dérive reprennent à la prochaine interrogation
+
+## Démon, miroir et webhooks
+daemon-interrupted = Signal d'interruption reçu, arrêt du démon
+docker-events-watching = Surveillance des événements Docker pour les changements faits hors de DockerOps
+docker-events-unavailable = ⚠️  Impossible de surveiller les événements Docker, les alertes de dérive sont désactivées : { $error }
+drift-event = ⚠️  [{ $time }] Dérive de la stack '{ $stack }' : { $object_type } { $object_name } a subi '{ $action }' hors de DockerOps
+self-deploy-webhook-secret-missing = Définissez 'webhook_secret' dans { $file_path } avant de déployer DockerOps comme service
+self-deploy-context-unsupported = Le contexte Docker '{ $context }' n'est pas utilisable depuis un service, définissez un docker_host tcp:// ou utilisez le socket du manager
+self-deploy-file-setting = ⚠️  '{ $setting }' désigne un fichier qui doit aussi exister dans le conteneur du service
+self-deploy-creating-secret = Création du secret { $secret_name } depuis { $config_path }
+docker-has-no-stdin = docker n'a pas d'entrée standard
+self-deploy-secret-failed = Échec de la création du secret { $secret_name } : { $error }
+self-deploy-deploying = Déploiement de la stack '{ $stack }' avec { $image }
+failed-to-deploy-stack = Échec du déploiement de la stack '{ $stack }' : { $error }
+self-deploy-done = ✅ DockerOps tourne en tant que stack '{ $stack }', webhooks sur le port { $port }
+self-deploy-watch-hint = Surveillez les dépôts depuis le service : docker exec $(docker ps -qf label=com.docker.swarm.service.name={ $stack }_dockerops) dockerops watch <url>
+self-deploy-update-hint = Pour mettre à jour DockerOps depuis git, commitez { $compose_path } en tant que { $stack }/docker-compose.yml d'un dépôt surveillé
+mirror-target-is-database = La cible du miroir est la base de données elle-même
+invalid-mirror-target = Cible de miroir { $target } invalide : { $error }
+unsupported-mirror-target = Les cibles de miroir en { $scheme }:// ne sont pas prises en charge, utilisez un chemin de fichier, une URL sqlite: ou ssh://[utilisateur@]hôte[:port]/chemin
+mirror-started = Copie de l'état vers { $target } toutes les { $interval }s (Ctrl+C pour arrêter)
+mirror-failed = ❌ Échec de la copie de l'état vers { $target } : { $error }
+mirror-interrupted = Signal d'interruption reçu, arrêt du miroir
+mirror-done = [{ $time }] État copié vers { $target }
+could-not-read-repository-cache = ❌ Impossible de lire le cache des dépôts : { $error }
+polling-repositories = { $count ->
+        [one] [{ $time }] Interrogation de { $count } dépôt...
+       *[other] [{ $time }] Interrogation de { $count } dépôts...
+    }
+webhook-deliveries-not-recorded = ⚠️  Impossible d'enregistrer les livraisons de webhook de { $url } : { $error }
+webhook-deliveries-unreadable = ⚠️  Impossible de lire les livraisons de webhook : { $error }
+repository-unreadable = ⚠️  Impossible de lire le dépôt { $url } : { $error }
+webhook-replaying-pushes = { $pushes ->
+        [one] Rejeu de { $pushes } push manqué vers { $url }, le dernier reçu le { $received_at }
+       *[other] Rejeu de { $pushes } pushs manqués vers { $url }, le dernier reçu le { $received_at }
+    }
+webhook-pushes-replayed = ✅ Pushs rejoués vers { $url }
+webhook-replay-failed = ❌ Échec du rejeu pour { $url } : { $error }
+
+## Synchronisation des dépôts
+remote-tip-already-processed = Le sommet distant { $tip } a déjà été traité, rien à récupérer
+no-newer-tag = Aucun tag plus récent que { $deployed } ne correspond à '{ $pattern }', rien à déployer
+commit-already-processed = Le commit { $head } a déjà été traité, rien à déployer
+signed-commits-unsupported-locally = Les commits signés sont exigés mais les arbres de travail locaux n'ont pas de commit à vérifier
+stack-ref-needs-repository = Les stacks déclarées avec un ref nécessitent un dépôt git
+stack-ref-needs-working-copy = Les stacks déclarées avec un ref nécessitent une copie de travail git
+stack-ref-not-found = Ref '{ $git_ref }' de la stack '{ $stack }' introuvable : { $error }
+commit-signed-by = ✅ { $signer } (stack { $stack })
+stack-checked-out = Stack '{ $stack }' extraite de { $git_ref } ({ $id })
+unwatch-removing-stacks = Suppression des stacks du dépôt : { $url }
+found-stacks-to-remove = { $count ->
+        [one] { $count } stack à supprimer
+       *[other] { $count } stacks à supprimer
+    }
+removing-stack = Suppression de la stack : { $stack }
+removing-stack-of-repository = Suppression de la stack '{ $stack }' de { $url }
+removed-stack-still-declared = ⚠️  La stack '{ $stack }' est toujours déclarée dans le stacks.yaml de { $url }, la prochaine synchronisation la redéploie
+cleanup-started = Arrêt de DockerOps et nettoyage de toutes les ressources...
+found-images-to-remove = { $count ->
+        [one] { $count } image à supprimer
+       *[other] { $count } images à supprimer
+    }
+cleanup-keeping-image = ⏸️  Image { $name } conservée, utilisée par { $users }
+removing-image = Suppression de l'image : { $name }
+cleanup-images-kept = { $kept ->
+        [one] ⚠️  { $kept } image conservée car des conteneurs ou services non gérés par DockerOps l'utilisent
+       *[other] ⚠️  { $kept } images conservées car des conteneurs ou services non gérés par DockerOps les utilisent
+    }
+cleaning-up-database = Nettoyage de la base de données...
+cleanup-working-copies-not-removed = Attention : impossible de supprimer les copies de travail des dépôts : { $error }
+cleanup-cache-not-empty = { $count ->
+        [one] Attention : le cache des dépôts contient encore { $count } entrée, nettoyage forcé...
+       *[other] Attention : le cache des dépôts contient encore { $count } entrées, nettoyage forcé...
+    }
+cleanup-cache-failed = { $count ->
+        [one] ❌ Échec du nettoyage du cache ! Il contient encore { $count } entrée
+       *[other] ❌ Échec du nettoyage du cache ! Il contient encore { $count } entrées
+    }
+cleanup-cache-cleared = ✅ Cache vidé
+cleanup-done = Toutes les stacks et images ont été supprimées.
+cleanup-restore-hint = Les stacks supprimées peuvent être restaurées avec 'restore-stack' (voir 'trash').
+cleanup-database-closing = La connexion à la base de données va être fermée.
+
+## Installation et contextes
+cli-version = DockerOps CLI v{ $version }
+
+## Installation et contextes
+cli-description = Un gestionnaire de stacks Docker Swarm pour dépôts git
+
+## Installation et contextes
+cli-repository = Dépôt : https://github.com/TomBedinoVT/DockerOps
+schema-unknown-file = Fichier inconnu '{ $file }' (attendu : { $list })
+setup-started = Configuration de DockerOps, écriture de { $path }
+setup-ask-update-config = Un fichier de configuration existe, le mettre à jour ?
+setup-ask-database = Fichier de base de données SQLite
+setup-ask-docker-host = DOCKER_HOST (ex. ssh://admin@manager01, vide pour le démon local)
+setup-ask-another-endpoint = Essayer un autre point d'accès ?
+setup-ask-github-token = Jeton GitHub (vide pour aucun ou GITHUB_TOKEN)
+setup-ask-ssh-key = Clé SSH pour les dépôts distants ssh:// et git@ (vide pour le ssh-agent)
+setup-ask-nfs = Répertoire où est monté le partage NFS des volumes binding (vide pour passer)
+setup-ask-failure-issues = Ouvrir une issue GitHub dans le dépôt quand une stack échoue à se déployer de façon répétée ?
+setup-ask-failure-threshold = Échecs consécutifs avant qu'une stack soit signalée et ne soit plus réessayée
+setup-ask-repository = Dépôt git à surveiller (vide pour passer)
+setup-ask-path = Répertoire contenant stacks.yaml dans le dépôt (vide pour la racine)
+setup-step-database = 1. Base de données
+setup-database-not-writable = ❌ { $db_path } ne peut pas être créé ou écrit
+setup-step-docker = 2. Point d'accès Docker
+setup-docker-reached = ✅ Docker { $version } joint
+setup-not-swarm-manager = ⚠️  Ce démon n'est pas un manager Swarm, les stacks ne peuvent pas être déployées par lui
+setup-docker-failed = ❌ Impossible de lancer docker : { $error }
+setup-step-git = 3. Identifiants git
+setup-github-token-valid = ✅ Jeton de l'utilisateur GitHub { $login }
+setup-github-token-rejected = ❌ GitHub a refusé le jeton : { $error }
+setup-key-missing = ❌ { $key } n'existe pas
+setup-step-nfs = 4. Partage NFS (facultatif)
+setup-nfs-hint = ✅ Déclarez-le dans le nfs.yaml de vos dépôts :
+setup-nfs-not-a-directory = ❌ { $answer } n'est pas un répertoire, montez d'abord le partage
+setup-step-notifications = 5. Notifications
+setup-issues-need-token = ⚠️  Les issues nécessitent un jeton GitHub (ou une GitHub App) avec un accès en écriture aux issues
+setup-expected-positive-number = ❌ Un nombre supérieur à 0 est attendu
+setup-config-written = ✅ Configuration écrite dans { $path }
+setup-step-repository = 6. Premier dépôt
+setup-watch-hint = Lancez 'dockerops watch <url>' pour ajouter un dépôt
+compose-file-not-found = Fichier compose introuvable : { $compose_file }
+import-stack-name-unknown = Impossible de déduire un nom de stack, utilisez --name
+import-started = Import du projet compose '{ $stack }' dans { $repo }
+import-directory-exists = Le répertoire de stack existe déjà : { $stack_dir }
+import-stack-already-declared = La stack '{ $stack }' est déjà déclarée dans stacks.yaml
+import-copied-directory = Répertoire copié { $host_path } -> { $destination }
+import-copied-file = Fichier copié { $host_path } -> { $destination }
+import-bind-source-missing = Attention : la source du bind { $host_path } n'existe pas, binding créé vide
+import-kept-host-bind-mount = { $service_name } : { $host_path } n'est ni un fichier ni un répertoire, le service continue de le monter depuis l'hôte
+skipped-special-file = ⚠️  { $path } ignoré : les sockets, FIFO et périphériques ne sont pas copiés
+import-unsupported-volume-type = Attention : type de volume '{ $other }' non pris en charge ignoré dans le service { $service_name }
+import-service-volume = Service { $service_name } : volume { $original_source } -> { $reference }
+import-volume-ids-already-defined = Attention : ids de volume déjà définis dans volumes.yaml, définitions existantes conservées : { $conflicting }
+import-wrote = Écrit : { $stack_compose_path }
+import-added-to = '{ $stack }' ajoutée à { $stacks_file_path }
+import-added-volumes = { $count ->
+        [one] { $count } définition de volume ajoutée à { $volumes_file_path }
+       *[other] { $count } définitions de volume ajoutées à { $volumes_file_path }
+    }
+import-bindings-need-nfs = Note : les bindings nécessitent un nfs.yaml à la racine du dépôt (voir VOLUMES.md)
+import-completed = Import terminé, relisez les fichiers générés et commitez-les dans votre dépôt.
+contexts-configured = Contextes de config.yaml :
+list-none = (aucun)
+docker-contexts = Contextes Docker :
+context-none-docker-host = Aucun contexte sélectionné, utilisation du docker_host { $host }
+context-none-docker-defaults = Aucun contexte sélectionné, utilisation des valeurs par défaut du CLI docker
+context-selection-cleared = ✅ Sélection du contexte effacée
+unknown-context = Contexte inconnu '{ $name }' (voir 'contexts')
+context-selected = ✅ Contexte '{ $name }' utilisé désormais
+context-state-kept-in = Sa base, ses copies de travail et sa corbeille sont conservées dans { $dir }
+cannot-switch-context-shared-database = { $count ->
+        [one] La base { $path } définie par db_path ou DOCKEROPS_DB_PATH est partagée par tous les contextes et surveille { $count } dépôt : retirez-le ou donnez à chaque contexte sa propre base avant de changer
+       *[other] La base { $path } définie par db_path ou DOCKEROPS_DB_PATH est partagée par tous les contextes et surveille { $count } dépôts : retirez-les ou donnez à chaque contexte sa propre base avant de changer
+    }
+
+## Inspection et maintenance
+debug-checking-repository-cache = Debug : vérification du cache des dépôts...
+history-line = { $status } { $time } { $stack_name } ({ $kind }) en { $seconds }s : empreinte { $hash }{ $commit }{ $canary }{ $operator }
+history-from = depuis { $url }
+trash-line = - { $stack } de { $url } (supprimée : { $time }, raison : { $reason }{ $deleted_by }){ $restorable }
+trash-stack-not-found = La stack '{ $stack }' n'est pas dans la corbeille
+trash-stack-ambiguous = La stack '{ $stack }' a été mise à la corbeille depuis plusieurs dépôts, utilisez --repo pour en choisir un
+restore-stack-already-deployed = La stack '{ $stack }' est déjà déployée depuis { $url }
+restore-no-snapshot = Aucun instantané compose n'a été conservé pour la stack '{ $stack }'
+restore-started = Restauration de la stack '{ $stack }' de { $url } (supprimée : { $time })
+restore-directory-gone = ⚠️  La copie du répertoire de la stack a disparu, déploiement depuis le dépôt
+gc-none-recorded = Aucun nettoyage enregistré pour l'instant
+gc-nothing-enabled = Rien à nettoyer : activez gc.dangling_images, gc.stopped_containers ou gc.build_cache dans { $file_path }
+restore-directory-gone-no-secrets = ⚠️  Le répertoire de la stack a disparu du dépôt, déploiement sans secrets
+restore-repository-unwatched-no-secrets = ⚠️  Le dépôt n'est plus surveillé, déploiement sans secrets
+restore-directory-cleanup-failed = Attention : impossible de nettoyer le répertoire de restauration : { $error }
+restore-compose-cleanup-failed = Attention : impossible de nettoyer le fichier compose de restauration : { $error }
+rollback-no-previous-revision = { $reason }, aucune révision précédente vers laquelle revenir
+rollback-after-unhealthy-update = Retour de la stack '{ $stack }' à la révision { $revision } après sa mise à jour défaillante
+rollback-done = { $reason }, retour à la révision { $revision }
+rollback-failed = { $reason }, le retour à la révision { $revision } a échoué aussi : { $error }
+rollback-no-revision = Aucune révision de la stack '{ $stack }' n'a encore été enregistrée
+rollback-unknown-revision = La stack '{ $stack }' n'a pas de révision { $number } (conservées : { $list })
+rollback-no-older-revision = La stack '{ $stack }' n'a pas de révision plus ancienne que celle déployée
+rollback-revision-already-deployed = La révision { $revision } de la stack '{ $stack }' est déjà déployée
+rollback-started = Retour de la stack '{ $stack }' à la révision { $revision } (déployée : { $time })
+export-no-snapshot = Aucun instantané compose n'a été conservé pour la stack '{ $stack }', déployez-la d'abord
+export-images-not-pinned = ⚠️  Aucun service en cours de la stack '{ $stack }' ne rapporte d'empreinte d'image, les images ne sont pas épinglées
+import-bundle-started = Import de la stack '{ $stack }' exportée de { $url } ({ $time }{ $commit })
+import-bundle-volumes = ⚠️  La stack utilise ces volumes, ils doivent exister sur ce cluster :
+failed-to-inspect-services-of-stack = Échec de l'inspection des services de la stack '{ $stack }' : { $error }
+drill-started = Début de l'exercice sur la stack '{ $stack }' de { $url } (délai : { $timeout }s)
+drill-nothing-to-disrupt = La stack '{ $stack }' n'a aucun service en cours, rien à exercer
+drill-stack-unhealthy = La stack '{ $stack }' n'est pas saine avant l'exercice ({ $name } a { $running }/{ $desired } réplicas), corrigez-la d'abord
+drill-stopping-services = Arrêt des services de la stack '{ $stack }'...
+drill-skipping-global-service = Service global { $name } ignoré (ne peut pas être mis à l'échelle)
+drill-scale-down-failed = Échec de la réduction de { $name } : { $error }
+drill-scaled-down = { $name } réduit à 0
+drill-waiting-for-detection = Attente de la détection de la dérive par DockerOps...
+drill-drift-not-detected = Échec de l'exercice : la dérive de la stack '{ $stack }' n'a pas été détectée en { $timeout }s, le démon tourne-t-il ?
+drill-drift-detected = Dérive détectée après { $seconds }s, attente de la réconciliation pour restaurer la stack...
+drill-stack-not-restored = Échec de l'exercice : la stack '{ $stack }' n'a pas été restaurée en { $timeout }s
+drill-restoring-replicas = Restauration des réplicas de la stack '{ $stack }'
+drill-replicas-not-restored = ⚠️  Impossible de restaurer les réplicas de { $name } : { $error }
+drill-report = Rapport d'exercice de la stack '{ $stack }' :
+drill-services-disrupted = Services perturbés :  { $count }
+drill-detection-latency = Latence de détection : { $seconds }s
+drill-recovery-latency = Latence de reprise :  { $recovery }s (réparation en { $repair }s)
+drill-passed = Résultat :            ✅ RÉUSSI (rétablie en moins de { $timeout }s)
+stack-not-managed = La stack '{ $stack }' n'est pas gérée par DockerOps
+stack-ambiguous = La stack '{ $stack }' existe dans plusieurs dépôts ({ $list }), utilisez --repo pour en choisir un
+app-has-no-stacks = Aucune stack n'appartient à l'application '{ $name }'
+status-stack-line = { $desired ->
+        [one] { $stack } (statut : { $status }) : { $health } - { $running }/{ $desired } réplica en cours
+       *[other] { $stack } (statut : { $status }) : { $health } - { $running }/{ $desired } réplicas en cours
+    }
+notification-line = { $attempts ->
+        [one] - { $time } vers { $url } ({ $attempts } tentative, { $state }) : { $text }
+       *[other] - { $time } vers { $url } ({ $attempts } tentatives, { $state }) : { $text }
+    }
+stack-line = { $stack } ({ $url }, statut : { $status }, empreinte : { $hash })
+no-service-running-in-swarm = ⚠️  Aucun service en cours dans Swarm
+state-at = État au { $time }
+deployed-stack-line = { $stack_name } ({ $url }, empreinte : { $hash }{ $commit })
+deployed-line = Déployée le { $time } ({ $kind })
+deployed-images-unknown = ⚠️  Images inconnues, la révision de cette empreinte n'est plus conservée
+deployed-images-not-pinned = ⚠️  Images telles qu'écrites dans le fichier compose, leurs empreintes n'ont pas été enregistrées
+restarting-service = Redémarrage du service '{ $name }'
+failed-to-restart-service = Échec du redémarrage du service '{ $name }' : { $error }
+service-not-running = Le service '{ $service }' ne tourne pas dans la stack '{ $stack }'
+stack-has-no-running-service = La stack '{ $stack }' n'a aucun service en cours dans Swarm
+running-in-container = Exécution dans { $name } sur { $hostname } (conteneur { $container })
+docker-could-not-run = Impossible de lancer docker : { $error }
+failed-to-list-tasks-of-service = Échec de la liste des tâches du service '{ $service }' : { $error }
+service-has-no-task = Le service '{ $service }' n'a aucune tâche destinée à tourner
+replica-not-running = Le réplica { $slot } du service '{ $service }' ne tourne pas
+service-has-no-running-task = Aucune tâche du service '{ $service }' ne tourne
+task-node-endpoint-missing = La tâche tourne sur le nœud '{ $hostname }', déclarez son point d'accès Docker dans node_endpoints de config.yaml (ex. { $hostname }: ssh://root@{ $hostname }) ou créez un contexte docker nommé '{ $hostname }'
+failed-to-list-tasks-of-stack = Échec de la liste des tâches de la stack '{ $stack }' : { $error }
+no-task-in-swarm = ⚠️  Aucune tâche dans Swarm
+image-freshness-check-failed-for = ⚠️  Impossible de vérifier la fraîcheur des images de { $url } : { $error }
+image-digest-check-failed = ⚠️  Impossible de vérifier le digest de { $image } : { $error }
+no-deployed-services-found = Aucun service déployé
+image-report-stack = Stack { $stack_name } ({ $url }) :
+digest-not-checked = Empreinte : non vérifiée
+digest-outdated = ⚠️  Empreinte périmée depuis { $since } (déployée { $deployed }, dernière { $latest })
+digest-up-to-date = ✅ Empreinte à jour
+newer-tags-available = { $newer_tags ->
+        [one] ⚠️  { $newer_tags } tag plus récent disponible (dernier : { $latest_tag })
+       *[other] ⚠️  { $newer_tags } tags plus récents disponibles (dernier : { $latest_tag })
+    }
+
+## Copies de travail git
+fetching-repository = Récupération des mises à jour du dépôt depuis : { $clone_url }
+
+## Copies de travail git
+working-copy-update-failed = ⚠️  Impossible de mettre à jour la copie de travail ({ $error }), nouveau clone
+
+## Copies de travail git
+cloning-repository-branch = Clonage du dépôt depuis : { $clone_url } (branche : { $branch })
+
+## Copies de travail git
+cloning-repository = Clonage du dépôt depuis : { $clone_url }
+
+## Copies de travail git
+shallow-clone = { $depth ->
+        [one] Clone superficiel avec le dernier commit
+       *[other] Clone superficiel avec les { $depth } derniers commits
+    }
+
+## Copies de travail git
+failed-to-clone-repository = Échec du clonage du dépôt : { $error }
+
+## Copies de travail git
+sparse-checkout-done = stacks.yaml, les fichiers racine et les répertoires des stacks déclarées ont été extraits
+updating-submodule-from = Mise à jour du sous-module { $path } depuis { $url }
+failed-to-update-submodule = Échec de la mise à jour du sous-module { $path } : { $error }
+copying-local-working-tree-from = Copie de l'arbre de travail local depuis : { $source }
+local-repository-is-not-a-directory = Le dépôt local { $source } n'est pas un répertoire
+working-copy-not-removed = Attention : impossible de supprimer la copie de travail du dépôt : { $error }
+full-history-needed = l'historique complet est nécessaire et la copie de travail est superficielle
+branch-not-found = Branche '{ $target }' introuvable : { $error }
+working-copy-updated-to = Copie de travail mise à jour vers { $id }
+remote-refs-unavailable = ⚠️  Impossible de lister les refs distantes ({ $error }), récupération
+ref-not-found-on-remote = '{ $target }' introuvable sur le dépôt distant
+auth-github-app = Authentification par jeton d'installation de la GitHub App
+auth-token = Authentification par jeton { $name }
+auth-no-token = Aucun jeton { $name } trouvé. Tentative d'accès au dépôt sans authentification...
+auth-no-token-hint = En cas d'échec, définissez la variable d'environnement { $token_variable }
+
+## Traitement des stacks
+processing-stacks-from-repository = Traitement des stacks du dépôt...
+
+## Traitement des stacks
+stacks-yaml-not-found-in-repository = stacks.yaml introuvable dans le dépôt
+
+## Traitement des stacks
+found-stack-definitions = { $count ->
+        [one] { $count } définition de stack :
+       *[other] { $count } définitions de stack :
+    }
+
+## Traitement des stacks
+stack-not-declared = La stack '{ $only_stack }' n'est pas déclarée dans stacks.yaml
+
+## Traitement des stacks
+deployment-order = Ordre de déploiement : { $list }
+
+## Traitement des stacks
+invalid-prerequisites-for-stack = Prérequis invalides pour la stack '{ $stack }' : { $error }
+
+## Traitement des stacks
+invalid-hooks-for-stack = Hooks invalides pour la stack '{ $stack }' : { $error }
+
+## Traitement des stacks
+stack-teardown-needs-disabled = Stack '{ $stack }' invalide : 'teardown' ne s'applique qu'aux stacks avec 'enabled: false'
+
+## Traitement des stacks
+deploy-parallelism = { $max_parallel ->
+        [one] Déploiement de { $max_parallel } stack à la fois au plus
+       *[other] Déploiement de { $max_parallel } stacks à la fois au plus
+    }
+
+## Traitement des stacks
+changed-only-nothing-changed = Aucune stack n'a changé, volumes et images laissés tels quels (--changed-only)
+
+## Traitement des stacks
+processing-images = Traitement des images...
+
+## Traitement des stacks
+image-freshness-check-failed = ⚠️  Impossible de vérifier la fraîcheur des images : { $error }
+skipping-stack-filtered = Stack { $stack } ignorée (hors de --only / --exclude)
+skipping-stack-disabled = Stack { $stack } ignorée (désactivée dans stacks.yaml)
+skipping-stack-unchanged = Stack { $stack } ignorée (inchangée, --changed-only)
+processing-stack = Traitement de la stack : { $stack }
+stack-directory-not-found = Attention : répertoire de stack '{ $directory }' introuvable
+stack-compose-file-not-found = Attention : aucun fichier docker-compose dans le répertoire de stack '{ $directory }'
+render-cache-hit = Entrées inchangées depuis le dernier rendu, utilisation du fichier compose en cache
+output-not-published = La stack '{ $stack }' référence la sortie '{ $output }' de la stack '{ $stack2 }', qui n'a pas été publiée
+compose-deprecated-syntax = ⚠️  { $directory }/{ $file_name } utilise une syntaxe compose obsolète :
+processing-compose-volumes = Traitement des volumes du fichier docker-compose...
+volume-processing-completed = Traitement des volumes terminé
+enforcing-encrypted-overlay-networks = Application du chiffrement des réseaux overlay...
+processing-secrets = Traitement des secrets...
+secret-processing-completed = Traitement des secrets terminé
+compose-file-updated = Fichier docker-compose mis à jour avec les volumes traités dans { $compose_path }
+plan-stack-render-changed = La stack '{ $stack }' donne { $compose_hash } au lieu de { $planned } prévu, ses entrées ont changé depuis le plan (values, sorties, refs...), relancez plan
+stack-failures-not-retrying = ⚠️  La stack '{ $stack }' a échoué { $failures } fois de suite, pas de nouvel essai tant que son fichier compose ne change pas (utilisez --force pour réessayer)
+stack-rolled-back-not-redeploying = ⏸️  La stack '{ $stack }' est revenue en arrière depuis ce fichier compose, pas de redéploiement tant qu'il ne change pas (utilisez --force pour redéployer)
+stack-changed = La stack '{ $stack }' a changé (empreinte : { $hash } -> { $compose_hash })
+stack-unchanged-forced = Stack '{ $stack }' inchangée mais mode forcé activé, redéploiement
+stack-retrying = Le dernier déploiement de la stack '{ $stack }' a échoué, nouvel essai
+stack-modified-in-swarm = Stack '{ $stack }' inchangée dans git mais modifiée dans Swarm, redéploiement
+stack-unchanged = Stack '{ $stack }' inchangée
+stopping-existing-stack = Arrêt de la stack existante '{ $stack }'
+deploying-updated-stack = Déploiement de la stack mise à jour '{ $stack }'
+stack-owned-by-other-repository = ❌ La stack '{ $stack }' est déjà déployée depuis { $url }, ignorée
+stack-adopting-running = Adoption de la stack '{ $stack }' en cours dans Swarm (empreinte : { $compose_hash })
+stack-new = Nouvelle stack '{ $stack }' trouvée, déploiement
+stack-has-no-service = ⚠️  La stack '{ $stack }' n'a aucun service dans Swarm
+changed-outside-dockerops = ⚠️  Modifié hors de DockerOps : { $list }
+conflict-deploying-resolution = Déploiement avec la résolution '{ $resolution }' des changements manuels
+conflict-inspect-failed = ⚠️  Impossible d'inspecter la stack '{ $stack }' pour les changements manuels : { $error }
+conflict-detected = ⚠️  La stack '{ $stack }' a été modifiée à la main et git la modifie aussi :
+override-line = { $service } { $field } : déployé { $deployed }, en cours { $live }, git { $git }
+conflict-resolve-hint = Pas de déploiement avant `dockerops resolve { $stack } --keep-override|--take-git|--merge`
+stack-drifted = ⚠️  La stack '{ $stack }' ({ $url }) a dérivé :
+drift-no-snapshot = ❌ Aucun instantané compose n'a été conservé pour la stack '{ $stack }', lancez plutôt 'deploy { $stack }'
+re-applying-stack = Réapplication de la stack '{ $stack }'
+drift-reapply-failures = { $failed ->
+        [one] { $failed } stack dérivée n'a pas pu être réappliquée
+       *[other] { $failed } stacks dérivées n'ont pas pu être réappliquées
+    }
+failed-to-list-stacks = Échec de la liste des stacks : { $error }
+conflict-waiting-line = ⚠️  { $stack_name } ({ $url }) - en attente depuis { $time }
+conflict-line = { $stack_name } ({ $url }) - résolu avec { $resolution }{ $operator }
+conflict-override-line = [{ $marker }] { $service } { $field } : déployé { $deployed }, en cours { $live }, git { $git }
+conflicts-resolve-hint = Résolvez avec : dockerops resolve <stack> --keep-override | --take-git | --merge
+conflict-none-for-stack = La stack '{ $stack }' n'a aucun conflit avec des changements manuels
+conflict-keeping-value = { $service } { $field } = { $live } conservé
+traffic-delay-expired = La mise à jour de la stack '{ $stack }' a été retardée de { $waited }s, déploiement quelle que soit la charge
+traffic-load-unavailable = ⚠️  Impossible d'interroger la charge de la stack '{ $stack }' ({ $error }), déploiement
+traffic-update-delayed = ⏸️  La stack '{ $stack }' est occupée (charge { $load } > { $threshold }), sa mise à jour attend une période plus calme
+env-example-keys-missing = Des clés listées dans .env.example ne sont fournies ni par values.yaml, ni par secrets.yaml, ni par l'environnement :
+    { $details }
+dry-run-message = [simulation] { $message }
+dry-run-would-pull = [simulation] Téléchargerait : { $list }
+dry-run-image-kept-referenced = [simulation] L'image { $image } serait conservée, un autre dépôt la référence encore
+dry-run-image-kept-in-use = [simulation] L'image { $image } serait conservée, { $users } l'utilise encore
+dry-run-would-remove-unused-image = [simulation] Supprimerait l'image inutilisée : { $image }
+prune-stack-undeclared-line = { $status ->
+        [restored] ⚠️  La stack restaurée '{ $stack }' manque toujours dans stacks.yaml
+       *[other] ⚠️  La stack importée '{ $stack }' manque toujours dans stacks.yaml
+    }
+prune-stack-kept = ⏸️  La stack '{ $stack }' n'est plus déclarée dans stacks.yaml, elle est conservée (supprimez-la avec 'rm', --prune ou prune_stacks)
+dry-run-would-trash-stack = [simulation] Supprimerait la stack '{ $stack }', { $reason }, et la mettrait à la corbeille
+stack-trashed = La stack '{ $stack }' a été { $reason }, mise à la corbeille
+
+## Résultats de déploiement et notifications
+canary-started = Analyse canary de la stack '{ $stack }' pendant { $duration }s
+
+## Résultats de déploiement et notifications
+canary-passed = ✅ Analyse canary de la stack '{ $stack }' réussie
+
+## Résultats de déploiement et notifications
+canary-failed = ❌ Analyse canary de la stack '{ $stack }' échouée, retour en arrière
+
+## Résultats de déploiement et notifications
+canary-service-rolled-back = { $service } revenu en arrière
+
+## Résultats de déploiement et notifications
+canary-rollback-failed = ⚠️  Impossible de revenir en arrière sur { $service } : { $error }
+
+## Résultats de déploiement et notifications
+canary-failure-reason = Analyse canary échouée, la mise à jour a été annulée :
+    { $summary }
+issue-close-failed = ⚠️  Impossible de fermer l'issue #{ $number } : { $error }
+stack-failing = ❌ La stack '{ $stack }' a échoué { $failures } fois de suite
+issue-report-failed = ⚠️  Impossible de signaler l'échec sur GitHub : { $error }
+notification-queue-failed = ⚠️  Impossible de mettre en file la notification pour { $url } : { $error }
+outbox-unreadable = ⚠️  Impossible de lire la file des notifications : { $error }
+notification-given-up = ❌ Abandon de la notification vers { $url } après { $attempts } tentatives : { $error }
+notification-retrying = ⚠️  Impossible de notifier { $url } (tentative { $attempts }), nouvel essai le { $next_attempt } : { $error }
+outbox-update-failed = ⚠️  Impossible de mettre à jour la file des notifications : { $error }
+outbox-purge-failed = ⚠️  Impossible de purger la file des notifications : { $error }
+not-a-github-repository = { $url } n'est pas un dépôt GitHub
+issue-opened = Issue #{ $number } ouverte dans { $owner }/{ $repo }
+issue-updated = Issue #{ $number } mise à jour dans { $owner }/{ $repo }
+issue-closed-log = Issue #{ $number } fermée dans { $owner }/{ $repo }
+
+## Nettoyage
+gc-failed = ⚠️  Échec du nettoyage : { $error }
+gc-skipped = Nettoyage ignoré : { $reclaimable } récupérables, sous gc.min_reclaimable ({ $threshold })
+gc-started = Nettoyage des ressources de plus de { $older_than }s sur le manager...
+gc-dangling-images-failed = ⚠️  Impossible de supprimer les images orphelines : { $error }
+gc-stopped-containers-failed = ⚠️  Impossible de supprimer les conteneurs arrêtés : { $error }
+gc-build-cache-failed = ⚠️  Impossible de vider le cache de build : { $error }
+docker-image-ls-failed = Échec de docker image ls : { $error }
+gc-pruning-dangling-images = Suppression des images orphelines...
+docker-container-ls-failed = Échec de docker container ls : { $error }
+gc-removing-containers = Suppression des conteneurs arrêtés des stacks gérées...
+gc-containers-not-removed = ⚠️  Certains conteneurs n'ont pas pu être supprimés : { $error }
+docker-system-df-failed = Échec de docker system df : { $error }
+gc-pruning-build-cache = Vidage du cache de build...
+task-history-limit-failed = ⚠️  Impossible de définir la limite d'historique des tâches Swarm : { $error }
+task-prune-failed = ⚠️  Impossible de supprimer les tâches arrêtées : { $error }
+task-prune-stack-failed = ⚠️  Impossible de supprimer les tâches arrêtées de la stack '{ $stack }' : { $error }
+task-prune-nodes-unreachable = ⚠️  Tâches arrêtées laissées sur { $list }, déclarez leurs points d'accès Docker dans node_endpoints de config.yaml
+task-history-limit-set = Limite d'historique des tâches Swarm fixée à { $limit } (au lieu de { $current })
+tasks-pruned = { $removed ->
+        [one] { $removed } tâche arrêtée de la stack '{ $stack }' supprimée
+       *[other] { $removed } tâches arrêtées de la stack '{ $stack }' supprimées
+    }
+trash-purged = { $purged ->
+        [one] { $purged } stack expirée purgée de la corbeille
+       *[other] { $purged } stacks expirées purgées de la corbeille
+    }
+
+## Variables et registres
+github-variables-failed = Impossible de récupérer les variables GitHub de { $owner }/{ $repo } : { $error }
+
+## Variables et registres
+github-variables-loaded = { $count ->
+        [one] { $count } variable GitHub Actions chargée
+       *[other] { $count } variables GitHub Actions chargées
+    }
+
+## Variables et registres
+github-variables-not-github = ⚠️  Les variables GitHub sont activées mais { $url } n'est pas un dépôt GitHub
+invalid-registries-file = Fichier de registres invalide { $path } : { $error }
+registries-sops-failed = registries.yaml est chiffré avec SOPS mais sops n'a pas pu être lancé : { $error }
+registries-decrypt-failed = Impossible de déchiffrer registries.yaml : { $error }
+registries-not-encrypted = ⚠️  registries.yaml n'est pas chiffré, les identifiants sont stockés en clair dans le dépôt
+registries-loaded = { $count ->
+        [one] Identifiants chargés pour { $count } registre
+       *[other] Identifiants chargés pour { $count } registres
+    }
+
+## Déploiement des stacks
+yaml-parse-failed = Attention : impossible d'analyser le fichier YAML { $file_path } : { $error }
+
+## Déploiement des stacks
+images-found-in-file = { $count ->
+        [one] { $count } image dans { $file_path } : { $images_found }
+       *[other] { $count } images dans { $file_path } : { $images_found }
+    }
+stack-deploying = Déploiement de la stack '{ $stack }' avec docker stack deploy
+self-update-warning = ⚠️  C'est la stack qui fait tourner DockerOps, le service redémarre une fois la mise à jour appliquée
+extracting-images-from-compose-file = Extraction des images du fichier compose...
+images-pulling = { $count ->
+        [one] { $count } image, téléchargement avant le déploiement : { $images_found }
+       *[other] { $count } images, téléchargement avant le déploiement : { $images_found }
+    }
+all-images-pulled-successfully = Toutes les images ont été téléchargées
+no-images-found-in-compose-file = Aucune image dans le fichier compose
+secret-env-added = Variable d'environnement ajoutée : { $env_name } (secret)
+successfully-deployed-stack = Stack '{ $stack }' déployée
+error-deploying-stack = Erreur lors du déploiement de la stack '{ $stack }' : { $error }
+deploy-failed = Échec du déploiement de la stack : { $error }
+hook-running = Exécution du hook { $phase } { $index } de '{ $stack }' : { $describe }
+hook-failed = Le hook { $phase } { $index } de la stack '{ $stack }' ({ $describe }) a échoué : { $error }
+hooks-succeeded = ✅ Hooks { $phase } de '{ $stack }' réussis
+timed-out = délai dépassé après { $timeout }s
+job-start-failed = impossible de démarrer le job : { $error }
+prerequisites-checking = Vérification des prérequis d'hôte de '{ $stack }'
+failed-to-list-the-swarm-nodes = Échec de la liste des nœuds Swarm : { $error }
+failed-to-inspect-the-swarm-nodes = Échec de l'inspection des nœuds Swarm : { $error }
+prerequisites-not-met = { $failed ->
+        [one] Les prérequis d'hôte de la stack '{ $stack }' ne sont pas remplis sur { $failed } nœud sur { $nodes } :
+       *[other] Les prérequis d'hôte de la stack '{ $stack }' ne sont pas remplis sur { $failed } nœuds sur { $nodes } :
+    }
+    {"  "}{ $details }
+prerequisites-met = { $count ->
+        [one] ✅ Prérequis d'hôte remplis sur { $count } nœud
+       *[other] ✅ Prérequis d'hôte remplis sur { $count } nœuds
+    }
+prerequisites-probe-failed = Échec du démarrage de la sonde des prérequis : { $error }
+convergence-waiting = Attente de la convergence des services de '{ $stack }' pendant { $timeout }s au plus
+convergence-done = ✅ Tous les services de '{ $stack }' ont convergé
+convergence-failed = Aucun réplica de la stack '{ $stack }' ne tourne après { $timeout }s : { $list }
+convergence-degraded = ⚠️  La stack '{ $stack }' est dégradée après { $timeout }s : { $list }
+dependencies-not-ready = ⏸️  Stack '{ $stack }' non déployée, ses dépendances ne sont pas prêtes : { $list }
+dependencies-waiting = Attente de la convergence des dépendances de '{ $stack }' : { $list }
+registry-auth-disabled = ⚠️  with_registry_auth est désactivé, les nœuds workers peuvent échouer à télécharger les images privées de registries.yaml
+registry-auth-docker-cli = Envoi des identifiants de registre du CLI docker aux nœuds
+registry-auth-sending = Envoi des identifiants de registre aux nœuds ({ $list })
+updating-service = Mise à jour du service '{ $stack }_{ $name }'{ $image }{ $replicas }
+failed-to-update-service = Échec de la mise à jour du service '{ $stack }_{ $name }' : { $error }
+services-updated = { $count ->
+        [one] { $count } service de la stack '{ $stack }' mis à jour
+       *[other] { $count } services de la stack '{ $stack }' mis à jour
+    }
+trash-copy-failed = Attention : impossible de copier le répertoire de la stack '{ $stack }' dans la corbeille : { $error }
+stack-stopping = Arrêt de la stack '{ $stack }' avec docker stack rm
+successfully-stopped-stack = Stack '{ $stack }' arrêtée
+stack-stop-failed = Attention : erreur lors de l'arrêt de la stack '{ $stack }' : { $error }
+
+## Images
+images-used-by-repository = { $count ->
+        [one] { $count } image utilisée par le dépôt
+       *[other] { $count } images utilisées par le dépôt
+    }
+
+## Images
+images-checking-changed = Vérification de { $count } d'entre elles, les autres appartiennent à des stacks inchangées
+
+## Images
+processing-image = Traitement de l'image : { $image }
+image-kept-referenced = L'image { $image } est encore référencée par un autre dépôt, conservée
+image-kept-in-use = ⏸️  L'image { $image } n'est plus utilisée par DockerOps mais { $users } l'utilise encore, conservée
+removing-unused-image = Suppression de l'image inutilisée : { $image }
+image-users-unknown-containers = ⚠️  Impossible de lister les conteneurs, toutes les images sont conservées : { $error }
+image-users-unknown-services = ⚠️  Impossible d'inspecter les services, toutes les images sont conservées
+image-digest-mismatch = SHA différent pour { $image } : local={ $local }, distant={ $remote }
+image-replacing = Suppression de l'ancienne image et téléchargement de la nouvelle version
+image-is-up-to-date = L'image { $image } est à jour
+image-missing-locally = Image { $image } absente localement, téléchargement
+image-remote-digest-unknown = Impossible d'obtenir le SHA distant de { $image }
+removing-image-indented = Suppression de l'image : { $image }
+successfully-removed-image = Image supprimée : { $image }
+image-remove-failed = Attention : erreur lors de la suppression de l'image { $image } : { $error }
+provenance-unverified = La provenance de { $image } n'a pas pu être vérifiée : { $error }
+provenance-verified = ✅ Provenance vérifiée : construite par { $workflow } depuis { $source }
+pulling-image = Téléchargement de l'image : { $image }
+successfully-pulled-image = Image téléchargée : { $image }
+error-pulling-image = Erreur lors du téléchargement de l'image { $image } : { $error }
+pull-failed = Échec du téléchargement de l'image : { $error }
+pull-trying-mirror = ⚠️  Échec du téléchargement depuis { $registry_name }, essai du miroir { $mirror } : { $error }
+mirror-tag-failed = Impossible de taguer { $mirrored } en { $image } : { $error }
+pulled-from-mirror = { $image } téléchargée depuis le miroir { $mirror }
+bandwidth-limit-ignored = ⚠️  Limite de bande passante ignorée : le démon Docker est distant et ne peut pas joindre le proxy de téléchargement local
+bandwidth-limited-fetch = Récupération des couches de { $image } à { $limit } Ko/s au plus
+bandwidth-limited-pull-failed = ⚠️  Échec du téléchargement limité, téléchargement direct : { $error }
+
+## Secrets et volumes
+checking-for-secrets-yaml-file = Recherche du fichier secrets.yaml...
+
+## Secrets et volumes
+secrets-file-missing = Aucun fichier secrets.yaml, traitement des secrets ignoré
+
+## Secrets et volumes
+secrets-file-found = Fichier secrets.yaml trouvé, lecture des secrets...
+
+## Secrets et volumes
+found-secret-definitions = { $count ->
+        [one] { $count } définition de secret
+       *[other] { $count } définitions de secrets
+    }
+
+## Secrets et volumes
+using-secrets-path = Chemin des secrets : { $secrets_base_path }
+
+## Secrets et volumes
+processing-secret = Traitement du secret : { $id } -> { $env }
+
+## Secrets et volumes
+secret-file-not-found = Fichier de secret introuvable : { $secret_path }
+
+## Secrets et volumes
+secret-value-loaded-from = Valeur du secret chargée depuis : { $secret_path }
+
+## Secrets et volumes
+successfully-loaded-secrets = { $count ->
+        [one] { $count } secret chargé
+       *[other] { $count } secrets chargés
+    }
+nfs-yaml-not-found-at = nfs.yaml introuvable à : { $nfs_file_path }
+reading-nfs-yaml-from = Lecture de nfs.yaml depuis : { $nfs_file_path }
+nfs-config = Configuration NFS : { $config }
+looking-for-volumes-yaml-in = Recherche de volumes.yaml dans : { $repo_path }
+volumes-file-missing = Pas de volumes.yaml à { $volumes_file_path }, traitement des volumes ignoré
+found-volumes-yaml-at = volumes.yaml trouvé à : { $volumes_file_path }
+volumes-file-read = { $count ->
+        [one] Contenu de volumes.yaml lu ({ $count } caractère)
+       *[other] Contenu de volumes.yaml lu ({ $count } caractères)
+    }
+volumes-file-parsed = { $count ->
+        [one] { $count } définition de volume lue dans volumes.yaml
+       *[other] { $count } définitions de volume lues dans volumes.yaml
+    }
+found-nfs-yaml-at = nfs.yaml trouvé à : { $nfs_file_path }
+nfs-file-missing = Pas de nfs.yaml à { $nfs_file_path }, les bindings NFS seront ignorés
+processing-volume-definitions = { $count ->
+        [one] Traitement de { $count } définition de volume
+       *[other] Traitement de { $count } définitions de volume
+    }
+processing-volume-definition = Traitement de la définition de volume : { $volume_def }
+processing-volume = Traitement du volume : { $id } (type : volume, chemin : { $path })
+processing-binding = Traitement du binding : { $id } (type : binding, chemin : { $path })
+binding-skipped-no-nfs = Attention : aucune configuration NFS, volume binding ignoré
+finished-processing-all-volume-definitions = Traitement de toutes les définitions de volume terminé
+binding-source-missing = Attention : le chemin local n'existe pas : { $local_path }
+copying-to-nfs = Copie de { $local_path } vers NFS : { $nfs_dest_path }
+removing-existing-directory-on-nfs = Suppression du répertoire existant sur NFS : { $nfs_dest_path }
+removing-existing-file-on-nfs = Suppression du fichier existant sur NFS : { $nfs_dest_path }
+successfully-copied-to-nfs = Copié vers NFS : { $nfs_dest_path }
+source-is-not-a-directory = La source n'est pas un répertoire : { $src }
+fixing-permissions-for-docker-compatibility = Correction des permissions pour Docker...
+permissions-directories-set = Permissions des répertoires fixées à 755
+permissions-directories-failed = Attention : échec de la modification des permissions des répertoires : { $error }
+permissions-files-set = Permissions des fichiers fixées à 644
+permissions-files-failed = Attention : échec de la modification des permissions des fichiers : { $error }
+ownership-changed = Propriétaire changé pour { $current_user }
+ownership-change-failed = Attention : échec du changement de propriétaire : { $error }
+compose-not-a-mapping = Le contenu docker-compose n'est pas un mapping
+compose-networks-not-a-mapping = La section 'networks' de la stack '{ $stack }' n'est pas un mapping
+network-encryption-violation = ⚠️  Violation de politique : le réseau externe '{ $external_name }' utilisé par la stack '{ $stack }' n'est pas chiffré
+enabled-encryption-on-network = Chiffrement activé sur le réseau '{ $name }'
+external-network-does-not-exist = ⚠️  Le réseau externe '{ $network_name }' n'existe pas
+parsing-docker-compose-content = Analyse du contenu docker-compose...
+successfully-parsed-yaml-content = Contenu YAML analysé
+processing-services = { $count ->
+        [one] Section services trouvée, traitement de { $count } service
+       *[other] Section services trouvée, traitement de { $count } services
+    }
+processing-service = Traitement du service : { $service_name_str }
+found-volumes-section-in-service = Section volumes trouvée dans le service { $service_name_str }
+no-volumes-section-found-in-service = Pas de section volumes dans le service { $service_name_str }
+compose-has-no-services = Pas de section services dans docker-compose
+converting-modified-yaml-back-to-string = Conversion du YAML modifié en texte...
+compose-serialized = { $count ->
+        [one] YAML converti en texte ({ $count } caractère)
+       *[other] YAML converti en texte ({ $count } caractères)
+    }
+processing-service-volumes = Traitement des volumes du service...
+found-volume-entries = { $count ->
+        [one] { $count } entrée de volume
+       *[other] { $count } entrées de volume
+    }
+processing-volume-entry = Traitement de l'entrée de volume { $index } : { $volume }
+volume-string = Chaîne de volume : '{ $volume_str }'
+split-into-parts = { $count ->
+        [one] Découpée en { $count } partie : { $parts }
+       *[other] Découpée en { $count } parties : { $parts }
+    }
+volume-string-parts = ID de volume : '{ $volume_id }', chemin dans le conteneur : '{ $container_path }', options : '{ $options }'
+found-volume-definition = Définition de volume trouvée : { $volume_def }
+replacing-volume = Remplacement du volume Docker { $volume_id } par : { $volume_path }
+nfs-config-path = Chemin de la configuration NFS : { $path }
+volume-path = Chemin du volume : { $path }
+full-nfs-path = Chemin NFS complet : { $full_nfs_path }
+dry-run-would-create-nfs-directory = [simulation] Créerait le répertoire NFS : { $full_nfs_path }
+creating-nfs-directory = Création du répertoire NFS : { $full_nfs_path }
+successfully-created-nfs-directory = Répertoire NFS créé
+nfs-directory-already-exists = Le répertoire NFS existe déjà : { $full_nfs_path }
+replacing-binding = Remplacement du volume binding { $volume_id } par le chemin NFS : { $nfs_path }
+volume-definition-missing = Attention : définition de volume introuvable pour l'ID : '{ $volume_id }'
+available-volume-definitions = Définitions de volume disponibles : { $ids }
+volume-string-invalid-parts = La chaîne de volume n'a pas 2 ou 3 parties, ignorée
+volume-string-without-colon = La chaîne de volume ne contient pas ':', ignorée
+volume-entry-is-not-a-string = L'entrée de volume n'est pas une chaîne, ignorée
+volume-format-is-not-a-sequence = Le format de volume n'est pas une séquence, ignoré
+finished-processing-service-volumes = Traitement des volumes du service terminé
+adding-volumes-section = Ajout de la section volumes à docker-compose...
+created-new-volumes-section = Nouvelle section volumes créée
+adding-volume-to-volumes-section = Ajout du volume '{ $id }' à la section volumes
+volumes-section-skipping-binding = Binding '{ $id }' ignoré dans la section volumes (traité dans les volumes des services)
+volumes-section-updated = Section volumes mise à jour
+
+## Structure des dépôts et utilitaires
+trash-copy-not-removed = Attention : impossible de supprimer la copie en corbeille de la stack '{ $stack }' : { $error }
+invalid-tag-pattern = Motif de tag invalide '{ $pattern }' : { $error }
+no-tag-matches = Aucun tag ne correspond à '{ $pattern }'
+newest-tag-matching = Tag le plus récent correspondant à '{ $pattern }' : { $tag }
+revision-not-found = Révision '{ $revision }' introuvable dans le dépôt (supprimée par un push forcé ?) : { $error }
+checked-out-commit = Commit { $id } extrait
+directory-not-found-in-commit = Répertoire '{ $prefix }' introuvable dans le commit { $id }
+invalid-stacks-yaml-in-commit = stacks.yaml invalide dans le commit { $id } : { $error }
+path-outside-repository = --path doit être un répertoire du dépôt (reçu '{ $path }')
+directory-not-found-in-repository = Répertoire '{ $path }' introuvable dans le dépôt
+failed-to-list-docker-contexts = Échec de la liste des contextes docker : { $error }
+invalid-values-file = Fichier de valeurs invalide { $path } : { $error }
+invalid-value-type = La valeur '{ $key }' de { $path } doit être une chaîne, un nombre ou un booléen
+dependency-not-declared = La stack '{ $stack }' dépend de '{ $dependency }', qui n'est pas déclarée dans stacks.yaml
+stack-depends-on-itself = La stack '{ $stack }' dépend d'elle-même
+dependency-cycle = Les dépendances et les références de sorties forment un cycle entre les stacks : { $list }
+gc-reclaimable = Récupérable : { $total } (le cache de build sans tenir compte de gc.older_than)
+gc-reclaimed = ✅ { $total } récupérés
+setup-aborted = Configuration interrompue, plus d'entrée
+setup-answer-y-or-n = Répondez par y ou n
@@ -0,0 +1,145 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::atomic;
+
+const FORMAT: u32 = 1;
+const MANIFEST: &str = "manifest.yaml";
+const COMPOSE: &str = "docker-compose.yml";
+const VOLUMES: &str = "volumes.yaml";
+
+/// Description of an exported stack, `manifest.yaml` of the archive
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StackManifest {
+    pub format: u32,
+    pub name: String,
+    pub repository_url: String,
+    /// Compose hash of the deployment, as in the stacks table
+    pub hash: String,
+    /// Commit the compose file was deployed from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    pub exported_at: String,
+    pub dockerops_version: String,
+    /// SHA-256 of docker-compose.yml, checked on import
+    pub compose_sha256: String,
+    /// Digests the services were running when exported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<PinnedImage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PinnedImage {
+    pub service: String,
+    pub image: String,
+    pub digest: String,
+}
+
+/// Content of a stack archive
+pub struct StackArchive {
+    pub manifest: StackManifest,
+    /// Rendered compose file, as deployed
+    pub compose: String,
+    /// Entries of volumes.yaml used by the compose file
+    pub volumes: Option<String>,
+}
+
+impl StackArchive {
+    pub fn new(mut manifest: StackManifest, compose: String, volumes: Option<String>) -> Self {
+        manifest.format = FORMAT;
+        manifest.compose_sha256 = sha256(&compose);
+        Self { manifest, compose, volumes }
+    }
+
+    /// Write the archive as a gzipped tarball, atomically
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let dir = ScratchDir::new("export")?;
+        fs::write(dir.path.join(MANIFEST), serde_yaml::to_string(&self.manifest)?)?;
+        fs::write(dir.path.join(COMPOSE), &self.compose)?;
+        let mut members = vec![MANIFEST, COMPOSE];
+        if let Some(volumes) = &self.volumes {
+            fs::write(dir.path.join(VOLUMES), volumes)?;
+            members.push(VOLUMES);
+        }
+
+        let partial = dir.path.join("archive.tar.gz");
+        tar(Command::new("tar").arg("-czf").arg(&partial).arg("-C").arg(&dir.path).args(&members))?;
+        // The archive is built on the scratch filesystem, copied next to the target then renamed
+        let staged = path.with_file_name(format!(".{}.partial", path.file_name().unwrap_or_default().to_string_lossy()));
+        fs::copy(&partial, &staged)?;
+        atomic::persist(&staged, path)?;
+        Ok(())
+    }
+
+    /// Read and check an archive written by `write`
+    pub fn read(path: &Path) -> Result<Self> {
+        let listing = tar(Command::new("tar").arg("-tzf").arg(path))?;
+        let members: Vec<&str> = listing.lines().map(|line| line.trim_start_matches("./")).collect();
+        for required in [MANIFEST, COMPOSE] {
+            if !members.contains(&required) {
+                return Err(anyhow::anyhow!("{} is not a stack archive ({} is missing)", path.display(), required));
+            }
+        }
+
+        // Only the known members are extracted, whatever else the archive holds
+        let dir = ScratchDir::new("import")?;
+        let mut wanted = vec![MANIFEST, COMPOSE];
+        if members.contains(&VOLUMES) {
+            wanted.push(VOLUMES);
+        }
+        tar(Command::new("tar").arg("-xzf").arg(path).arg("-C").arg(&dir.path).args(&wanted))?;
+
+        let manifest: StackManifest = serde_yaml::from_str(&fs::read_to_string(dir.path.join(MANIFEST))?)
+            .map_err(|e| anyhow::anyhow!("Invalid manifest in {}: {}", path.display(), e))?;
+        if manifest.format != FORMAT {
+            return Err(anyhow::anyhow!("{} uses archive format {}, this version of DockerOps reads format {}",
+                path.display(), manifest.format, FORMAT));
+        }
+        let compose = fs::read_to_string(dir.path.join(COMPOSE))?;
+        if sha256(&compose) != manifest.compose_sha256 {
+            return Err(anyhow::anyhow!("The compose file of {} does not match its manifest, the archive is corrupted", path.display()));
+        }
+        let volumes = fs::read_to_string(dir.path.join(VOLUMES)).ok();
+        Ok(Self { manifest, compose, volumes })
+    }
+}
+
+fn sha256(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+fn tar(command: &mut Command) -> Result<String> {
+    let output = command.output().map_err(|e| anyhow::anyhow!("could not run tar: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("tar failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Temporary directory removed when dropped
+struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    fn new(purpose: &str) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("dockerops-{}-{}-{}", purpose, std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()));
+        fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
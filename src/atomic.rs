@@ -0,0 +1,43 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Replace a file's content so that readers, and the next run after a crash
+/// or a power loss, see either the old content or the new one, never a
+/// truncated file: the content is written and synced to a temporary file
+/// next to the target, which is then renamed over it.
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref();
+    let temporary = temporary_path(path);
+    let result = (|| {
+        let mut file = fs::File::create(&temporary)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        drop(file);
+        persist(&temporary, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    result
+}
+
+/// Move a complete file over `target`, syncing it first and the directory
+/// after so the rename itself survives a power loss
+pub fn persist(source: &Path, target: &Path) -> io::Result<()> {
+    fs::File::open(source)?.sync_all()?;
+    fs::rename(source, target)?;
+    #[cfg(unix)]
+    if let Some(dir) = target.parent() {
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Hidden file in the target's directory, renames across filesystems are not atomic
+fn temporary_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!(".{}.{}-{}.tmp", name, std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()))
+}
@@ -1,1082 +1,7721 @@
 use anyhow::Result;
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncBufReadExt;
 use serde_yaml::Value;
+use futures_util::StreamExt;
 
+use crate::archive::{PinnedImage, StackArchive, StackManifest};
+use crate::atomic;
+use crate::compose;
+use crate::config::{CanarySettings, Config, DeploySettings, EffectiveSetting, GcSettings, RepositorySettings};
 use crate::database::Database;
-use crate::models::{Image, Stack, StackDefinition, VolumeDefinition, VolumeType, NfsConfig, SecretDefinition};
+use crate::github::{self, GithubApi, GithubApp, GithubClient};
+use crate::hooks::Hook;
+use crate::i18n;
+use crate::notify;
+use crate::prerequisites::{self, HostPrerequisites};
+use crate::provider::{self, Provider};
+use crate::plan::{ChangeAction, Plan, RepositoryPlan, StackChange};
+use crate::prometheus;
+use crate::schema;
+use crate::provenance;
+use crate::signing;
+use crate::pull_proxy::PullProxy;
+use crate::registry::{self, ImageReference, RegistryClient, RegistryCredentials, RegistryLogin};
+use crate::models::{Deployment, ImageFreshness, RepositoryCache, ServiceOverride, Stack, StackDefinition, TrashedStack, VolumeDefinition, VolumeType, NfsConfig, SecretDefinition, WatchEntry, WebhookDelivery};
 
 pub struct Commands {
     db: Database,
+    config: Config,
+    github: Arc<GithubApi>,
+    github_app: Option<GithubApp>,
+    /// Pulls running per registry, limited by `max_concurrent_pulls`
+    pull_slots: std::sync::Mutex<BTreeMap<String, Arc<tokio::sync::Semaphore>>>,
+    /// Print what syncs would change instead of changing Docker, the NFS share or the database
+    dry_run: bool,
+    /// Redeploy the stacks of `watch` even when their compose hash is unchanged
+    force: bool,
+    /// Leave out of the syncs the stacks rendering to their deployed compose
+    /// file, with their volumes and images
+    changed_only: bool,
+    /// Remove the stacks no longer declared in stacks.yaml whatever `prune_stacks`
+    prune: bool,
+    /// Changes found by the dry run, by repository, saved by `plan`
+    planned: std::sync::Mutex<BTreeMap<String, RepositoryPlan>>,
+}
+
+/// Options controlling a run of `process_and_deploy_stacks`
+#[derive(Debug, Default, Clone)]
+struct DeployOptions {
+    /// Stop existing stacks before redeploying them
+    is_reconcile: bool,
+    /// Redeploy stacks even when their hash is unchanged
+    force: bool,
+    /// Restrict the run to one stack, leaving image bookkeeping untouched
+    only_stack: Option<String>,
+    /// `--only` / `--exclude` globs, stacks outside of it are left untouched
+    filter: StackFilter,
+    /// Daemon or webhook run, updates may wait for a quiet period
+    is_automatic: bool,
+    /// Record new stacks as deployed without deploying them, they already run in Swarm
+    adopt: bool,
+    /// Who the deployments are recorded for instead of the operator of the
+    /// process, the sender of a webhook
+    operator: Option<String>,
+    /// Compose hashes of the stacks `apply` deploys, a stack rendering to
+    /// another one fails instead of deploying what was not reviewed
+    planned_hashes: BTreeMap<String, String>,
+    /// Namespace of a repository deployed before it is recorded, by `watch`
+    namespace: Option<String>,
+}
+
+impl DeployOptions {
+    /// Whether the run covers every stack of the repository
+    fn is_full_run(&self) -> bool {
+        self.only_stack.is_none() && self.filter.is_empty()
+    }
+}
+
+/// What the stacks of one run of `process_and_deploy_stacks` share
+#[derive(Clone, Copy)]
+struct StackRun<'a> {
+    repo_path: &'a str,
+    repository_url: &'a str,
+    options: &'a DeployOptions,
+    settings: &'a RepositorySettings,
+    volumes_definitions: Option<&'a [VolumeDefinition]>,
+    repository_values: &'a BTreeMap<String, String>,
+    credentials: &'a RegistryCredentials,
+    /// Fingerprint of the files volumes.yaml binds with `--changed-only`,
+    /// see `bindings_fingerprint`
+    bindings: Option<&'a str>,
+    /// Stacks `--changed-only` leaves out of the run
+    unchanged: &'a BTreeSet<String>,
+}
+
+/// How the processing of one stack ended
+enum StackOutcome {
+    /// Deployed or unchanged, with the outputs it publishes
+    Done(BTreeMap<String, String>),
+    /// Rendered by a dry run, with the outputs it would publish and its images
+    Planned { outputs: BTreeMap<String, String>, images: Vec<String> },
+    /// Left aside, such as a stack outside of the filter or without directory
+    Skipped,
+    /// Left for a later run, such as an update waiting for a quiet period
+    Postponed,
+}
+
+/// Task of a service running on a node, for `exec`
+struct RunningTask {
+    /// Name of the task as `docker service ps` shows it
+    name: String,
+    node_id: String,
+    hostname: String,
+    container_id: String,
+}
+
+/// Task of a stack no longer meant to run, whose container may still be on its node
+struct StoppedTask {
+    /// Time of its last state change
+    stopped_at: Option<chrono::DateTime<chrono::Utc>>,
+    node_id: String,
+    container_id: String,
+}
+
+/// Outcome of one `healthz` check
+#[derive(Debug, serde::Serialize)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub healthy: bool,
+    pub detail: String,
+}
+
+/// Combined state of the stacks of an application (`app:` in stacks.yaml),
+/// a stack without application being an application of its own
+#[derive(Debug, serde::Serialize)]
+pub struct AppStatus {
+    pub name: String,
+    pub health: &'static str, // "healthy", "degraded" or "down"
+    pub stacks: Vec<AppStackStatus>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct AppStackStatus {
+    pub name: String,
+    pub repository: String,
+    pub status: String,
+    pub health: &'static str,
+    pub running: u32,
+    pub desired: u32,
+}
+
+/// Stack name globs selecting the stacks a run may touch
+#[derive(Debug, Default, Clone)]
+pub struct StackFilter {
+    only: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl StackFilter {
+    pub fn new(only: Vec<String>, exclude: Vec<String>) -> Self {
+        Self { only, exclude }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn matches(&self, stack_name: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|pattern| glob_match(pattern, stack_name)))
+            && !self.exclude.iter().any(|pattern| glob_match(pattern, stack_name))
+    }
 }
 
 impl Commands {
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(db: Database, config: Config) -> Self {
+        let github = Arc::new(GithubApi::default());
+        let github_app = match config.github_app() {
+            Ok(app) => app.map(|(app_id, private_key)| GithubApp::new(github.clone(), app_id, private_key, config.github_app_installation_id)),
+            Err(e) => {
+                println!("{}", i18n::tr("github-app-authentication-disabled", &[("error", &e)]));
+                None
+            }
+        };
+        Self { db, config, github, github_app, pull_slots: Default::default(), dry_run: false, force: false, changed_only: false, prune: false, planned: Default::default() }
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    pub fn set_force(&mut self, force: bool) {
+        self.force = force;
+    }
+
+    pub fn set_changed_only(&mut self, changed_only: bool) {
+        self.changed_only = changed_only;
+    }
+
+    pub fn set_prune(&mut self, prune: bool) {
+        self.prune = prune;
     }
 
-    pub async fn watch(&self, github_url: &str) -> Result<()> {
-        println!("Watching GitHub repository: {}", github_url);
+    pub async fn watch(&self, entry: &WatchEntry, filter: &StackFilter) -> Result<()> {
+        let (url, branch, tag_pattern, commit) = (entry.url.as_str(), entry.branch.as_deref(), entry.tag.as_deref(), entry.commit.as_deref());
+        if let Some(namespace) = &entry.namespace {
+            if namespace.is_empty() || !namespace.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(anyhow::anyhow!(i18n::tr("invalid-namespace", &[("namespace", &namespace)])));
+            }
+        }
+        let path = normalize_subpath(entry.path.as_deref())?;
+        let path = path.as_deref();
+        // Local repositories are recorded by absolute path so the daemon finds them from any directory
+        let local_url = match provider::local_path(url) {
+            Some(path) => {
+                if branch.is_some() || tag_pattern.is_some() || commit.is_some() {
+                    return Err(anyhow::anyhow!(i18n::tr("local-repository-refs-unsupported", &[])));
+                }
+                let path = fs::canonicalize(&path)
+                    .map_err(|e| anyhow::anyhow!(i18n::tr("local-repository-not-found", &[("path", &path.display()), ("error", &e)])))?;
+                Some(path.to_string_lossy().to_string())
+            }
+            None => None,
+        };
+        let url = local_url.as_deref().unwrap_or(url);
+        println!("{}", i18n::tr("watching-repository", &[("url", &url)]));
         
         // Check if repository is already in cache
-        if let Some(cached_repo) = self.db.get_repository_from_cache(github_url).await? {
-            return Err(anyhow::anyhow!("Repository '{}' is already being watched (last watch: {})", 
-                github_url, cached_repo.last_watch));
+        if let Some(cached_repo) = self.db.get_repository_from_cache(url).await? {
+            if !self.force {
+                return Err(anyhow::anyhow!(i18n::tr("repository-already-watched-use-force", &[("url", &url), ("last_watch", &self.config.format_time(&cached_repo.last_watch))])));
+            }
+            if branch.is_some() || tag_pattern.is_some() || commit.is_some() || path.is_some() || entry.namespace != cached_repo.namespace {
+                println!("{}", i18n::tr("repository-watch-options-kept", &[]));
+            }
+            // Services changed by hand are put back as git declares them
+            println!("{}", i18n::tr("repository-already-watched-redeploying", &[]));
+            let options = DeployOptions { force: true, filter: filter.clone(), ..Default::default() };
+            return self.sync_repository(&cached_repo, &options).await;
+        }
+        if let Some(pattern) = tag_pattern {
+            parse_tag_pattern(pattern)?;
         }
         
         // Clone the repository
-        let repo_path = self.clone_repository(github_url).await?;
-        println!("Repository cloned to: {}", repo_path);
+        let repo_path = self.clone_repository(url, branch, commit.is_none(), path).await?;
+        println!("{}", i18n::tr("repository-working-copy", &[("repo_path", &repo_path)]));
+        
+        // Release based watches deploy the newest matching tag instead of HEAD
+        let deployed_tag = match tag_pattern {
+            Some(pattern) => Some(checkout_newest_tag(&repo_path, pattern, self.sparse_prefix(path))?.1),
+            None => None,
+        };
+        let pinned_commit = match commit {
+            Some(commit) => Some(checkout_revision(&repo_path, commit, self.sparse_prefix(path))?),
+            None => None,
+        };
+        if deployed_tag.is_some() || pinned_commit.is_some() {
+            self.checkout_submodules(&repo_path, url, path).await?;
+        }
+        
+        let head = head_commit(&repo_path)?;
+        self.verify_commit_signature(&repo_path, head.as_deref())?;
         
         // Process stacks and deploy them
-        self.process_and_deploy_stacks(&repo_path, github_url, false, false).await?;
+        let stacks_path = stacks_root(&repo_path, path)?;
+        let options = DeployOptions { force: self.force, filter: filter.clone(), namespace: entry.namespace.clone(), ..Default::default() };
+        self.process_and_deploy_stacks(&stacks_path, url, &options).await?;
+        if self.dry_run {
+            println!("{}", i18n::tr("watch-dry-run", &[]));
+            return Ok(());
+        }
         
         // Add repository to cache
-        self.db.add_repository_to_cache(github_url).await?;
-        println!("Repository added to cache");
+        // Stacks left out of a scoped watch are deployed by the next sync
+        let deployed_tag = deployed_tag.filter(|_| options.is_full_run());
+        self.db.add_repository_to_cache(url, branch, tag_pattern, deployed_tag.as_deref(), path).await?;
+        if let Some(namespace) = &entry.namespace {
+            self.db.set_repository_namespace(url, namespace).await?;
+        }
+        if let (true, Some(head)) = (options.is_full_run(), &head) {
+            self.db.update_repository_last_commit(url, head).await?;
+        }
+        if let Some(commit) = &pinned_commit {
+            self.db.pin_repository_commit(url, commit).await?;
+            println!("{}", i18n::tr("repository-pinned-to-commit", &[("commit", &commit)]));
+        }
+        println!("{}", i18n::tr("repository-added-to-cache", &[]));
+        
+        Ok(())
+    }
+
+    /// Watch every repository of a YAML list, `parallel` at a time.
+    /// Repositories already watched are skipped so the file can be re-applied,
+    /// or redeployed with `--force`.
+    pub async fn watch_from_file(self: Arc<Self>, path: &str, parallel: usize, default_filter: &StackFilter) -> Result<()> {
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct WatchFile {
+            repositories: Vec<WatchEntry>,
+        }
+        
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!(i18n::tr("could-not-read", &[("path", &path), ("error", &e)])))?;
+        let file: WatchFile = serde_yaml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!(i18n::tr("invalid-repository-list", &[("path", &path), ("error", &e)])))?;
+        for entry in &file.repositories {
+            if entry.tag.is_some() && (entry.branch.is_some() || entry.commit.is_some()) {
+                return Err(anyhow::anyhow!(i18n::tr("repository-list-tag-conflict", &[("url", &entry.url)])));
+            }
+        }
+        
+        println!("{}", i18n::tr("bootstrap-started", &[("count", &file.repositories.len()), ("path", &path), ("parallel", &parallel.max(1))]));
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(parallel.max(1)));
+        let mut tasks = Vec::new();
+        
+        for entry in file.repositories {
+            let commands = self.clone();
+            let semaphore = semaphore.clone();
+            let filter = if entry.only.is_empty() && entry.exclude.is_empty() {
+                default_filter.clone()
+            } else {
+                StackFilter::new(entry.only.clone(), entry.exclude.clone())
+            };
+            
+            let url = entry.url.clone();
+            tasks.push((url, tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await?;
+                if !commands.force && commands.db.get_repository_from_cache(&entry.url).await?.is_some() {
+                    println!("{}", i18n::tr("bootstrap-repository-skipped", &[("url", &entry.url)]));
+                    return Ok(false);
+                }
+                commands.watch(&entry, &filter).await?;
+                anyhow::Ok(true)
+            })));
+        }
         
-        // Clean up cloned repository
-        if let Err(e) = fs::remove_dir_all(&repo_path) {
-            println!("Warning: Could not clean up repository directory: {}", e);
+        // Output of concurrent watches is interleaved, summarize at the end
+        println!("\n{}", i18n::tr("bootstrap-summary", &[]));
+        let total = tasks.len();
+        let mut failures = 0;
+        for (url, task) in tasks {
+            match task.await? {
+                Ok(true) => println!("  ✅ {}", url),
+                Ok(false) => println!("  {}", i18n::tr("already-watched", &[("url", &url)])),
+                Err(e) => {
+                    failures += 1;
+                    println!("  ❌ {}: {}", url, e);
+                }
+            }
         }
         
+        if failures > 0 {
+            return Err(anyhow::anyhow!(i18n::tr("bootstrap-failures", &[("failures", &failures), ("total", &total)])));
+        }
+        println!("{}", i18n::tr("bootstrap-succeeded", &[]));
         Ok(())
     }
 
-    pub async fn reconcile(&self, force: bool) -> Result<()> {
-        println!("Reconciling database...");
+    pub async fn reconcile(&self, force: bool, repository_url: Option<&str>, filter: &StackFilter) -> Result<()> {
+        println!("{}", i18n::tr("reconciling-database", &[]));
         
         // Check if there are any repositories in cache
-        let repositories = self.db.get_all_repositories().await?;
+        let mut repositories = self.db.get_all_repositories().await?;
         if repositories.is_empty() {
-            return Err(anyhow::anyhow!("No repositories found in cache. Please run 'watch' command first."));
+            return Err(anyhow::anyhow!(i18n::tr("no-repositories-in-cache", &[])));
+        }
+        if let Some(url) = repository_url {
+            repositories.retain(|repo| repo.url == url);
+            if repositories.is_empty() {
+                return Err(anyhow::anyhow!(i18n::tr("repository-not-watched", &[("url", &url)])));
+            }
         }
         
-        println!("Found {} repositories in cache:", repositories.len());
+        println!("{}", i18n::tr("found-repositories-in-cache", &[("count", &repositories.len())]));
         for repo in &repositories {
-            println!("  - {} (last watch: {})", repo.url, repo.last_watch);
+            println!("  - {} [{}] ({})", repo.url, repo.tracking(), self.sync_state(repo));
         }
         
         // Get all stacks and display them
         let stacks = self.db.get_all_stacks().await?;
-        println!("\nFound {} stacks in database:", stacks.len());
+        println!("\n{}", i18n::tr("found-stacks-in-database", &[("count", &stacks.len())]));
         
+        let freshness = self.db.get_all_image_freshness().await?;
+        let drift = self.db.get_drift_events().await?;
         for stack in &stacks {
-            println!("  - {} (status: {}, hash: {})", stack.name, stack.status, stack.hash);
+            let outdated = freshness.iter()
+                .filter(|f| f.stack_name == stack.name && f.repository_url == stack.repository_url && f.is_outdated())
+                .count();
+            let staleness = if outdated > 0 { format!(", {} outdated images", outdated) } else { String::new() };
+            let app = stack.app.as_ref().map(|app| format!("app: {}, ", app)).unwrap_or_default();
+            println!("  {}", i18n::tr("repository-stack-line", &[("stack", &stack.name), ("app", &app), ("status", &stack.status), ("hash", &stack.hash), ("staleness", &staleness)]));
+            for event in drift.iter().filter(|e| e.stack_name == stack.name && e.repository_url == stack.repository_url) {
+                println!("      {}", i18n::tr("repository-drift-line", &[("object_type", &event.object_type), ("object_name", &event.object_name), ("action", &event.action), ("time", &self.config.format_timestamp(&event.detected_at))]));
+            }
         }
         
         // Get all images and display them
         let images = self.db.get_all_images().await?;
-        println!("\nFound {} images in database:", images.len());
+        println!("\n{}", i18n::tr("found-images-in-database", &[("count", &images.len())]));
         
         for image in &images {
-            println!("  - {} (referenced {} times)", image.name, image.reference_count);
+            println!("  {}", i18n::tr("cached-image-line", &[("name", &image.name), ("reference_count", &image.reference_count)]));
         }
         
         // Now reconcile each repository
-        println!("\nStarting reconciliation process...");
+        println!("\n{}", i18n::tr("starting-reconciliation-process", &[]));
         if force {
-            println!("⚠️  Force mode enabled - will redeploy all stacks regardless of changes");
+            println!("{}", i18n::tr("reconcile-forced", &[]));
         }
         for repo in &repositories {
-            println!("Reconciling repository: {}", repo.url);
-            
-            // Clone the repository
-            let repo_path = self.clone_repository(&repo.url).await?;
-            println!("Repository cloned to: {}", repo_path);
+            println!("{}", i18n::tr("reconciling-repository", &[("url", &repo.url)]));
             
             // Process stacks and deploy them (with is_reconcile=true and force flag)
-            self.process_and_deploy_stacks(&repo_path, &repo.url, true, force).await?;
-            
-            // Clean up cloned repository
-            if let Err(e) = fs::remove_dir_all(&repo_path) {
-                println!("Warning: Could not clean up repository directory: {}", e);
-            }
+            let options = DeployOptions { is_reconcile: true, force, filter: filter.clone(), ..Default::default() };
+            self.sync_repository(repo, &options).await?;
         }
         
-        println!("Reconciliation completed!");
+        println!("{}", i18n::tr("reconciliation-completed", &[]));
         Ok(())
     }
 
-    pub async fn stop(&self) -> Result<()> {
-        println!("Stopping DockerOps and cleaning up all resources...");
-        
-        // Get all stacks from database
-        let stacks = self.db.get_all_stacks().await?;
-        println!("Found {} stacks to remove", stacks.len());
+    /// Compute what a sync of the watched repositories would change, without
+    /// changing anything, and save it to `output` for `apply`
+    pub async fn plan(&mut self, repository_url: Option<&str>, filter: &StackFilter, output: &str) -> Result<()> {
+        self.dry_run = true;
+        let mut repositories = self.db.get_all_repositories().await?;
+        if let Some(url) = repository_url {
+            repositories.retain(|repo| repo.url == url);
+            if repositories.is_empty() {
+                return Err(anyhow::anyhow!(i18n::tr("repository-not-watched", &[("url", &url)])));
+            }
+        }
         
-        // Remove all stacks
-        for stack in &stacks {
-            println!("Removing stack: {}", stack.name);
-            self.stop_stack(&stack.name).await?;
+        for repo in &repositories {
+            println!("{}", i18n::tr("planning-repository", &[("url", &repo.url)]));
+            let options = DeployOptions { filter: filter.clone(), ..Default::default() };
+            self.sync_repository(repo, &options).await?;
         }
         
-        // Get all images from database
-        let images = self.db.get_all_images().await?;
-        println!("Found {} images to remove", images.len());
+        let mut planned = std::mem::take(self.planned.get_mut().unwrap());
+        let mut plans = Vec::new();
+        for repo in &repositories {
+            let Some(mut plan) = planned.remove(&repo.url).filter(|plan| !plan.changes.is_empty()) else {
+                continue;
+            };
+            // The checkout the stacks were rendered from, tag or pinned commit included
+            if provider::local_path(&repo.url).is_none() {
+                plan.commit = head_commit(&self.working_copy_path(&repo.url).to_string_lossy())?;
+            }
+            plan.changes.sort_by(|a, b| a.stack.cmp(&b.stack));
+            plans.push(plan);
+        }
+        let plan = Plan::new(&self.config.operator(), plans);
+        if plan.is_empty() {
+            println!("{}", i18n::tr("plan-no-changes", &[]));
+            return Ok(());
+        }
         
-        // Remove all images
-        for image in &images {
-            println!("Removing image: {}", image.name);
-            self.remove_image(&image.name).await?;
+        println!("\n{}", i18n::tr("planned-changes", &[]));
+        for repository in &plan.repositories {
+            let commit = repository.commit.as_deref().map(|commit| &commit[..12]).unwrap_or("working tree");
+            println!("  {} ({})", repository.url, commit);
+            for change in &repository.changes {
+                println!("    {:<9} {}", change.action.as_str(), change.stack);
+            }
+            for image in &repository.removed_images {
+                println!("    {:<9} image {}", "remove", image);
+            }
+        }
+        plan.save(Path::new(output))?;
+        println!("{}", i18n::tr("plan-saved", &[("output", &output)]));
+        Ok(())
+    }
+
+    /// Carry out a plan saved by `plan`: its commits are deployed again, only
+    /// the stacks it changes are touched, and nothing happens unless they are
+    /// still in the state the plan was made from
+    pub async fn apply(&self, path: &str) -> Result<()> {
+        let plan = Plan::load(Path::new(path))?;
+        println!("{}", i18n::tr("plan-applying", &[("created_by", &plan.created_by), ("time", &self.config.format_timestamp(&plan.created_at))]));
+        if plan.is_empty() {
+            println!("{}", i18n::tr("plan-empty", &[]));
+            return Ok(());
         }
         
-        // Clean up database
-        println!("Cleaning up database...");
-        self.db.delete_all_stacks().await?;
-        self.db.reset_image_reference_counts().await?;
-        self.db.delete_images_with_zero_count().await?;
-        self.db.clear_repository_cache().await?;
+        // Every repository is checked before the first change
+        let mut repositories = Vec::new();
+        for repository in plan.repositories.iter().filter(|repository| !repository.changes.is_empty()) {
+            let repo = self.db.get_repository_from_cache(&repository.url).await?
+                .ok_or_else(|| anyhow::anyhow!(i18n::tr("plan-repository-unwatched", &[("url", &repository.url)])))?;
+            for change in &repository.changes {
+                let current = self.db.get_stack_by_name(&change.stack, &repository.url).await?.map(|stack| stack.hash);
+                if current != change.previous_hash {
+                    let describe = |hash: &Option<String>| hash.clone().unwrap_or_else(|| "not deployed".to_string());
+                    return Err(anyhow::anyhow!(i18n::tr("plan-stack-changed", &[("stack", &change.stack), ("current", &describe(&current)), ("previous_hash", &describe(&change.previous_hash))])));
+                }
+            }
+            repositories.push((repository, repo));
+        }
         
-        // Verify cache is cleared
-        let repositories = self.db.get_all_repositories().await?;
-        if !repositories.is_empty() {
-            println!("Warning: Repository cache still contains {} entries, forcing cleanup...", repositories.len());
-            self.db.clear_repository_cache().await?;
+        let mut not_applied = Vec::new();
+        for (repository, repo) in repositories {
+            println!("{}", i18n::tr("plan-applying-changes", &[("count", &repository.changes.len()), ("url", &repo.url)]));
+            // The planned commit stands in for the tip of the branch or the newest tag
+            let repo = match &repository.commit {
+                Some(commit) => RepositoryCache { pinned_commit: Some(commit.clone()), tag_pattern: None, ..repo },
+                None => repo,
+            };
+            let options = DeployOptions {
+                filter: StackFilter::new(repository.changes.iter().map(|change| change.stack.clone()).collect(), Vec::new()),
+                planned_hashes: repository.changes.iter()
+                    .filter_map(|change| Some((change.stack.clone(), change.hash.clone()?)))
+                    .collect(),
+                ..Default::default()
+            };
+            self.sync_repository(&repo, &options).await?;
             
-            // Verify again after forced cleanup
-            let repositories_after = self.db.get_all_repositories().await?;
-            if !repositories_after.is_empty() {
-                println!("❌ Cache cleanup failed! Still contains {} entries", repositories_after.len());
-                for repo in &repositories_after {
-                    println!("  - {}", repo.url);
+            for change in &repository.changes {
+                let stack = self.db.get_stack_by_name(&change.stack, &repo.url).await?;
+                let applied = match (change.action, &stack) {
+                    (ChangeAction::Remove, stack) => stack.is_none(),
+                    (_, Some(stack)) => Some(&stack.hash) == change.hash.as_ref() && stack.status == "deployed",
+                    (_, None) => false,
+                };
+                if !applied {
+                    let status = stack.map(|stack| stack.status).unwrap_or_else(|| "not deployed".to_string());
+                    not_applied.push(format!("{} {} ({})", change.action.as_str(), change.stack, status));
                 }
-            } else {
-                println!("✅ Cache successfully cleared");
             }
         }
         
-        println!("All stacks and images have been removed.");
-        println!("Database connection will be closed.");
+        if !not_applied.is_empty() {
+            return Err(anyhow::anyhow!(i18n::tr("plan-changes-not-applied", &[("count", &not_applied.len()), ("details", &not_applied.join("\n  "))])));
+        }
+        println!("{}", i18n::tr("plan-applied", &[]));
         Ok(())
     }
 
-    pub fn show_version() {
-        println!("DockerOps CLI v{}", env!("CARGO_PKG_VERSION"));
-        println!("A Docker Swarm stack manager for GitHub repositories");
-        println!("Repository: https://github.com/TomBedinoVT/DockerOps");
-    }
-
-    pub async fn debug_cache(&self) -> Result<()> {
-        println!("Debug: Checking repository cache...");
-        
-        let repositories = self.db.get_all_repositories().await?;
-        println!("Found {} repositories in cache:", repositories.len());
-        
-        for repo in &repositories {
-            println!("  - {} (last watch: {})", repo.url, repo.last_watch);
+    /// Re-evaluate one managed stack against its repository, redeploying it
+    /// when git changed, it failed or it drifted in Swarm
+    pub async fn reconcile_stack(&self, stack_name: &str, repository_url: Option<&str>, force: bool) -> Result<()> {
+        let stack = self.resolve_stack(stack_name, repository_url).await?;
+        let repo = self.db.get_repository_from_cache(&stack.repository_url).await?
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("repository-no-longer-watched", &[("url", &stack.repository_url)])))?;
+        println!("{}", i18n::tr("reconciling-stack", &[("stack", &stack.name), ("url", &repo.url), ("status", &stack.status)]));
+        if force {
+            println!("{}", i18n::tr("reconcile-stack-forced", &[]));
         }
         
+        let options = DeployOptions { is_reconcile: true, force, only_stack: Some(stack.name.clone()), ..Default::default() };
+        self.sync_repository(&repo, &options).await?;
+        
+        let status = self.db.get_stack_by_name(&stack.name, &repo.url).await?
+            .map(|stack| stack.status)
+            .unwrap_or_else(|| "removed".to_string());
+        println!("{}", i18n::tr("stack-reconciliation-completed", &[("stack", &stack.name), ("status", &status)]));
         Ok(())
     }
 
-    async fn clone_repository(&self, github_url: &str) -> Result<String> {
-        // Convert GitHub URL to clone URL if needed
-        let clone_url = if github_url.starts_with("https://github.com/") {
-            github_url.to_string()
-        } else if github_url.starts_with("github.com/") {
-            format!("https://{}", github_url)
-        } else {
-            github_url.to_string()
+    /// Deploy one stack from its repository right away, whether or not its
+    /// compose file changed. A stack declared in stacks.yaml but not deployed
+    /// yet is looked up in the repository given with `--repo`.
+    pub async fn deploy(&self, stack_name: &str, repository_url: Option<&str>) -> Result<()> {
+        let repository_url = match (self.resolve_stack(stack_name, repository_url).await, repository_url) {
+            (Ok(stack), _) => stack.repository_url,
+            (Err(_), Some(url)) => url.to_string(),
+            (Err(e), None) => return Err(e),
         };
+        let repo = self.db.get_repository_from_cache(&repository_url).await?
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("repository-not-watched", &[("url", &repository_url)])))?;
+        println!("{}", i18n::tr("deploying-stack-from", &[("stack", &stack_name), ("url", &repo.url)]));
         
-        // Create temporary directory for cloning in /tmp
-        let temp_dir = format!("/tmp/temp_repo_{}", chrono::Utc::now().timestamp());
-        let repo_path = Path::new(&temp_dir);
-        
-        println!("Cloning repository from: {}", clone_url);
-        
-        // Check for GitHub token in environment
-        let github_token = std::env::var("GITHUB_TOKEN").ok();
-        
-        // Clone the repository with authentication if token is available
-        let mut callbacks = git2::RemoteCallbacks::new();
+        let options = DeployOptions { force: true, only_stack: Some(stack_name.to_string()), ..Default::default() };
+        self.sync_repository(&repo, &options).await?;
         
-        if let Some(token) = github_token {
-            println!("Using GitHub token for authentication");
-            // Move token into the closure to ensure it lives long enough
-            let token_clone = token.clone();
-            callbacks.credentials(move |_url, username_from_url, _allowed_types| {
-                git2::Cred::userpass_plaintext(username_from_url.unwrap_or("git"), &token_clone)
-            });
-        } else {
-            println!("No GitHub token found. Trying to clone without authentication...");
-            println!("If this fails, set the GITHUB_TOKEN environment variable");
+        match self.db.get_stack_by_name(stack_name, &repo.url).await? {
+            Some(stack) if stack.status == "deployed" => {
+                println!("{}", i18n::tr("stack-deployed", &[("stack", &stack_name)]));
+                Ok(())
+            }
+            Some(stack) => Err(anyhow::anyhow!(i18n::tr("stack-not-deployed", &[("stack", &stack_name), ("status", &stack.status)]))),
+            None => Err(anyhow::anyhow!(i18n::tr("stack-was-not-deployed", &[("stack", &stack_name)]))),
         }
+    }
+
+    /// Start managing a stack deployed in Swarm outside DockerOps: it is
+    /// recorded with the hash of its compose file in the repository, without
+    /// being redeployed. The next sync only redeploys it if git changes.
+    pub async fn adopt(&self, stack_name: &str, repository_url: &str) -> Result<()> {
+        if let Some(owner) = self.db.get_stacks_named(stack_name).await?.first() {
+            return Err(anyhow::anyhow!(i18n::tr("stack-already-managed", &[("stack", &stack_name), ("url", &owner.repository_url)])));
+        }
+        let repo = self.db.get_repository_from_cache(repository_url).await?
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("repository-not-watched-run-watch", &[("url", &repository_url)])))?;
+        if !self.swarm_stacks()?.iter().any(|name| name == stack_name) {
+            return Err(anyhow::anyhow!(i18n::tr("stack-not-running-use-deploy", &[("stack", &stack_name)])));
+        }
+        println!("{}", i18n::tr("adopting-stack-from", &[("stack", &stack_name), ("url", &repo.url)]));
         
-        let mut fetch_options = git2::FetchOptions::new();
-        fetch_options.remote_callbacks(callbacks);
+        let options = DeployOptions { adopt: true, only_stack: Some(stack_name.to_string()), ..Default::default() };
+        self.sync_repository(&repo, &options).await?;
         
-        let mut builder = git2::build::RepoBuilder::new();
-        builder.fetch_options(fetch_options);
+        let stack = self.db.get_stack_by_name(stack_name, &repo.url).await?
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("stack-was-not-adopted", &[("stack", &stack_name)])))?;
+        println!("{}", i18n::tr("stack-adopted", &[("stack", &stack.name), ("hash", &stack.hash)]));
+        println!("   {}", i18n::tr("stack-adopted-hint", &[]));
+        Ok(())
+    }
+
+    /// Print the layered settings: the global ones of config.yaml, or the
+    /// ones a stack is deployed with and the file each one comes from
+    pub async fn config_show(&self, effective: Option<&str>, repository_url: Option<&str>) -> Result<()> {
+        let Some(stack_name) = effective else {
+            println!("{}", i18n::tr("settings-global-header", &[]));
+            let settings = RepositorySettings::default().explain(&self.config, &StackDefinition::default());
+            print_effective_settings(&settings);
+            return Ok(());
+        };
         
-        let _repo = builder.clone(&clone_url, repo_path)
-            .map_err(|e| anyhow::anyhow!("Failed to clone repository: {}", e))?;
+        let repository_url = match (self.resolve_stack(stack_name, repository_url).await, repository_url) {
+            (Ok(stack), _) => stack.repository_url,
+            (Err(_), Some(url)) => url.to_string(),
+            (Err(e), None) => return Err(e),
+        };
+        let (settings, definition) = self.declared_stack(&repository_url, stack_name).await?;
         
-        Ok(temp_dir)
+        // Same validation as a deployment
+        settings.for_stack(&self.config, &definition)?;
+        println!("{}", i18n::tr("settings-stack-header", &[("stack", &stack_name), ("url", &repository_url)]));
+        print_effective_settings(&settings.explain(&self.config, &definition));
+        Ok(())
     }
 
-    async fn process_and_deploy_stacks(&self, repo_path: &str, repository_url: &str, is_reconcile: bool, force: bool) -> Result<()> {
-        println!("Processing stacks from repository...");
+    /// dockerops.yaml and stacks.yaml entry of a stack, as found in the
+    /// working copy of its repository
+    async fn declared_stack(&self, repository_url: &str, stack_name: &str) -> Result<(RepositorySettings, StackDefinition)> {
+        let repo = self.db.get_repository_from_cache(repository_url).await?
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("repository-not-watched", &[("url", &repository_url)])))?;
+        let stacks_path = self.working_copy_path(&repo.url).join(repo.path.as_deref().unwrap_or_default());
+        let stacks_content = fs::read_to_string(stacks_path.join("stacks.yaml"))
+            .map_err(|_| anyhow::anyhow!(i18n::tr("settings-no-stacks-yaml", &[("url", &repo.url)])))?;
         
-        // Reset image reference counts at the beginning
-        println!("Resetting image reference counts...");
-        self.db.reset_image_reference_counts().await?;
-        
-        // Look for stacks.yaml file
-        let stacks_file_path = Path::new(repo_path).join("stacks.yaml");
-        if !stacks_file_path.exists() {
-            return Err(anyhow::anyhow!("stacks.yaml not found in repository"));
+        let settings = RepositorySettings::load(&stacks_path)?.in_namespace(repo.namespace.as_deref());
+        let definitions: Vec<StackDefinition> = serde_yaml::from_str(&stacks_content)?;
+        let mut definition = definitions.into_iter()
+            .find(|definition| settings.stack_name(&definition.name) == stack_name)
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("settings-stack-not-declared", &[("stack", &stack_name), ("url", &repo.url)])))?;
+        definition.name = stack_name.to_string();
+        Ok((settings, definition))
+    }
+
+    pub async fn daemon(&self, interval: Duration) -> Result<()> {
+        if let Some(gc) = &self.config.gc {
+            gc.validate()?;
         }
+        if let Some(history) = &self.config.task_history {
+            history.max_age().map_err(|e| anyhow::anyhow!(i18n::tr("task-history-max-age", &[("error", &e)])))?;
+        }
+        println!("{}", i18n::tr("daemon-started", &[("interval", &interval.as_secs())]));
+        self.replay_webhook_deliveries().await;
         
-        // Read and parse stacks.yaml
-        let stacks_content = fs::read_to_string(&stacks_file_path)?;
-        let stacks_definitions: Vec<StackDefinition> = serde_yaml::from_str(&stacks_content)?;
-        
-        // Process volumes configuration
-        let volumes_definitions = self.process_volumes_config(repo_path).await?;
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
         
-        println!("Found {} stack definitions:", stacks_definitions.len());
+        // Changes made to the managed stacks between two polls are reported as they happen
+        let mut events: Option<DockerEvents> = None;
+        let mut own_run: Option<(i64, i64)> = None;
+        let mut last_task_prune: Option<std::time::Instant> = None;
         
-        for stack_def in &stacks_definitions {
-            println!("Processing stack: {}", stack_def.name);
-            
-            // Look for the stack directory
-            let stack_dir = Path::new(repo_path).join(&stack_def.name);
-            if !stack_dir.exists() || !stack_dir.is_dir() {
-                println!("  Warning: Stack directory '{}' not found", stack_def.name);
-                continue;
-            }
-            
-            // Look for docker-compose file in the stack directory
-            let compose_files = vec![
-                stack_dir.join("docker-compose.yml"),
-                stack_dir.join("docker-compose.yaml"),
-                stack_dir.join("compose.yml"),
-                stack_dir.join("compose.yaml"),
-            ];
-            
-            let mut compose_file_path = None;
-            for compose_file in &compose_files {
-                if compose_file.exists() {
-                    compose_file_path = Some(compose_file.clone());
-                    break;
-                }
-            }
-            
-            if compose_file_path.is_none() {
-                println!("  Warning: No docker-compose file found in stack directory '{}'", stack_def.name);
-                continue;
-            }
-            
-            let compose_path = compose_file_path.unwrap();
-            let mut compose_content = fs::read_to_string(&compose_path)?;
-            
-            // Process volumes in compose file if volumes definitions exist
-            if let Some(ref volumes_defs) = volumes_definitions {
-                println!("  Processing volumes in docker-compose file...");
-                let nfs_config = self.read_nfs_config(repo_path).await?;
-                compose_content = self.process_compose_volumes(&compose_content, volumes_defs, &nfs_config).await?;
-                println!("  Volume processing completed");
-            }
-            
-            // Process secrets
-            println!("  Processing secrets...");
-            let secrets_env_vars = self.process_compose_secrets(&stack_dir, repo_path).await?;
-            println!("  Secret processing completed");
-            
-            // Write the modified compose content back to the file
-            fs::write(&compose_path, &compose_content)?;
-            println!("  Updated docker-compose file with processed volumes at {}", compose_path.to_string_lossy());
-            
-            let compose_hash = self.calculate_md5(&compose_content);
-            
-            // Calculate relative path for database
-            let relative_compose_path = compose_path.strip_prefix(repo_path)
-                .unwrap_or(&compose_path)
-                .to_string_lossy()
-                .replace('\\', "/")
-                .to_string();
-            
-            // Check if stack exists in database
-            if let Some(existing_stack) = self.db.get_stack_by_name(&stack_def.name, repository_url).await? {
-                let has_changed = existing_stack.hash != compose_hash;
-                let should_deploy = has_changed || force;
-                
-                if has_changed {
-                    println!("  Stack '{}' has changed (hash: {} -> {})", 
-                        stack_def.name, existing_stack.hash, compose_hash);
-                } else if force {
-                    println!("  Stack '{}' unchanged but force mode enabled, redeploying", stack_def.name);
-                } else {
-                    println!("  Stack '{}' unchanged", stack_def.name);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let started = now_nanos();
+                    self.poll_repositories().await;
+                    self.scheduled_gc().await;
+                    if last_task_prune.is_none_or(|last| last.elapsed() >= TASK_HISTORY_PRUNE_INTERVAL) {
+                        self.prune_task_history().await;
+                        last_task_prune = Some(std::time::Instant::now());
+                    }
+                    own_run = Some((started, now_nanos()));
+                    if events.is_none() {
+                        events = self.docker_events();
+                    }
                 }
-                
-                if should_deploy {
-                    if is_reconcile {
-                        // For reconcile, stop the existing stack first
-                        println!("  Stopping existing stack '{}'", stack_def.name);
-                        self.stop_stack(&stack_def.name).await?;
+                line = DockerEvents::next(&mut events) => {
+                    match line {
+                        Some(line) => {
+                            if let Err(e) = self.record_drift(&line, own_run).await {
+                                println!("{}", i18n::tr("docker-event-failed", &[("error", &e)]));
+                            }
+                        }
+                        None => {
+                            println!("{}", i18n::tr("docker-events-ended", &[]));
+                            events = None;
+                        }
                     }
-                    
-                    // Update stack in database
-                    self.db.update_stack_hash(&stack_def.name, repository_url, &compose_hash).await?;
-                    
-                    // Deploy the updated stack
-                    println!("  Deploying updated stack '{}'", stack_def.name);
-                    self.deploy_stack(&stack_def.name, &compose_path, &secrets_env_vars).await?;
-                    self.db.update_stack_status(&stack_def.name, repository_url, "deployed").await?;
                 }
-            } else {
-                // New stack
-                println!("  New stack '{}' found, deploying", stack_def.name);
-                let stack = Stack::new(
-                    stack_def.name.clone(),
-                    repository_url.to_string(),
-                    relative_compose_path.clone(),
-                    compose_hash.clone(),
-                );
-                self.db.create_stack(&stack).await?;
-                
-                // Deploy the new stack
-                self.deploy_stack(&stack_def.name, &compose_path, &secrets_env_vars).await?;
-                self.db.update_stack_status(&stack_def.name, repository_url, "deployed").await?;
+                _ = tokio::signal::ctrl_c() => {
+                    println!("{}", i18n::tr("daemon-interrupted", &[]));
+                    break;
+                }
             }
-            
-            // Process compose file for image extraction
-            self.process_yaml_file(&compose_content, &relative_compose_path).await?;
         }
         
-        // Process images: check SHA, pull if needed, remove unused
-        println!("Processing images...");
-        self.process_images().await?;
-        
         Ok(())
     }
 
-    fn calculate_md5(&self, content: &str) -> String {
-        let result = md5::compute(content.as_bytes());
-        format!("{:x}", result)
+    /// Follow the Docker events of the objects `docker stack deploy` creates
+    fn docker_events(&self) -> Option<DockerEvents> {
+        let mut command = tokio::process::Command::from(self.docker());
+        command.args(["events", "--format", "{{json .}}"])
+            .args(["--filter", "type=service", "--filter", "type=network", "--filter", "type=secret", "--filter", "type=config"])
+            .args(["--filter", "event=update", "--filter", "event=remove", "--filter", "event=destroy"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true);
+        match command.spawn() {
+            Ok(mut child) => {
+                let stdout = child.stdout.take()?;
+                println!("{}", i18n::tr("docker-events-watching", &[]));
+                Some(DockerEvents { _child: child, lines: tokio::io::BufReader::new(stdout).lines() })
+            }
+            Err(e) => {
+                println!("{}", i18n::tr("docker-events-unavailable", &[("error", &e)]));
+                None
+            }
+        }
     }
 
-    async fn process_yaml_file(&self, content: &str, file_path: &str) -> Result<()> {
-        // Parse YAML content
-        let yaml_value: Value = match serde_yaml::from_str(content) {
-            Ok(value) => value,
+    /// Flag a Docker event on an object of a managed stack, unless the
+    /// daemon's own sync (`own_run`, in nanoseconds) caused it
+    async fn record_drift(&self, line: &str, own_run: Option<(i64, i64)>) -> Result<()> {
+        let event: serde_json::Value = serde_json::from_str(line)?;
+        let time = event["timeNano"].as_i64().unwrap_or_else(|| event["time"].as_i64().unwrap_or_default() * 1_000_000_000);
+        // Events of the last sync are read once it is over, some arrive a little later
+        if own_run.is_some_and(|(started, ended)| time >= started && time <= ended + DRIFT_GRACE_NANOS) {
+            return Ok(());
+        }
+        
+        let attributes = event["Actor"]["Attributes"].as_object().cloned().unwrap_or_default();
+        // Swarm reports the progress of rolling updates as updates too
+        if attributes.keys().any(|key| key.starts_with("updatestate.")) {
+            return Ok(());
+        }
+        let Some(object_name) = attributes.get("name").and_then(|name| name.as_str()) else {
+            return Ok(());
+        };
+        
+        // Stack objects are named <stack>_<name>
+        let stacks = self.db.get_all_stacks().await?;
+        let Some(stack) = stacks.iter()
+            .filter(|stack| object_name.starts_with(&format!("{}_", stack.name)))
+            .max_by_key(|stack| stack.name.len()) else {
+            return Ok(());
+        };
+        
+        let object_type = event["Type"].as_str().unwrap_or_default();
+        let action = event["Action"].as_str().unwrap_or_default();
+        println!("{}", i18n::tr("drift-event", &[("time", &self.config.format_timestamp(&chrono::Utc::now().to_rfc3339())), ("stack", &stack.name), ("object_type", &object_type), ("object_name", &object_name), ("action", &action)]));
+        self.db.record_drift_event(&stack.name, &stack.repository_url, object_type, object_name, action).await?;
+        Ok(())
+    }
+
+    /// Database, docker daemon and age of the last successful sync, failing
+    /// when the sync is older than `max_age`
+    pub async fn health_checks(&self, max_age: Option<Duration>) -> Vec<HealthCheck> {
+        let mut checks = Vec::new();
+        
+        let repositories = match self.db.ping().await {
+            Ok(()) => {
+                checks.push(HealthCheck { name: "database", healthy: true, detail: self.config.database_path().display().to_string() });
+                self.db.get_all_repositories().await.ok()
+            }
             Err(e) => {
-                println!("  Warning: Could not parse YAML file {}: {}", file_path, e);
-                return Ok(());
+                checks.push(HealthCheck { name: "database", healthy: false, detail: e.to_string() });
+                None
             }
         };
         
-        // Extract images from YAML structure
-        let mut images_found = Vec::new();
-        self.extract_images_from_yaml(&yaml_value, &mut images_found);
+        let docker = self.docker().args(["version", "--format", "{{.Server.Version}}"]).output();
+        checks.push(match docker {
+            Ok(output) if output.status.success() => HealthCheck {
+                name: "docker",
+                healthy: true,
+                detail: format!("server {}", String::from_utf8_lossy(&output.stdout).trim()),
+            },
+            Ok(output) => HealthCheck { name: "docker", healthy: false, detail: String::from_utf8_lossy(&output.stderr).trim().to_string() },
+            Err(e) => HealthCheck { name: "docker", healthy: false, detail: format!("could not run docker: {}", e) },
+        });
         
-        // Update database with found images
-        for image_name in &images_found {
-            self.update_image_reference(image_name).await?;
+        // The most recent sync tells whether DockerOps keeps running, a single
+        // broken repository must not get it restarted
+        if let Some(repositories) = repositories {
+            let last_sync = repositories.iter().filter_map(|repo| repo.last_success).max();
+            checks.push(match last_sync {
+                None => HealthCheck { name: "last_sync", healthy: true, detail: "no repository watched".to_string() },
+                Some(last_sync) => {
+                    // Written by another node's clock after a failover, a sync may look a bit in the future
+                    let age = (chrono::Utc::now() - last_sync).to_std().unwrap_or_default();
+                    HealthCheck {
+                        name: "last_sync",
+                        healthy: max_age.is_none_or(|max_age| age <= max_age),
+                        detail: match max_age {
+                            Some(max_age) => format!("{}s ago (max {}s)", age.as_secs(), max_age.as_secs()),
+                            None => format!("{}s ago", age.as_secs()),
+                        },
+                    }
+                }
+            });
         }
         
-        if !images_found.is_empty() {
-            println!("  Found {} images in {}: {:?}", images_found.len(), file_path, images_found);
+        checks
+    }
+
+    /// Print the health checks, failing when one of them does
+    pub async fn healthz(&self, max_age: Option<Duration>) -> Result<()> {
+        let checks = self.health_checks(max_age).await;
+        for check in &checks {
+            let marker = if check.healthy { "✅" } else { "❌" };
+            println!("{} {}: {}", marker, check.name, check.detail);
         }
         
+        if checks.iter().any(|check| !check.healthy) {
+            return Err(anyhow::anyhow!(i18n::tr("unhealthy", &[])));
+        }
         Ok(())
     }
 
-    fn extract_images_from_yaml(&self, value: &Value, images: &mut Vec<String>) {
-        match value {
-            Value::Mapping(mapping) => {
-                for (key, val) in mapping {
-                    if let Some(key_str) = key.as_str() {
-                        if key_str == "image" {
-                            if let Some(image_name) = val.as_str() {
-                                if !image_name.is_empty() {
-                                    images.push(image_name.to_string());
-                                }
-                            }
-                        } else {
-                            // Recursively search in nested structures
-                            self.extract_images_from_yaml(val, images);
-                        }
-                    } else {
-                        // Recursively search in nested structures
-                        self.extract_images_from_yaml(val, images);
-                    }
-                }
-            }
-            Value::Sequence(sequence) => {
-                for item in sequence {
-                    self.extract_images_from_yaml(item, images);
-                }
+    /// Run DockerOps itself as a Swarm service in serve mode: the configuration
+    /// file becomes a secret, the database and working copies live on a
+    /// volume and the docker endpoint is the manager's socket or `docker_host`
+    pub async fn self_deploy(&self, image: &str, stack_name: &str, port: u16, print_only: bool) -> Result<()> {
+        // The service only gets config.yaml, serve refuses to start without a secret
+        if self.config.webhook_secret.is_none() {
+            return Err(anyhow::anyhow!(i18n::tr("self-deploy-webhook-secret-missing", &[("file_path", &self.config.file_path().display())])));
+        }
+        let config_path = self.config.file_path();
+        let config_content = fs::read(&config_path).unwrap_or_default();
+        // Secrets are immutable, a changed configuration gets a new one
+        let hash = format!("{:x}", md5::compute(&config_content));
+        let secret_name = &format!("{}-config-{}", stack_name, &hash[..8]);
+        
+        let mut environment = serde_json::json!({
+            "DOCKEROPS_CONFIG": "/run/secrets/dockerops-config",
+            "DOCKEROPS_SELF_STACK": stack_name,
+        });
+        let mut volumes = vec![serde_json::json!("data:/root/.dockerops")];
+        match self.config.docker_env() {
+            Some(("DOCKER_HOST", host)) if !host.starts_with("unix://") => {
+                environment["DOCKER_HOST"] = serde_json::json!(host);
             }
-            _ => {
-                // For other types (String, Number, etc.), do nothing
+            Some(("DOCKER_CONTEXT", context)) => {
+                return Err(anyhow::anyhow!(i18n::tr("self-deploy-context-unsupported", &[("context", &context)])));
             }
+            _ => volumes.push(serde_json::json!("/var/run/docker.sock:/var/run/docker.sock")),
         }
-    }
-
-    async fn update_image_reference(&self, image_name: &str) -> Result<()> {
-        // Try to get existing image
-        if let Some(existing_image) = self.db.get_image_by_name(image_name).await? {
-            // Increment reference count
-            let new_count = existing_image.reference_count + 1;
-            self.db.update_image_reference_count(image_name, new_count).await?;
-            println!("    Incremented reference count for '{}' to {}", image_name, new_count);
-        } else {
-            // Create new image with reference count 1
-            let new_image = Image::new(image_name.to_string(), 1);
-            self.db.create_image(&new_image).await?;
-            println!("    Added new image '{}' with reference count 1", image_name);
-        }
-        
-        Ok(())
-    }
-
-    async fn deploy_stack(&self, stack_name: &str, compose_path: &Path, secrets_env_vars: &[(String, String)]) -> Result<()> {
-        println!("    Deploying stack '{}' with docker stack deploy", stack_name);
         
-        // Read compose file to extract images
-        let compose_content = fs::read_to_string(compose_path)?;
+        let compose = serde_json::json!({
+            "services": {
+                "dockerops": {
+                    "image": image,
+                    "command": ["serve", "--listen", format!("0.0.0.0:{}", port)],
+                    "environment": environment,
+                    "volumes": volumes,
+                    "secrets": [{ "source": "dockerops-config", "target": "dockerops-config" }],
+                    "ports": [format!("{}:{}", port, port)],
+                    "healthcheck": {
+                        "test": ["CMD", "dockerops", "healthz"],
+                        "interval": "1m",
+                        "timeout": "10s",
+                        "retries": 3,
+                    },
+                    "deploy": {
+                        "replicas": 1,
+                        "placement": { "constraints": ["node.role == manager"] },
+                        // A single instance writes the SQLite database
+                        "update_config": { "order": "stop-first", "failure_action": "rollback" },
+                        "restart_policy": { "condition": "any" },
+                    },
+                },
+            },
+            "secrets": {
+                "dockerops-config": { "external": true, "name": secret_name },
+            },
+            "volumes": {
+                "data": {},
+            },
+        });
+        let compose = serde_yaml::to_string(&compose)?;
         
-        // Extract and pull images before deployment
-        println!("    Extracting images from compose file...");
-        let yaml_value: Value = serde_yaml::from_str(&compose_content)?;
-        let mut images_found = Vec::new();
-        self.extract_images_from_yaml(&yaml_value, &mut images_found);
+        if print_only {
+            print!("{}", compose);
+            return Ok(());
+        }
         
-        if !images_found.is_empty() {
-            println!("    Found {} images, pulling before deployment: {:?}", images_found.len(), images_found);
-            for image_name in &images_found {
-                println!("    Pulling image: {}", image_name);
-                self.pull_image(image_name).await?;
+        for (setting, value) in [("ssh_key", self.config.ssh_key.is_some()), ("github_app_private_key", self.config.github_app_private_key.is_some())] {
+            if value {
+                println!("{}", i18n::tr("self-deploy-file-setting", &[("setting", &setting)]));
             }
-            println!("    All images pulled successfully");
-        } else {
-            println!("    No images found in compose file");
         }
         
-        // Now deploy the stack with secrets as environment variables
-        let mut command = Command::new("docker");
-        command.args(&["stack", "deploy", "--detach=false", "-c", compose_path.to_str().unwrap(), stack_name]);
-        
-        // Add secrets as environment variables
-        for (env_name, env_value) in secrets_env_vars {
-            command.env(env_name, env_value);
-            println!("    Added environment variable: {} (secret)", env_name);
+        let secret_exists = self.docker().args(["secret", "inspect", secret_name]).output()?.status.success();
+        if !secret_exists {
+            println!("{}", i18n::tr("self-deploy-creating-secret", &[("secret_name", &secret_name), ("config_path", &config_path.display())]));
+            let mut child = self.docker()
+                .args(["secret", "create", secret_name, "-"])
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::piped())
+                .spawn()?;
+            std::io::Write::write_all(&mut child.stdin.take().ok_or_else(|| anyhow::anyhow!(i18n::tr("docker-has-no-stdin", &[])))?, &config_content)?;
+            let output = child.wait_with_output()?;
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(i18n::tr("self-deploy-secret-failed", &[("secret_name", &secret_name), ("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+            }
         }
         
-        let output = command.output()?;
+        let dir = self.config.self_deploy_dir();
+        fs::create_dir_all(&dir)?;
+        let compose_path = dir.join("docker-compose.yml");
+        atomic::write(&compose_path, &compose)?;
         
-        if output.status.success() {
-            println!("    Successfully deployed stack '{}'", stack_name);
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            println!("    Error deploying stack '{}': {}", stack_name, error);
-            return Err(anyhow::anyhow!("Failed to deploy stack: {}", error));
+        println!("{}", i18n::tr("self-deploy-deploying", &[("stack", &stack_name), ("image", &image)]));
+        let output = self.docker()
+            .args(["stack", "deploy", "--prune", "-c"])
+            .arg(&compose_path)
+            .arg(stack_name)
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("failed-to-deploy-stack", &[("stack", &stack_name), ("error", &String::from_utf8_lossy(&output.stderr).trim())])));
         }
         
+        println!("{}", i18n::tr("self-deploy-done", &[("stack", &stack_name), ("port", &port)]));
+        println!("   {}", i18n::tr("self-deploy-watch-hint", &[("stack", &stack_name)]));
+        println!("   {}", i18n::tr("self-deploy-update-hint", &[("compose_path", &compose_path.display()), ("stack", &stack_name)]));
         Ok(())
     }
 
-    async fn stop_stack(&self, stack_name: &str) -> Result<()> {
-        println!("    Stopping stack '{}' with docker stack rm", stack_name);
+    /// Keep a standby copy of the DockerOps state (repositories, stacks,
+    /// compose snapshots, trash...) up to date at `target`
+    pub async fn mirror(&self, target: &str, interval: Duration, once: bool) -> Result<()> {
+        let target = MirrorTarget::parse(target)?;
+        if let MirrorTarget::File(path) = &target {
+            if canonical_target(path)? == canonical_target(&self.config.database_path())? {
+                return Err(anyhow::anyhow!(i18n::tr("mirror-target-is-database", &[])));
+            }
+        }
+        if once {
+            return self.mirror_database(&target).await;
+        }
         
-        let output = Command::new("docker")
-            .args(&["stack", "rm", stack_name])
-            .output()?;
+        println!("{}", i18n::tr("mirror-started", &[("target", &target), ("interval", &interval.as_secs())]));
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
         
-        if output.status.success() {
-            println!("    Successfully stopped stack '{}'", stack_name);
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            println!("    Warning: Error stopping stack '{}': {}", stack_name, error);
-            // Don't return error here as the stack might not exist
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    // A transient failure (unmounted share, unreachable host...) must not stop the mirror
+                    if let Err(e) = self.mirror_database(&target).await {
+                        println!("{}", i18n::tr("mirror-failed", &[("target", &target), ("error", &e)]));
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    println!("{}", i18n::tr("mirror-interrupted", &[]));
+                    break;
+                }
+            }
         }
         
         Ok(())
     }
 
-    async fn process_images(&self) -> Result<()> {
-        // Get all images from database
-        let images = self.db.get_all_images().await?;
-        println!("  Found {} images in database", images.len());
-        
-        for image in &images {
-            if image.reference_count == 0 {
-                // Remove unused images
-                println!("  Removing unused image: {}", image.name);
-                self.remove_image(&image.name).await?;
-            } else {
-                // Check and update image if needed
-                println!("  Processing image: {} (referenced {} times)", image.name, image.reference_count);
-                self.check_and_update_image(&image.name).await?;
+    async fn mirror_database(&self, target: &MirrorTarget) -> Result<()> {
+        match target {
+            MirrorTarget::File(path) => self.snapshot_database(path).await?,
+            MirrorTarget::Ssh { destination, port, path } => {
+                let snapshot = std::env::temp_dir().join(format!("dockerops-mirror-{}.db", std::process::id()));
+                let copied = match self.snapshot_database(&snapshot).await {
+                    Ok(()) => copy_over_ssh(&snapshot, destination, *port, path).await,
+                    Err(e) => Err(e),
+                };
+                let _ = fs::remove_file(&snapshot);
+                copied?;
             }
         }
         
-        // Remove images with zero count from database
-        self.db.delete_images_with_zero_count().await?;
-        
+        println!("{}", i18n::tr("mirror-done", &[("time", &self.config.format_timestamp(&chrono::Utc::now().to_rfc3339())), ("target", &target)]));
         Ok(())
     }
 
-    async fn check_and_update_image(&self, image_name: &str) -> Result<()> {
-        // Parse image name to get registry, repository, and tag
-        let (registry, repository, tag) = self.parse_image_name(image_name);
-        
-        // Check if image exists locally
-        let local_sha = self.get_local_image_sha(image_name).await?;
-        
-        // Get remote SHA from registry
-        let remote_sha = self.get_remote_image_sha(&registry, &repository, &tag).await?;
-        
-        if let (Some(local), Some(remote)) = (&local_sha, &remote_sha) {
-            if local != remote {
-                println!("    SHA mismatch for {}: local={}, remote={}", image_name, local, remote);
-                println!("    Removing old image and pulling new version");
-                self.remove_image(image_name).await?;
-                self.pull_image(image_name).await?;
-            } else {
-                println!("    Image {} is up to date", image_name);
-            }
-        } else if local_sha.is_none() {
-            // Image doesn't exist locally, pull it
-            println!("    Image {} not found locally, pulling", image_name);
-            self.pull_image(image_name).await?;
-        } else {
-            println!("    Could not get remote SHA for {}", image_name);
+    /// Write a consistent copy of the database to `target`
+    async fn snapshot_database(&self, target: &Path) -> Result<()> {
+        // The snapshot is written next to the target and renamed over it, so
+        // a standby never opens a partially written file
+        let partial = PathBuf::from(format!("{}.partial", target.display()));
+        if partial.exists() {
+            fs::remove_file(&partial)?;
+        }
+        if let Some(parent) = target.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
         }
         
+        self.db.snapshot_to(&partial.to_string_lossy()).await?;
+        atomic::persist(&partial, target)?;
         Ok(())
     }
 
-    fn parse_image_name(&self, image_name: &str) -> (String, String, String) {
-        // Default to Docker Hub
-        let mut registry = "registry-1.docker.io".to_string();
-        let mut repository = image_name.to_string();
-        let mut tag = "latest".to_string();
-        
-        // Extract tag first
-        if image_name.contains(':') {
-            let parts: Vec<&str> = image_name.split(':').collect();
-            if parts.len() == 2 {
-                repository = parts[0].to_string();
-                tag = parts[1].to_string();
+    async fn poll_repositories(&self) {
+        let repositories = match self.db.get_all_repositories().await {
+            Ok(repositories) => repositories,
+            Err(e) => {
+                println!("{}", i18n::tr("could-not-read-repository-cache", &[("error", &e)]));
+                return;
             }
+        };
+        
+        if repositories.is_empty() {
+            println!("{}", i18n::tr("no-repositories-to-poll", &[]));
+            return;
         }
         
-        // Check if it's a custom registry
-        if repository.contains('/') {
-            let parts: Vec<&str> = repository.split('/').collect();
-            if parts.len() >= 2 {
-                if parts[0].contains('.') || parts[0] == "localhost" {
-                    // Custom registry
-                    registry = parts[0].to_string();
-                    repository = parts[1..].join("/");
-                }
-                // For Docker Hub with organization, keep as is
+        println!("{}", i18n::tr("polling-repositories", &[("time", &self.config.format_timestamp(&chrono::Utc::now().to_rfc3339())), ("count", &repositories.len())]));
+        for repo in &repositories {
+            // A failing repository must not stop the daemon or the other repositories
+            if let Err(e) = self.refresh_repository(repo, None).await {
+                println!("{}", i18n::tr("repository-sync-failed", &[("url", &repo.url), ("error", &e)]));
             }
         }
-        
-        // For Docker Hub, add library prefix if no organization
-        if registry == "registry-1.docker.io" && !repository.contains('/') {
-            repository = format!("library/{}", repository);
+    }
+
+    /// Deploy the changes of one watched repository, recorded for `operator`
+    /// when given
+    pub async fn refresh_repository(&self, repo: &RepositoryCache, operator: Option<String>) -> Result<()> {
+        let options = DeployOptions { is_automatic: true, operator, ..Default::default() };
+        self.sync_repository(repo, &options).await
+    }
+
+    /// Last sync outcome of a repository: when it last succeeded, or since
+    /// when and why it fails
+    fn sync_state(&self, repo: &RepositoryCache) -> String {
+        let last_success = match &repo.last_success {
+            Some(time) => format!("last success: {}", self.config.format_time(time)),
+            None => "never synced".to_string(),
+        };
+        match &repo.last_error {
+            Some(error) => format!("❌ failing, {}, error: {}", last_success, error.lines().next().unwrap_or_default()),
+            None => last_success,
         }
+    }
+
+    /// Find the cached repository matching any of the given URLs, ignoring
+    /// scheme, `.git` suffix, trailing slash and case
+    pub async fn find_watched_repository(&self, candidate_urls: &[&str]) -> Result<Option<RepositoryCache>> {
+        let candidates: Vec<String> = candidate_urls.iter().map(|url| normalize_repository_url(url)).collect();
+        let repositories = self.db.get_all_repositories().await?;
         
-        (registry, repository, tag)
+        Ok(repositories.into_iter()
+            .find(|repo| candidates.contains(&normalize_repository_url(&repo.url))))
     }
 
-    async fn get_local_image_sha(&self, image_name: &str) -> Result<Option<String>> {
-        let output = Command::new("docker")
-            .args(&["image", "inspect", image_name, "--format", "{{.Id}}"])
-            .output()?;
+    /// Record a push `serve` schedules, false for a redelivery of one already
+    /// recorded. Processed deliveries are forgotten after a month.
+    pub async fn record_webhook_delivery(&self, delivery: &WebhookDelivery) -> Result<bool> {
+        let cutoff = (chrono::Utc::now() - chrono::Duration::days(WEBHOOK_DELIVERY_RETENTION_DAYS)).to_rfc3339();
+        self.db.purge_webhook_deliveries_before(&cutoff).await?;
+        Ok(self.db.record_webhook_delivery(delivery).await?)
+    }
+
+    /// Mark the deliveries of a repository received before a deployment
+    /// started at `started` (RFC 3339) as processed by it
+    pub async fn finish_webhook_deliveries(&self, repository_url: &str, started: &str, result: &str) {
+        if let Err(e) = self.db.finish_webhook_deliveries(repository_url, started, result).await {
+            println!("{}", i18n::tr("webhook-deliveries-not-recorded", &[("url", &repository_url), ("error", &e)]));
+        }
+    }
+
+    /// Deploy the repositories with pushes `serve` recorded but did not finish
+    /// deploying, the process or the machine having stopped meanwhile
+    pub async fn replay_webhook_deliveries(&self) {
+        let deliveries = match self.db.get_pending_webhook_deliveries().await {
+            Ok(deliveries) => deliveries,
+            Err(e) => {
+                println!("{}", i18n::tr("webhook-deliveries-unreadable", &[("error", &e)]));
+                return;
+            }
+        };
+        let started = chrono::Utc::now().to_rfc3339();
         
-        if output.status.success() {
-            let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !sha.is_empty() {
-                Ok(Some(sha))
-            } else {
-                Ok(None)
+        // One deployment per repository covers all its pushes, the last one
+        // telling whether the repository is behind
+        let mut latest: BTreeMap<&str, &WebhookDelivery> = BTreeMap::new();
+        for delivery in &deliveries {
+            latest.insert(&delivery.repository_url, delivery);
+        }
+        for (url, delivery) in latest {
+            let repo = match self.db.get_repository_from_cache(url).await {
+                Ok(Some(repo)) => repo,
+                Ok(None) => {
+                    self.finish_webhook_deliveries(url, &started, "unwatched").await;
+                    continue;
+                }
+                Err(e) => {
+                    println!("{}", i18n::tr("repository-unreadable", &[("url", &url), ("error", &e)]));
+                    continue;
+                }
+            };
+            if delivery.commit_hash.is_some() && delivery.commit_hash == repo.last_commit {
+                self.finish_webhook_deliveries(url, &started, "success").await;
+                continue;
             }
-        } else {
-            Ok(None)
+            
+            let pushes = deliveries.iter().filter(|pending| pending.repository_url == url).count();
+            println!("{}", i18n::tr("webhook-replaying-pushes", &[("pushes", &pushes), ("url", &url), ("received_at", &delivery.received_at)]));
+            let result = self.refresh_repository(&repo, Some(delivery.sender.clone())).await;
+            match &result {
+                Ok(()) => println!("{}", i18n::tr("webhook-pushes-replayed", &[("url", &url)])),
+                Err(e) => println!("{}", i18n::tr("webhook-replay-failed", &[("url", &url), ("error", &e)])),
+            }
+            self.finish_webhook_deliveries(url, &started, if result.is_ok() { "success" } else { "failure" }).await;
         }
     }
 
-    async fn get_remote_image_sha(&self, registry: &str, repository: &str, tag: &str) -> Result<Option<String>> {
-        let url = format!("https://{}/v2/{}/manifests/{}", registry, repository, tag);
-        
-        let client = reqwest::Client::new();
-        let response = client
-            .head(&url)
-            .header("Accept", "application/vnd.docker.distribution.manifest.v2+json")
-            .send()
-            .await?;
-        
-        if response.status().is_success() {
-            if let Some(digest) = response.headers().get("Docker-Content-Digest") {
-                let sha = digest.to_str()?.to_string();
-                Ok(Some(sha))
-            } else {
-                Ok(None)
+    pub fn webhook_secret(&self) -> Option<String> {
+        self.config.webhook_secret()
+    }
+
+    pub fn api_token(&self) -> Option<String> {
+        self.config.api_token()
+    }
+
+    pub fn health_max_age(&self) -> Result<Option<Duration>> {
+        self.config.health_max_age()
+    }
+
+    /// Update the working copy of a cached repository and deploy its stacks
+    async fn sync_repository(&self, repo: &RepositoryCache, options: &DeployOptions) -> Result<()> {
+        if self.dry_run {
+            let repo_path = self.clone_repository(&repo.url, repo.branch.as_deref(), repo.pinned_commit.is_none(), repo.path.as_deref()).await?;
+            return self.deploy_checkout(&repo_path, repo, options).await;
+        }
+        self.db.record_repository_attempt(&repo.url).await?;
+        // Notifications waiting for a retry go out with the next sync
+        self.deliver_notifications().await;
+        let result = async {
+            if let Some(tip) = self.unchanged_remote_tip(repo, options).await {
+                println!("{}", i18n::tr("remote-tip-already-processed", &[("tip", &&tip[..12])]));
+                return Ok(());
             }
-        } else {
-            Ok(None)
+            let repo_path = self.clone_repository(&repo.url, repo.branch.as_deref(), repo.pinned_commit.is_none(), repo.path.as_deref()).await?;
+            println!("{}", i18n::tr("repository-working-copy", &[("repo_path", &repo_path)]));
+            
+            self.deploy_checkout(&repo_path, repo, options).await
+        }.await;
+        
+        match &result {
+            Ok(()) => self.db.record_repository_success(&repo.url).await?,
+            Err(e) => self.db.record_repository_error(&repo.url, &e.to_string()).await?,
         }
+        result
     }
 
-    async fn remove_image(&self, image_name: &str) -> Result<()> {
-        println!("    Removing image: {}", image_name);
+    /// Deploy a fresh clone, moving it to the newest matching tag first for
+    /// release based repositories. Nothing is processed when the checkout is
+    /// the commit of the last successful run, unless forced or reconciling.
+    async fn deploy_checkout(&self, repo_path: &str, repo: &RepositoryCache, options: &DeployOptions) -> Result<()> {
+        if let Some(commit) = &repo.pinned_commit {
+            checkout_revision(repo_path, commit, self.sparse_prefix(repo.path.as_deref()))?;
+        }
         
-        let output = Command::new("docker")
-            .args(&["image", "rm", image_name])
-            .output()?;
+        // Stacks following another ref can change without a new commit or tag on the watched one
+        let follows_refs = |repo_path: &str| !declared_refs(&Path::new(repo_path).join(repo.path.as_deref().unwrap_or_default())).is_empty();
+        let deployed_tag = match &repo.tag_pattern {
+            Some(pattern) => {
+                let (version, tag) = checkout_newest_tag(repo_path, pattern, self.sparse_prefix(repo.path.as_deref()))?;
+                if let Some(deployed) = repo.deployed_tag.as_deref() {
+                    let is_newer = parse_tag_version(deployed).is_none_or(|deployed| version > deployed);
+                    if !is_newer && !options.force && !options.is_reconcile && !options.adopt && !follows_refs(repo_path) {
+                        println!("{}", i18n::tr("no-newer-tag", &[("deployed", &deployed), ("pattern", &pattern)]));
+                        return Ok(());
+                    }
+                }
+                Some(tag)
+            }
+            None => None,
+        };
         
-        if output.status.success() {
-            println!("    Successfully removed image: {}", image_name);
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            println!("    Warning: Error removing image {}: {}", image_name, error);
+        let head = head_commit(repo_path)?;
+        if let Some(head) = head.as_deref().filter(|head| repo.last_commit.as_deref() == Some(*head) && !follows_refs(repo_path)) {
+            if !options.force && !options.is_reconcile && !options.adopt {
+                println!("{}", i18n::tr("commit-already-processed", &[("head", &&head[..12])]));
+                return Ok(());
+            }
+        }
+        
+        if repo.pinned_commit.is_some() || deployed_tag.is_some() {
+            self.checkout_submodules(repo_path, &repo.url, repo.path.as_deref()).await?;
         }
         
+        self.verify_commit_signature(repo_path, head.as_deref())?;
+        let stacks_path = stacks_root(repo_path, repo.path.as_deref())?;
+        let is_complete = self.process_and_deploy_stacks(&stacks_path, &repo.url, options).await?;
+        
+        // A partial run doesn't reflect the whole commit, and delayed updates
+        // must be looked at again by the next run
+        if options.is_full_run() && is_complete {
+            if let Some(tag) = &deployed_tag {
+                self.db.update_repository_deployed_tag(&repo.url, tag).await?;
+            }
+            if let Some(head) = &head {
+                self.db.update_repository_last_commit(&repo.url, head).await?;
+            }
+        }
         Ok(())
     }
 
-    async fn pull_image(&self, image_name: &str) -> Result<()> {
-        println!("    Pulling image: {}", image_name);
-        
-        let output = Command::new("docker")
-            .args(&["image", "pull", image_name])
-            .output()?;
-        
-        if output.status.success() {
-            println!("    Successfully pulled image: {}", image_name);
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            println!("    Error pulling image {}: {}", image_name, error);
-            return Err(anyhow::anyhow!("Failed to pull image: {}", error));
+    /// Refuse checkouts whose commit isn't signed by an allowed key, when
+    /// `commit_signers` is configured
+    fn verify_commit_signature(&self, repo_path: &str, head: Option<&str>) -> Result<()> {
+        let Some(signers) = self.config.commit_signers() else {
+            return Ok(());
+        };
+        if head.is_none() {
+            return Err(anyhow::anyhow!(i18n::tr("signed-commits-unsupported-locally", &[])));
         }
         
+        let signer = signing::verify_head(Path::new(repo_path), &signers)?;
+        println!("✅ {}", signer);
         Ok(())
     }
 
-    async fn process_compose_secrets(&self, stack_dir: &Path, repo_path: &str) -> Result<Vec<(String, String)>> {
-        println!("    Checking for secrets.yaml file...");
-        
-        // Read secrets.yaml file if it exists
-        let secrets_file_path = stack_dir.join("secrets.yaml");
-        if !secrets_file_path.exists() {
-            println!("    No secrets.yaml file found, skipping secret processing");
-            return Ok(Vec::new());
+    /// Refs other than the watched one that stacks of the repository are deployed from
+    pub fn stack_refs(&self, repo: &RepositoryCache) -> Vec<String> {
+        declared_refs(&self.working_copy_path(&repo.url).join(repo.path.as_deref().unwrap_or_default()))
+    }
+
+    /// Replace the directories of the stacks declared with a `ref` by their
+    /// content at that branch, tag or commit
+    fn checkout_stack_refs(&self, repo_path: &str, definitions: &[StackDefinition]) -> Result<()> {
+        if definitions.iter().all(|definition| definition.git_ref.is_none()) {
+            return Ok(());
         }
+        let repo = git2::Repository::discover(repo_path)
+            .map_err(|_| anyhow::anyhow!(i18n::tr("stack-ref-needs-repository", &[])))?;
+        let workdir = repo.workdir()
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("stack-ref-needs-working-copy", &[])))?
+            .to_path_buf();
+        // With --path the stack directories are below the root of the repository
+        let prefix = Path::new(repo_path).strip_prefix(&workdir).unwrap_or(Path::new(""));
         
-        println!("    Found secrets.yaml file, reading secrets...");
-        let secrets_content = fs::read_to_string(&secrets_file_path)?;
-        let secrets_definitions: Vec<SecretDefinition> = serde_yaml::from_str(&secrets_content)?;
-        println!("    Found {} secret definitions", secrets_definitions.len());
-        
-        // Read NFS configuration to get the secrets path
-        let nfs_config = self.read_nfs_config(repo_path).await?;
-        let secrets_base_path = Path::new(&nfs_config.path).join("secret");
-        println!("    Using secrets path: {}", secrets_base_path.display());
-        
-        let mut env_vars = Vec::new();
-        
-        // Process each secret definition
-        for secret_def in &secrets_definitions {
-            println!("    Processing secret: {} -> {}", secret_def.id, secret_def.env);
-            
-            // Read secret value from NFS secrets directory
-            let secret_path = secrets_base_path.join(&secret_def.id);
-            if !secret_path.exists() {
-                return Err(anyhow::anyhow!("Secret file not found: {}", secret_path.display()));
+        for definition in definitions {
+            let Some(git_ref) = &definition.git_ref else {
+                continue;
+            };
+            let commit = resolve_ref(&repo, git_ref)
+                .map_err(|e| anyhow::anyhow!(i18n::tr("stack-ref-not-found", &[("git_ref", &git_ref), ("stack", &definition.name), ("error", &e)])))?;
+            if let Some(signers) = self.config.commit_signers() {
+                let signer = signing::verify_commit(&repo, commit.id(), &signers)?;
+                println!("{}", i18n::tr("commit-signed-by", &[("signer", &signer), ("stack", &definition.name)]));
             }
             
-            let secret_value = fs::read_to_string(&secret_path)?;
-            let secret_value = secret_value.trim(); // Remove trailing whitespace/newlines
-            
-            println!("    Secret value loaded from: {}", secret_path.display());
-            
-            // Add to environment variables list
-            env_vars.push((secret_def.env.clone(), secret_value.to_string()));
+            // Files of the watched checkout the ref doesn't have must not be deployed
+            let stack_dir = Path::new(repo_path).join(definition.directory());
+            if stack_dir.exists() {
+                fs::remove_dir_all(&stack_dir)?;
+            }
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.force().path(format!("{}/", prefix.join(definition.directory()).to_string_lossy().replace('\\', "/")));
+            repo.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+            println!("{}", i18n::tr("stack-checked-out", &[("stack", &definition.name), ("git_ref", &git_ref), ("id", &&commit.id().to_string()[..12])]));
         }
         
-        println!("    Successfully loaded {} secrets", env_vars.len());
-        Ok(env_vars)
+        Ok(())
     }
 
-    async fn read_nfs_config(&self, repo_path: &str) -> Result<NfsConfig> {
-        // Look for nfs.yaml file
-        let nfs_file_path = Path::new(repo_path).join("nfs.yaml");
-        if !nfs_file_path.exists() {
-            return Err(anyhow::anyhow!("nfs.yaml not found at: {}", nfs_file_path.display()));
-        }
-        
-        println!("  Reading nfs.yaml from: {}", nfs_file_path.display());
-        let nfs_content = fs::read_to_string(&nfs_file_path)?;
-        let config = serde_yaml::from_str::<NfsConfig>(&nfs_content)?;
-        println!("  NFS config: {:?}", config);
-        
-        Ok(config)
+    /// Stop watching one repository: its stacks are removed (and kept in the
+    /// trash) and the images only it used are deleted
+    pub async fn unwatch(&self, url: &str) -> Result<()> {
+        self.remove_repository(url, "unwatch").await
     }
 
-    async fn process_volumes_config(&self, repo_path: &str) -> Result<Option<Vec<VolumeDefinition>>> {
-        println!("  Looking for volumes.yaml in: {}", repo_path);
-        
-        // Look for volumes.yaml file
-        let volumes_file_path = Path::new(repo_path).join("volumes.yaml");
-        if !volumes_file_path.exists() {
-            println!("  No volumes.yaml found at {}, skipping volume processing", volumes_file_path.display());
-            return Ok(None);
-        }
+    /// Remove the stacks of one repository, the images no other repository
+    /// uses and the repository itself, leaving the other repositories running
+    async fn remove_repository(&self, url: &str, reason: &str) -> Result<()> {
+        let repo = self.find_watched_repository(&[url]).await?
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("repository-not-watched", &[("url", &url)])))?;
+        println!("{}", i18n::tr("unwatch-removing-stacks", &[("url", &repo.url)]));
         
-        println!("  Found volumes.yaml at: {}", volumes_file_path.display());
+        let images = self.db.get_repository_images(&repo.url).await?;
+        let stacks: Vec<Stack> = self.db.get_all_stacks().await?
+            .into_iter()
+            .filter(|s| s.repository_url == repo.url)
+            .collect();
+        println!("{}", i18n::tr("found-stacks-to-remove", &[("count", &stacks.len())]));
         
-        // Read and parse volumes.yaml
-        let volumes_content = fs::read_to_string(&volumes_file_path)?;
-        println!("  Read volumes.yaml content ({} characters)", volumes_content.len());
+        for stack in &stacks {
+            println!("{}", i18n::tr("removing-stack", &[("stack", &stack.name)]));
+            self.stop_stack(&stack.name).await?;
+            self.trash_stack(stack, reason).await?;
+        }
         
-        let volumes_definitions: Vec<VolumeDefinition> = serde_yaml::from_str(&volumes_content)?;
-        println!("  Parsed {} volume definitions from volumes.yaml", volumes_definitions.len());
+        self.db.refresh_image_reference_counts().await?;
+        self.remove_unreferenced_images(&images).await?;
+        self.db.remove_repository_from_cache(&repo.url).await?;
+        self.remove_working_copy(&repo.url);
         
-        // Look for nfs.yaml file
-        let nfs_file_path = Path::new(repo_path).join("nfs.yaml");
-        let nfs_config = if nfs_file_path.exists() {
-            println!("  Found nfs.yaml at: {}", nfs_file_path.display());
-            let nfs_content = fs::read_to_string(&nfs_file_path)?;
-            let config = serde_yaml::from_str::<NfsConfig>(&nfs_content)?;
-            println!("  NFS config: {:?}", config);
-            Some(config)
-        } else {
-            println!("  No nfs.yaml found at {}, NFS bindings will be skipped", nfs_file_path.display());
-            None
-        };
+        println!("{}", i18n::tr("repository-unwatched", &[("url", &repo.url)]));
+        if !stacks.is_empty() {
+            println!("   {}", i18n::tr("repository-unwatched-hint", &[]));
+        }
+        Ok(())
+    }
+
+    /// Remove one stack from Swarm and from the database, keeping it in the
+    /// trash, and remove the images no other stack uses anymore
+    pub async fn remove_stack(&self, stack_name: &str, repository_url: Option<&str>) -> Result<()> {
+        let stack = self.resolve_stack(stack_name, repository_url).await?;
+        println!("{}", i18n::tr("removing-stack-of-repository", &[("stack", &stack.name), ("url", &stack.repository_url)]));
         
-        println!("  Processing {} volume definitions", volumes_definitions.len());
+        let images = self.db.get_stack_images(&stack.name, &stack.repository_url).await?;
+        self.stop_stack(&stack.name).await?;
+        self.trash_stack(&stack, "rm").await?;
+        self.db.refresh_image_reference_counts().await?;
+        self.remove_unreferenced_images(&images).await?;
         
-        let mut volumes_definitions = volumes_definitions;
+        println!("{}", i18n::tr("stack-removed", &[("stack", &stack.name)]));
         
-        for volume_def in &mut volumes_definitions {
-            println!("  Processing volume definition: {:?}", volume_def);
-            
-            match volume_def.r#type {
-                VolumeType::Volume => {
-                    println!("  Processing volume: {} (type: volume, path: {})", 
-                        volume_def.id, volume_def.path);
-                    // For Docker volumes, we just need to ensure they exist
-                    //self.ensure_docker_volume_exists(&volume_def.path).await?;
-                }
-                VolumeType::Binding => {
-                    println!("  Processing binding: {} (type: binding, path: {})", 
-                        volume_def.id, volume_def.path);
-                    if let Some(nfs_config) = &nfs_config {
-                        self.process_binding_volume(volume_def, nfs_config, repo_path).await?;
-                    } else {
-                        println!("    Warning: No NFS configuration found, skipping binding volume");
-                    }
-                }
+        // The working copy tells whether the next synchronization deploys it again
+        if let Some(repo) = self.db.get_repository_from_cache(&stack.repository_url).await? {
+            let stacks_path = self.working_copy_path(&repo.url).join(repo.path.as_deref().unwrap_or_default());
+            let settings = RepositorySettings::load(&stacks_path).unwrap_or_default().in_namespace(repo.namespace.as_deref());
+            let declared = fs::read_to_string(stacks_path.join("stacks.yaml")).ok()
+                .and_then(|content| serde_yaml::from_str::<Vec<StackDefinition>>(&content).ok())
+                .is_some_and(|definitions| definitions.iter().any(|definition| settings.stack_name(&definition.name) == stack.name));
+            if declared {
+                println!("{}", i18n::tr("removed-stack-still-declared", &[("stack", &stack.name), ("url", &repo.url)]));
             }
         }
-        
-        println!("  Finished processing all volume definitions");
-        Ok(Some(volumes_definitions))
+        Ok(())
     }
 
-    async fn process_binding_volume(&self, volume_def: &mut VolumeDefinition, nfs_config: &NfsConfig, repo_path: &str) -> Result<()> {
-        let local_path = Path::new(repo_path).join(&volume_def.path);
-        
-        if !local_path.exists() {
-            println!("    Warning: Local path does not exist: {}", local_path.display());
-            return Ok(());
+    /// Remove every stack, image and repository, or only those of one repository
+    pub async fn stop(&self, repository_url: Option<&str>) -> Result<()> {
+        if let Some(url) = repository_url {
+            return self.remove_repository(url, "stop").await;
         }
+        println!("{}", i18n::tr("cleanup-started", &[]));
         
-        // Create NFS destination path
-        let nfs_dest_path = Path::new(&nfs_config.path).join(&volume_def.path);
+        // Get all stacks from database
+        let stacks = self.db.get_all_stacks().await?;
+        println!("{}", i18n::tr("found-stacks-to-remove", &[("count", &stacks.len())]));
         
-        println!("    Copying {} to NFS: {}", local_path.display(), nfs_dest_path.display());
+        // Remove all stacks
+        for stack in &stacks {
+            println!("{}", i18n::tr("removing-stack", &[("stack", &stack.name)]));
+            self.stop_stack(&stack.name).await?;
+            self.trash_stack(stack, "stop").await?;
+        }
         
-        // Remove existing file or directory on NFS if it exists
-        if nfs_dest_path.exists() {
-            let metadata = fs::metadata(&nfs_dest_path)?;
-            if metadata.is_dir() {
-                println!("    Removing existing directory on NFS: {}", nfs_dest_path.display());
-                fs::remove_dir_all(&nfs_dest_path)?;
+        // Get all images from database
+        let images = self.db.get_all_images().await?;
+        println!("{}", i18n::tr("found-images-to-remove", &[("count", &images.len())]));
+        
+        // Remove all images, except those other workloads still run
+        let users = self.unmanaged_image_users(&stacks).await;
+        let mut kept = 0;
+        for image in &images {
+            if let Some(users) = image_kept_for(&image.name, &users) {
+                println!("{}", i18n::tr("cleanup-keeping-image", &[("name", &image.name), ("users", &users)]));
+                kept += 1;
+                continue;
+            }
+            println!("{}", i18n::tr("removing-image", &[("name", &image.name)]));
+            self.remove_image(&image.name).await?;
+        }
+        if kept > 0 {
+            println!("{}", i18n::tr("cleanup-images-kept", &[("kept", &kept)]));
+        }
+        
+        // Clean up database
+        println!("{}", i18n::tr("cleaning-up-database", &[]));
+        self.db.delete_all_stacks().await?;
+        self.db.refresh_image_reference_counts().await?;
+        self.db.delete_images_with_zero_count().await?;
+        self.db.clear_repository_cache().await?;
+        if let Err(e) = fs::remove_dir_all(self.config.repos_dir()) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                println!("{}", i18n::tr("cleanup-working-copies-not-removed", &[("error", &e)]));
+            }
+        }
+        
+        // Verify cache is cleared
+        let repositories = self.db.get_all_repositories().await?;
+        if !repositories.is_empty() {
+            println!("{}", i18n::tr("cleanup-cache-not-empty", &[("count", &repositories.len())]));
+            self.db.clear_repository_cache().await?;
+            
+            // Verify again after forced cleanup
+            let repositories_after = self.db.get_all_repositories().await?;
+            if !repositories_after.is_empty() {
+                println!("{}", i18n::tr("cleanup-cache-failed", &[("count", &repositories_after.len())]));
+                for repo in &repositories_after {
+                    println!("  - {}", repo.url);
+                }
             } else {
-                println!("    Removing existing file on NFS: {}", nfs_dest_path.display());
-                fs::remove_file(&nfs_dest_path)?;
+                println!("{}", i18n::tr("cleanup-cache-cleared", &[]));
             }
         }
         
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = nfs_dest_path.parent() {
-            fs::create_dir_all(parent)?;
+        println!("{}", i18n::tr("cleanup-done", &[]));
+        println!("{}", i18n::tr("cleanup-restore-hint", &[]));
+        println!("{}", i18n::tr("cleanup-database-closing", &[]));
+        Ok(())
+    }
+
+    pub fn show_version() {
+        println!("{}", i18n::tr("cli-version", &[("version", &env!("CARGO_PKG_VERSION"))]));
+        println!("{}", i18n::tr("cli-description", &[]));
+        println!("{}", i18n::tr("cli-repository", &[]));
+    }
+
+    /// Print the JSON schema of a repository file, or the files having one
+    pub fn print_schema(file: Option<&str>) -> Result<()> {
+        let Some(file) = file else {
+            for (name, path) in schema::FILES {
+                println!("{:<10} {}", name, path);
+            }
+            return Ok(());
+        };
+        let schema = schema::for_file(file).ok_or_else(|| anyhow::anyhow!(i18n::tr("schema-unknown-file", &[("file", &file), ("list", &schema::FILES.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", "))])))?;
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        Ok(())
+    }
+
+    /// Create config.yaml interactively, checking every answer before moving
+    /// on: database location, docker endpoint, git credentials, NFS share,
+    /// failure notifications, then optionally watch a first repository
+    pub async fn setup(mut config: Config) -> Result<()> {
+        let path = config.file_path();
+        println!("{}", i18n::tr("setup-started", &[("path", &path.display())]));
+        if path.exists() && !ask_yes_no(&i18n::tr("setup-ask-update-config", &[]), true)? {
+            return Ok(());
         }
         
-        // Copy recursively
-        if local_path.is_dir() {
-            self.copy_directory_recursive(&local_path, &nfs_dest_path).await?;
-        } else {
-            // For files, copy to parent directory
-            if let Some(parent) = nfs_dest_path.parent() {
-                fs::create_dir_all(parent)?;
+        println!("\n{}", i18n::tr("setup-step-database", &[]));
+        loop {
+            let current = config.database_path().display().to_string();
+            let answer = ask(&i18n::tr("setup-ask-database", &[]), Some(&current))?;
+            let db_path = PathBuf::from(&answer);
+            let writable = db_path.parent().map(|dir| dir.as_os_str().is_empty() || fs::create_dir_all(dir).is_ok()).unwrap_or(false)
+                && fs::OpenOptions::new().create(true).append(true).open(&db_path).is_ok();
+            if writable {
+                if answer != current {
+                    config.db_path = Some(answer);
+                }
+                break;
             }
-            fs::copy(&local_path, &nfs_dest_path)?;
+            println!("  {}", i18n::tr("setup-database-not-writable", &[("db_path", &db_path.display())]));
         }
         
-        // Fix permissions for Docker compatibility
-        self.fix_permissions_recursive(&nfs_dest_path).await?;
+        println!("\n{}", i18n::tr("setup-step-docker", &[]));
+        loop {
+            let answer = ask(&i18n::tr("setup-ask-docker-host", &[]), config.docker_host.as_deref())?;
+            config.docker_host = Some(answer).filter(|host| !host.is_empty());
+            let mut docker = Command::new("docker");
+            if let Some(host) = &config.docker_host {
+                docker.env("DOCKER_HOST", host);
+            }
+            match docker.args(["info", "--format", "{{.ServerVersion}}\t{{.Swarm.LocalNodeState}}\t{{.Swarm.ControlAvailable}}"]).output() {
+                Ok(output) if output.status.success() => {
+                    let info = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                    let fields: Vec<&str> = info.split('\t').collect();
+                    println!("  {}", i18n::tr("setup-docker-reached", &[("version", &fields.first().copied().unwrap_or_default())]));
+                    if fields.get(1) == Some(&"active") && fields.get(2) == Some(&"true") {
+                        break;
+                    }
+                    println!("  {}", i18n::tr("setup-not-swarm-manager", &[]));
+                }
+                Ok(output) => println!("  ❌ {}", String::from_utf8_lossy(&output.stderr).trim()),
+                Err(e) => println!("  {}", i18n::tr("setup-docker-failed", &[("error", &e)])),
+            }
+            if !ask_yes_no(&i18n::tr("setup-ask-another-endpoint", &[]), true)? {
+                break;
+            }
+        }
         
-        // Update the volume definition path to point to NFS
-        volume_def.path = nfs_dest_path.to_string_lossy().to_string();
+        println!("\n{}", i18n::tr("setup-step-git", &[]));
+        loop {
+            let answer = ask(&i18n::tr("setup-ask-github-token", &[]), config.github_token.as_deref())?;
+            config.github_token = Some(answer).filter(|token| !token.is_empty());
+            let Some(token) = &config.github_token else {
+                break;
+            };
+            match GithubClient::new(Arc::new(GithubApi::default()), Some(token.clone())).authenticated_user().await {
+                Ok(login) => {
+                    println!("  {}", i18n::tr("setup-github-token-valid", &[("login", &login)]));
+                    break;
+                }
+                Err(e) => println!("  {}", i18n::tr("setup-github-token-rejected", &[("error", &e)])),
+            }
+        }
+        loop {
+            let answer = ask(&i18n::tr("setup-ask-ssh-key", &[]), config.ssh_key.as_deref())?;
+            config.ssh_key = Some(answer).filter(|key| !key.is_empty());
+            match config.ssh_key() {
+                Some(key) if !key.is_file() => println!("  {}", i18n::tr("setup-key-missing", &[("key", &key.display())])),
+                _ => break,
+            }
+        }
+        
+        println!("\n{}", i18n::tr("setup-step-nfs", &[]));
+        loop {
+            let answer = ask(&i18n::tr("setup-ask-nfs", &[]), None)?;
+            if answer.is_empty() {
+                break;
+            }
+            if Path::new(&answer).is_dir() {
+                println!("  {}", i18n::tr("setup-nfs-hint", &[]));
+                println!("      path: \"{}\"", answer);
+                break;
+            }
+            println!("  {}", i18n::tr("setup-nfs-not-a-directory", &[("answer", &answer)]));
+        }
+        
+        println!("\n{}", i18n::tr("setup-step-notifications", &[]));
+        let issues = ask_yes_no(&i18n::tr("setup-ask-failure-issues", &[]), config.failure_issues.unwrap_or(false))?;
+        if issues && config.github_token().is_none() && config.github_app().ok().flatten().is_none() {
+            println!("  {}", i18n::tr("setup-issues-need-token", &[]));
+        }
+        config.failure_issues = Some(issues);
+        loop {
+            let answer = ask(&i18n::tr("setup-ask-failure-threshold", &[]), Some(&config.failure_threshold.unwrap_or(3).to_string()))?;
+            match answer.parse::<u32>() {
+                Ok(threshold) if threshold > 0 => {
+                    config.failure_threshold = Some(threshold);
+                    break;
+                }
+                _ => println!("  {}", i18n::tr("setup-expected-positive-number", &[])),
+            }
+        }
         
-        println!("    Successfully copied to NFS: {}", nfs_dest_path.display());
+        config.save()?;
+        println!("\n{}", i18n::tr("setup-config-written", &[("path", &path.display())]));
         
+        println!("\n{}", i18n::tr("setup-step-repository", &[]));
+        let url = ask(&i18n::tr("setup-ask-repository", &[]), None)?;
+        if url.is_empty() {
+            println!("{}", i18n::tr("setup-watch-hint", &[]));
+            return Ok(());
+        }
+        let path = ask(&i18n::tr("setup-ask-path", &[]), None)?;
+        let database = Database::new(&format!("sqlite:{}", config.database_path().display())).await?;
+        let commands = Commands::new(database, config);
+        let entry = WatchEntry { url, path: Some(path).filter(|path| !path.is_empty()), ..Default::default() };
+        commands.watch(&entry, &StackFilter::default()).await
+    }
+
+    /// Turn an existing docker-compose project into a DockerOps stack inside `repo`:
+    /// creates the stack directory, appends the stacks.yaml entry and extracts
+    /// bind mounts and named volumes into volumes.yaml definitions.
+    pub fn import_project(compose_file: &str, repo: &str, name: Option<&str>) -> Result<()> {
+        let compose_path = Path::new(compose_file);
+        if !compose_path.is_file() {
+            return Err(anyhow::anyhow!(i18n::tr("compose-file-not-found", &[("compose_file", &compose_file)])));
+        }
+        let compose_dir = compose_path.canonicalize()?
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| Path::new(".").to_path_buf());
+
+        // Default the stack name to the compose project name (its directory)
+        let stack_name = match name {
+            Some(name) => name.to_string(),
+            None => compose_dir.file_name()
+                .map(|n| n.to_string_lossy().to_lowercase().replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_"))
+                .ok_or_else(|| anyhow::anyhow!(i18n::tr("import-stack-name-unknown", &[])))?,
+        };
+        println!("{}", i18n::tr("import-started", &[("stack", &stack_name), ("repo", &repo)]));
+
+        // Everything is checked and planned before the first file is written
+        let repo_path = Path::new(repo);
+        let stack_dir = repo_path.join(&stack_name);
+        if stack_dir.exists() {
+            return Err(anyhow::anyhow!(i18n::tr("import-directory-exists", &[("stack_dir", &stack_dir.display())])));
+        }
+
+        let stacks_file_path = repo_path.join("stacks.yaml");
+        let existing_stacks: Vec<StackDefinition> = if stacks_file_path.exists() {
+            serde_yaml::from_str(&fs::read_to_string(&stacks_file_path)?)?
+        } else {
+            Vec::new()
+        };
+        if existing_stacks.iter().any(|s| s.name == stack_name) {
+            return Err(anyhow::anyhow!(i18n::tr("import-stack-already-declared", &[("stack", &stack_name)])));
+        }
+
+        let volumes_file_path = repo_path.join("volumes.yaml");
+        let existing_volumes: Vec<VolumeDefinition> = if volumes_file_path.exists() {
+            serde_yaml::from_str(&fs::read_to_string(&volumes_file_path)?)?
+        } else {
+            Vec::new()
+        };
+
+        let mut yaml_value: Value = serde_yaml::from_str(&fs::read_to_string(compose_path)?)?;
+        let mut new_volumes: Vec<VolumeDefinition> = Vec::new();
+        let mut named_volumes: Vec<String> = Vec::new();
+        // Host path of each bind mount, with its volume id and its copy in the repository
+        let mut bindings: Vec<(PathBuf, String, PathBuf)> = Vec::new();
+
+        if let Some(services) = yaml_value.get_mut("services").and_then(|s| s.as_mapping_mut()) {
+            for (service_name, service) in services {
+                let service_name = service_name.as_str().unwrap_or("unknown").to_string();
+                let Some(volumes) = service.get_mut("volumes").and_then(|v| v.as_sequence_mut()) else {
+                    continue;
+                };
+
+                for volume in volumes.iter_mut() {
+                    // Normalize short and long syntax to (type, source, target, options)
+                    let (kind, source, target, options) = if let Some(volume_str) = volume.as_str() {
+                        let parts: Vec<&str> = volume_str.split(':').collect();
+                        if parts.len() < 2 {
+                            continue; // Anonymous volume, nothing to extract
+                        }
+                        let source = parts[0].to_string();
+                        let kind = if source.starts_with('/') || source.starts_with('.') || source.starts_with('~') { "bind" } else { "volume" };
+                        (kind.to_string(), source, parts[1].to_string(), parts.get(2).map(|o| o.to_string()))
+                    } else {
+                        let kind = volume.get("type").and_then(|v| v.as_str()).unwrap_or("volume").to_string();
+                        let Some(source) = volume.get("source").and_then(|v| v.as_str()) else {
+                            continue;
+                        };
+                        let target = volume.get("target").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                        let read_only = volume.get("read_only").and_then(|v| v.as_bool()).unwrap_or(false);
+                        (kind, source.to_string(), target, read_only.then(|| "ro".to_string()))
+                    };
+
+                    let original_source = source.clone();
+                    let volume_id = match kind.as_str() {
+                        "bind" => {
+                            let host_path = if let Some(rest) = source.strip_prefix('~') {
+                                Path::new(&std::env::var("HOME").unwrap_or_default()).join(rest.trim_start_matches('/'))
+                            } else {
+                                compose_dir.join(source.trim_start_matches("./"))
+                            };
+                            if host_path.exists() && !host_path.is_dir() && !host_path.is_file() {
+                                // Sockets and devices (docker.sock...) can't be versioned, the service keeps the host's
+                                println!("  {}", i18n::tr("import-kept-host-bind-mount", &[("service_name", &service_name), ("host_path", &host_path.display())]));
+                                // A relative source would resolve against the stack directory
+                                let host_source = host_path.to_string_lossy().to_string();
+                                match volume.as_mapping_mut() {
+                                    Some(mapping) => {
+                                        mapping.insert(Value::from("source"), Value::from(host_source));
+                                    }
+                                    None => *volume = Value::String(match &options {
+                                        Some(options) => format!("{}:{}:{}", host_source, target, options),
+                                        None => format!("{}:{}", host_source, target),
+                                    }),
+                                }
+                                continue;
+                            }
+                            match bindings.iter().find(|(path, _, _)| *path == host_path) {
+                                Some((_, volume_id, _)) => volume_id.clone(),
+                                None => {
+                                    // Directories with the same name in different places get distinct ids
+                                    let base_name = host_path.file_name()
+                                        .map(|n| n.to_string_lossy().to_string())
+                                        .unwrap_or_else(|| service_name.clone());
+                                    let taken = |id: &str| new_volumes.iter().chain(&existing_volumes).any(|v| v.id == id);
+                                    let name = (1..)
+                                        .map(|n| if n == 1 { base_name.clone() } else { format!("{}_{}", base_name, n) })
+                                        .find(|name| !taken(&format!("{}_{}", stack_name, name)))
+                                        .unwrap_or(base_name);
+                                    let volume_id = format!("{}_{}", stack_name, name);
+                                    let relative_path = format!("{}/{}", stack_name, name);
+                                    
+                                    bindings.push((host_path, volume_id.clone(), repo_path.join(&relative_path)));
+                                    new_volumes.push(VolumeDefinition { id: volume_id.clone(), r#type: VolumeType::Binding, path: relative_path });
+                                    volume_id
+                                }
+                            }
+                        }
+                        "volume" => {
+                            if !new_volumes.iter().any(|v| v.id == source) {
+                                new_volumes.push(VolumeDefinition { id: source.clone(), r#type: VolumeType::Volume, path: source.clone() });
+                            }
+                            named_volumes.push(source.clone());
+                            source
+                        }
+                        other => {
+                            println!("  {}", i18n::tr("import-unsupported-volume-type", &[("other", &other), ("service_name", &service_name)]));
+                            continue;
+                        }
+                    };
+
+                    let reference = match options {
+                        Some(options) => format!("{}:{}:{}", volume_id, target, options),
+                        None => format!("{}:{}", volume_id, target),
+                    };
+                    println!("  {}", i18n::tr("import-service-volume", &[("service_name", &service_name), ("original_source", &original_source), ("reference", &reference)]));
+                    *volume = Value::String(reference);
+                }
+            }
+        }
+
+        // Named volumes are re-declared by DockerOps from volumes.yaml
+        if let Some(volumes_section) = yaml_value.get_mut("volumes").and_then(|v| v.as_mapping_mut()) {
+            for named_volume in &named_volumes {
+                volumes_section.remove(named_volume.as_str());
+            }
+            if volumes_section.is_empty() {
+                if let Some(root) = yaml_value.as_mapping_mut() {
+                    root.remove("volumes");
+                }
+            }
+        }
+
+        let conflicting: Vec<&str> = new_volumes.iter()
+            .filter(|v| existing_volumes.iter().any(|e| e.id == v.id))
+            .map(|v| v.id.as_str())
+            .collect();
+        if !conflicting.is_empty() {
+            println!("  {}", i18n::tr("import-volume-ids-already-defined", &[("conflicting", &format!("{:?}", conflicting))]));
+        }
+        new_volumes.retain(|v| !existing_volumes.iter().any(|e| e.id == v.id));
+        let compose_content = serde_yaml::to_string(&yaml_value)?;
+
+        fs::create_dir_all(&stack_dir)?;
+        // Copy the current content of the bind mounts so that it gets versioned with the stack
+        for (host_path, _, destination) in &bindings {
+            if host_path.is_dir() {
+                for skipped in copy_tree(host_path, destination, |_| true)? {
+                    println!("  {}", i18n::tr("skipped-special-file", &[("path", &skipped.display())]));
+                }
+                println!("  {}", i18n::tr("import-copied-directory", &[("host_path", &host_path.display()), ("destination", &destination.display())]));
+            } else if host_path.is_file() {
+                fs::copy(host_path, destination)?;
+                println!("  {}", i18n::tr("import-copied-file", &[("host_path", &host_path.display()), ("destination", &destination.display())]));
+            } else {
+                println!("  {}", i18n::tr("import-bind-source-missing", &[("host_path", &host_path.display())]));
+                fs::create_dir_all(destination)?;
+            }
+        }
+
+        let stack_compose_path = stack_dir.join("docker-compose.yml");
+        atomic::write(&stack_compose_path, compose_content)?;
+        println!("  {}", i18n::tr("import-wrote", &[("stack_compose_path", &stack_compose_path.display())]));
+
+        // Append rather than rewrite so existing formatting and comments are preserved
+        append_yaml_list(&stacks_file_path, &serde_yaml::to_string(&vec![StackDefinition { name: stack_name.clone(), ..Default::default() }])?)?;
+        println!("  {}", i18n::tr("import-added-to", &[("stack", &stack_name), ("stacks_file_path", &stacks_file_path.display())]));
+
+        if !new_volumes.is_empty() {
+            append_yaml_list(&volumes_file_path, &serde_yaml::to_string(&new_volumes)?)?;
+            println!("  {}", i18n::tr("import-added-volumes", &[("count", &new_volumes.len()), ("volumes_file_path", &volumes_file_path.display())]));
+        }
+
+        if new_volumes.iter().any(|v| matches!(v.r#type, VolumeType::Binding)) && !repo_path.join("nfs.yaml").exists() {
+            println!("  {}", i18n::tr("import-bindings-need-nfs", &[]));
+        }
+
+        println!("{}", i18n::tr("import-completed", &[]));
         Ok(())
     }
 
-    async fn copy_directory_recursive(&self, src: &Path, dst: &Path) -> Result<()> {
-        if !src.is_dir() {
-            return Err(anyhow::anyhow!("Source is not a directory: {}", src.display()));
+    /// List the endpoints of config.yaml and the docker CLI contexts, marking the selected one
+    pub fn contexts(config: &Config) -> Result<()> {
+        let current = config.context();
+        let marker = |name: &str| if current == Some(name) { "*" } else { " " };
+        
+        let configured = config.contexts.clone().unwrap_or_default();
+        println!("{}", i18n::tr("contexts-configured", &[]));
+        if configured.is_empty() {
+            println!("  {}", i18n::tr("list-none", &[]));
+        }
+        for (name, host) in &configured {
+            println!("{} {} ({})", marker(name), name, host);
         }
         
-        fs::create_dir_all(dst)?;
+        println!("\n{}", i18n::tr("docker-contexts", &[]));
+        for (name, endpoint) in docker_contexts()? {
+            println!("{} {} ({})", marker(&name), name, endpoint);
+        }
         
-        for entry in fs::read_dir(src)? {
-            let entry = entry?;
-            let file_type = entry.file_type()?;
-            let src_path = entry.path();
-            let dst_path = dst.join(entry.file_name());
-            
-            if file_type.is_dir() {
-                // Use Box::pin for recursive async call
-                Box::pin(self.copy_directory_recursive(&src_path, &dst_path)).await?;
-            } else {
-                fs::copy(&src_path, &dst_path)?;
+        if current.is_none() {
+            match &config.docker_host {
+                Some(host) => println!("\n{}", i18n::tr("context-none-docker-host", &[("host", &host)])),
+                None => println!("\n{}", i18n::tr("context-none-docker-defaults", &[])),
             }
         }
         
         Ok(())
     }
 
-    async fn fix_permissions_recursive(&self, path: &Path) -> Result<()> {
-        println!("    Fixing permissions for Docker compatibility...");
+    /// Persist the context targeted by every following command, `default`
+    /// going back to `docker_host` / the environment
+    pub async fn use_context(config: &Config, name: &str) -> Result<()> {
+        let context = Some(name).filter(|name| *name != "default");
+        if let Some(name) = context {
+            let configured = config.contexts.as_ref().is_some_and(|contexts| contexts.contains_key(name));
+            if !configured && !docker_contexts()?.iter().any(|(context, _)| context == name) {
+                return Err(anyhow::anyhow!(i18n::tr("unknown-context", &[("name", &name)])));
+            }
+        }
         
-        // Use chmod command to set appropriate permissions
-        let output = Command::new("chmod")
-            .args(&["-R", "755", path.to_str().unwrap()])
-            .output()?;
+        // Each context has its own database, unless one was set explicitly:
+        // the repositories it watches would then be deployed to the new cluster
+        if let Some(path) = config.explicit_database_path().filter(|path| path.exists() && config.context() != context) {
+            let db = Database::new(&format!("sqlite:{}", path.display())).await?;
+            let watched = db.get_all_repositories().await?.len();
+            if watched > 0 {
+                return Err(anyhow::anyhow!(i18n::tr("cannot-switch-context-shared-database", &[("path", &path.display()), ("count", &watched)])));
+            }
+        }
         
-        if output.status.success() {
-            println!("    Successfully set directory permissions to 755");
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            println!("    Warning: Failed to set directory permissions: {}", error);
+        config.set_context(context)?;
+        match context {
+            Some(name) => println!("{}", i18n::tr("context-selected", &[("name", &name)])),
+            None => println!("{}", i18n::tr("context-selection-cleared", &[])),
+        }
+        if config.explicit_database_path().is_none() {
+            println!("   {}", i18n::tr("context-state-kept-in", &[("dir", &config.state_dir_of(context).display())]));
         }
+        Ok(())
+    }
+
+    pub async fn debug_cache(&self) -> Result<()> {
+        println!("{}", i18n::tr("debug-checking-repository-cache", &[]));
         
-        // For files, set 644 permissions (readable by all, writable by owner)
-        let output = Command::new("find")
-            .args(&[path.to_str().unwrap(), "-type", "f", "-exec", "chmod", "644", "{}", ";"])
-            .output()?;
+        let repositories = self.db.get_all_repositories().await?;
+        println!("{}", i18n::tr("found-repositories-in-cache", &[("count", &repositories.len())]));
         
-        if output.status.success() {
-            println!("    Successfully set file permissions to 644");
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            println!("    Warning: Failed to set file permissions: {}", error);
+        for repo in &repositories {
+            println!("  - {} [{}] ({})", repo.url, repo.tracking(), self.sync_state(repo));
         }
         
-        // Change ownership to a more Docker-friendly user/group if possible
-        // Try to use the current user or a common Docker user
-        let current_user = std::env::var("SUDO_USER").ok()
-            .or_else(|| std::env::var("USER").ok())
-            .unwrap_or_else(|| "1000".to_string());
+        Ok(())
+    }
+
+    /// Latest deploy attempts, of all stacks or of one, newest first
+    pub async fn history(&self, stack_name: Option<&str>, repository_url: Option<&str>, limit: i64) -> Result<()> {
+        let deployments = self.db.get_deployments(stack_name, repository_url, limit).await?;
+        if deployments.is_empty() {
+            println!("{}", i18n::tr("no-deployments", &[]));
+            return Ok(());
+        }
         
-        let output = Command::new("chown")
-            .args(&["-R", &format!("{}:{}", current_user, current_user), path.to_str().unwrap()])
-            .output()?;
+        for deployment in &deployments {
+            let status = if deployment.result == "success" { "✅" } else { "❌" };
+            let commit = deployment.commit_hash.as_deref().map(|commit| format!(", commit {}", &commit[..commit.len().min(12)])).unwrap_or_default();
+            let canary = match deployment.canary_passed {
+                Some(true) => ", canary passed",
+                Some(false) => ", canary failed",
+                None => "",
+            };
+            let operator = deployment.operator.as_deref().map(|operator| format!(", by {}", operator)).unwrap_or_default();
+            println!("{}", i18n::tr("history-line", &[("status", &status), ("time", &self.config.format_timestamp(&deployment.started_at)), ("stack_name", &deployment.stack_name), ("kind", &deployment.kind), ("seconds", &format!("{:.1}", deployment.duration_ms as f64 / 1000.0)), ("hash", &deployment.hash), ("commit", &commit), ("canary", &canary), ("operator", &operator)]));
+            if repository_url.is_none() {
+                println!("     {}", i18n::tr("history-from", &[("url", &deployment.repository_url)]));
+            }
+            if let Some(error) = &deployment.error {
+                println!("     {}", last_lines(error, 5).replace('\n', "\n     "));
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn trash(&self) -> Result<()> {
+        self.purge_expired_trash().await?;
         
-        if output.status.success() {
-            println!("    Successfully changed ownership to {}", current_user);
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            println!("    Warning: Failed to change ownership: {}", error);
+        let trashed = self.db.get_trashed_stacks().await?;
+        if trashed.is_empty() {
+            println!("{}", i18n::tr("trash-empty", &[]));
+            return Ok(());
+        }
+        
+        println!("{}", i18n::tr("trash-count", &[("count", &trashed.len())]));
+        for stack in &trashed {
+            let restorable = if stack.compose_content.is_some() { "" } else { " (no snapshot, cannot be restored)" };
+            let deleted_by = stack.deleted_by.as_deref().map(|operator| format!(", by: {}", operator)).unwrap_or_default();
+            println!("  {}", i18n::tr("trash-line", &[("stack", &stack.name), ("url", &stack.repository_url), ("time", &self.config.format_timestamp(&stack.deleted_at)), ("reason", &stack.reason), ("deleted_by", &deleted_by), ("restorable", &restorable)]));
         }
         
         Ok(())
     }
 
-    async fn process_compose_volumes(&self, compose_content: &str, volumes_definitions: &[VolumeDefinition], nfs_config: &NfsConfig) -> Result<String> {
-        println!("    Parsing docker-compose content...");
+    /// Redeploy the most recently trashed stack with this name from its
+    /// stored compose snapshot
+    pub async fn restore_stack(&self, stack_name: &str, repository_url: Option<&str>) -> Result<()> {
+        let mut candidates: Vec<TrashedStack> = self.db.get_trashed_stacks().await?
+            .into_iter()
+            .filter(|s| s.name == stack_name && repository_url.is_none_or(|url| s.repository_url == url))
+            .collect();
         
-        // Parse the compose content to find volume references
-        let mut yaml_value: serde_yaml::Value = serde_yaml::from_str(compose_content)?;
-        println!("    Successfully parsed YAML content");
+        if candidates.is_empty() {
+            return Err(anyhow::anyhow!(i18n::tr("trash-stack-not-found", &[("stack", &stack_name)])));
+        }
+        if candidates.iter().any(|s| s.repository_url != candidates[0].repository_url) {
+            return Err(anyhow::anyhow!(i18n::tr("trash-stack-ambiguous", &[("stack", &stack_name)])));
+        }
+        // Trash entries are sorted newest first
+        let trashed = candidates.remove(0);
         
-        // Process services section
-        if let Some(services) = yaml_value.get_mut("services") {
-            println!("    Found services section, processing {} services", 
-                services.as_mapping().map(|m| m.len()).unwrap_or(0));
-            
-            if let Some(services_mapping) = services.as_mapping_mut() {
-                for (service_name, service) in services_mapping {
-                    let service_name_str = service_name.as_str().unwrap_or("unknown");
-                    println!("    Processing service: {}", service_name_str);
-                    
-                    if let Some(volumes) = service.get_mut("volumes") {
-                        println!("    Found volumes section in service {}", service_name_str);
-                        self.process_service_volumes(volumes, volumes_definitions, nfs_config).await?;
-                    } else {
-                        println!("    No volumes section found in service {}", service_name_str);
-                    }
-                }
-            }
-        } else {
-            println!("    No services section found in docker-compose");
+        if self.db.get_stack_by_name(&trashed.name, &trashed.repository_url).await?.is_some() {
+            return Err(anyhow::anyhow!(i18n::tr("restore-stack-already-deployed", &[("stack", &trashed.name), ("url", &trashed.repository_url)])));
         }
+        let compose_content = trashed.compose_content.as_deref()
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("restore-no-snapshot", &[("stack", &trashed.name)])))?;
         
-        // Add volumes section to docker-compose if it doesn't exist
-        self.add_volumes_section(&mut yaml_value, volumes_definitions).await?;
+        println!("{}", i18n::tr("restore-started", &[("stack", &trashed.name), ("url", &trashed.repository_url), ("time", &self.config.format_timestamp(&trashed.deleted_at))]));
         
-        // Convert back to string
-        println!("    Converting modified YAML back to string...");
-        let modified_content = serde_yaml::to_string(&yaml_value)?;
-        println!("    Successfully converted YAML to string ({} characters)", modified_content.len());
+        let since = chrono::Utc::now();
+        let snapshot_dir = trashed.snapshot_dir.as_deref().map(Path::new).filter(|dir| dir.is_dir());
+        if trashed.snapshot_dir.is_some() && snapshot_dir.is_none() {
+            println!("  {}", i18n::tr("restore-directory-gone", &[]));
+        }
+        let deployed = self.deploy_snapshot(&trashed.name, &trashed.repository_url, compose_content, snapshot_dir).await;
+        self.record_deployment(Deployment::new(&trashed.name, &trashed.repository_url, &trashed.hash, "restore", since), &deployed).await?;
+        deployed?;
         
-        Ok(modified_content)
+        let stack = Stack::new(
+            trashed.name.clone(),
+            trashed.repository_url.clone(),
+            trashed.compose_path.clone(),
+            trashed.hash.clone(),
+        );
+        self.db.create_stack(&stack).await?;
+        // Restored stacks survive syncs until they are added back to stacks.yaml
+        self.db.update_stack_status(&trashed.name, &trashed.repository_url, "restored").await?;
+        self.db.update_stack_compose_content(&trashed.name, &trashed.repository_url, compose_content).await?;
+        let images = self.process_yaml_file(compose_content, &trashed.compose_path).await?;
+        self.db.set_stack_images(&trashed.name, &trashed.repository_url, &images).await?;
+        self.db.refresh_image_reference_counts().await?;
+        self.db.delete_trashed_stack(trashed.id).await?;
+        remove_snapshot_dir(&trashed);
+        
+        println!("{}", i18n::tr("stack-restored", &[("stack", &trashed.name)]));
+        println!("   {}", i18n::tr("stack-restored-hint", &[]));
+        Ok(())
     }
 
-    async fn process_service_volumes(&self, volumes: &mut serde_yaml::Value, volumes_definitions: &[VolumeDefinition], nfs_config: &NfsConfig) -> Result<()> {
-        println!("      Processing service volumes...");
+    /// Remove the dangling images, stopped containers of the managed stacks
+    /// and build cache enabled in the `gc` settings, whatever their schedule
+    /// and threshold. `dry_run` only reports what could be reclaimed.
+    pub async fn gc(&self, dry_run: bool, history: bool) -> Result<()> {
+        if history {
+            let runs = self.db.get_gc_runs(20).await?;
+            if runs.is_empty() {
+                println!("{}", i18n::tr("gc-none-recorded", &[]));
+                return Ok(());
+            }
+            let rows: Vec<[String; 5]> = runs.iter()
+                .map(|run| [self.config.format_timestamp(&run.started_at), run.trigger.clone(), run.operator.clone().unwrap_or_default(),
+                    format_size(run.reclaimed_bytes.max(0) as u64), gc_report_summary(&run.report)])
+                .collect();
+            print_table(["DATE", "TRIGGER", "BY", "RECLAIMED", "DETAILS"], &rows);
+            return Ok(());
+        }
         
-        match volumes {
-            serde_yaml::Value::Sequence(seq) => {
-                println!("      Found {} volume entries", seq.len());
-                
-                for (index, volume) in seq.iter_mut().enumerate() {
-                    println!("      Processing volume entry {}: {:?}", index, volume);
-                    
-                    if let Some(volume_str) = volume.as_str() {
-                        println!("      Volume string: '{}'", volume_str);
-                        
-                        // Check if this is a volume reference (format: volume_id:container_path)
-                        if volume_str.contains(':') {
-                            let parts: Vec<&str> = volume_str.split(':').collect();
-                            println!("      Split into {} parts: {:?}", parts.len(), parts);
-                            
-                            if parts.len() >= 2 && parts.len() <= 3 {
-                                let volume_id = parts[0];
-                                let container_path = parts[1];
-                                let options = if parts.len() == 3 { parts[2] } else { "" };
-                                
-                                println!("      Volume ID: '{}', Container path: '{}', Options: '{}'", 
-                                    volume_id, container_path, options);
-                                
-                                // Find the volume definition
-                                if let Some(volume_def) = volumes_definitions.iter().find(|v| v.id == volume_id) {
-                                    println!("      Found volume definition: {:?}", volume_def);
-                                    
-                                    match volume_def.r#type {
-                                        VolumeType::Volume => {
-                                            // For Docker volumes, use the path as volume name
-                                            let volume_path = if !options.is_empty() {
-                                                format!("{}:{}:{}", volume_def.path, container_path, options)
-                                            } else {
-                                                format!("{}:{}", volume_def.path, container_path)
-                                            };
-                                            println!("      Replacing Docker volume {} with: {}", volume_id, volume_path);
-                                            *volume = serde_yaml::Value::String(volume_path);
-                                        }
-                                        VolumeType::Binding => {
-                                            // For bindings, replace with NFS path
-                                            // Construct the NFS path: nfs_config.path + volume_def.path
-                                            let full_nfs_path = Path::new(&nfs_config.path).join(&volume_def.path);
-                                            println!("      NFS config path: {}", nfs_config.path);
-                                            println!("      Volume path: {}", volume_def.path);
-                                            println!("      Full NFS path: {}", full_nfs_path.display());
-                                            
-                                            // Create the NFS directory if it doesn't exist
-                                            if !full_nfs_path.exists() {
-                                                println!("      Creating NFS directory: {}", full_nfs_path.display());
-                                                fs::create_dir_all(&full_nfs_path)?;
-                                                println!("      Successfully created NFS directory");
-                                            } else {
-                                                println!("      NFS directory already exists: {}", full_nfs_path.display());
-                                            }
-                                            
-                                            let nfs_path = if !options.is_empty() {
-                                                format!("{}:{}:{}", full_nfs_path.display(), container_path, options)
-                                            } else {
-                                                format!("{}:{}", full_nfs_path.display(), container_path)
-                                            };
-                                            println!("      Replacing binding volume {} with NFS path: {}", volume_id, nfs_path);
-                                            *volume = serde_yaml::Value::String(nfs_path);
-                                        }
-                                    }
-                                } else {
-                                    println!("      Warning: Volume definition not found for ID: '{}'", volume_id);
-                                    println!("      Available volume definitions: {:?}", 
-                                        volumes_definitions.iter().map(|v| &v.id).collect::<Vec<_>>());
-                                }
-                            } else {
-                                println!("      Volume string does not have 2 or 3 parts, skipping");
-                            }
-                        } else {
-                            println!("      Volume string does not contain ':', skipping");
-                        }
-                    } else {
-                        println!("      Volume entry is not a string, skipping");
-                    }
+        let settings = self.config.gc.as_ref().filter(|settings| settings.is_enabled())
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("gc-nothing-enabled", &[("file_path", &self.config.file_path().display())])))?;
+        settings.validate()?;
+        
+        if dry_run {
+            let estimate = self.gc_estimate(settings).await?;
+            print_gc_report(&estimate, true);
+            return Ok(());
+        }
+        self.collect_garbage(settings, "manual").await
+    }
+
+    /// Deploy a compose file kept in the database, resolving its secrets,
+    /// registry logins and settings again from the repository. The file is
+    /// deployed from `snapshot_dir`, a copy of the stack directory, or else
+    /// from the stack directory of the repository, for its relative paths.
+    async fn deploy_snapshot(&self, stack_name: &str, repository_url: &str, compose_content: &str, snapshot_dir: Option<&Path>) -> Result<()> {
+        let mut deploy_dir = snapshot_dir.map(Path::to_path_buf);
+        // Secrets are never stored, they are resolved again from the repository
+        let (secrets_env_vars, credentials, settings) = match self.db.get_repository_from_cache(repository_url).await? {
+            Some(repo) => {
+                let repo_path = self.clone_repository(&repo.url, repo.branch.as_deref(), repo.pinned_commit.is_none(), repo.path.as_deref()).await?;
+                let sparse = self.sparse_prefix(repo.path.as_deref());
+                if let Some(commit) = &repo.pinned_commit {
+                    checkout_revision(&repo_path, commit, sparse)?;
+                } else if let Some(tag) = &repo.deployed_tag {
+                    checkout_revision(&repo_path, &format!("refs/tags/{}", tag), sparse)?;
+                }
+                if repo.pinned_commit.is_some() || repo.deployed_tag.is_some() {
+                    self.checkout_submodules(&repo_path, &repo.url, repo.path.as_deref()).await?;
                 }
+                let repo_path = stacks_root(&repo_path, repo.path.as_deref())?;
+                let settings = RepositorySettings::load(Path::new(&repo_path))?.in_namespace(repo.namespace.as_deref());
+                let stack_dir = Path::new(&repo_path).join(settings.declared_name(stack_name));
+                let secrets_env_vars = if stack_dir.is_dir() {
+                    let secrets_env_vars = self.process_compose_secrets(&stack_dir, &repo_path).await?;
+                    deploy_dir.get_or_insert(stack_dir);
+                    secrets_env_vars
+                } else if let Some(snapshot_dir) = snapshot_dir {
+                    self.process_compose_secrets(snapshot_dir, &repo_path).await?
+                } else {
+                    println!("  {}", i18n::tr("restore-directory-gone-no-secrets", &[]));
+                    Vec::new()
+                };
+                // Settings of the stack's entry when it is still declared
+                let mut definition = fs::read_to_string(Path::new(&repo_path).join("stacks.yaml")).ok()
+                    .and_then(|content| serde_yaml::from_str::<Vec<StackDefinition>>(&content).ok())
+                    .and_then(|definitions| definitions.into_iter().find(|definition| settings.stack_name(&definition.name) == stack_name))
+                    .unwrap_or_default();
+                definition.name = stack_name.to_string();
+                (secrets_env_vars, self.registry_credentials(Path::new(&repo_path))?, settings.for_stack(&self.config, &definition)?)
             }
-            _ => {
-                println!("      Volume format is not a sequence, skipping");
+            None => {
+                println!("  {}", i18n::tr("restore-repository-unwatched-no-secrets", &[]));
+                (Vec::new(), RegistryCredentials::default(), RepositorySettings::default().for_stack(&self.config, &StackDefinition::default())?)
+            }
+        };
+        self.deploy_compose_content(stack_name, compose_content, &secrets_env_vars, &credentials, &settings.deploy, deploy_dir.as_deref()).await
+    }
+
+    /// Deploy a compose file kept in the database, written next to the files
+    /// of `stack_dir` or else to a temporary directory
+    async fn deploy_compose_content(&self, stack_name: &str, compose_content: &str, secrets_env_vars: &[(String, String)], credentials: &RegistryCredentials, deploy: &DeploySettings, stack_dir: Option<&Path>) -> Result<()> {
+        let Some(stack_dir) = stack_dir else {
+            let restore_dir = format!("/tmp/restore_{}_{}", stack_name, chrono::Utc::now().timestamp());
+            fs::create_dir_all(&restore_dir)?;
+            let compose_path = Path::new(&restore_dir).join("docker-compose.yml");
+            atomic::write(&compose_path, compose_content)?;
+            
+            let result = self.deploy_stack(stack_name, &compose_path, secrets_env_vars, credentials, deploy).await;
+            if let Err(e) = fs::remove_dir_all(&restore_dir) {
+                println!("{}", i18n::tr("restore-directory-cleanup-failed", &[("error", &e)]));
+            }
+            return result;
+        };
+        
+        // Relative env_file, configs and secrets resolve against the compose file's directory
+        let compose_path = stack_dir.join(format!(".dockerops-restore-{}.yml", chrono::Utc::now().timestamp()));
+        atomic::write(&compose_path, compose_content)?;
+        let result = self.deploy_stack(stack_name, &compose_path, secrets_env_vars, credentials, deploy).await;
+        if let Err(e) = fs::remove_file(&compose_path) {
+            println!("{}", i18n::tr("restore-compose-cleanup-failed", &[("error", &e)]));
+        }
+        result
+    }
+
+    /// Redeploy the last successfully deployed revision of a stack whose
+    /// update did not become healthy. The update remains a failure, with the
+    /// outcome of the rollback added to its error.
+    async fn auto_rollback(&self, stack: &Stack, hash: &str, unhealthy: Result<()>, secrets_env_vars: &[(String, String)], credentials: &RegistryCredentials, deploy: &DeploySettings) -> Result<()> {
+        let (stack_name, repository_url) = (stack.name.as_str(), stack.repository_url.as_str());
+        let reason = match unhealthy {
+            Ok(()) => format!("Stack '{}' is degraded after {}s", stack_name, deploy.convergence_timeout()?.as_secs()),
+            Err(e) => e.to_string(),
+        };
+        let revisions = self.db.get_stack_revisions(stack_name, repository_url).await?;
+        // A degraded deployment of the same compose file may have been kept as a revision
+        let Some(previous) = revisions.iter().find(|revision| revision.hash != hash) else {
+            return Err(anyhow::anyhow!(i18n::tr("rollback-no-previous-revision", &[("reason", &reason)])));
+        };
+        
+        println!("  {}", i18n::tr("rollback-after-unhealthy-update", &[("stack", &stack_name), ("revision", &previous.revision)]));
+        let since = chrono::Utc::now();
+        let rolled_back = self.deploy_compose_content(stack_name, &previous.compose_content, secrets_env_vars, credentials, deploy, None).await;
+        let rolled_back = match rolled_back {
+            Ok(()) => self.converge(stack_name, Ok(()), deploy).await.0,
+            Err(e) => Err(e),
+        };
+        self.record_deployment(Deployment::new(stack_name, repository_url, &previous.hash, "rollback", since), &rolled_back).await?;
+        match rolled_back {
+            Ok(()) => Err(anyhow::anyhow!(i18n::tr("rollback-done", &[("reason", &reason), ("revision", &previous.revision)]))),
+            Err(e) => Err(anyhow::anyhow!(i18n::tr("rollback-failed", &[("reason", &reason), ("revision", &previous.revision), ("error", &e)]))),
+        }
+    }
+
+    /// Redeploy an earlier revision of a stack, by default the one deployed
+    /// before the current compose file. The compose file rolled back from is
+    /// not deployed again until the repository changes it.
+    pub async fn rollback(&self, stack_name: &str, repository_url: Option<&str>, to: Option<i64>) -> Result<()> {
+        let stack = self.resolve_stack(stack_name, repository_url).await?;
+        let revisions = self.db.get_stack_revisions(&stack.name, &stack.repository_url).await?;
+        if revisions.is_empty() {
+            return Err(anyhow::anyhow!(i18n::tr("rollback-no-revision", &[("stack", &stack.name)])));
+        }
+        
+        // The current compose file has no revision when its deployment failed
+        let current = revisions.iter().find(|revision| revision.hash == stack.hash).map(|revision| revision.revision);
+        let target = match to {
+            Some(number) => revisions.iter().find(|revision| revision.revision == number)
+                .ok_or_else(|| anyhow::anyhow!(i18n::tr("rollback-unknown-revision", &[("stack", &stack.name), ("number", &number), ("list", &revisions.iter().map(|revision| revision.revision.to_string()).collect::<Vec<_>>().join(", "))])))?,
+            None => revisions.iter()
+                .find(|revision| current.is_none_or(|current| revision.revision < current) && revision.hash != stack.hash)
+                .ok_or_else(|| anyhow::anyhow!(i18n::tr("rollback-no-older-revision", &[("stack", &stack.name)])))?,
+        };
+        if target.hash == stack.hash {
+            return Err(anyhow::anyhow!(i18n::tr("rollback-revision-already-deployed", &[("revision", &target.revision), ("stack", &stack.name)])));
+        }
+        
+        println!("{}", i18n::tr("rollback-started", &[("stack", &stack.name), ("revision", &target.revision), ("time", &self.config.format_timestamp(&target.deployed_at))]));
+        let since = chrono::Utc::now();
+        let deployed = self.deploy_snapshot(&stack.name, &stack.repository_url, &target.compose_content, None).await;
+        self.record_deployment(Deployment::new(&stack.name, &stack.repository_url, &target.hash, "rollback", since), &deployed).await?;
+        deployed?;
+        
+        self.db.set_stack_revision_rolled_back_from(target.id, &stack.hash).await?;
+        self.db.update_stack_hash(&stack.name, &stack.repository_url, &target.hash).await?;
+        self.db.update_stack_compose_content(&stack.name, &stack.repository_url, &target.compose_content).await?;
+        self.db.update_stack_status(&stack.name, &stack.repository_url, "deployed").await?;
+        self.db.delete_stack_failure(&stack.name, &stack.repository_url).await?;
+        let images = self.process_yaml_file(&target.compose_content, &stack.compose_path).await?;
+        self.db.set_stack_images(&stack.name, &stack.repository_url, &images).await?;
+        self.db.refresh_image_reference_counts().await?;
+        
+        println!("{}", i18n::tr("stack-rolled-back", &[("stack", &stack.name), ("revision", &target.revision)]));
+        println!("   {}", i18n::tr("stack-rolled-back-hint", &[]));
+        Ok(())
+    }
+
+    /// Hash of the compose file replaced by the deployed revision of a stack with `rollback`
+    async fn rolled_back_from(&self, stack: &Stack) -> Result<Option<String>> {
+        let revisions = self.db.get_stack_revisions(&stack.name, &stack.repository_url).await?;
+        Ok(revisions.into_iter()
+            .find(|revision| revision.hash == stack.hash)
+            .and_then(|revision| revision.rolled_back_from))
+    }
+
+    /// Write a stack's last deployed compose file, the digests its services
+    /// run and the volumes it uses to an archive `import-stack` deploys on
+    /// another DockerOps instance
+    pub async fn export_stack(&self, stack_name: &str, repository_url: Option<&str>, output: Option<&str>) -> Result<()> {
+        let stack = self.resolve_stack(stack_name, repository_url).await?;
+        let compose = self.db.get_stack_compose_content(&stack.name, &stack.repository_url).await?
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("export-no-snapshot", &[("stack", &stack.name)])))?;
+        
+        let commit = self.db.get_deployments(Some(&stack.name), Some(&stack.repository_url), 100).await?
+            .into_iter()
+            .find(|deployment| deployment.result == "success" && deployment.hash == stack.hash)
+            .and_then(|deployment| deployment.commit_hash);
+        let images = self.pinned_images(&stack).await?;
+        if images.is_empty() {
+            println!("{}", i18n::tr("export-images-not-pinned", &[("stack", &stack.name)]));
+        }
+        
+        // Definitions of volumes.yaml whose volume or path the rendered compose file uses
+        let volumes = match self.db.get_repository_from_cache(&stack.repository_url).await? {
+            Some(repo) => {
+                let volumes_path = self.working_copy_path(&repo.url).join(repo.path.as_deref().unwrap_or_default()).join("volumes.yaml");
+                let definitions: Vec<VolumeDefinition> = match fs::read_to_string(&volumes_path) {
+                    Ok(content) => serde_yaml::from_str(&content)?,
+                    Err(_) => Vec::new(),
+                };
+                let used: Vec<&VolumeDefinition> = definitions.iter().filter(|volume| compose.contains(&volume.path)).collect();
+                if used.is_empty() { None } else { Some(serde_yaml::to_string(&used)?) }
+            }
+            None => None,
+        };
+        
+        let manifest = StackManifest {
+            format: 0,
+            name: stack.name.clone(),
+            repository_url: stack.repository_url.clone(),
+            hash: stack.hash.clone(),
+            commit,
+            app: stack.app.clone(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            dockerops_version: env!("CARGO_PKG_VERSION").to_string(),
+            compose_sha256: String::new(),
+            images,
+        };
+        let output = output.map(str::to_string)
+            .unwrap_or_else(|| format!("{}-{}.tar.gz", stack.name, &stack.hash[..stack.hash.len().min(12)]));
+        let archive = StackArchive::new(manifest, compose, volumes);
+        archive.write(Path::new(&output))?;
+        
+        println!("{}", i18n::tr("stack-exported", &[("stack", &stack.name), ("path", &output)]));
+        for image in &archive.manifest.images {
+            println!("   {}: {}@{}", image.service, image.image, image.digest);
+        }
+        Ok(())
+    }
+
+    /// Deploy a stack archive written by `export-stack` and register the
+    /// stack, attached to its original repository or to `repository_url`
+    pub async fn import_stack(&self, path: &str, repository_url: Option<&str>) -> Result<()> {
+        let archive = StackArchive::read(Path::new(path))?;
+        let manifest = &archive.manifest;
+        let repository_url = repository_url.unwrap_or(&manifest.repository_url);
+        
+        if let Some(owner) = self.db.get_stacks_named(&manifest.name).await?.first() {
+            return Err(anyhow::anyhow!(i18n::tr("restore-stack-already-deployed", &[("stack", &manifest.name), ("url", &owner.repository_url)])));
+        }
+        println!("{}", i18n::tr("import-bundle-started", &[("stack", &manifest.name), ("url", &manifest.repository_url), ("time", &self.config.format_timestamp(&manifest.exported_at)), ("commit", &manifest.commit.as_deref().map(|commit| format!(", commit {}", &commit[..commit.len().min(12)])).unwrap_or_default())]));
+        if let Some(volumes) = &archive.volumes {
+            println!("  {}", i18n::tr("import-bundle-volumes", &[]));
+            for line in volumes.lines() {
+                println!("      {}", line);
             }
         }
         
-        println!("      Finished processing service volumes");
+        // The images are the exact ones that ran where the stack was exported
+        let compose = pin_images(&archive.compose, &manifest.images)?;
+        let since = chrono::Utc::now();
+        let deployed = self.deploy_snapshot(&manifest.name, repository_url, &compose, None).await;
+        self.record_deployment(Deployment::new(&manifest.name, repository_url, &manifest.hash, "import", since), &deployed).await?;
+        deployed?;
+        
+        let mut stack = Stack::new(
+            manifest.name.clone(),
+            repository_url.to_string(),
+            format!("{}/docker-compose.yml", manifest.name),
+            manifest.hash.clone(),
+        );
+        stack.app = manifest.app.clone();
+        self.db.create_stack(&stack).await?;
+        // Imported stacks survive syncs until they are declared in stacks.yaml
+        self.db.update_stack_status(&stack.name, repository_url, "imported").await?;
+        self.db.update_stack_compose_content(&stack.name, repository_url, &compose).await?;
+        self.db.record_stack_revision(&stack.name, repository_url, &manifest.hash, &compose, self.config.revisions_kept()).await?;
+        let images = self.process_yaml_file(&compose, &stack.compose_path).await?;
+        self.db.set_stack_images(&stack.name, repository_url, &images).await?;
+        self.db.refresh_image_reference_counts().await?;
+        
+        println!("{}", i18n::tr("stack-imported", &[("stack", &stack.name)]));
         Ok(())
     }
 
-    async fn add_volumes_section(&self, yaml_value: &mut serde_yaml::Value, volumes_definitions: &[VolumeDefinition]) -> Result<()> {
-        println!("    Adding volumes section to docker-compose...");
+    /// Image digests the services of a stack run, as pinned by Swarm
+    async fn pinned_images(&self, stack: &Stack) -> Result<Vec<PinnedImage>> {
+        let services: Vec<String> = self.stack_services(&stack.name).await?.into_iter().map(|s| s.name).collect();
+        if services.is_empty() {
+            return Ok(Vec::new());
+        }
+        let output = self.docker()
+            .args(["service", "inspect", "--format", "{{.Spec.Name}}\t{{.Spec.TaskTemplate.ContainerSpec.Image}}"])
+            .args(&services)
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("failed-to-inspect-services-of-stack", &[("stack", &stack.name), ("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
         
-        // Create volumes section if it doesn't exist
-        if yaml_value.get("volumes").is_none() {
-            yaml_value["volumes"] = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
-            println!("    Created new volumes section");
+        let prefix = format!("{}_", stack.name);
+        Ok(String::from_utf8_lossy(&output.stdout).lines()
+            .filter_map(|line| {
+                let (name, image) = line.split_once('\t')?;
+                let (image, digest) = image.trim().split_once('@')?;
+                Some(PinnedImage {
+                    service: name.trim().strip_prefix(&prefix).unwrap_or(name.trim()).to_string(),
+                    image: image.to_string(),
+                    digest: digest.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    /// Deliberately disrupt a stack and measure how long DockerOps takes to
+    /// detect the drift and converge it back from its repository
+    pub async fn drill(&self, stack_name: &str, repository_url: Option<&str>, timeout: Duration) -> Result<()> {
+        let stack = self.resolve_stack(stack_name, repository_url).await?;
+        
+        println!("{}", i18n::tr("drill-started", &[("stack", &stack_name), ("url", &stack.repository_url), ("timeout", &timeout.as_secs())]));
+        
+        // Record the healthy state the stack must return to
+        let baseline = self.stack_services(stack_name).await?;
+        if baseline.is_empty() {
+            return Err(anyhow::anyhow!(i18n::tr("drill-nothing-to-disrupt", &[("stack", &stack_name)])));
+        }
+        if let Some(service) = baseline.iter().find(|s| s.running < s.desired) {
+            return Err(anyhow::anyhow!(i18n::tr("drill-stack-unhealthy", &[("stack", &stack_name), ("name", &service.name), ("running", &service.running), ("desired", &service.desired)])));
         }
         
-        let volumes_section = yaml_value.get_mut("volumes").unwrap();
+        // A failed drill must not leave the stack scaled down
+        let (detection, recovery) = match self.disrupt_stack(&stack, &baseline, timeout).await {
+            Ok(latencies) => latencies,
+            Err(e) => {
+                self.restore_replicas(stack_name, &baseline);
+                return Err(e);
+            }
+        };
         
-        // Add each volume definition to the volumes section
-        for volume_def in volumes_definitions {
-            match volume_def.r#type {
-                VolumeType::Volume => {
-                    println!("    Adding volume '{}' to volumes section", volume_def.id);
-                    
-                    // Create volume configuration
-                    let mut volume_config = serde_yaml::Mapping::new();
-                    
-                    // For now, we'll use the default driver
-                    // In the future, we could add driver and driver_opts support
-                    volume_config.insert(
-                        serde_yaml::Value::String("driver".to_string()),
-                        serde_yaml::Value::String("local".to_string())
-                    );
-                    
-                    volumes_section[&volume_def.id] = serde_yaml::Value::Mapping(volume_config);
+        println!("\n{}", i18n::tr("drill-report", &[("stack", &stack_name)]));
+        println!("  {}", i18n::tr("drill-services-disrupted", &[("count", &baseline.iter().filter(|s| s.mode != "global").count())]));
+        println!("  {}", i18n::tr("drill-detection-latency", &[("seconds", &format!("{:.1}", detection.as_secs_f64()))]));
+        println!("  {}", i18n::tr("drill-recovery-latency", &[("recovery", &format!("{:.1}", recovery.as_secs_f64())), ("repair", &format!("{:.1}", (recovery - detection).as_secs_f64()))]));
+        println!("  {}", i18n::tr("drill-passed", &[("timeout", &timeout.as_secs())]));
+        
+        Ok(())
+    }
+
+    /// Scale the replicated services of a stack down to zero, then wait for
+    /// the drift to be detected and repaired, returning both latencies
+    async fn disrupt_stack(&self, stack: &Stack, baseline: &[ServiceState], timeout: Duration) -> Result<(Duration, Duration)> {
+        let stack_name = stack.name.as_str();
+        // Only drift recorded after the disruption counts as a detection
+        let known_drift = self.db.get_drift_events().await?.iter().map(|e| e.id).max().unwrap_or_default();
+        
+        // Disrupt: scale every replicated service down to zero
+        println!("  {}", i18n::tr("drill-stopping-services", &[("stack", &stack_name)]));
+        let started = std::time::Instant::now();
+        for service in baseline {
+            if service.mode == "global" {
+                println!("    {}", i18n::tr("drill-skipping-global-service", &[("name", &service.name)]));
+                continue;
+            }
+            let output = self.docker()
+                .args(["service", "scale", "--detach", &format!("{}=0", service.name)])
+                .output()?;
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(i18n::tr("drill-scale-down-failed", &[("name", &service.name), ("error", &String::from_utf8_lossy(&output.stderr))])));
+            }
+            println!("    {}", i18n::tr("drill-scaled-down", &[("name", &service.name)]));
+        }
+        
+        // Detect: the running daemon records the drift from the Docker events. A
+        // reconcile clears the events of the stacks it redeploys, a stack already
+        // back to its baseline was detected too.
+        println!("  {}", i18n::tr("drill-waiting-for-detection", &[]));
+        let detection = loop {
+            let detected = self.db.get_drift_events().await?.iter()
+                .any(|e| e.id > known_drift && e.stack_name == stack.name && e.repository_url == stack.repository_url);
+            if detected || !stack_has_drifted(baseline, &self.stack_services(stack_name).await?) {
+                break started.elapsed();
+            }
+            if started.elapsed() > timeout {
+                return Err(anyhow::anyhow!(i18n::tr("drill-drift-not-detected", &[("stack", &stack_name), ("timeout", &timeout.as_secs())])));
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        };
+        println!("  {}", i18n::tr("drill-drift-detected", &[("seconds", &format!("{:.1}", detection.as_secs_f64()))]));
+        
+        // Recover: the daemon or a scheduled reconcile redeploys the stack from git
+        let recovery = loop {
+            let current = self.stack_services(stack_name).await?;
+            if !stack_has_drifted(baseline, &current) {
+                break started.elapsed();
+            }
+            if started.elapsed() > timeout {
+                return Err(anyhow::anyhow!(i18n::tr("drill-stack-not-restored", &[("stack", &stack_name), ("timeout", &timeout.as_secs())])));
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        };
+        
+        Ok((detection, recovery))
+    }
+
+    /// Scale the replicated services of a stack back to their baseline
+    fn restore_replicas(&self, stack_name: &str, baseline: &[ServiceState]) {
+        println!("  {}", i18n::tr("drill-restoring-replicas", &[("stack", &stack_name)]));
+        for service in baseline.iter().filter(|service| service.mode != "global") {
+            let output = self.docker()
+                .args(["service", "scale", "--detach", &format!("{}={}", service.name, service.desired)])
+                .output();
+            match output {
+                Ok(output) if output.status.success() => {}
+                Ok(output) => eprintln!("    {}", i18n::tr("drill-replicas-not-restored", &[("name", &service.name), ("error", &String::from_utf8_lossy(&output.stderr).trim())])),
+                Err(e) => eprintln!("    {}", i18n::tr("drill-replicas-not-restored", &[("name", &service.name), ("error", &e)])),
+            }
+        }
+    }
+
+    /// Find a managed stack by name, disambiguating with the repository URL when needed
+    async fn resolve_stack(&self, stack_name: &str, repository_url: Option<&str>) -> Result<Stack> {
+        let mut stacks = self.db.get_stacks_named(stack_name).await?;
+        if let Some(repository_url) = repository_url {
+            stacks.retain(|s| s.repository_url == repository_url);
+        }
+        
+        match stacks.len() {
+            0 => Err(anyhow::anyhow!(i18n::tr("stack-not-managed", &[("stack", &stack_name)]))),
+            1 => Ok(stacks.remove(0)),
+            _ => Err(anyhow::anyhow!(i18n::tr("stack-ambiguous", &[("stack", &stack_name), ("list", &stacks.iter().map(|s| s.repository_url.as_str()).collect::<Vec<_>>().join(", "))]))),
+        }
+    }
+
+    /// Live replica state of the services of a Swarm stack
+    async fn stack_services(&self, stack_name: &str) -> Result<Vec<ServiceState>> {
+        let output = tokio::process::Command::from(self.docker())
+            .args(["stack", "services", stack_name, "--format", "{{.Name}}\t{{.Mode}}\t{{.Replicas}}"])
+            .output()
+            .await?;
+        
+        if !output.status.success() {
+            // The stack doesn't exist (anymore)
+            return Ok(Vec::new());
+        }
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().filter_map(ServiceState::parse).collect())
+    }
+
+    /// Status of every application, from the stacks in the database and the
+    /// replicas running in Swarm
+    pub async fn app_statuses(&self) -> Result<Vec<AppStatus>> {
+        let mut apps: BTreeMap<String, Vec<AppStackStatus>> = BTreeMap::new();
+        for stack in self.db.get_all_stacks().await? {
+            let services = self.stack_services(&stack.name).await?;
+            let running = services.iter().map(|s| s.running.min(s.desired)).sum();
+            let desired = services.iter().map(|s| s.desired).sum();
+            // A failed deployment leaves the previous version running, the stack is not up to date
+            let health = if services.is_empty() || (running == 0 && desired > 0) {
+                "down"
+            } else if running < desired || stack.status == "error" || stack.status == "degraded" {
+                "degraded"
+            } else {
+                "healthy"
+            };
+            
+            apps.entry(stack.app.clone().unwrap_or_else(|| stack.name.clone()))
+                .or_default()
+                .push(AppStackStatus { name: stack.name, repository: stack.repository_url, status: stack.status, health, running, desired });
+        }
+        
+        Ok(apps.into_iter().map(|(name, stacks)| {
+            let health = if stacks.iter().all(|s| s.health == "healthy") {
+                "healthy"
+            } else if stacks.iter().all(|s| s.health == "down") {
+                "down"
+            } else {
+                "degraded"
+            };
+            AppStatus { name, health, stacks }
+        }).collect())
+    }
+
+    /// Print the status of the applications, or of one of them
+    pub async fn apps(&self, app: Option<&str>) -> Result<()> {
+        let mut apps = self.app_statuses().await?;
+        if let Some(name) = app {
+            apps.retain(|a| a.name == name);
+            if apps.is_empty() {
+                return Err(anyhow::anyhow!(i18n::tr("app-has-no-stacks", &[("name", &name)])));
+            }
+        }
+        if apps.is_empty() {
+            println!("{}", i18n::tr("no-stacks-deployed", &[]));
+            return Ok(());
+        }
+        
+        for app in &apps {
+            let marker = match app.health {
+                "healthy" => "✅",
+                "degraded" => "⚠️ ",
+                _ => "❌",
+            };
+            println!("{} {} ({})", marker, app.name, app.health);
+            for stack in &app.stacks {
+                println!("    {}", i18n::tr("status-stack-line", &[("stack", &stack.name), ("status", &stack.status), ("health", &stack.health), ("running", &stack.running), ("desired", &stack.desired)]));
+            }
+        }
+        Ok(())
+    }
+
+    /// Live state of the managed stacks in Swarm: replicas, convergence and
+    /// image of every service, as reported by `docker stack services` and
+    /// `docker stack ps`
+    pub async fn status(&self, stack_name: Option<&str>, repository_url: Option<&str>, at: Option<&str>) -> Result<()> {
+        if let Some(at) = at {
+            return self.status_at(stack_name, repository_url, at).await;
+        }
+        
+        let undelivered = self.db.get_undelivered_notifications().await?;
+        if !undelivered.is_empty() {
+            println!("{}", i18n::tr("notifications-undelivered", &[("count", &undelivered.len())]));
+            for notification in &undelivered {
+                let state = match notification.status.as_str() {
+                    "dead" => "given up".to_string(),
+                    _ => format!("next attempt {}", self.config.format_timestamp(&notification.next_attempt_at)),
+                };
+                let text = serde_json::from_str::<serde_json::Value>(&notification.payload).ok()
+                    .and_then(|payload| payload["text"].as_str().map(str::to_string))
+                    .unwrap_or_default();
+                println!("  {}", i18n::tr("notification-line", &[("time", &self.config.format_timestamp(&notification.created_at)), ("url", &notify::display_url(&notification.url)), ("attempts", &notification.attempts), ("state", &state), ("text", &text)]));
+                if let Some(error) = &notification.last_error {
+                    println!("    {}", error);
                 }
-                VolumeType::Binding => {
-                    // Bindings don't need to be in the volumes section
-                    // They are handled directly in the service volumes
-                    println!("    Skipping binding '{}' in volumes section (handled in service volumes)", volume_def.id);
+            }
+            println!();
+        }
+        let stacks: Vec<Stack> = match stack_name {
+            Some(name) => vec![self.resolve_stack(name, repository_url).await?],
+            None => self.db.get_all_stacks().await?
+                .into_iter()
+                .filter(|stack| repository_url.is_none_or(|url| stack.repository_url == url))
+                .collect(),
+        };
+        if stacks.is_empty() {
+            println!("{}", i18n::tr("no-stacks-deployed", &[]));
+            return Ok(());
+        }
+        
+        for (index, stack) in stacks.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            println!("{}", i18n::tr("stack-line", &[("stack", &stack.name), ("url", &stack.repository_url), ("status", &stack.status), ("hash", &stack.hash)]));
+            let services = self.service_statuses(&stack.name).await?;
+            if services.is_empty() {
+                println!("  {}", i18n::tr("no-service-running-in-swarm", &[]));
+                continue;
+            }
+            
+            let rows: Vec<[String; 5]> = services.iter()
+                .map(|service| [
+                    service.name.strip_prefix(&format!("{}_", stack.name)).unwrap_or(&service.name).to_string(),
+                    service.mode.clone(),
+                    format!("{}/{}", service.running, service.desired),
+                    service.state(),
+                    short_image(&service.image),
+                ])
+                .collect();
+            print_table(["SERVICE", "MODE", "REPLICAS", "STATE", "IMAGE"], &rows);
+            for service in services.iter().filter(|service| service.error.is_some()) {
+                println!("  ❌ {}: {}", service.name, service.error.as_deref().unwrap_or_default());
+            }
+        }
+        Ok(())
+    }
+
+    /// What was deployed at a past time: the stacks, their compose hash and
+    /// commit, and the images of their last successful deployment until then
+    async fn status_at(&self, stack_name: Option<&str>, repository_url: Option<&str>, at: &str) -> Result<()> {
+        let time = self.config.parse_timestamp(at)?;
+        let deployments: Vec<Deployment> = self.db.get_deployments_at(&time.to_rfc3339(), repository_url).await?
+            .into_iter()
+            .filter(|deployment| stack_name.is_none_or(|name| deployment.stack_name == name))
+            .collect();
+        println!("{}", i18n::tr("state-at", &[("time", &self.config.format_time(&time))]));
+        if deployments.is_empty() {
+            println!("{}", i18n::tr("no-stacks-deployed", &[]));
+            return Ok(());
+        }
+        
+        for deployment in &deployments {
+            println!();
+            let commit = deployment.commit_hash.as_deref().map(|commit| format!(", commit {}", &commit[..commit.len().min(12)])).unwrap_or_default();
+            println!("{}", i18n::tr("deployed-stack-line", &[("stack_name", &deployment.stack_name), ("url", &deployment.repository_url), ("hash", &deployment.hash), ("commit", &commit)]));
+            println!("  {}", i18n::tr("deployed-line", &[("time", &self.config.format_timestamp(&deployment.started_at)), ("kind", &deployment.kind)]));
+            
+            let rows: Vec<[String; 2]> = match &deployment.images {
+                Some(images) => images.lines()
+                    .filter_map(|line| line.split_once(' '))
+                    .map(|(service, image)| [service.to_string(), image.to_string()])
+                    .collect(),
+                None => {
+                    // Deployments recorded before the images were kept only have the tags of their revision
+                    let revisions = self.db.get_stack_revisions(&deployment.stack_name, &deployment.repository_url).await?;
+                    let Some(revision) = revisions.iter().find(|revision| revision.hash == deployment.hash) else {
+                        println!("  {}", i18n::tr("deployed-images-unknown", &[]));
+                        continue;
+                    };
+                    let compose: Value = serde_yaml::from_str(&revision.compose_content)?;
+                    println!("  {}", i18n::tr("deployed-images-not-pinned", &[]));
+                    compose["services"].as_mapping().into_iter().flatten()
+                        .filter_map(|(service, definition)| Some([service.as_str()?.to_string(), definition["image"].as_str()?.to_string()]))
+                        .collect()
+                }
+            };
+            print_table(["SERVICE", "IMAGE"], &rows);
+        }
+        Ok(())
+    }
+
+    /// Images of the services of a stack with the digests Swarm resolved, as
+    /// "service image" lines
+    async fn deployed_images(&self, stack_name: &str) -> Option<String> {
+        let services = self.service_statuses(stack_name).await.ok()?;
+        let lines: Vec<String> = services.iter()
+            .map(|service| format!("{} {}", service.name.strip_prefix(&format!("{}_", stack_name)).unwrap_or(&service.name), service.image))
+            .collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    /// Rolling restart of every service of a managed stack, or of one of
+    /// them, with `docker service update --force`: the tasks are replaced
+    /// following the update_config of the service, the compose file is unchanged
+    pub async fn restart(&self, stack_name: &str, service: Option<&str>, repository_url: Option<&str>) -> Result<()> {
+        let stack = self.resolve_stack(stack_name, repository_url).await?;
+        let services = self.selected_services(&stack.name, service).await?;
+        
+        let since = chrono::Utc::now();
+        let restarted = async {
+            for name in &services {
+                println!("{}", i18n::tr("restarting-service", &[("name", &name)]));
+                let output = self.docker()
+                    .args(["service", "update", "--force", "--detach=false", name])
+                    .output()?;
+                if !output.status.success() {
+                    return Err(anyhow::anyhow!(i18n::tr("failed-to-restart-service", &[("name", &name), ("error", &String::from_utf8_lossy(&output.stderr).trim())])));
                 }
             }
+            Ok(())
+        }.await;
+        self.record_deployment(Deployment::new(&stack.name, &stack.repository_url, &stack.hash, "restart", since), &restarted).await?;
+        restarted?;
+        
+        println!("{}", i18n::tr("stack-restarted", &[("stack", &stack.name), ("count", &services.len())]));
+        Ok(())
+    }
+
+    /// Swarm names of the services of a stack, or of the one named `service`
+    /// in its compose file, failing when none is running
+    async fn selected_services(&self, stack_name: &str, service: Option<&str>) -> Result<Vec<String>> {
+        let prefix = format!("{}_", stack_name);
+        let services: Vec<String> = self.stack_services(stack_name).await?
+            .into_iter()
+            .map(|state| state.name)
+            .filter(|name| service.is_none_or(|service| name.strip_prefix(&prefix) == Some(service)))
+            .collect();
+        if services.is_empty() {
+            return Err(match service {
+                Some(service) => anyhow::anyhow!(i18n::tr("service-not-running", &[("service", &service), ("stack", &stack_name)])),
+                None => anyhow::anyhow!(i18n::tr("stack-has-no-running-service", &[("stack", &stack_name)])),
+            });
+        }
+        Ok(services)
+    }
+
+    /// Stream the logs of the services of a managed stack, or of one of them,
+    /// interleaved as they arrive with a colored prefix per service
+    pub async fn logs(&self, stack_name: &str, service: Option<&str>, repository_url: Option<&str>, follow: bool, tail: Option<&str>) -> Result<()> {
+        let stack = self.resolve_stack(stack_name, repository_url).await?;
+        let prefix = format!("{}_", stack.name);
+        let services = self.selected_services(&stack.name, service).await?;
+        
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<(usize, String)>();
+        let mut children = Vec::new();
+        for (index, name) in services.iter().enumerate() {
+            let mut command = tokio::process::Command::from(self.docker());
+            command.args(["service", "logs", "--no-task-ids", "--tail", tail.unwrap_or("100")]);
+            if follow {
+                command.arg("--follow");
+            }
+            command.arg(name)
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped())
+                .kill_on_drop(true);
+            let mut child = command.spawn()?;
+            // Containers write to both streams, docker service logs keeps them apart
+            let stdout = child.stdout.take().map(|stdout| tokio::io::BufReader::new(stdout).lines());
+            let stderr = child.stderr.take().map(|stderr| tokio::io::BufReader::new(stderr).lines());
+            if let Some(mut lines) = stdout {
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let _ = sender.send((index, line));
+                    }
+                });
+            }
+            if let Some(mut lines) = stderr {
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let _ = sender.send((index, line));
+                    }
+                });
+            }
+            children.push(child);
         }
+        drop(sender);
         
-        println!("    Volumes section updated");
+        let colored = std::io::IsTerminal::is_terminal(&std::io::stdout()) && std::env::var_os("NO_COLOR").is_none();
+        let mut width = 0;
+        loop {
+            tokio::select! {
+                received = receiver.recv() => {
+                    let Some((index, line)) = received else {
+                        break;
+                    };
+                    // "stack_web.1@node    | message", the stack name is implied
+                    let (task, message) = match line.split_once("| ") {
+                        Some((task, message)) => (task.trim(), message),
+                        None => (services[index].as_str(), line.as_str()),
+                    };
+                    let task = task.strip_prefix(&prefix).unwrap_or(task);
+                    width = width.max(task.chars().count());
+                    match colored {
+                        true => println!("\x1b[{}m{:<width$}\x1b[0m | {}", LOG_COLORS[index % LOG_COLORS.len()], task, message, width = width),
+                        false => println!("{:<width$} | {}", task, message, width = width),
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
         Ok(())
     }
-} 
\ No newline at end of file
+
+    /// Run a command in the container of a running task of a service of a
+    /// managed stack, on the node running it, and return its exit code
+    pub async fn exec(&self, stack_name: &str, service: &str, repository_url: Option<&str>, slot: Option<u64>, command: &[String]) -> Result<i32> {
+        let stack = self.resolve_stack(stack_name, repository_url).await?;
+        let service_name = self.selected_services(&stack.name, Some(service)).await?.remove(0);
+        let task = self.running_task(&service_name, slot)?;
+        
+        // The container lives on the engine of its node, which is only the
+        // managed endpoint for the tasks of the manager
+        let mut docker = match self.swarm_node_id()? == task.node_id {
+            true => self.docker(),
+            false => self.node_docker(&task.hostname)?,
+        };
+        eprintln!("{}", i18n::tr("running-in-container", &[("name", &task.name), ("hostname", &task.hostname), ("container", &&task.container_id[..12.min(task.container_id.len())])]));
+        
+        docker.arg("exec");
+        match std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+            true => docker.arg("-it"),
+            false => docker.arg("-i"),
+        };
+        docker.arg(&task.container_id);
+        match command.is_empty() {
+            true => docker.arg("sh"),
+            false => docker.args(command),
+        };
+        let status = docker.status().map_err(|e| anyhow::anyhow!(i18n::tr("docker-could-not-run", &[("error", &e)])))?;
+        Ok(status.code().unwrap_or(1))
+    }
+
+    /// A running task of a service with its container, the one of replica
+    /// `slot` when given
+    fn running_task(&self, service: &str, slot: Option<u64>) -> Result<RunningTask> {
+        let output = self.docker()
+            .args(["service", "ps", service, "--filter", "desired-state=running", "--no-trunc", "--quiet"])
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("failed-to-list-tasks-of-service", &[("service", &service), ("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        let ids: Vec<String> = String::from_utf8_lossy(&output.stdout).split_whitespace().map(|id| id.to_string()).collect();
+        if ids.is_empty() {
+            return Err(anyhow::anyhow!(i18n::tr("service-has-no-task", &[("service", &service)])));
+        }
+        
+        let output = self.docker()
+            .args(["inspect", "--format", "{{.Status.State}}\t{{.Slot}}\t{{.NodeID}}\t{{.Status.ContainerStatus.ContainerID}}"])
+            .args(&ids)
+            .output()?;
+        let task = String::from_utf8_lossy(&output.stdout).lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t').map(str::trim);
+                let state = fields.next()?;
+                let task_slot = fields.next()?.parse::<u64>().unwrap_or_default();
+                let node_id = fields.next()?.to_string();
+                let container_id = fields.next()?.to_string();
+                (state == "running" && !container_id.is_empty() && slot.is_none_or(|slot| slot == task_slot))
+                    .then_some((task_slot, node_id, container_id))
+            })
+            .min_by_key(|(task_slot, _, _)| *task_slot);
+        let Some((task_slot, node_id, container_id)) = task else {
+            return Err(match slot {
+                Some(slot) => anyhow::anyhow!(i18n::tr("replica-not-running", &[("slot", &slot), ("service", &service)])),
+                None => anyhow::anyhow!(i18n::tr("service-has-no-running-task", &[("service", &service)])),
+            });
+        };
+        
+        let hostname = self.node_hostname(&node_id)?;
+        // Global services have no slot, their tasks are named after the node
+        let name = match task_slot {
+            0 => format!("{}.{}", service, hostname),
+            slot => format!("{}.{}", service, slot),
+        };
+        Ok(RunningTask { name, node_id, hostname, container_id })
+    }
+
+    /// ID of the Swarm node of the managed engine
+    fn swarm_node_id(&self) -> Result<String> {
+        let output = self.docker().args(["info", "--format", "{{.Swarm.NodeID}}"]).output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn node_hostname(&self, node_id: &str) -> Result<String> {
+        let output = self.docker().args(["node", "inspect", "--format", "{{.Description.Hostname}}", node_id]).output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Docker command talking to the engine of another Swarm node: its
+    /// endpoint in config.yaml, or a docker CLI context named after it
+    fn node_docker(&self, hostname: &str) -> Result<Command> {
+        let mut command = Command::new("docker");
+        if let Some(endpoint) = self.config.node_endpoint(hostname) {
+            command.env("DOCKER_HOST", endpoint);
+            return Ok(command);
+        }
+        if docker_contexts().unwrap_or_default().iter().any(|(name, _)| name == hostname) {
+            command.env("DOCKER_CONTEXT", hostname);
+            return Ok(command);
+        }
+        Err(anyhow::anyhow!(i18n::tr("task-node-endpoint-missing", &[("hostname", &hostname)])))
+    }
+
+    /// List the tasks of a managed stack with their node, state and error,
+    /// only the ones meant to run unless `all` includes the stopped history
+    pub async fn ps(&self, stack_name: &str, repository_url: Option<&str>, all: bool) -> Result<()> {
+        let stack = self.resolve_stack(stack_name, repository_url).await?;
+        let mut command = self.docker();
+        command.args(["stack", "ps", &stack.name, "--no-trunc", "--format", "{{.Name}}\t{{.Node}}\t{{.DesiredState}}\t{{.CurrentState}}\t{{.Error}}"]);
+        if !all {
+            command.args(["--filter", "desired-state=running"]);
+        }
+        let output = command.output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("failed-to-list-tasks-of-stack", &[("stack", &stack.name), ("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        
+        println!("{}", i18n::tr("stack-line", &[("stack", &stack.name), ("url", &stack.repository_url), ("status", &stack.status), ("hash", &stack.hash)]));
+        let rows: Vec<[String; 5]> = String::from_utf8_lossy(&output.stdout).lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t').map(str::trim);
+                let task = fields.next()?;
+                Some([
+                    task.strip_prefix(&format!("{}_", stack.name)).unwrap_or(task).to_string(),
+                    fields.next()?.to_string(),
+                    fields.next()?.to_string(),
+                    fields.next()?.to_string(),
+                    fields.next().unwrap_or_default().to_string(),
+                ])
+            })
+            .collect();
+        if rows.is_empty() {
+            println!("  {}", i18n::tr("no-task-in-swarm", &[]));
+            return Ok(());
+        }
+        print_table(["TASK", "NODE", "DESIRED", "STATE", "ERROR"], &rows);
+        Ok(())
+    }
+
+    /// Replicas, update state, image and task errors of the services of a stack
+    async fn service_statuses(&self, stack_name: &str) -> Result<Vec<ServiceStatus>> {
+        let services = self.stack_services(stack_name).await?;
+        if services.is_empty() {
+            return Ok(Vec::new());
+        }
+        let names: Vec<&str> = services.iter().map(|service| service.name.as_str()).collect();
+        let output = tokio::process::Command::from(self.docker())
+            .args(["service", "inspect", "--format",
+                "{{.Spec.Name}}\t{{.Spec.TaskTemplate.ContainerSpec.Image}}\t{{if .UpdateStatus}}{{.UpdateStatus.State}}{{end}}"])
+            .args(&names)
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("failed-to-inspect-services-of-stack", &[("stack", &stack_name), ("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        let inspected: BTreeMap<String, (String, String)> = String::from_utf8_lossy(&output.stdout).lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                Some((fields.next()?.trim().to_string(), (fields.next()?.trim().to_string(), fields.next().unwrap_or_default().trim().to_string())))
+            })
+            .collect();
+        
+        // Tasks meant to run but not running yet, and their last error
+        let output = tokio::process::Command::from(self.docker())
+            .args(["stack", "ps", stack_name, "--filter", "desired-state=running", "--format", "{{.Name}}\t{{.CurrentState}}\t{{.Error}}"])
+            .output()
+            .await?;
+        let tasks = String::from_utf8_lossy(&output.stdout).to_string();
+        
+        Ok(services.into_iter()
+            .map(|service| {
+                let (image, update_state) = inspected.get(&service.name).cloned().unwrap_or_default();
+                let error = tasks.lines()
+                    .filter_map(|line| {
+                        let mut fields = line.split('\t');
+                        let task = fields.next()?;
+                        let state = fields.next()?;
+                        let error = fields.next().unwrap_or_default().trim();
+                        let is_service_task = task.rsplit_once('.').is_some_and(|(name, _)| name == service.name);
+                        (is_service_task && !state.starts_with("Running") && !error.is_empty()).then(|| error.to_string())
+                    })
+                    .next();
+                ServiceStatus { name: service.name, mode: service.mode, running: service.running, desired: service.desired, image, update_state, error }
+            })
+            .collect())
+    }
+
+    /// JSON status of the applications for the `serve` API
+    pub async fn apps_json(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(self.app_statuses().await?)?)
+    }
+
+    /// Report how far the images running in each stack lag behind their registries
+    pub async fn image_report(&self, stack_name: Option<&str>, prometheus: bool) -> Result<()> {
+        let repositories = self.db.get_all_repositories().await?;
+        for repo in &repositories {
+            // The working copy keeps the registries.yaml of the last sync
+            let credentials = match self.registry_credentials(&self.working_copy_path(&repo.url).join(repo.path.as_deref().unwrap_or_default())) {
+                Ok(credentials) => credentials,
+                Err(e) => {
+                    eprintln!("⚠️  {}", e);
+                    RegistryCredentials::default()
+                }
+            };
+            if let Err(e) = self.record_image_freshness(&repo.url, &credentials, &BTreeSet::new()).await {
+                eprintln!("{}", i18n::tr("image-freshness-check-failed-for", &[("url", &repo.url), ("error", &e)]));
+            }
+        }
+        
+        let rows: Vec<ImageFreshness> = self.db.get_all_image_freshness().await?
+            .into_iter()
+            .filter(|f| stack_name.is_none_or(|name| f.stack_name == name))
+            .collect();
+        
+        if prometheus {
+            print!("{}", freshness_metrics(&rows));
+            return Ok(());
+        }
+        
+        if rows.is_empty() {
+            println!("{}", i18n::tr("no-deployed-services-found", &[]));
+            return Ok(());
+        }
+        
+        let mut current_stack = None;
+        for row in &rows {
+            if current_stack != Some((&row.stack_name, &row.repository_url)) {
+                println!("\n{}", i18n::tr("image-report-stack", &[("stack_name", &row.stack_name), ("url", &row.repository_url)]));
+                current_stack = Some((&row.stack_name, &row.repository_url));
+            }
+            
+            println!("  - {} [{}]", row.service, row.image);
+            match (&row.deployed_digest, &row.latest_digest) {
+                (_, None) => println!("    {}", i18n::tr("digest-not-checked", &[])),
+                (deployed, Some(latest)) if row.is_digest_stale() => {
+                    let since = row.stale_since.as_deref().map(|s| self.config.format_timestamp(s)).unwrap_or_default();
+                    println!("    {}", i18n::tr("digest-outdated", &[("since", &since), ("deployed", &short_digest(deployed.as_deref().unwrap_or("?"))), ("latest", &short_digest(latest))]));
+                }
+                _ => println!("    {}", i18n::tr("digest-up-to-date", &[])),
+            }
+            if let Some(latest_tag) = &row.latest_tag {
+                println!("    {}", i18n::tr("newer-tags-available", &[("newer_tags", &row.newer_tags), ("latest_tag", &latest_tag)]));
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// Compare the image digests running in each service of the repository's
+    /// stacks with the latest ones published in their registries
+    /// Check the images the stacks of a repository run against their
+    /// registries, except the stacks of `skipped`
+    async fn record_image_freshness(&self, repository_url: &str, credentials: &RegistryCredentials, skipped: &BTreeSet<String>) -> Result<()> {
+        let client = RegistryClient::with_credentials(credentials.clone());
+        let stacks = self.db.get_all_stacks().await?;
+        
+        for stack in stacks.iter().filter(|s| s.repository_url == repository_url && !skipped.contains(&s.name)) {
+            let checked_at = chrono::Utc::now().to_rfc3339();
+            let mut complete = true;
+            
+            for (service, image) in self.service_images(&stack.name).await? {
+                let reference = ImageReference::parse(&image);
+                let mut freshness = ImageFreshness {
+                    id: 0,
+                    stack_name: stack.name.clone(),
+                    repository_url: stack.repository_url.clone(),
+                    service,
+                    image: image.split('@').next().unwrap_or_default().to_string(),
+                    deployed_digest: reference.digest.clone(),
+                    latest_digest: None,
+                    newer_tags: 0,
+                    latest_tag: None,
+                    stale_since: None,
+                    checked_at: checked_at.clone(),
+                };
+                
+                if self.config.freshness_allowed(reference.registry_name()) {
+                    // An unreachable registry must not hide the other images
+                    freshness.latest_digest = match client.manifest_digest(&reference).await {
+                        Ok(digest) => digest,
+                        Err(e) => {
+                            eprintln!("{}", i18n::tr("image-digest-check-failed", &[("image", &image), ("error", &e)]));
+                            complete = false;
+                            continue;
+                        }
+                    };
+                    
+                    let tags = client.list_tags(&reference).await.unwrap_or_default();
+                    let newer = registry::newer_tags(&reference.tag, &tags);
+                    freshness.newer_tags = newer.len() as i64;
+                    freshness.latest_tag = newer.into_iter().next();
+                }
+                
+                if freshness.is_digest_stale() {
+                    freshness.stale_since = Some(checked_at.clone());
+                }
+                self.db.upsert_image_freshness(&freshness).await?;
+            }
+            
+            // The last results of the images that could not be checked are kept
+            if complete {
+                self.db.delete_image_freshness_not_checked_at(&stack.name, &stack.repository_url, &checked_at).await?;
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// Image reference (with the digest pinned by Swarm) of each service of a stack
+    async fn service_images(&self, stack_name: &str) -> Result<Vec<(String, String)>> {
+        let services: Vec<String> = self.stack_services(stack_name).await?
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        if services.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let output = self.docker()
+            .args(["service", "inspect", "--format", "{{.Spec.Name}}\t{{.Spec.TaskTemplate.ContainerSpec.Image}}"])
+            .args(&services)
+            .output()?;
+        
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!(i18n::tr("failed-to-inspect-services-of-stack", &[("stack", &stack_name), ("error", &error)])));
+        }
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(service, image)| (service.trim().to_string(), image.trim().to_string()))
+            .collect())
+    }
+
+    /// Bring the persistent working copy of a repository up to date: a
+    /// `git fetch` and hard reset when it exists, a clone otherwise.
+    /// `clone_depth` only applies when `shallow` allows it, pinned commits
+    /// being usually out of reach of a shallow clone.
+    async fn clone_repository(&self, url: &str, branch: Option<&str>, shallow: bool, path: Option<&str>) -> Result<String> {
+        let repo_path = self.working_copy_path(url);
+        let repo_path_str = repo_path.to_string_lossy().to_string();
+        
+        if let Some(source) = provider::local_path(url) {
+            self.copy_working_tree(&source, &repo_path)?;
+            return Ok(repo_path_str);
+        }
+        
+        let clone_url = provider::clone_url(url, &self.config);
+        let depth = self.config.clone_depth.filter(|_| shallow);
+        
+        let app_token = self.app_token(url).await?;
+        
+        if repo_path.join(".git").exists() {
+            println!("{}", i18n::tr("fetching-repository", &[("clone_url", &clone_url)]));
+            match self.fetch_repository(&repo_path, url, branch, app_token.as_deref(), depth, path) {
+                Ok(()) => {
+                    self.update_submodules(&repo_path, url, app_token.as_deref(), path)?;
+                    return Ok(repo_path_str);
+                }
+                Err(e) => println!("{}", i18n::tr("working-copy-update-failed", &[("error", &e)])),
+            }
+        }
+        
+        // Missing, broken or left over by an interrupted clone
+        if repo_path.exists() {
+            fs::remove_dir_all(&repo_path)?;
+        }
+        if let Some(parent) = repo_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        match branch {
+            Some(branch) => println!("{}", i18n::tr("cloning-repository-branch", &[("clone_url", &clone_url), ("branch", &branch)])),
+            None => println!("{}", i18n::tr("cloning-repository", &[("clone_url", &clone_url)])),
+        }
+        
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(self.remote_callbacks(url, app_token.as_deref()));
+        if let Some(depth) = depth {
+            println!("{}", i18n::tr("shallow-clone", &[("depth", &depth)]));
+            fetch_options.depth(depth as i32);
+        }
+        
+        let mut builder = git2::build::RepoBuilder::new();
+        builder.fetch_options(fetch_options);
+        if let Some(branch) = branch {
+            builder.branch(branch);
+        }
+        // The files to check out are only known once stacks.yaml can be read
+        let sparse = self.sparse_prefix(path);
+        if sparse.is_some() {
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.dry_run();
+            builder.with_checkout(checkout);
+        }
+        
+        let repo = builder.clone(&clone_url, &repo_path)
+            .map_err(|e| anyhow::anyhow!(i18n::tr("failed-to-clone-repository", &[("error", &e)])))?;
+        if sparse.is_some() {
+            let commit = repo.head()?.peel_to_commit()?;
+            repo.checkout_tree(commit.as_object(), Some(&mut checkout_options(&repo, &commit, sparse)?))?;
+            println!("{}", i18n::tr("sparse-checkout-done", &[]));
+        }
+        self.update_submodules(&repo_path, url, app_token.as_deref(), path)?;
+        
+        Ok(repo_path_str)
+    }
+
+    /// GitHub App installation token for a github.com HTTPS URL. Tokens
+    /// expire, a fresh one (or a cached one still valid) is used for every
+    /// clone or fetch.
+    async fn app_token(&self, url: &str) -> Result<Option<String>> {
+        match (&self.github_app, github::parse_repository_url(url)) {
+            (Some(app), Some((owner, repo))) if !provider::is_ssh(url) => Ok(Some(app.installation_token(&owner, &repo).await?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Bring the submodules to the commits recorded by a revision checked out after the fetch
+    async fn checkout_submodules(&self, repo_path: &str, url: &str, path: Option<&str>) -> Result<()> {
+        let app_token = self.app_token(url).await?;
+        self.update_submodules(Path::new(repo_path), url, app_token.as_deref(), path)
+    }
+
+    /// Initialize and update the submodules of a working copy, recursively,
+    /// so stacks and bindings living in one get their complete content.
+    /// With `sparse_checkout`, only the submodules of declared stacks are.
+    fn update_submodules(&self, repo_path: &Path, url: &str, app_token: Option<&str>, path: Option<&str>) -> Result<()> {
+        let repo = git2::Repository::open(repo_path)?;
+        let stack_dirs: Option<Vec<PathBuf>> = match self.sparse_prefix(path) {
+            Some(prefix) => {
+                let stacks: Vec<StackDefinition> = match fs::read_to_string(repo_path.join(prefix).join("stacks.yaml")) {
+                    Ok(content) => serde_yaml::from_str(&content)?,
+                    Err(_) => Vec::new(),
+                };
+                Some(stacks.into_iter().map(|stack| Path::new(prefix).join(stack.name)).collect())
+            }
+            None => None,
+        };
+        
+        self.update_submodules_in(&repo, url, app_token, stack_dirs.as_deref())
+    }
+
+    fn update_submodules_in(&self, repo: &git2::Repository, url: &str, app_token: Option<&str>, stack_dirs: Option<&[PathBuf]>) -> Result<()> {
+        for mut submodule in repo.submodules()? {
+            let path = submodule.path().to_path_buf();
+            if stack_dirs.is_some_and(|stack_dirs| !stack_dirs.iter().any(|dir| path.starts_with(dir))) {
+                continue;
+            }
+            
+            // Relative URLs live next to the superproject, with the same credentials
+            let submodule_url = match submodule.url() {
+                Some(submodule_url) if !submodule_url.starts_with('.') => submodule_url.to_string(),
+                _ => url.to_string(),
+            };
+            let token = app_token.filter(|_| github::parse_repository_url(&submodule_url).is_some() && !provider::is_ssh(&submodule_url));
+            println!("{}", i18n::tr("updating-submodule-from", &[("path", &path.display()), ("url", &submodule.url().unwrap_or_default())]));
+            
+            let mut fetch_options = git2::FetchOptions::new();
+            fetch_options.remote_callbacks(self.remote_callbacks(&submodule_url, token));
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.force();
+            let mut options = git2::SubmoduleUpdateOptions::new();
+            options.fetch(fetch_options).checkout(checkout);
+            submodule.update(true, Some(&mut options))
+                .map_err(|e| anyhow::anyhow!(i18n::tr("failed-to-update-submodule", &[("path", &path.display()), ("error", &e)])))?;
+            
+            // Compose files rendered in place by the previous run are dropped
+            let submodule_repo = submodule.open()?;
+            let head = submodule_repo.head()?.peel(git2::ObjectType::Commit)?;
+            submodule_repo.reset(&head, git2::ResetType::Hard,
+                Some(git2::build::CheckoutBuilder::new().force().remove_untracked(true)))?;
+            
+            self.update_submodules_in(&submodule_repo, &submodule_url, token, None)?;
+        }
+        
+        Ok(())
+    }
+
+    /// Snapshot the working tree of a local repository, uncommitted changes
+    /// included. Compose files are rendered in place, so the repository
+    /// itself is never processed directly.
+    fn copy_working_tree(&self, source: &Path, repo_path: &Path) -> Result<()> {
+        println!("{}", i18n::tr("copying-local-working-tree-from", &[("source", &source.display())]));
+        if !source.is_dir() {
+            return Err(anyhow::anyhow!(i18n::tr("local-repository-is-not-a-directory", &[("source", &source.display())])));
+        }
+        if repo_path.exists() {
+            fs::remove_dir_all(repo_path)?;
+        }
+        
+        for skipped in copy_tree(source, repo_path, |entry| entry.file_name() != ".git")? {
+            println!("{}", i18n::tr("skipped-special-file", &[("path", &skipped.display())]));
+        }
+        
+        Ok(())
+    }
+
+    /// Directory of stacks.yaml the checkouts are limited to with
+    /// `sparse_checkout` ("" for the root), `None` for full checkouts
+    fn sparse_prefix<'a>(&self, path: Option<&'a str>) -> Option<&'a str> {
+        self.config.sparse_checkout.unwrap_or(false).then_some(path.unwrap_or_default())
+    }
+
+    fn working_copy_path(&self, url: &str) -> std::path::PathBuf {
+        self.config.repos_dir().join(format!("{:x}", md5::compute(url)))
+    }
+
+    fn remove_working_copy(&self, url: &str) {
+        let repo_path = self.working_copy_path(url);
+        if repo_path.exists() {
+            if let Err(e) = fs::remove_dir_all(&repo_path) {
+                println!("{}", i18n::tr("working-copy-not-removed", &[("error", &e)]));
+            }
+        }
+    }
+
+    /// Fetch all branches and tags into an existing working copy and hard
+    /// reset it (dropping files rendered by previous runs) to the watched branch
+    fn fetch_repository(&self, repo_path: &Path, url: &str, branch: Option<&str>, app_token: Option<&str>, depth: Option<u32>, path: Option<&str>) -> Result<()> {
+        let repo = git2::Repository::open(repo_path)?;
+        if repo.is_shallow() && depth.is_none() {
+            return Err(anyhow::anyhow!(i18n::tr("full-history-needed", &[])));
+        }
+        repo.remote_set_url("origin", &provider::clone_url(url, &self.config))?;
+        let mut remote = repo.find_remote("origin")?;
+        
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(self.remote_callbacks(url, app_token));
+        fetch_options.prune(git2::FetchPrune::On);
+        if let Some(depth) = depth {
+            fetch_options.depth(depth as i32);
+        }
+        remote.fetch(&["+refs/heads/*:refs/remotes/origin/*", "+refs/tags/*:refs/tags/*"], Some(&mut fetch_options), None)?;
+        
+        // origin/HEAD is set by the initial clone and points to the default branch
+        let target = match branch {
+            Some(branch) => format!("refs/remotes/origin/{}", branch),
+            None => "refs/remotes/origin/HEAD".to_string(),
+        };
+        let commit = repo.revparse_single(&target)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| anyhow::anyhow!(i18n::tr("branch-not-found", &[("target", &target), ("error", &e)])))?;
+        
+        repo.set_head_detached(commit.id())?;
+        let mut checkout = checkout_options(&repo, &commit, self.sparse_prefix(path))?;
+        repo.reset(commit.as_object(), git2::ResetType::Hard, Some(checkout.remove_untracked(true)))?;
+        println!("{}", i18n::tr("working-copy-updated-to", &[("id", &commit.id())]));
+        
+        Ok(())
+    }
+
+    /// Tip of the watched branch when it is the last commit processed, so that
+    /// an automatic run can skip the fetch. Pinned commits, tags, local
+    /// repositories and stacks following other refs are always fetched.
+    async fn unchanged_remote_tip(&self, repo: &RepositoryCache, options: &DeployOptions) -> Option<String> {
+        let last_commit = repo.last_commit.as_deref()?;
+        let is_plain_run = options.is_automatic && !options.force && !options.is_reconcile && !options.adopt;
+        if !is_plain_run || repo.pinned_commit.is_some() || repo.tag_pattern.is_some() || provider::local_path(&repo.url).is_some() {
+            return None;
+        }
+        let working_copy = self.working_copy_path(&repo.url);
+        if !working_copy.join(".git").exists() || !declared_refs(&working_copy.join(repo.path.as_deref().unwrap_or_default())).is_empty() {
+            return None;
+        }
+        
+        let tip = match self.app_token(&repo.url).await {
+            Ok(app_token) => self.remote_tip(&repo.url, repo.branch.as_deref(), app_token.as_deref()),
+            Err(e) => Err(e),
+        };
+        match tip {
+            Ok(tip) => (tip == last_commit).then_some(tip),
+            Err(e) => {
+                println!("{}", i18n::tr("remote-refs-unavailable", &[("error", &e)]));
+                None
+            }
+        }
+    }
+
+    /// Commit a branch of a remote points to, its default branch when none,
+    /// without fetching anything (`git ls-remote`)
+    fn remote_tip(&self, url: &str, branch: Option<&str>, app_token: Option<&str>) -> Result<String> {
+        let mut remote = git2::Remote::create_detached(provider::clone_url(url, &self.config))?;
+        let connection = remote.connect_auth(git2::Direction::Fetch, Some(self.remote_callbacks(url, app_token)), None)?;
+        let target = match branch {
+            Some(branch) => format!("refs/heads/{}", branch),
+            None => "HEAD".to_string(),
+        };
+        let tip = connection.list()?.iter()
+            .find(|head| head.name() == target)
+            .map(|head| head.oid().to_string());
+        tip.ok_or_else(|| anyhow::anyhow!(i18n::tr("ref-not-found-on-remote", &[("target", &target)])))
+    }
+
+    /// Credentials used for clones and fetches: the GitHub App installation
+    /// token or the provider's token for HTTPS remotes, the configured key
+    /// or the ssh-agent for SSH remotes
+    fn remote_callbacks(&self, url: &str, app_token: Option<&str>) -> git2::RemoteCallbacks<'static> {
+        let mut callbacks = git2::RemoteCallbacks::new();
+        
+        let provider = Provider::detect(url, &self.config);
+        let token = match app_token {
+            Some(app_token) => Some((Some("x-access-token".to_string()), app_token.to_string())),
+            None => provider.and_then(|provider| provider.credentials(&self.config)),
+        };
+        match provider {
+            _ if provider::is_ssh(url) => {}
+            _ if app_token.is_some() => println!("{}", i18n::tr("auth-github-app", &[])),
+            Some(provider) if token.is_some() => println!("{}", i18n::tr("auth-token", &[("name", &provider.name())])),
+            Some(provider) => {
+                println!("{}", i18n::tr("auth-no-token", &[("name", &provider.name())]));
+                println!("{}", i18n::tr("auth-no-token-hint", &[("token_variable", &provider.token_variable())]));
+            }
+            None => {}
+        }
+        let ssh_key = self.config.ssh_key();
+        let passphrase = self.config.ssh_key_passphrase.clone();
+        
+        // libgit2 asks again after rejected credentials, give up instead of looping
+        let mut attempts = 0;
+        callbacks.credentials(move |_url, username_from_url, allowed_types| {
+            attempts += 1;
+            if attempts > 3 {
+                return Err(git2::Error::from_str("authentication failed (check the access token or the SSH key)"));
+            }
+            let username = username_from_url.unwrap_or("git");
+            
+            if allowed_types.contains(git2::CredentialType::USERNAME) {
+                return git2::Cred::username(username);
+            }
+            if allowed_types.contains(git2::CredentialType::SSH_KEY) {
+                return match &ssh_key {
+                    Some(key) => git2::Cred::ssh_key(username, None, key, passphrase.as_deref()),
+                    None => git2::Cred::ssh_key_from_agent(username),
+                };
+            }
+            match &token {
+                Some((token_username, token)) if allowed_types.contains(git2::CredentialType::USER_PASS_PLAINTEXT) => {
+                    git2::Cred::userpass_plaintext(token_username.as_deref().unwrap_or(username), token)
+                }
+                _ => Err(git2::Error::from_str("no credentials available for this remote")),
+            }
+        });
+        
+        callbacks
+    }
+
+    /// Returns false when stack updates were delayed until the load decreases
+    async fn process_and_deploy_stacks(&self, repo_path: &str, repository_url: &str, options: &DeployOptions) -> Result<bool> {
+        println!("{}", i18n::tr("processing-stacks-from-repository", &[]));
+        
+        if !self.dry_run {
+            self.purge_expired_trash().await?;
+        }
+        
+        // Images referenced by this repository before the run, the ones no
+        // longer used afterwards are candidates for removal
+        let previous_images = self.db.get_repository_images(repository_url).await?;
+        
+        // Look for stacks.yaml file
+        let stacks_file_path = Path::new(repo_path).join("stacks.yaml");
+        if !stacks_file_path.exists() {
+            return Err(anyhow::anyhow!(i18n::tr("stacks-yaml-not-found-in-repository", &[])));
+        }
+        
+        // Read and parse stacks.yaml
+        let stacks_content = fs::read_to_string(&stacks_file_path)?;
+        let mut stacks_definitions: Vec<StackDefinition> = serde_yaml::from_str(&stacks_content)?;
+        
+        // Defaults versioned with the stacks in dockerops.yaml, the stack names
+        // of a repository watched in a namespace starting with it
+        let namespace = match &options.namespace {
+            Some(namespace) => Some(namespace.clone()),
+            None => self.db.get_repository_from_cache(repository_url).await?.and_then(|repo| repo.namespace),
+        };
+        let settings = RepositorySettings::load(Path::new(repo_path))?.in_namespace(namespace.as_deref());
+        if settings.stack_prefix.is_some() {
+            for definition in &mut stacks_definitions {
+                definition.declared_name = Some(definition.name.clone());
+                definition.name = settings.stack_name(&definition.name);
+                definition.depends_on = definition.depends_on.iter().map(|name| settings.stack_name(name)).collect();
+            }
+        }
+        self.checkout_stack_refs(repo_path, &stacks_definitions)?;
+        
+        // Process volumes configuration, with --changed-only the bindings are
+        // copied to the NFS share once a stack turns out to have changed
+        let changed_only = self.changed_only && !options.force && !options.is_reconcile && !self.dry_run;
+        let volumes_definitions = self.process_volumes_config(repo_path, !changed_only, namespace.as_deref()).await?;
+        let bindings = match changed_only {
+            true => bindings_fingerprint(repo_path),
+            false => None,
+        };
+        
+        // Values substituted in the compose files
+        let repository_values = self.load_repository_values(repo_path, repository_url).await?;
+        
+        // Logins used for this repository's images only
+        let credentials = self.registry_credentials(Path::new(repo_path))?;
+        
+        println!("{}", i18n::tr("found-stack-definitions", &[("count", &stacks_definitions.len())]));
+        
+        if let Some(only_stack) = &options.only_stack {
+            if !stacks_definitions.iter().any(|s| &s.name == only_stack) {
+                return Err(anyhow::anyhow!(i18n::tr("stack-not-declared", &[("only_stack", &only_stack)])));
+            }
+        }
+        
+        // Stacks are deployed after the stacks they depend on or whose outputs
+        // they reference, then by priority, and DockerOps' own stack last
+        // since deploying it restarts this process
+        let self_stack = self.config.self_stack();
+        let dependencies = stack_dependencies(Path::new(repo_path), &stacks_definitions)?;
+        let ordered_definitions = deployment_order(&stacks_definitions, &dependencies, self_stack.as_deref())?;
+        if stacks_definitions.iter().any(|definition| definition.priority.is_some() || !definition.depends_on.is_empty()) {
+            let names: Vec<&str> = ordered_definitions.iter().map(|definition| definition.name.as_str()).collect();
+            println!("{}", i18n::tr("deployment-order", &[("list", &names.join(", "))]));
+        }
+        
+        // Every selected stack is checked before the first one deploys
+        self.check_env_examples(repo_path, &ordered_definitions, options, &repository_values)?;
+        for definition in &ordered_definitions {
+            settings.for_stack(&self.config, definition)?;
+            if let Some(prerequisites) = &definition.prerequisites {
+                prerequisites.validate().map_err(|e| anyhow::anyhow!(i18n::tr("invalid-prerequisites-for-stack", &[("stack", &definition.name), ("error", &e)])))?;
+            }
+            if let Some(hooks) = &definition.hooks {
+                hooks.validate().map_err(|e| anyhow::anyhow!(i18n::tr("invalid-hooks-for-stack", &[("stack", &definition.name), ("error", &e)])))?;
+            }
+            if definition.teardown.is_some() && definition.is_enabled() {
+                return Err(anyhow::anyhow!(i18n::tr("stack-teardown-needs-disabled", &[("stack", &definition.name)])));
+            }
+        }
+        
+        // Outputs published during this run, later stacks see them before they are stored
+        let mut run_outputs: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        let mut is_complete = true;
+        
+        // Stacks start once the stacks they depend on are done, up to
+        // max_parallel_deploys at a time, DockerOps' own stack alone at the end
+        let max_parallel = self.config.max_parallel_deploys();
+        if max_parallel > 1 {
+            println!("{}", i18n::tr("deploy-parallelism", &[("max_parallel", &max_parallel)]));
+        }
+        let no_stack = BTreeSet::new();
+        let mut run = StackRun {
+            repo_path,
+            repository_url,
+            options,
+            settings: &settings,
+            volumes_definitions: volumes_definitions.as_deref(),
+            repository_values: &repository_values,
+            credentials: &credentials,
+            bindings: bindings.as_deref(),
+            unchanged: &no_stack,
+        };
+        let unchanged = match changed_only {
+            true => self.unchanged_stacks(&run, &ordered_definitions, &dependencies).await?,
+            false => BTreeSet::new(),
+        };
+        if changed_only {
+            match ordered_definitions.iter().any(|definition| !unchanged.contains(&definition.name)) {
+                true => {
+                    self.process_volumes_config(repo_path, true, namespace.as_deref()).await?;
+                }
+                false => println!("{}", i18n::tr("changed-only-nothing-changed", &[])),
+            }
+        }
+        run.unchanged = &unchanged;
+        // Images of the stacks of a dry run, by stack
+        let mut planned_images: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut waiting = ordered_definitions;
+        let mut done: Vec<&str> = Vec::new();
+        let mut running = futures_util::stream::FuturesUnordered::new();
+        let mut first_error = None;
+        loop {
+            while first_error.is_none() && running.len() < max_parallel {
+                let is_ready = |definition: &&StackDefinition| dependencies[definition.name.as_str()].iter().all(|stack| done.contains(&stack.as_str()))
+                    && (Some(definition.name.as_str()) != self_stack.as_deref() || (waiting.len() == 1 && running.is_empty()));
+                // The ready stack with the highest priority, the first in deployment order among equals
+                let Some(index) = waiting.iter().enumerate().filter(|(_, definition)| is_ready(definition))
+                    .min_by_key(|(_, definition)| std::cmp::Reverse(definition.priority.unwrap_or(0)))
+                    .map(|(index, _)| index) else {
+                    break;
+                };
+                let stack_def = waiting.remove(index);
+                let (run, outputs) = (&run, run_outputs.clone());
+                running.push(async move { (stack_def, self.process_stack(stack_def, run, outputs).await) });
+            }
+            let Some((stack_def, result)) = running.next().await else {
+                break;
+            };
+            match result {
+                Ok(StackOutcome::Done(outputs)) => {
+                    run_outputs.insert(stack_def.name.clone(), outputs);
+                }
+                Ok(StackOutcome::Planned { outputs, images }) => {
+                    run_outputs.insert(stack_def.name.clone(), outputs);
+                    planned_images.insert(stack_def.name.clone(), images);
+                }
+                Ok(StackOutcome::Skipped) => {}
+                Ok(StackOutcome::Postponed) => is_complete = false,
+                // The stacks already deploying finish, no other one starts
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+            done.push(&stack_def.name);
+        }
+        if let Some(e) = first_error {
+            return Err(e);
+        }
+        
+        // Stacks deleted from stacks.yaml are removed and kept in the trash when pruning
+        if options.only_stack.is_none() {
+            let prune = self.prune || settings.for_stack(&self.config, &StackDefinition::default())?.prune_stacks();
+            self.remove_undeclared_stacks(repository_url, &stacks_definitions, &options.filter, prune).await?;
+        }
+        
+        // Nothing was changed, the next run has everything left to do
+        if self.dry_run {
+            if options.only_stack.is_none() {
+                self.print_planned_image_removals(repository_url, &previous_images, &stacks_definitions, &planned_images).await?;
+            }
+            return Ok(false);
+        }
+        
+        self.db.refresh_image_reference_counts().await?;
+        
+        // Process images: check SHA, pull if needed, remove unused
+        if options.only_stack.is_none() {
+            println!("{}", i18n::tr("processing-images", &[]));
+            self.process_images(repository_url, &previous_images, &credentials, &unchanged).await?;
+            
+            // Freshness is informational, registry failures must not fail the deployment
+            if let Err(e) = self.record_image_freshness(repository_url, &credentials, &unchanged).await {
+                println!("{}", i18n::tr("image-freshness-check-failed", &[("error", &e)]));
+            }
+        }
+        
+        Ok(is_complete)
+    }
+
+    /// Render, compare and deploy one stack of a repository, `run_outputs`
+    /// holding the outputs published so far during the run
+    async fn process_stack(&self, stack_def: &StackDefinition, run: &StackRun<'_>, run_outputs: BTreeMap<String, BTreeMap<String, String>>) -> Result<StackOutcome> {
+        let StackRun { repo_path, repository_url, options, settings, volumes_definitions, repository_values, credentials, bindings, unchanged } = *run;
+        let force = options.force;
+        
+        if options.only_stack.as_ref().is_some_and(|only| only != &stack_def.name) {
+            return Ok(StackOutcome::Skipped);
+        }
+        if !options.filter.matches(&stack_def.name) {
+            println!("{}", i18n::tr("skipping-stack-filtered", &[("stack", &stack_def.name)]));
+            return Ok(StackOutcome::Skipped);
+        }
+        if !stack_def.is_enabled() {
+            println!("{}", i18n::tr("skipping-stack-disabled", &[("stack", &stack_def.name)]));
+            return Ok(StackOutcome::Skipped);
+        }
+        if unchanged.contains(&stack_def.name) {
+            println!("{}", i18n::tr("skipping-stack-unchanged", &[("stack", &stack_def.name)]));
+            return Ok(StackOutcome::Skipped);
+        }
+        println!("{}", i18n::tr("processing-stack", &[("stack", &stack_def.name)]));
+        let stack_settings = settings.for_stack(&self.config, stack_def)?;
+        
+        // Look for the stack directory
+        let stack_dir = Path::new(repo_path).join(stack_def.directory());
+        if !stack_dir.exists() || !stack_dir.is_dir() {
+            println!("  {}", i18n::tr("stack-directory-not-found", &[("directory", &stack_def.directory())]));
+            return Ok(StackOutcome::Skipped);
+        }
+        
+        // Look for docker-compose file in the stack directory
+        let Some(compose_path) = find_compose_file(&stack_dir) else {
+            println!("  {}", i18n::tr("stack-compose-file-not-found", &[("directory", &stack_def.directory())]));
+            return Ok(StackOutcome::Skipped);
+        };
+        let mut compose_content = fs::read_to_string(&compose_path)?;
+        
+        // A stack's own values.yaml overrides the repository values
+        let mut stack_values = repository_values.clone();
+        stack_values.extend(read_values_file(&stack_dir.join("values.yaml"))?);
+        
+        // A stack rendered from the same inputs is not rendered again,
+        // unless forced since rendering also creates its NFS directories
+        let render_hash = self.render_inputs_hash(repo_path, &stack_def.name, &compose_content, &stack_values, volumes_definitions, bindings.unwrap_or_default());
+        let cached = match force || options.is_reconcile {
+            true => None,
+            false => self.cached_render(&stack_def.name, repository_url, &render_hash, &run_outputs).await?,
+        };
+        if let Some(rendered) = cached {
+            println!("  {}", i18n::tr("render-cache-hit", &[]));
+            compose_content = rendered;
+        } else {
+            if !stack_values.is_empty() {
+                compose_content = render_values(&compose_content, &stack_values);
+            }
+            
+            // Outputs of the referenced stacks, from this run or from their last deployment
+            let mut referenced_outputs = BTreeMap::new();
+            for (stack, output) in output_references(&compose_content) {
+                let Some(value) = self.output_value(&run_outputs, &stack, &output).await? else {
+                    return Err(anyhow::anyhow!(i18n::tr("output-not-published", &[("stack", &stack_def.name), ("output", &output), ("stack2", &stack)])));
+                };
+                referenced_outputs.insert((stack, output), value);
+            }
+            if !referenced_outputs.is_empty() {
+                compose_content = render_outputs(&compose_content, &referenced_outputs);
+            }
+            
+            // Legacy syntax docker stack deploy rejects or ignores is rewritten
+            // in the deployed file, invalid YAML is left for docker to report
+            if let Ok((normalized, deprecations)) = compose::normalize(&compose_content) {
+                if !deprecations.is_empty() {
+                    let file_name = compose_path.file_name().unwrap_or_default().to_string_lossy();
+                    println!("  {}", i18n::tr("compose-deprecated-syntax", &[("directory", &stack_def.directory()), ("file_name", &file_name)]));
+                    for deprecation in &deprecations {
+                        println!("      - {}", deprecation);
+                    }
+                }
+                compose_content = normalized;
+            }
+            
+            // Process volumes in compose file if volumes definitions exist
+            if let Some(volumes_defs) = volumes_definitions {
+                println!("  {}", i18n::tr("processing-compose-volumes", &[]));
+                let nfs_config = self.read_nfs_config(repo_path).await?;
+                compose_content = self.process_compose_volumes(&compose_content, volumes_defs, &nfs_config).await?;
+                println!("  {}", i18n::tr("volume-processing-completed", &[]));
+            }
+            
+            if self.config.requires_encrypted_networks(&stack_def.name) {
+                println!("  {}", i18n::tr("enforcing-encrypted-overlay-networks", &[]));
+                compose_content = self.enforce_network_encryption(&stack_def.name, &compose_content)?;
+            }
+            
+            let outputs: Vec<(&String, &String, &String)> = referenced_outputs.iter()
+                .map(|((stack, output), value)| (stack, output, value))
+                .collect();
+            if !self.dry_run {
+                self.db.set_render_cache(&stack_def.name, repository_url, &render_hash, &compose_content, &serde_json::to_string(&outputs)?).await?;
+            }
+        }
+        
+        // Process secrets
+        println!("  {}", i18n::tr("processing-secrets", &[]));
+        let secrets_env_vars = self.process_compose_secrets(&stack_dir, repo_path).await?;
+        println!("  {}", i18n::tr("secret-processing-completed", &[]));
+        
+        // Write the modified compose content back to the file
+        atomic::write(&compose_path, &compose_content)?;
+        println!("  {}", i18n::tr("compose-file-updated", &[("compose_path", &compose_path.to_string_lossy())]));
+        
+        let compose_hash = self.calculate_md5(&compose::hashed_content(&compose_content, &stack_settings.hash_exclusions()));
+        
+        // Labels tracing the services back to their source, added after
+        // hashing so that a commit not touching the stack doesn't redeploy it
+        let mut labels = vec![
+            ("com.dockerops.repo", repository_url.to_string()),
+            ("com.dockerops.stack", stack_def.name.clone()),
+            ("com.dockerops.hash", compose_hash.clone()),
+        ];
+        if let Some(commit) = stack_commit(repo_path, stack_def) {
+            labels.push(("com.dockerops.commit", commit));
+        }
+        if let Ok(labeled) = compose::label_services(&compose_content, &labels) {
+            compose_content = labeled;
+            atomic::write(&compose_path, &compose_content)?;
+        }
+        
+        // Calculate relative path for database
+        let relative_compose_path = compose_path.strip_prefix(repo_path)
+            .unwrap_or(&compose_path)
+            .to_string_lossy()
+            .replace('\\', "/")
+            .to_string();
+        
+        if let Some(planned) = options.planned_hashes.get(&stack_def.name).filter(|planned| **planned != compose_hash) {
+            return Err(anyhow::anyhow!(i18n::tr("plan-stack-render-changed", &[("stack", &stack_def.name), ("compose_hash", &compose_hash), ("planned", &planned)])));
+        }
+        
+        // A dry run stops here, with what a run would do to the stack
+        if self.dry_run {
+            let images = self.process_yaml_file(&compose_content, &relative_compose_path).await?;
+            self.print_planned_stack(stack_def, repository_url, &compose_hash, &images, options, &stack_settings).await?;
+            let outputs = stack_def.outputs.iter()
+                .map(|(name, value)| (name.clone(), render_values(value, &stack_values)))
+                .collect();
+            return Ok(StackOutcome::Planned { outputs, images });
+        }
+        
+        // Check if stack exists in database
+        if let Some(existing_stack) = self.db.get_stack_by_name(&stack_def.name, repository_url).await? {
+            let has_changed = existing_stack.hash != compose_hash;
+            let has_failed = existing_stack.status == "error";
+            // Reconcile converges Swarm to the declared state, whatever happened to it since the last deploy
+            let has_drifted = options.is_reconcile && !has_changed && !force && !has_failed
+                && self.stack_drifted(&stack_def.name, repository_url).await?;
+            let should_deploy = has_changed || force || has_failed || has_drifted;
+            
+            if existing_stack.app != stack_def.app {
+                self.db.update_stack_app(&stack_def.name, repository_url, stack_def.app.as_deref()).await?;
+            }
+            
+            // Circuit breaker: a stack failing over and over is left alone until its compose file changes
+            if let Some(failure) = self.db.get_stack_failure(&stack_def.name, repository_url).await? {
+                if failure.failures >= stack_settings.failure_threshold() && failure.hash == compose_hash && !force {
+                    println!("  {}", i18n::tr("stack-failures-not-retrying", &[("stack", &stack_def.name), ("failures", &failure.failures)]));
+                    return Ok(StackOutcome::Skipped);
+                }
+            }
+            
+            // A rolled back change stays out until git moves past it
+            if has_changed && !force && self.rolled_back_from(&existing_stack).await?.as_deref() == Some(compose_hash.as_str()) {
+                println!("  {}", i18n::tr("stack-rolled-back-not-redeploying", &[("stack", &stack_def.name)]));
+                return Ok(StackOutcome::Skipped);
+            }
+            
+            if has_changed {
+                println!("  {}", i18n::tr("stack-changed", &[("stack", &stack_def.name), ("hash", &existing_stack.hash), ("compose_hash", &compose_hash)]));
+            } else if force {
+                println!("  {}", i18n::tr("stack-unchanged-forced", &[("stack", &stack_def.name)]));
+            } else if has_failed {
+                println!("  {}", i18n::tr("stack-retrying", &[("stack", &stack_def.name)]));
+            } else if has_drifted {
+                println!("  {}", i18n::tr("stack-modified-in-swarm", &[("stack", &stack_def.name)]));
+            } else {
+                println!("  {}", i18n::tr("stack-unchanged", &[("stack", &stack_def.name)]));
+            }
+            
+            // Changes are not urgent for automatic runs, a busy stack keeps running its current version
+            if has_changed && options.is_automatic && !force && !has_failed && stack_settings.traffic_aware() && self.defer_for_load(&existing_stack).await? {
+                return Ok(StackOutcome::Postponed);
+            }
+            
+            if should_deploy {
+                // The stacks it depends on have to be up before it is updated
+                if !self.dependencies_ready(stack_def, &stack_settings.deploy).await? {
+                    return Ok(StackOutcome::Postponed);
+                }
+                
+                // Manual changes to the running services that git changes too wait for an operator
+                let Some(content) = self.resolve_overrides(&stack_def.name, repository_url, &compose_hash, &compose_content).await? else {
+                    return Ok(StackOutcome::Postponed);
+                };
+                if content != compose_content {
+                    compose_content = content;
+                    atomic::write(&compose_path, &compose_content)?;
+                }
+                
+                if existing_stack.deferred_since.is_some() {
+                    self.db.update_stack_deferred_since(&stack_def.name, repository_url, None).await?;
+                }
+                if options.is_reconcile {
+                    // For reconcile, stop the existing stack first
+                    println!("  {}", i18n::tr("stopping-existing-stack", &[("stack", &stack_def.name)]));
+                    self.stop_stack(&stack_def.name).await?;
+                }
+                
+                // Update stack in database
+                self.db.update_stack_hash(&stack_def.name, repository_url, &compose_hash).await?;
+                
+                // Image and replica changes can be applied to the changed services alone
+                let changes = match stack_settings.deploy.selective.unwrap_or(false) && has_changed && !options.is_reconcile {
+                    true => self.db.get_stack_compose_content(&stack_def.name, repository_url).await?
+                        .and_then(|previous| selective_changes(&previous, &compose_content))
+                        .filter(|changes| !changes.is_empty()),
+                    false => None,
+                };
+                
+                // Deploy the updated stack
+                println!("  {}", i18n::tr("deploying-updated-stack", &[("stack", &stack_def.name)]));
+                let since = chrono::Utc::now();
+                let deployed = match (self.before_deploy(stack_def, &stack_dir, &secrets_env_vars, credentials, &stack_settings.deploy).await, &changes) {
+                    (Err(e), _) => Err(e),
+                    (Ok(()), Some(changes)) => self.update_services(&stack_def.name, changes, &compose_content, credentials, &stack_settings.deploy).await,
+                    (Ok(()), None) => self.deploy_stack(&stack_def.name, &compose_path, &secrets_env_vars, credentials, &stack_settings.deploy).await,
+                };
+                let was_deployed = deployed.is_ok();
+                let (deployed, degraded) = self.converge(&stack_def.name, deployed, &stack_settings.deploy).await;
+                // An update whose services never become healthy is replaced by the previous revision
+                let (deployed, degraded) = match was_deployed && (deployed.is_err() || degraded) && stack_settings.auto_rollback() {
+                    true => (self.auto_rollback(&existing_stack, &compose_hash, deployed, &secrets_env_vars, credentials, &stack_settings.deploy).await, false),
+                    false => (deployed, degraded),
+                };
+                // Failing post hooks bring the previous revision back as well
+                let (deployed, degraded) = match deployed {
+                    Ok(()) => match self.run_hooks(stack_def, "post", &stack_dir, &secrets_env_vars, credentials, &stack_settings.deploy).await {
+                        Ok(()) => (Ok(()), degraded),
+                        Err(e) => (self.auto_rollback(&existing_stack, &compose_hash, Err(e), &secrets_env_vars, credentials, &stack_settings.deploy).await, false),
+                    },
+                    Err(e) => (Err(e), degraded),
+                };
+                // A successful rollout still has to prove itself before being kept
+                let deployed = match (deployed, &stack_settings.updates.canary) {
+                    (Ok(()), Some(canary)) => self.canary_analysis(&stack_def.name, repository_url, &compose_hash, canary, since).await,
+                    (deployed, _) => deployed,
+                };
+                let mut deployment = Deployment::new(&stack_def.name, repository_url, &compose_hash, "update", since);
+                deployment.commit_hash = stack_commit(repo_path, stack_def);
+                deployment.operator = options.operator.clone();
+                self.record_deployment(deployment, &deployed).await?;
+                self.record_deploy_result(repository_url, stack_def, &compose_hash, &deployed, &stack_settings).await?;
+                deployed?;
+                if degraded {
+                    self.db.update_stack_status(&stack_def.name, repository_url, "degraded").await?;
+                }
+            } else if existing_stack.status == "restored" || existing_stack.status == "imported" {
+                // The stack is back in stacks.yaml, it is managed normally again
+                self.db.update_stack_status(&stack_def.name, repository_url, "deployed").await?;
+            }
+        } else {
+            // Swarm stack names are global, another repository must not take over its stack
+            let owners = self.db.get_stacks_named(&stack_def.name).await?;
+            if let Some(owner) = owners.first() {
+                println!("  {}", i18n::tr("stack-owned-by-other-repository", &[("stack", &stack_def.name), ("url", &owner.repository_url)]));
+                return Ok(StackOutcome::Skipped);
+            }
+            
+            // Deployed once the stacks it depends on are up, an adopted stack runs already
+            if !options.adopt && !self.dependencies_ready(stack_def, &stack_settings.deploy).await? {
+                return Ok(StackOutcome::Postponed);
+            }
+            
+            // New stack
+            let mut stack = Stack::new(
+                stack_def.name.clone(),
+                repository_url.to_string(),
+                relative_compose_path.clone(),
+                compose_hash.clone(),
+            );
+            stack.app = stack_def.app.clone();
+            self.db.create_stack(&stack).await?;
+            
+            let since = chrono::Utc::now();
+            let (deployed, degraded, kind) = if options.adopt {
+                // Running already, the next syncs compare git with this hash
+                println!("  {}", i18n::tr("stack-adopting-running", &[("stack", &stack_def.name), ("compose_hash", &compose_hash)]));
+                (Ok(()), false, "adopt")
+            } else {
+                println!("  {}", i18n::tr("stack-new", &[("stack", &stack_def.name)]));
+                let deployed = match self.before_deploy(stack_def, &stack_dir, &secrets_env_vars, credentials, &stack_settings.deploy).await {
+                    Ok(()) => self.deploy_stack(&stack_def.name, &compose_path, &secrets_env_vars, credentials, &stack_settings.deploy).await,
+                    Err(e) => Err(e),
+                };
+                let (deployed, degraded) = self.converge(&stack_def.name, deployed, &stack_settings.deploy).await;
+                // Without a previous revision, a stack failing its post hooks stays deployed but failed
+                let deployed = match deployed {
+                    Ok(()) => self.run_hooks(stack_def, "post", &stack_dir, &secrets_env_vars, credentials, &stack_settings.deploy).await,
+                    Err(e) => Err(e),
+                };
+                (deployed, degraded, "new")
+            };
+            let mut deployment = Deployment::new(&stack_def.name, repository_url, &compose_hash, kind, since);
+            deployment.commit_hash = stack_commit(repo_path, stack_def);
+            deployment.operator = options.operator.clone();
+            self.record_deployment(deployment, &deployed).await?;
+            self.record_deploy_result(repository_url, stack_def, &compose_hash, &deployed, &stack_settings).await?;
+            deployed?;
+            if degraded {
+                self.db.update_stack_status(&stack_def.name, repository_url, "degraded").await?;
+            }
+        }
+        
+        // Keep the deployed (or unchanged) compose file as the snapshot used by restore-stack
+        self.db.update_stack_compose_content(&stack_def.name, repository_url, &compose_content).await?;
+        self.db.record_stack_revision(&stack_def.name, repository_url, &compose_hash, &compose_content, self.config.revisions_kept()).await?;
+        
+        // Record the images of the stack, reference counts are derived
+        // from the stacks of every repository
+        let images = self.process_yaml_file(&compose_content, &relative_compose_path).await?;
+        self.db.set_stack_images(&stack_def.name, repository_url, &images).await?;
+        
+        let outputs: BTreeMap<String, String> = stack_def.outputs.iter()
+            .map(|(name, value)| (name.clone(), render_values(value, &stack_values)))
+            .collect();
+        self.db.set_stack_outputs(&stack_def.name, repository_url, &outputs).await?;
+        Ok(StackOutcome::Done(outputs))
+    }
+
+    /// Whether a deployed stack is gone from Swarm or had objects changed
+    /// outside DockerOps since its last deployment
+    async fn stack_drifted(&self, stack_name: &str, repository_url: &str) -> Result<bool> {
+        if self.stack_services(stack_name).await?.is_empty() {
+            println!("  {}", i18n::tr("stack-has-no-service", &[("stack", &stack_name)]));
+            return Ok(true);
+        }
+        let drift = self.db.get_drift_events().await?;
+        let mut changes: Vec<String> = drift.iter()
+            .filter(|event| event.stack_name == stack_name && event.repository_url == repository_url)
+            .map(|event| format!("{} {} ({})", event.object_type, event.object_name, event.action))
+            .collect();
+        changes.sort();
+        changes.dedup();
+        if !changes.is_empty() {
+            println!("  {}", i18n::tr("changed-outside-dockerops", &[("list", &changes.join(", "))]));
+        }
+        Ok(!changes.is_empty())
+    }
+
+    /// Compare the running services with the last deployed compose file and
+    /// the new git state. Returns the compose content to deploy, with the
+    /// manual changes the operator chose to keep, or `None` while a manual
+    /// change that git also changes waits for `resolve`.
+    async fn resolve_overrides(&self, stack_name: &str, repository_url: &str, compose_hash: &str, compose_content: &str) -> Result<Option<String>> {
+        let conflict = self.db.get_override_conflict(stack_name, repository_url).await?;
+        
+        // The resolution was chosen for exactly this git state
+        if let Some(conflict) = &conflict {
+            if let (true, Some(resolution)) = (conflict.hash == compose_hash, &conflict.resolution) {
+                println!("  {}", i18n::tr("conflict-deploying-resolution", &[("resolution", &resolution)]));
+                let kept = kept_overrides(resolution, &conflict.service_overrides());
+                return Ok(Some(apply_overrides(compose_content, &kept)?));
+            }
+        }
+        
+        // Manual changes kept by an earlier resolution
+        let (standing, resolution) = match &conflict {
+            Some(conflict) => match &conflict.resolution {
+                Some(resolution) => (kept_overrides(resolution, &conflict.service_overrides()), Some(resolution.clone())),
+                None => (Vec::new(), None),
+            },
+            None => (Vec::new(), None),
+        };
+        
+        let Some(deployed_content) = self.db.get_stack_compose_content(stack_name, repository_url).await? else {
+            return Ok(Some(compose_content.to_string()));
+        };
+        let live = match self.live_service_fields(stack_name).await {
+            Ok(live) => live,
+            Err(e) => {
+                println!("  {}", i18n::tr("conflict-inspect-failed", &[("stack", &stack_name), ("error", &e)]));
+                return Ok(Some(apply_overrides(compose_content, &standing)?));
+            }
+        };
+        let deployed = compose_fields(&deployed_content)?;
+        let git = compose_fields(compose_content)?;
+        
+        let mut overrides = Vec::new();
+        for (key, git_value) in &git {
+            let (Some(live_value), Some(deployed_value)) = (live.get(key), deployed.get(key)) else {
+                continue;
+            };
+            let kept = standing.iter().find(|o| o.service == key.0 && o.field == key.1);
+            let is_manual = !same_field_value(&key.1, live_value, deployed_value);
+            if !is_manual && kept.is_none() {
+                continue;
+            }
+            
+            // What git said when the change was made: at the last deployment, or when it was kept
+            let baseline = kept.map_or(deployed_value, |o| &o.git);
+            overrides.push(ServiceOverride {
+                service: key.0.clone(),
+                field: key.1.clone(),
+                deployed: baseline.clone(),
+                live: live_value.clone(),
+                git: git_value.clone(),
+                conflicting: !same_field_value(&key.1, git_value, baseline) && !same_field_value(&key.1, git_value, live_value),
+            });
+        }
+        
+        if overrides.iter().any(|o| o.conflicting) {
+            self.db.record_override_conflict(stack_name, repository_url, compose_hash, &serde_json::to_string(&overrides)?).await?;
+            println!("  {}", i18n::tr("conflict-detected", &[("stack", &stack_name)]));
+            for o in overrides.iter().filter(|o| o.conflicting) {
+                println!("      {}", i18n::tr("override-line", &[("service", &o.service), ("field", &o.field), ("deployed", &o.deployed), ("live", &o.live), ("git", &o.git)]));
+            }
+            println!("  {}", i18n::tr("conflict-resolve-hint", &[("stack", &stack_name)]));
+            return Ok(None);
+        }
+        
+        // Kept changes stay while git leaves their field alone, other manual
+        // changes are reverted by the deployment
+        let still_kept: Vec<ServiceOverride> = overrides.into_iter()
+            .filter(|o| standing.iter().any(|s| s.service == o.service && s.field == o.field))
+            .collect();
+        match resolution {
+            Some(resolution) if !still_kept.is_empty() => {
+                self.db.record_override_conflict(stack_name, repository_url, compose_hash, &serde_json::to_string(&still_kept)?).await?;
+                let resolved_by = conflict.as_ref().and_then(|conflict| conflict.resolved_by.clone());
+                self.db.set_override_resolution(stack_name, repository_url, &resolution, resolved_by.as_deref()).await?;
+            }
+            _ if conflict.is_some() => self.db.delete_override_conflict(stack_name, repository_url).await?,
+            _ => {}
+        }
+        
+        Ok(Some(apply_overrides(compose_content, &still_kept)?))
+    }
+
+    /// Replicas and image of the running services of a stack, by compose service name
+    async fn live_service_fields(&self, stack_name: &str) -> Result<BTreeMap<(String, String), String>> {
+        let services: Vec<String> = self.stack_services(stack_name).await?
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        if services.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        
+        let output = self.docker()
+            .args(["service", "inspect", "--format",
+                "{{.Spec.Name}}\t{{if .Spec.Mode.Replicated}}{{.Spec.Mode.Replicated.Replicas}}{{end}}\t{{.Spec.TaskTemplate.ContainerSpec.Image}}"])
+            .args(&services)
+            .output()?;
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!(i18n::tr("failed-to-inspect-services-of-stack", &[("stack", &stack_name), ("error", &error)])));
+        }
+        
+        let mut fields = BTreeMap::new();
+        let prefix = format!("{}_", stack_name);
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let mut parts = line.split('\t');
+            let (Some(name), Some(replicas), Some(image)) = (parts.next(), parts.next(), parts.next()) else {
+                continue;
+            };
+            let service = name.trim().strip_prefix(&prefix).unwrap_or(name.trim()).to_string();
+            if !replicas.trim().is_empty() {
+                fields.insert((service.clone(), "replicas".to_string()), replicas.trim().to_string());
+            }
+            // Swarm pins the digest resolved at deployment time
+            let image = image.trim().split('@').next().unwrap_or_default();
+            fields.insert((service, "image".to_string()), image.to_string());
+        }
+        Ok(fields)
+    }
+
+    /// Compare Swarm with the last deployed compose file of every stack,
+    /// reporting the stacks removed or changed outside DockerOps. With `fix`
+    /// the drifted stacks are deployed again from their snapshot.
+    pub async fn drift(&self, repository_url: Option<&str>, fix: bool) -> Result<()> {
+        let swarm_stacks = self.swarm_stacks()?;
+        let events = self.db.get_drift_events().await?;
+        let stacks: Vec<Stack> = self.db.get_all_stacks().await?
+            .into_iter()
+            .filter(|stack| repository_url.is_none_or(|url| stack.repository_url == url))
+            .filter(|stack| ["deployed", "restored", "imported"].contains(&stack.status.as_str()))
+            .collect();
+        
+        let mut drifted = Vec::new();
+        for stack in &stacks {
+            let content = self.db.get_stack_compose_content(&stack.name, &stack.repository_url).await?;
+            let mut changes = Vec::new();
+            if !swarm_stacks.contains(&stack.name) {
+                changes.push("removed from Swarm".to_string());
+            } else if let Some(content) = &content {
+                let live = self.live_service_fields(&stack.name).await?;
+                for ((service, field), deployed) in compose_fields(content)? {
+                    match live.get(&(service.clone(), field.clone())) {
+                        None if field == "image" => changes.push(format!("service {} is missing", service)),
+                        Some(running) if !same_field_value(&field, running, &deployed) => {
+                            changes.push(format!("{} {}: deployed {}, running {}", service, field, deployed, running));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            for event in events.iter().filter(|e| e.stack_name == stack.name && e.repository_url == stack.repository_url) {
+                changes.push(format!("{} {} got '{}' ({})", event.object_type, event.object_name, event.action,
+                    self.config.format_timestamp(&event.detected_at)));
+            }
+            
+            if !changes.is_empty() {
+                println!("{}", i18n::tr("stack-drifted", &[("stack", &stack.name), ("url", &stack.repository_url)]));
+                for change in &changes {
+                    println!("      {}", change);
+                }
+                drifted.push((stack, content));
+            }
+        }
+        
+        if stacks.is_empty() {
+            println!("{}", i18n::tr("drift-nothing-to-check", &[]));
+            return Ok(());
+        }
+        if drifted.is_empty() {
+            println!("{}", i18n::tr("drift-none", &[("count", &stacks.len())]));
+            return Ok(());
+        }
+        if !fix {
+            return Err(anyhow::anyhow!(i18n::tr("drift-found", &[("drifted", &drifted.len()), ("count", &stacks.len())])));
+        }
+        
+        let mut failed = 0;
+        for (stack, content) in drifted {
+            let Some(content) = content else {
+                println!("{}", i18n::tr("drift-no-snapshot", &[("stack", &stack.name)]));
+                failed += 1;
+                continue;
+            };
+            println!("{}", i18n::tr("re-applying-stack", &[("stack", &stack.name)]));
+            let since = chrono::Utc::now();
+            let deployed = self.deploy_snapshot(&stack.name, &stack.repository_url, &content, None).await;
+            self.record_deployment(Deployment::new(&stack.name, &stack.repository_url, &stack.hash, "reapply", since), &deployed).await?;
+            match deployed {
+                Ok(()) => {
+                    self.db.delete_drift_events(&stack.name, &stack.repository_url).await?;
+                    println!("{}", i18n::tr("drift-reapplied", &[("stack", &stack.name)]));
+                }
+                Err(e) => {
+                    println!("{}", i18n::tr("drift-reapply-failed", &[("stack", &stack.name), ("error", &e)]));
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            return Err(anyhow::anyhow!(i18n::tr("drift-reapply-failures", &[("failed", &failed)])));
+        }
+        Ok(())
+    }
+
+    /// Names of the stacks deployed in Swarm
+    fn swarm_stacks(&self) -> Result<Vec<String>> {
+        let output = self.docker()
+            .args(["stack", "ls", "--format", "{{.Name}}"])
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("failed-to-list-stacks", &[("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).lines().map(|line| line.trim().to_string()).collect())
+    }
+
+    /// Conflicts with manual changes, unresolved first
+    pub async fn pending(&self) -> Result<()> {
+        let conflicts = self.db.get_override_conflicts().await?;
+        if conflicts.is_empty() {
+            println!("{}", i18n::tr("no-conflicts", &[]));
+            return Ok(());
+        }
+        
+        for conflict in conflicts.iter().filter(|c| c.resolution.is_none()).chain(conflicts.iter().filter(|c| c.resolution.is_some())) {
+            match &conflict.resolution {
+                None => println!("{}", i18n::tr("conflict-waiting-line", &[("stack_name", &conflict.stack_name), ("url", &conflict.repository_url), ("time", &self.config.format_timestamp(&conflict.detected_at))])),
+                Some(resolution) => println!("{}", i18n::tr("conflict-line", &[("stack_name", &conflict.stack_name), ("url", &conflict.repository_url), ("resolution", &resolution), ("operator", &conflict.resolved_by.as_deref().map(|operator| format!(" by {}", operator)).unwrap_or_default())])),
+            }
+            for o in conflict.service_overrides() {
+                let marker = if o.conflicting { "conflict" } else { "manual" };
+                println!("    {}", i18n::tr("conflict-override-line", &[("marker", &marker), ("service", &o.service), ("field", &o.field), ("deployed", &o.deployed), ("live", &o.live), ("git", &o.git)]));
+            }
+        }
+        println!();
+        println!("{}", i18n::tr("conflicts-resolve-hint", &[]));
+        Ok(())
+    }
+
+    /// JSON list of the conflicts for the `serve` API
+    pub async fn pending_json(&self) -> Result<serde_json::Value> {
+        let conflicts = self.db.get_override_conflicts().await?;
+        Ok(serde_json::Value::Array(conflicts.iter().map(|conflict| serde_json::json!({
+            "stack": conflict.stack_name,
+            "repository": conflict.repository_url,
+            "detected_at": conflict.detected_at,
+            "resolution": conflict.resolution,
+            "resolved_by": conflict.resolved_by,
+            "overrides": conflict.service_overrides(),
+        })).collect()))
+    }
+
+    /// Choose how a conflict is settled and redeploy the stack with it
+    pub async fn resolve(&self, stack_name: &str, repository_url: Option<&str>, resolution: &str) -> Result<()> {
+        let stack = self.resolve_stack(stack_name, repository_url).await?;
+        let conflict = self.db.get_override_conflict(&stack.name, &stack.repository_url).await?
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("conflict-none-for-stack", &[("stack", &stack.name)])))?;
+        let repo = self.db.get_repository_from_cache(&stack.repository_url).await?
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("repository-no-longer-watched", &[("url", &stack.repository_url)])))?;
+        
+        self.db.set_override_resolution(&stack.name, &stack.repository_url, resolution, Some(&self.config.operator())).await?;
+        for o in kept_overrides(resolution, &conflict.service_overrides()) {
+            println!("{}", i18n::tr("conflict-keeping-value", &[("service", &o.service), ("field", &o.field), ("live", &o.live)]));
+        }
+        
+        let options = DeployOptions { only_stack: Some(stack.name.clone()), ..Default::default() };
+        self.sync_repository(&repo, &options).await?;
+        println!("{}", i18n::tr("conflict-resolved", &[("stack", &stack.name), ("resolution", &resolution)]));
+        Ok(())
+    }
+
+    /// Whether an automatic update of the stack waits for a quieter period:
+    /// its load metric is above the threshold and the update has not been
+    /// delayed for `max_delay` yet. Prometheus failures never hold updates back.
+    async fn defer_for_load(&self, stack: &Stack) -> Result<bool> {
+        let Some(updates) = &self.config.traffic_aware_updates else {
+            return Ok(false);
+        };
+        
+        let now = chrono::Utc::now();
+        if let Some(since) = stack.deferred_since.as_deref().and_then(|since| chrono::DateTime::parse_from_rfc3339(since).ok()) {
+            let waited = (now - since.with_timezone(&chrono::Utc)).to_std().unwrap_or_default();
+            if waited >= updates.max_delay()? {
+                println!("  {}", i18n::tr("traffic-delay-expired", &[("stack", &stack.name), ("waited", &waited.as_secs())]));
+                return Ok(false);
+            }
+        }
+        
+        let query = updates.query.replace("$stack", &stack.name);
+        let load = match prometheus::query_value(&updates.prometheus_url, &query).await {
+            Ok(load) => load,
+            Err(e) => {
+                println!("  {}", i18n::tr("traffic-load-unavailable", &[("stack", &stack.name), ("error", &e)]));
+                return Ok(false);
+            }
+        };
+        let threshold = updates.threshold(&stack.name);
+        if load <= threshold {
+            return Ok(false);
+        }
+        
+        println!("  {}", i18n::tr("traffic-update-delayed", &[("stack", &stack.name), ("load", &load), ("threshold", &threshold)]));
+        if stack.deferred_since.is_none() {
+            self.db.update_stack_deferred_since(&stack.name, &stack.repository_url, Some(&now.to_rfc3339())).await?;
+        }
+        Ok(true)
+    }
+
+    /// Fail with the keys listed in the stacks' `.env.example` files that no
+    /// source provides: values files, secrets.yaml or the environment
+    /// `docker stack deploy` runs in
+    fn check_env_examples(&self, repo_path: &str, definitions: &[&StackDefinition], options: &DeployOptions, repository_values: &BTreeMap<String, String>) -> Result<()> {
+        let mut problems = Vec::new();
+        
+        for stack_def in definitions {
+            if options.only_stack.as_ref().is_some_and(|only| only != &stack_def.name) || !options.filter.matches(&stack_def.name) || !stack_def.is_enabled() {
+                continue;
+            }
+            let stack_dir = Path::new(repo_path).join(stack_def.directory());
+            let example_path = stack_dir.join(".env.example");
+            if !example_path.exists() {
+                continue;
+            }
+            
+            let mut provided: Vec<String> = repository_values.keys().cloned().collect();
+            provided.extend(read_values_file(&stack_dir.join("values.yaml"))?.into_keys());
+            let secrets_path = stack_dir.join("secrets.yaml");
+            if secrets_path.exists() {
+                let secrets: Vec<SecretDefinition> = serde_yaml::from_str(&fs::read_to_string(&secrets_path)?)?;
+                provided.extend(secrets.into_iter().map(|secret| secret.env));
+            }
+            
+            let missing: Vec<String> = read_env_example_keys(&example_path)?
+                .into_iter()
+                .filter(|key| !provided.contains(key) && std::env::var_os(key).is_none())
+                .collect();
+            if !missing.is_empty() {
+                problems.push(format!("  {}: {}", stack_def.name, missing.join(", ")));
+            }
+        }
+        
+        if !problems.is_empty() {
+            return Err(anyhow::anyhow!(i18n::tr("env-example-keys-missing", &[("details", &problems.join("\n"))])));
+        }
+        Ok(())
+    }
+
+    /// Print what a run would do to a stack whose compose file hashes to
+    /// `compose_hash`, and the images it would pull, and add it to the plan
+    async fn print_planned_stack(&self, stack_def: &StackDefinition, repository_url: &str, compose_hash: &str, images: &[String], options: &DeployOptions, settings: &RepositorySettings) -> Result<()> {
+        let stack_name = stack_def.name.as_str();
+        let existing = self.db.get_stack_by_name(stack_name, repository_url).await?;
+        // What would happen, and the change if the stack would be deployed
+        let (message, change) = match &existing {
+            None => match self.db.get_stacks_named(stack_name).await?.first() {
+                Some(owner) => (format!("Would skip stack '{}', already deployed from {}", stack_name, owner.repository_url), None),
+                None => (format!("Would deploy new stack '{}'", stack_name), Some(ChangeAction::Create)),
+            },
+            Some(existing) => {
+                let has_changed = existing.hash != compose_hash;
+                let failure = self.db.get_stack_failure(stack_name, repository_url).await?
+                    .filter(|failure| failure.failures >= settings.failure_threshold() && failure.hash == compose_hash);
+                if let (Some(failure), false) = (&failure, options.force) {
+                    (format!("Would skip stack '{}', it failed to deploy {} times in a row", stack_name, failure.failures), None)
+                } else if has_changed && !options.force && self.rolled_back_from(existing).await?.as_deref() == Some(compose_hash) {
+                    (format!("Would skip stack '{}', it was rolled back from this compose file", stack_name), None)
+                } else if has_changed {
+                    (format!("Would update stack '{}' (hash: {} -> {})", stack_name, existing.hash, compose_hash), Some(ChangeAction::Update))
+                } else if options.force {
+                    (format!("Would redeploy stack '{}' (force mode)", stack_name), Some(ChangeAction::Redeploy))
+                } else if existing.status == "error" {
+                    (format!("Would redeploy stack '{}', it failed to deploy last time", stack_name), Some(ChangeAction::Redeploy))
+                } else if options.is_reconcile && self.stack_drifted(stack_name, repository_url).await? {
+                    (format!("Would redeploy stack '{}', modified in Swarm", stack_name), Some(ChangeAction::Redeploy))
+                } else {
+                    (format!("Stack '{}' unchanged", stack_name), None)
+                }
+            }
+        };
+        println!("  {}", i18n::tr("dry-run-message", &[("message", &message)]));
+        let Some(action) = change else {
+            return Ok(());
+        };
+        if !images.is_empty() {
+            println!("  {}", i18n::tr("dry-run-would-pull", &[("list", &images.join(", "))]));
+        }
+        self.record_planned(repository_url, |plan| plan.changes.push(StackChange {
+            stack: stack_name.to_string(),
+            action,
+            previous_hash: existing.map(|existing| existing.hash),
+            hash: Some(compose_hash.to_string()),
+            images: images.to_vec(),
+        }));
+        Ok(())
+    }
+
+    /// Add what a dry run found to the plan of a repository
+    fn record_planned(&self, repository_url: &str, update: impl FnOnce(&mut RepositoryPlan)) {
+        let mut planned = self.planned.lock().unwrap();
+        update(planned.entry(repository_url.to_string()).or_insert_with(|| RepositoryPlan {
+            url: repository_url.to_string(),
+            commit: None,
+            changes: Vec::new(),
+            removed_images: Vec::new(),
+        }));
+    }
+
+    /// Print the images of the repository a run would stop using and remove,
+    /// `planned` holding the images of the stacks of the dry run
+    async fn print_planned_image_removals(&self, repository_url: &str, previous_images: &[String], definitions: &[StackDefinition], planned: &BTreeMap<String, Vec<String>>) -> Result<()> {
+        let mut images = Vec::new();
+        for definition in definitions {
+            match planned.get(&definition.name) {
+                Some(planned) => images.extend(planned.iter().cloned()),
+                None => images.extend(self.db.get_stack_images(&definition.name, repository_url).await?),
+            }
+        }
+        let unused: Vec<&String> = previous_images.iter().filter(|image| !images.contains(image)).collect();
+        if unused.is_empty() {
+            return Ok(());
+        }
+        
+        let stacks = self.db.get_all_stacks().await?;
+        let mut elsewhere = Vec::new();
+        for stack in stacks.iter().filter(|stack| stack.repository_url != repository_url) {
+            elsewhere.extend(self.db.get_stack_images(&stack.name, &stack.repository_url).await?);
+        }
+        let users = self.unmanaged_image_users(&stacks).await;
+        for image in unused {
+            if elsewhere.contains(image) {
+                println!("  {}", i18n::tr("dry-run-image-kept-referenced", &[("image", &image)]));
+            } else if let Some(users) = image_kept_for(image, &users) {
+                println!("  {}", i18n::tr("dry-run-image-kept-in-use", &[("image", &image), ("users", &users)]));
+            } else {
+                println!("  {}", i18n::tr("dry-run-would-remove-unused-image", &[("image", &image)]));
+                self.record_planned(repository_url, |plan| plan.removed_images.push(image.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    async fn remove_undeclared_stacks(&self, repository_url: &str, stacks_definitions: &[StackDefinition], filter: &StackFilter, prune: bool) -> Result<()> {
+        let stacks = self.db.get_all_stacks().await?;
+        
+        for stack in stacks.iter().filter(|s| s.repository_url == repository_url && filter.matches(&s.name)) {
+            // Disabled stacks declaring `teardown` are removed like the undeclared ones
+            let declared = stacks_definitions.iter().find(|d| d.name == stack.name);
+            if declared.is_some_and(|d| !d.is_torn_down()) {
+                continue;
+            }
+            let reason = match declared {
+                Some(_) => "disabled in stacks.yaml",
+                None => "removed from stacks.yaml",
+            };
+            
+            if declared.is_none() && (stack.status == "restored" || stack.status == "imported") {
+                // Restored by hand: keep it running until it is added back to stacks.yaml
+                println!("  {}", i18n::tr("prune-stack-undeclared-line", &[("status", &stack.status), ("stack", &stack.name)]));
+                continue;
+            }
+            if declared.is_none() && !prune {
+                println!("  {}", i18n::tr("prune-stack-kept", &[("stack", &stack.name)]));
+                continue;
+            }
+            
+            if self.dry_run {
+                println!("  {}", i18n::tr("dry-run-would-trash-stack", &[("stack", &stack.name), ("reason", &reason)]));
+                self.record_planned(repository_url, |plan| plan.changes.push(StackChange {
+                    stack: stack.name.clone(),
+                    action: ChangeAction::Remove,
+                    previous_hash: Some(stack.hash.clone()),
+                    hash: None,
+                    images: Vec::new(),
+                }));
+                continue;
+            }
+            println!("{}", i18n::tr("stack-trashed", &[("stack", &stack.name), ("reason", &reason)]));
+            self.stop_stack(&stack.name).await?;
+            self.trash_stack(stack, reason).await?;
+        }
+        
+        Ok(())
+    }
+
+    /// Sample the tasks started by an update of the stack during the canary
+    /// window and roll its services back to their previous spec when one
+    /// has more failed tasks or error log lines than allowed
+    async fn canary_analysis(&self, stack_name: &str, repository_url: &str, hash: &str, canary: &CanarySettings, since: chrono::DateTime<chrono::Utc>) -> Result<()> {
+        let duration = canary.duration()?;
+        println!("    {}", i18n::tr("canary-started", &[("stack", &stack_name), ("duration", &duration.as_secs())]));
+        
+        let started = std::time::Instant::now();
+        let mut samples: BTreeMap<String, CanarySample> = BTreeMap::new();
+        let is_failing = |sample: &CanarySample| sample.failed_tasks > canary.max_restarts() || sample.error_lines > canary.max_error_lines();
+        loop {
+            for service in self.stack_services(stack_name).await? {
+                let sample = self.canary_sample(&service.name, since, canary)?;
+                samples.insert(service.name, sample);
+            }
+            // Stop at the first service over its thresholds
+            if samples.values().any(is_failing) || started.elapsed() >= duration {
+                break;
+            }
+            tokio::time::sleep(duration.saturating_sub(started.elapsed()).min(Duration::from_secs(10))).await;
+        }
+        
+        let passed = !samples.values().any(is_failing);
+        let mut summary = Vec::new();
+        for (service, sample) in &samples {
+            summary.push(format!("{}: {} failed tasks (max {}), {} error lines (max {})", service,
+                sample.failed_tasks, canary.max_restarts(), sample.error_lines, canary.max_error_lines()));
+            if is_failing(sample) {
+                summary.extend(sample.examples.iter().map(|line| format!("    > {}", line)));
+            }
+        }
+        let summary = summary.join("\n");
+        self.db.record_canary_analysis(stack_name, repository_url, hash, &since.to_rfc3339(), passed, &summary).await?;
+        
+        for line in summary.lines() {
+            println!("      {}", line);
+        }
+        if passed {
+            println!("    {}", i18n::tr("canary-passed", &[("stack", &stack_name)]));
+            return Ok(());
+        }
+        
+        println!("    {}", i18n::tr("canary-failed", &[("stack", &stack_name)]));
+        for service in samples.keys() {
+            // Services created by the update have no previous spec to go back to
+            let output = self.docker().args(["service", "rollback", "--quiet", service]).output()?;
+            if output.status.success() {
+                println!("      {}", i18n::tr("canary-service-rolled-back", &[("service", &service)]));
+            } else {
+                println!("      {}", i18n::tr("canary-rollback-failed", &[("service", &service), ("error", &String::from_utf8_lossy(&output.stderr).trim())]));
+            }
+        }
+        Err(anyhow::anyhow!(i18n::tr("canary-failure-reason", &[("summary", &summary)])))
+    }
+
+    /// Failed tasks created since the update and error lines logged since then by a service
+    fn canary_sample(&self, service: &str, since: chrono::DateTime<chrono::Utc>, canary: &CanarySettings) -> Result<CanarySample> {
+        let mut sample = CanarySample::default();
+        
+        let output = self.docker().args(["service", "ps", "--quiet", "--no-trunc", service]).output()?;
+        let tasks: Vec<String> = String::from_utf8_lossy(&output.stdout).split_whitespace().map(|id| id.to_string()).collect();
+        if !tasks.is_empty() {
+            let output = self.docker()
+                .args(["inspect", "--format", "{{json .CreatedAt}}\t{{.Status.State}}"])
+                .args(&tasks)
+                .output()?;
+            sample.failed_tasks = String::from_utf8_lossy(&output.stdout).lines()
+                .filter_map(|line| line.split_once('\t'))
+                .filter(|(created, state)| {
+                    let created = chrono::DateTime::parse_from_rfc3339(created.trim_matches('"')).ok();
+                    created.is_some_and(|created| created >= since) && matches!(*state, "failed" | "rejected")
+                })
+                .count();
+        }
+        
+        // Services log to stdout and stderr alike
+        let output = self.docker()
+            .args(["service", "logs", "--raw", "--since", &since.to_rfc3339(), service])
+            .output()?;
+        for line in String::from_utf8_lossy(&output.stdout).lines().chain(String::from_utf8_lossy(&output.stderr).lines()) {
+            if canary.is_error(line) {
+                sample.error_lines += 1;
+                if sample.examples.len() < 3 {
+                    sample.examples.push(line.trim().to_string());
+                }
+            }
+        }
+        Ok(sample)
+    }
+
+    /// Keep a finished deploy attempt in the deployment history
+    async fn record_deployment(&self, mut deployment: Deployment, result: &Result<()>) -> Result<()> {
+        if deployment.operator.is_none() {
+            deployment.operator = Some(self.config.operator());
+        }
+        let started = chrono::DateTime::parse_from_rfc3339(&deployment.started_at)?;
+        deployment.duration_ms = (chrono::Utc::now() - started.with_timezone(&chrono::Utc)).num_milliseconds();
+        match result {
+            Ok(()) => deployment.images = self.deployed_images(&deployment.stack_name).await,
+            Err(e) => {
+                deployment.result = "failure".to_string();
+                deployment.error = Some(e.to_string());
+            }
+        }
+        self.db.record_deployment(&deployment).await?;
+        Ok(())
+    }
+
+    /// Update a stack's status and failure count after a deploy attempt,
+    /// opening or closing its failure issue when enabled
+    async fn record_deploy_result(&self, repository_url: &str, stack_def: &StackDefinition, hash: &str, result: &Result<()>, settings: &RepositorySettings) -> Result<()> {
+        let stack_name = stack_def.name.as_str();
+        let previous = self.db.get_stack_failure(stack_name, repository_url).await?;
+        
+        let error = match result {
+            Ok(()) => {
+                self.db.update_stack_status(stack_name, repository_url, "deployed").await?;
+                self.db.delete_drift_events(stack_name, repository_url).await?;
+                if let Some(failure) = previous {
+                    self.db.delete_stack_failure(stack_name, repository_url).await?;
+                    if failure.failures >= settings.failure_threshold() {
+                        let text = i18n::tr("notification-deploy-recovered", &[("stack", &stack_name), ("failures", &failure.failures), ("url", &repository_url)]);
+                        self.notify(settings.notification_webhooks(), "recovered", stack_name, repository_url, text, None).await;
+                    }
+                    if let Some(number) = failure.issue_number {
+                        if let Err(e) = self.close_failure_issue(repository_url, stack_name, number).await {
+                            println!("  {}", i18n::tr("issue-close-failed", &[("number", &number), ("error", &e)]));
+                        }
+                    }
+                }
+                return Ok(());
+            }
+            Err(e) => e.to_string(),
+        };
+        
+        self.db.update_stack_status(stack_name, repository_url, "error").await?;
+        let failures = self.db.record_stack_failure(stack_name, repository_url, hash, &error).await?;
+        let threshold = settings.failure_threshold();
+        if failures < threshold {
+            return Ok(());
+        }
+        println!("  {}", i18n::tr("stack-failing", &[("stack", &stack_name), ("failures", &failures)]));
+        let text = i18n::tr("notification-deploy-failed", &[("stack", &stack_name), ("failures", &failures), ("url", &repository_url), ("error", &last_lines(&error, 1))]);
+        self.notify(settings.notification_webhooks(), "failed", stack_name, repository_url, text, Some(&error)).await;
+        
+        if settings.failure_issues() {
+            let issue_number = previous.and_then(|failure| failure.issue_number);
+            // Reporting must not hide the deploy error itself
+            if let Err(e) = self.report_failure_issue(repository_url, stack_def, failures, &error, issue_number).await {
+                println!("  {}", i18n::tr("issue-report-failed", &[("error", &e)]));
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// Hash of everything a stack's compose file is rendered from, except
+    /// the outputs of other stacks: the file itself, its values, volumes.yaml
+    /// and the files it binds, nfs.yaml, the network encryption policy and
+    /// the DockerOps version
+    fn render_inputs_hash(&self, repo_path: &str, stack_name: &str, compose_content: &str, values: &BTreeMap<String, String>, volumes: Option<&[VolumeDefinition]>, bindings: &str) -> String {
+        let inputs = serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "compose": compose_content,
+            "values": values,
+            "volumes": volumes,
+            "bindings": bindings,
+            "nfs": fs::read_to_string(Path::new(repo_path).join("nfs.yaml")).ok(),
+            "encrypted_networks": self.config.requires_encrypted_networks(stack_name),
+        });
+        self.calculate_md5(&inputs.to_string())
+    }
+
+    /// Stacks of a run `--changed-only` leaves out: the ones outside of the
+    /// run and the ones with nothing to do, whose dependencies have nothing
+    /// to do either since their outputs may change what they render to
+    async fn unchanged_stacks(&self, run: &StackRun<'_>, ordered: &[&StackDefinition], dependencies: &BTreeMap<&str, Vec<String>>) -> Result<BTreeSet<String>> {
+        let mut unchanged = BTreeSet::new();
+        for definition in ordered {
+            let dependencies_unchanged = dependencies[definition.name.as_str()].iter().all(|stack| unchanged.contains(stack));
+            if dependencies_unchanged && self.stack_unchanged(definition, run).await? {
+                unchanged.insert(definition.name.clone());
+            }
+        }
+        Ok(unchanged)
+    }
+
+    /// Whether a stack renders from the inputs of its cached rendering to the
+    /// compose file it is deployed with, with the same outputs, and was
+    /// deployed successfully
+    async fn stack_unchanged(&self, stack_def: &StackDefinition, run: &StackRun<'_>) -> Result<bool> {
+        if run.options.only_stack.as_ref().is_some_and(|only| only != &stack_def.name) || !run.options.filter.matches(&stack_def.name) || !stack_def.is_enabled() {
+            return Ok(true);
+        }
+        let Some(existing) = self.db.get_stack_by_name(&stack_def.name, run.repository_url).await? else {
+            return Ok(false);
+        };
+        if !matches!(existing.status.as_str(), "deployed" | "degraded") || existing.deferred_since.is_some() || existing.app != stack_def.app {
+            return Ok(false);
+        }
+        // Bound files that could not be read may have changed
+        let Some(bindings) = run.bindings else {
+            return Ok(false);
+        };
+        let stack_dir = Path::new(run.repo_path).join(stack_def.directory());
+        let Some(compose_path) = find_compose_file(&stack_dir) else {
+            return Ok(false);
+        };
+        
+        let mut stack_values = run.repository_values.clone();
+        stack_values.extend(read_values_file(&stack_dir.join("values.yaml"))?);
+        let render_hash = self.render_inputs_hash(run.repo_path, &stack_def.name, &fs::read_to_string(&compose_path)?, &stack_values, run.volumes_definitions, bindings);
+        let Some(rendered) = self.cached_render(&stack_def.name, run.repository_url, &render_hash, &BTreeMap::new()).await? else {
+            return Ok(false);
+        };
+        let exclusions = run.settings.for_stack(&self.config, stack_def)?.hash_exclusions();
+        if self.calculate_md5(&compose::hashed_content(&rendered, &exclusions)) != existing.hash {
+            return Ok(false);
+        }
+        
+        // Outputs are declared in stacks.yaml, outside of the compose file
+        let outputs: BTreeMap<String, String> = stack_def.outputs.iter()
+            .map(|(name, value)| (name.clone(), render_values(value, &stack_values)))
+            .collect();
+        let published: BTreeMap<String, String> = self.db.get_stack_outputs(&stack_def.name).await?.into_iter()
+            .filter(|output| output.repository_url == run.repository_url)
+            .map(|output| (output.name, output.value))
+            .collect();
+        Ok(outputs == published)
+    }
+
+    /// Compose file last rendered for a stack from the inputs of `input_hash`,
+    /// when the outputs it references still have the values it was rendered with
+    async fn cached_render(&self, stack_name: &str, repository_url: &str, input_hash: &str, run_outputs: &BTreeMap<String, BTreeMap<String, String>>) -> Result<Option<String>> {
+        let Some(entry) = self.db.get_render_cache(stack_name, repository_url).await? else {
+            return Ok(None);
+        };
+        if entry.input_hash != input_hash {
+            return Ok(None);
+        }
+        let Ok(outputs) = serde_json::from_str::<Vec<(String, String, String)>>(&entry.outputs) else {
+            return Ok(None);
+        };
+        for (stack, output, value) in outputs {
+            if self.output_value(run_outputs, &stack, &output).await?.as_deref() != Some(value.as_str()) {
+                return Ok(None);
+            }
+        }
+        Ok(Some(entry.content))
+    }
+
+    /// Value of an output of a stack, published during this run or at its last deployment
+    async fn output_value(&self, run_outputs: &BTreeMap<String, BTreeMap<String, String>>, stack: &str, output: &str) -> Result<Option<String>> {
+        if let Some(outputs) = run_outputs.get(stack) {
+            return Ok(outputs.get(output).cloned());
+        }
+        Ok(self.db.get_stack_outputs(stack).await?
+            .into_iter()
+            .find(|stored| stored.name == output)
+            .map(|stored| stored.value))
+    }
+
+    /// Queue a notification for every webhook of the stack, from
+    /// `notification_webhooks` and its `notifications.webhooks`, and try to
+    /// deliver it right away
+    async fn notify(&self, webhooks: &[String], event: &str, stack_name: &str, repository_url: &str, text: String, error: Option<&str>) {
+        if webhooks.is_empty() {
+            return;
+        }
+        let payload = serde_json::json!({
+            "text": text,
+            "event": event,
+            "stack": stack_name,
+            "repository": repository_url,
+            "error": error,
+        }).to_string();
+        for url in webhooks {
+            if let Err(e) = self.db.enqueue_notification(url, &payload).await {
+                println!("  {}", i18n::tr("notification-queue-failed", &[("url", &notify::display_url(url)), ("error", &e)]));
+            }
+        }
+        self.deliver_notifications().await;
+    }
+
+    /// Post the notifications of the outbox whose attempt is due, retrying
+    /// the failed ones later with a growing delay. Delivery problems never
+    /// fail the deployment they report on.
+    async fn deliver_notifications(&self) {
+        let now = chrono::Utc::now();
+        let due = match self.db.get_due_notifications(&now.to_rfc3339()).await {
+            Ok(due) => due,
+            Err(e) => {
+                println!("{}", i18n::tr("outbox-unreadable", &[("error", &e)]));
+                return;
+            }
+        };
+        
+        for notification in due {
+            let payload: serde_json::Value = serde_json::from_str(&notification.payload).unwrap_or_default();
+            let result = match notify::post(&notification.url, &payload).await {
+                Ok(()) => self.db.delete_notification(notification.id).await,
+                Err(e) => {
+                    let attempts = notification.attempts + 1;
+                    let status = if attempts >= notify::MAX_ATTEMPTS { "dead" } else { "pending" };
+                    let next_attempt = now + chrono::Duration::from_std(notify::backoff(attempts)).unwrap_or_default();
+                    match status {
+                        "dead" => println!("  {}", i18n::tr("notification-given-up", &[("url", &notify::display_url(&notification.url)), ("attempts", &attempts), ("error", &e)])),
+                        _ => println!("  {}", i18n::tr("notification-retrying", &[("url", &notify::display_url(&notification.url)), ("attempts", &attempts), ("next_attempt", &self.config.format_time(&next_attempt)), ("error", &e)])),
+                    }
+                    self.db.record_notification_failure(notification.id, &next_attempt.to_rfc3339(), &e.to_string(), status).await
+                }
+            };
+            if let Err(e) = result {
+                println!("{}", i18n::tr("outbox-update-failed", &[("error", &e)]));
+            }
+        }
+        
+        // Undelivered notifications stay visible in status for a week
+        let cutoff = (now - chrono::Duration::days(NOTIFICATION_RETENTION_DAYS)).to_rfc3339();
+        if let Err(e) = self.db.purge_dead_notifications_before(&cutoff).await {
+            println!("{}", i18n::tr("outbox-purge-failed", &[("error", &e)]));
+        }
+    }
+
+    async fn report_failure_issue(&self, repository_url: &str, stack_def: &StackDefinition, failures: i64, error: &str, issue_number: Option<i64>) -> Result<()> {
+        let stack_name = stack_def.name.as_str();
+        let (owner, repo) = github::parse_repository_url(repository_url)
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("not-a-github-repository", &[("url", &repository_url)])))?;
+        let client = self.github_client(&owner, &repo).await?;
+        let watched = self.db.get_repository_from_cache(repository_url).await?;
+        
+        // Git history is read from the root of the working copy
+        let repo_path = self.working_copy_path(repository_url).to_string_lossy().to_string();
+        let stack_dir = match watched.as_ref().and_then(|repo| repo.path.as_deref()) {
+            Some(path) => format!("{}/{}", path, stack_def.directory()),
+            None => stack_def.directory().to_string(),
+        };
+        
+        let head = head_commit(&repo_path)?.unwrap_or_else(|| "working tree".to_string());
+        let mut body = format!("{}\n\n{}\n```\n{}\n```\n",
+            i18n::tr("issue-body", &[("stack", &stack_name), ("failures", &failures), ("commit", &head)]),
+            i18n::tr("issue-error", &[]), last_lines(error, 30));
+        
+        // The diff only exists when the issue is opened, comments just carry the new error
+        let Some(number) = issue_number else {
+            let last_deployed = watched.and_then(|repo| repo.last_commit)
+                .filter(|last| *last != head);
+            if let Some(last) = last_deployed {
+                // Shallow clones may not have the last deployed commit anymore
+                let diff = stack_diff(&repo_path, &last, &stack_dir).unwrap_or_default();
+                if !diff.is_empty() {
+                    let changes = i18n::tr("issue-changes", &[("directory", &stack_dir), ("commit", &&last[..last.len().min(12)])]);
+                    body.push_str(&format!("\n{}\n```diff\n{}\n```\n", changes, diff));
+                }
+            }
+            body.push_str(&format!("\n{}\n", i18n::tr("issue-footer", &[])));
+            
+            let title = i18n::tr("issue-title", &[("stack", &stack_name)]);
+            let number = client.create_issue(&owner, &repo, &title, &body).await?;
+            self.db.set_stack_failure_issue(stack_name, repository_url, number).await?;
+            println!("  {}", i18n::tr("issue-opened", &[("number", &number), ("owner", &owner), ("repo", &repo)]));
+            return Ok(());
+        };
+        
+        client.comment_issue(&owner, &repo, number, &body).await?;
+        println!("  {}", i18n::tr("issue-updated", &[("number", &number), ("owner", &owner), ("repo", &repo)]));
+        Ok(())
+    }
+
+    async fn close_failure_issue(&self, repository_url: &str, stack_name: &str, number: i64) -> Result<()> {
+        let (owner, repo) = github::parse_repository_url(repository_url)
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("not-a-github-repository", &[("url", &repository_url)])))?;
+        let client = self.github_client(&owner, &repo).await?;
+        
+        client.comment_issue(&owner, &repo, number, &i18n::tr("issue-closed", &[("stack", &stack_name)])).await?;
+        client.close_issue(&owner, &repo, number).await?;
+        println!("  {}", i18n::tr("issue-closed-log", &[("number", &number), ("owner", &owner), ("repo", &repo)]));
+        Ok(())
+    }
+
+    /// API client for a GitHub repository, authenticated as the GitHub App
+    /// when one is configured
+    async fn github_client(&self, owner: &str, repo: &str) -> Result<GithubClient> {
+        let token = match &self.github_app {
+            Some(app) => Some(app.installation_token(owner, repo).await?),
+            None => self.config.github_token(),
+        };
+        Ok(GithubClient::new(self.github.clone(), token))
+    }
+
+    /// Garbage collection run by the daemon after its polls, once per
+    /// `gc.interval` within `gc.hours`, when enough space can be reclaimed
+    async fn scheduled_gc(&self) {
+        let Some(settings) = self.config.gc.as_ref().filter(|settings| settings.is_enabled()) else {
+            return;
+        };
+        if let Err(e) = self.try_scheduled_gc(settings).await {
+            println!("{}", i18n::tr("gc-failed", &[("error", &e)]));
+        }
+    }
+
+    async fn try_scheduled_gc(&self, settings: &GcSettings) -> Result<()> {
+        let Some(interval) = settings.interval()? else {
+            return Ok(());
+        };
+        let now = chrono::Utc::now();
+        if !settings.in_hours(self.config.local_time(&now))? {
+            return Ok(());
+        }
+        let last_run = self.db.get_gc_runs(1).await?.into_iter().next()
+            .and_then(|run| chrono::DateTime::parse_from_rfc3339(&run.started_at).ok());
+        if last_run.is_some_and(|last_run| now.signed_duration_since(last_run) < chrono::Duration::from_std(interval).unwrap_or(chrono::Duration::MAX)) {
+            return Ok(());
+        }
+        
+        // A skipped run counts as a run so the estimate is not made on every poll
+        let estimate = self.gc_estimate(settings).await?;
+        let reclaimable: u64 = estimate.iter().map(|reclaimed| reclaimed.bytes).sum();
+        let threshold = settings.min_reclaimable()?;
+        if reclaimable < threshold {
+            println!("{}", i18n::tr("gc-skipped", &[("reclaimable", &format_size(reclaimable)), ("threshold", &format_size(threshold))]));
+            self.db.record_gc_run(&now.to_rfc3339(), "skipped", 0, &gc_report_lines(&estimate), &self.config.operator()).await?;
+            return Ok(());
+        }
+        self.collect_garbage(settings, "scheduled").await
+    }
+
+    /// Remove the resources enabled in the settings and record what was reclaimed
+    async fn collect_garbage(&self, settings: &GcSettings, trigger: &str) -> Result<()> {
+        let started_at = chrono::Utc::now().to_rfc3339();
+        let older_than = settings.older_than()?;
+        println!("{}", i18n::tr("gc-started", &[("older_than", &older_than.as_secs())]));
+        
+        // A failing kind of resource does not keep the others from being collected
+        let mut report = Vec::new();
+        if settings.dangling_images.unwrap_or(false) {
+            match self.prune_dangling_images(older_than).await {
+                Ok(reclaimed) => report.push(reclaimed),
+                Err(e) => println!("  {}", i18n::tr("gc-dangling-images-failed", &[("error", &e)])),
+            }
+        }
+        if settings.stopped_containers.unwrap_or(false) {
+            match self.remove_stopped_containers(older_than).await {
+                Ok(reclaimed) => report.push(reclaimed),
+                Err(e) => println!("  {}", i18n::tr("gc-stopped-containers-failed", &[("error", &e)])),
+            }
+        }
+        if settings.build_cache.unwrap_or(false) {
+            match self.prune_build_cache(older_than).await {
+                Ok(reclaimed) => report.push(reclaimed),
+                Err(e) => println!("  {}", i18n::tr("gc-build-cache-failed", &[("error", &e)])),
+            }
+        }
+        
+        print_gc_report(&report, false);
+        let total: u64 = report.iter().map(|reclaimed| reclaimed.bytes).sum();
+        self.db.record_gc_run(&started_at, trigger, total as i64, &gc_report_lines(&report), &self.config.operator()).await?;
+        Ok(())
+    }
+
+    /// What the enabled collections could remove. The build cache is
+    /// counted regardless of its age, so this is an upper bound.
+    async fn gc_estimate(&self, settings: &GcSettings) -> Result<Vec<Reclaimed>> {
+        let older_than = settings.older_than()?;
+        let mut estimate = Vec::new();
+        if settings.dangling_images.unwrap_or(false) {
+            let sizes = self.dangling_images(older_than)?;
+            estimate.push(Reclaimed { resource: "dangling images", removed: Some(sizes.len()), bytes: sizes.iter().sum() });
+        }
+        if settings.stopped_containers.unwrap_or(false) {
+            let containers = self.stopped_managed_containers(older_than).await?;
+            estimate.push(Reclaimed { resource: "stopped containers", removed: Some(containers.len()), bytes: containers.iter().map(|(_, size)| size).sum() });
+        }
+        if settings.build_cache.unwrap_or(false) {
+            estimate.push(Reclaimed { resource: "build cache", removed: None, bytes: self.build_cache_reclaimable()? });
+        }
+        Ok(estimate)
+    }
+
+    /// Sizes of the untagged images created before `older_than`
+    fn dangling_images(&self, older_than: Duration) -> Result<Vec<u64>> {
+        let output = self.docker()
+            .args(["image", "ls", "--filter", "dangling=true", "--format", "{{.CreatedAt}}\t{{.Size}}"])
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("docker-image-ls-failed", &[("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        let cutoff = chrono::Utc::now() - chrono::Duration::from_std(older_than)?;
+        Ok(String::from_utf8_lossy(&output.stdout).lines()
+            .filter_map(|line| line.split_once('\t'))
+            .filter(|(created, _)| docker_time(created).is_some_and(|created| created <= cutoff))
+            .map(|(_, size)| docker_size(size))
+            .collect())
+    }
+
+    async fn prune_dangling_images(&self, older_than: Duration) -> Result<Reclaimed> {
+        println!("  {}", i18n::tr("gc-pruning-dangling-images", &[]));
+        let mut command = tokio::process::Command::from(self.docker());
+        command.args(["image", "prune", "--force", "--filter", &format!("until={}s", older_than.as_secs())]);
+        let output = command.output().await?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(Reclaimed {
+            resource: "dangling images",
+            removed: Some(stdout.lines().filter(|line| line.trim_start().starts_with("deleted:")).count()),
+            bytes: reclaimed_space(&stdout),
+        })
+    }
+
+    /// Exited, dead or never started containers of the managed stacks
+    /// created before `older_than`, with the size of their writable layer
+    async fn stopped_managed_containers(&self, older_than: Duration) -> Result<Vec<(String, u64)>> {
+        let managed = self.db.get_all_stacks().await?;
+        let output = self.docker()
+            .args(["container", "ls", "--all", "--size", "--filter", "status=exited", "--filter", "status=dead", "--filter", "status=created",
+                "--filter", "label=com.docker.stack.namespace",
+                "--format", "{{.ID}}\t{{.Label \"com.docker.stack.namespace\"}}\t{{.CreatedAt}}\t{{.Size}}"])
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("docker-container-ls-failed", &[("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        let cutoff = chrono::Utc::now() - chrono::Duration::from_std(older_than)?;
+        Ok(String::from_utf8_lossy(&output.stdout).lines()
+            .filter_map(|line| match line.split('\t').map(str::trim).collect::<Vec<_>>()[..] {
+                [id, namespace, created, size] if managed.iter().any(|stack| stack.name == namespace)
+                    && docker_time(created).is_some_and(|created| created <= cutoff) => Some((id.to_string(), docker_size(size))),
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn remove_stopped_containers(&self, older_than: Duration) -> Result<Reclaimed> {
+        println!("  {}", i18n::tr("gc-removing-containers", &[]));
+        let containers = self.stopped_managed_containers(older_than).await?;
+        let mut reclaimed = Reclaimed { resource: "stopped containers", removed: Some(0), bytes: 0 };
+        if containers.is_empty() {
+            return Ok(reclaimed);
+        }
+        
+        let mut command = tokio::process::Command::from(self.docker());
+        command.args(["container", "rm"]).args(containers.iter().map(|(id, _)| id));
+        let output = command.output().await?;
+        // Containers removed in the meantime make the command fail, the others are still removed
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            println!("  {}", i18n::tr("gc-containers-not-removed", &[("error", &String::from_utf8_lossy(&output.stderr).trim())]));
+        }
+        for (_, size) in containers.iter().filter(|(id, _)| stdout.lines().any(|line| line.trim() == id)) {
+            reclaimed.removed = reclaimed.removed.map(|removed| removed + 1);
+            reclaimed.bytes += size;
+        }
+        Ok(reclaimed)
+    }
+
+    fn build_cache_reclaimable(&self) -> Result<u64> {
+        let output = self.docker()
+            .args(["system", "df", "--format", "{{.Type}}\t{{.Reclaimable}}"])
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("docker-system-df-failed", &[("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).lines()
+            .filter_map(|line| line.split_once('\t'))
+            .find(|(kind, _)| kind.trim() == "Build Cache")
+            .map(|(_, reclaimable)| docker_size(reclaimable))
+            .unwrap_or(0))
+    }
+
+    async fn prune_build_cache(&self, older_than: Duration) -> Result<Reclaimed> {
+        println!("  {}", i18n::tr("gc-pruning-build-cache", &[]));
+        let mut command = tokio::process::Command::from(self.docker());
+        command.args(["builder", "prune", "--force", "--filter", &format!("until={}s", older_than.as_secs())]);
+        let output = command.output().await?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(Reclaimed { resource: "build cache", removed: None, bytes: reclaimed_space(&String::from_utf8_lossy(&output.stdout)) })
+    }
+
+    /// Set Swarm's task history limit and remove the containers of the
+    /// stopped tasks the `task_history` settings of the managed stacks drop
+    async fn prune_task_history(&self) {
+        if let Err(e) = self.reconcile_task_history_limit() {
+            println!("{}", i18n::tr("task-history-limit-failed", &[("error", &e)]));
+        }
+        
+        let stacks = match self.db.get_all_stacks().await {
+            Ok(stacks) => stacks,
+            Err(e) => {
+                println!("{}", i18n::tr("task-prune-failed", &[("error", &e)]));
+                return;
+            }
+        };
+        let local_node = self.swarm_node_id().unwrap_or_default();
+        let mut unreachable = BTreeSet::new();
+        for stack in &stacks {
+            if let Err(e) = self.prune_stack_tasks(&stack.name, &stack.repository_url, &local_node, &mut unreachable).await {
+                println!("{}", i18n::tr("task-prune-stack-failed", &[("stack", &stack.name), ("error", &e)]));
+            }
+        }
+        if !unreachable.is_empty() {
+            println!("{}", i18n::tr("task-prune-nodes-unreachable", &[("list", &unreachable.into_iter().collect::<Vec<_>>().join(", "))]));
+        }
+    }
+
+    /// Align Swarm's `task-history-limit` on `swarm_task_history_limit`
+    fn reconcile_task_history_limit(&self) -> Result<()> {
+        let Some(limit) = self.config.swarm_task_history_limit else {
+            return Ok(());
+        };
+        let output = self.docker()
+            .args(["info", "--format", "{{.Swarm.Cluster.Spec.Orchestration.TaskHistoryRetentionLimit}}"])
+            .output()?;
+        let current = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if current == limit.to_string() {
+            return Ok(());
+        }
+        
+        let output = self.docker().args(["swarm", "update", "--task-history-limit", &limit.to_string()]).output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        println!("{}", i18n::tr("task-history-limit-set", &[("limit", &limit), ("current", &current)]));
+        Ok(())
+    }
+
+    /// Remove the containers of the stopped tasks of a stack beyond its
+    /// `task_history`, on the nodes they ran on. Nodes DockerOps can't reach
+    /// are added to `unreachable`.
+    async fn prune_stack_tasks(&self, stack_name: &str, repository_url: &str, local_node: &str, unreachable: &mut BTreeSet<String>) -> Result<()> {
+        // Adopted stacks and stacks no longer declared follow config.yaml
+        let (settings, definition) = self.declared_stack(repository_url, stack_name).await
+            .unwrap_or_else(|_| (RepositorySettings::default(), StackDefinition::default()));
+        let history = settings.for_stack(&self.config, &definition)?.task_history;
+        if !history.is_enabled() {
+            return Ok(());
+        }
+        let cutoff = history.max_age()?
+            .map(|max_age| chrono::Duration::from_std(max_age).map(|max_age| chrono::Utc::now() - max_age))
+            .transpose()?;
+        
+        let output = self.docker().args(["stack", "ps", stack_name, "--no-trunc", "--quiet"]).output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        let ids: Vec<String> = String::from_utf8_lossy(&output.stdout).split_whitespace().map(|id| id.to_string()).collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let output = self.docker()
+            .args(["inspect", "--format", "{{.ServiceID}}\t{{.Slot}}\t{{.NodeID}}\t{{.DesiredState}}\t{{json .Status.Timestamp}}\t{{with .Status.ContainerStatus}}{{.ContainerID}}{{end}}"])
+            .args(&ids)
+            .output()?;
+        
+        // Stopped tasks by replica slot, global services having one per node
+        let mut slots: BTreeMap<(String, String), Vec<StoppedTask>> = BTreeMap::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let [service, slot, node_id, desired_state, timestamp, container_id] = fields[..] else {
+                continue;
+            };
+            if !matches!(desired_state, "shutdown" | "remove" | "complete") || container_id.is_empty() {
+                continue;
+            }
+            let slot = match slot {
+                "0" => node_id.to_string(),
+                slot => slot.to_string(),
+            };
+            slots.entry((service.to_string(), slot)).or_default().push(StoppedTask {
+                stopped_at: chrono::DateTime::parse_from_rfc3339(timestamp.trim_matches('"')).ok().map(|time| time.with_timezone(&chrono::Utc)),
+                node_id: node_id.to_string(),
+                container_id: container_id.to_string(),
+            });
+        }
+        
+        let mut containers_by_node: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for tasks in slots.values_mut() {
+            tasks.sort_by_key(|task| std::cmp::Reverse(task.stopped_at));
+            for (index, task) in tasks.iter().enumerate() {
+                let beyond_keep = history.keep.is_some_and(|keep| index >= keep as usize);
+                let expired = cutoff.is_some_and(|cutoff| task.stopped_at.is_some_and(|stopped_at| stopped_at <= cutoff));
+                if beyond_keep || expired {
+                    containers_by_node.entry(task.node_id.clone()).or_default().push(task.container_id.clone());
+                }
+            }
+        }
+        
+        let mut removed = 0;
+        for (node_id, containers) in containers_by_node {
+            let mut docker = match node_id == local_node {
+                true => self.docker(),
+                false => {
+                    let hostname = self.node_hostname(&node_id)?;
+                    match self.node_docker(&hostname) {
+                        Ok(docker) => docker,
+                        Err(_) => {
+                            unreachable.insert(hostname);
+                            continue;
+                        }
+                    }
+                }
+            };
+            // Containers Swarm or the gc removed in the meantime make the command fail, the others are still removed
+            let output = docker.args(["container", "rm"]).args(&containers).output()?;
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            removed += containers.iter().filter(|id| stdout.lines().any(|line| line.trim() == id.as_str())).count();
+        }
+        if removed > 0 {
+            println!("{}", i18n::tr("tasks-pruned", &[("removed", &removed), ("stack", &stack_name)]));
+        }
+        Ok(())
+    }
+
+    async fn purge_expired_trash(&self) -> Result<()> {
+        let retention = chrono::Duration::from_std(self.config.trash_retention()?)?;
+        let cutoff = (chrono::Utc::now() - retention).to_rfc3339();
+        
+        for trashed in self.db.get_trashed_stacks().await?.iter().filter(|s| s.deleted_at < cutoff) {
+            remove_snapshot_dir(trashed);
+        }
+        let purged = self.db.purge_trash_before(&cutoff).await?;
+        if purged > 0 {
+            println!("{}", i18n::tr("trash-purged", &[("purged", &purged)]));
+        }
+        
+        Ok(())
+    }
+
+    /// Values of a repository: GitHub Actions variables (when enabled)
+    /// overridden by the repository's values.yaml
+    async fn load_repository_values(&self, repo_path: &str, repository_url: &str) -> Result<BTreeMap<String, String>> {
+        let mut values = BTreeMap::new();
+        
+        if self.config.github_variables.unwrap_or(false) {
+            match github::parse_repository_url(repository_url) {
+                Some((owner, repo)) => {
+                    let variables = self.github_client(&owner, &repo).await?
+                        .repository_variables(&owner, &repo)
+                        .await
+                        .map_err(|e| anyhow::anyhow!(i18n::tr("github-variables-failed", &[("owner", &owner), ("repo", &repo), ("error", &e)])))?;
+                    println!("{}", i18n::tr("github-variables-loaded", &[("count", &variables.len())]));
+                    values.extend(variables);
+                }
+                None => println!("{}", i18n::tr("github-variables-not-github", &[("url", &repository_url)])),
+            }
+        }
+        
+        values.extend(read_values_file(&Path::new(repo_path).join("values.yaml"))?);
+        Ok(values)
+    }
+
+    /// Registry logins declared in the repository's `registries.yaml`,
+    /// decrypted with `sops` when the file is SOPS-encrypted
+    fn registry_credentials(&self, repo_path: &Path) -> Result<RegistryCredentials> {
+        let path = repo_path.join("registries.yaml");
+        if !path.exists() {
+            return Ok(RegistryCredentials::default());
+        }
+        
+        let content = fs::read_to_string(&path)?;
+        let document: Value = serde_yaml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!(i18n::tr("invalid-registries-file", &[("path", &path.display()), ("error", &e)])))?;
+        
+        let content = if document.get("sops").is_some() {
+            let output = Command::new("sops")
+                .args(["--decrypt", "--input-type", "yaml", "--output-type", "yaml"])
+                .arg(&path)
+                .output()
+                .map_err(|e| anyhow::anyhow!(i18n::tr("registries-sops-failed", &[("error", &e)])))?;
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(i18n::tr("registries-decrypt-failed", &[("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+            }
+            String::from_utf8_lossy(&output.stdout).to_string()
+        } else {
+            println!("{}", i18n::tr("registries-not-encrypted", &[]));
+            content
+        };
+        
+        #[derive(serde::Deserialize)]
+        struct RegistriesFile {
+            #[serde(default)]
+            registries: BTreeMap<String, RegistryLogin>,
+        }
+        let file: RegistriesFile = serde_yaml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!(i18n::tr("invalid-registries-file", &[("path", &path.display()), ("error", &e)])))?;
+        
+        println!("{}", i18n::tr("registries-loaded", &[("count", &file.registries.len())]));
+        Ok(RegistryCredentials::new(file.registries))
+    }
+
+    fn calculate_md5(&self, content: &str) -> String {
+        let result = md5::compute(content.as_bytes());
+        format!("{:x}", result)
+    }
+
+    /// Images referenced by a compose file
+    async fn process_yaml_file(&self, content: &str, file_path: &str) -> Result<Vec<String>> {
+        // Parse YAML content
+        let yaml_value: Value = match serde_yaml::from_str(content) {
+            Ok(value) => value,
+            Err(e) => {
+                println!("  {}", i18n::tr("yaml-parse-failed", &[("file_path", &file_path), ("error", &e)]));
+                return Ok(Vec::new());
+            }
+        };
+        
+        // Extract images from YAML structure
+        let mut images_found = Vec::new();
+        self.extract_images_from_yaml(&yaml_value, &mut images_found);
+        images_found.sort();
+        images_found.dedup();
+        
+        if !images_found.is_empty() {
+            println!("  {}", i18n::tr("images-found-in-file", &[("count", &images_found.len()), ("file_path", &file_path), ("images_found", &format!("{:?}", images_found))]));
+        }
+        
+        Ok(images_found)
+    }
+
+    fn extract_images_from_yaml(&self, value: &Value, images: &mut Vec<String>) {
+        match value {
+            Value::Mapping(mapping) => {
+                for (key, val) in mapping {
+                    if let Some(key_str) = key.as_str() {
+                        if key_str == "image" {
+                            if let Some(image_name) = val.as_str() {
+                                if !image_name.is_empty() {
+                                    images.push(image_name.to_string());
+                                }
+                            }
+                        } else {
+                            // Recursively search in nested structures
+                            self.extract_images_from_yaml(val, images);
+                        }
+                    } else {
+                        // Recursively search in nested structures
+                        self.extract_images_from_yaml(val, images);
+                    }
+                }
+            }
+            Value::Sequence(sequence) => {
+                for item in sequence {
+                    self.extract_images_from_yaml(item, images);
+                }
+            }
+            _ => {
+                // For other types (String, Number, etc.), do nothing
+            }
+        }
+    }
+
+    /// Build a docker CLI invocation targeting the configured daemon
+    fn docker(&self) -> Command {
+        let mut command = Command::new("docker");
+        if let Some((variable, value)) = self.config.docker_env() {
+            command.env(variable, value);
+        }
+        command
+    }
+
+    async fn deploy_stack(&self, stack_name: &str, compose_path: &Path, secrets_env_vars: &[(String, String)], credentials: &RegistryCredentials, deploy: &DeploySettings) -> Result<()> {
+        println!("    {}", i18n::tr("stack-deploying", &[("stack", &stack_name)]));
+        if self.config.self_stack().as_deref() == Some(stack_name) {
+            println!("    {}", i18n::tr("self-update-warning", &[]));
+        }
+        
+        // Read compose file to extract images
+        let compose_content = fs::read_to_string(compose_path)?;
+        
+        // Extract and pull images before deployment
+        println!("    {}", i18n::tr("extracting-images-from-compose-file", &[]));
+        let yaml_value: Value = serde_yaml::from_str(&compose_content)?;
+        let mut images_found = Vec::new();
+        self.extract_images_from_yaml(&yaml_value, &mut images_found);
+        
+        if !images_found.is_empty() {
+            println!("    {}", i18n::tr("images-pulling", &[("count", &images_found.len()), ("images_found", &format!("{:?}", images_found))]));
+            // Images are pulled in parallel, within the limit of each registry
+            let pinned = futures_util::future::try_join_all(images_found.iter().map(|image_name| self.pull_image(image_name, credentials))).await?;
+            println!("    {}", i18n::tr("all-images-pulled-successfully", &[]));
+            
+            // Deploy exactly the images whose provenance was verified
+            let pinned: Vec<(&str, String)> = images_found.iter()
+                .zip(pinned)
+                .filter_map(|(image_name, pinned)| pinned.map(|pinned| (image_name.as_str(), pinned)))
+                .collect();
+            if !pinned.is_empty() {
+                atomic::write(compose_path, pin_verified_images(&compose_content, &pinned)?)?;
+            }
+        } else {
+            println!("    {}", i18n::tr("no-images-found-in-compose-file", &[]));
+        }
+        
+        // Now deploy the stack with secrets as environment variables
+        let mut command = self.docker();
+        command.args(["stack", "deploy", "--detach=false"]);
+        let _login_config = self.with_registry_auth(&mut command, &images_found, credentials, deploy)?;
+        if deploy.prune.unwrap_or(false) {
+            command.arg("--prune");
+        }
+        if let Some(resolve_image) = deploy.resolve_image {
+            command.args(["--resolve-image", resolve_image.as_str()]);
+        }
+        command.args(["-c", compose_path.to_str().unwrap(), stack_name]);
+        
+        // Add secrets as environment variables
+        for (env_name, env_value) in secrets_env_vars {
+            command.env(env_name, env_value);
+            println!("    {}", i18n::tr("secret-env-added", &[("env_name", &env_name)]));
+        }
+        
+        // Awaited so that the other stacks of a parallel run keep deploying
+        let output = tokio::process::Command::from(command).output().await?;
+        
+        if output.status.success() {
+            println!("    {}", i18n::tr("successfully-deployed-stack", &[("stack", &stack_name)]));
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr);
+            println!("    {}", i18n::tr("error-deploying-stack", &[("stack", &stack_name), ("error", &error)]));
+            return Err(anyhow::anyhow!(i18n::tr("deploy-failed", &[("error", &error)])));
+        }
+        
+        Ok(())
+    }
+
+    /// Checks and pre hooks of a stack, the stack is not deployed when they fail
+    async fn before_deploy(&self, stack_def: &StackDefinition, stack_dir: &Path, secrets_env_vars: &[(String, String)], credentials: &RegistryCredentials, deploy: &DeploySettings) -> Result<()> {
+        self.check_prerequisites(&stack_def.name, stack_def.prerequisites.as_ref()).await?;
+        self.run_hooks(stack_def, "pre", stack_dir, secrets_env_vars, credentials, deploy).await
+    }
+
+    /// Run the `pre` or `post` hooks of a stack one after the other, stopping
+    /// at the first failure
+    async fn run_hooks(&self, stack_def: &StackDefinition, phase: &str, stack_dir: &Path, secrets_env_vars: &[(String, String)], credentials: &RegistryCredentials, deploy: &DeploySettings) -> Result<()> {
+        let stack_name = stack_def.name.as_str();
+        let hooks = match (&stack_def.hooks, phase) {
+            (Some(hooks), "pre") => hooks.pre.as_slice(),
+            (Some(hooks), _) => hooks.post.as_slice(),
+            (None, _) => &[],
+        };
+        for (index, hook) in hooks.iter().enumerate() {
+            println!("    {}", i18n::tr("hook-running", &[("phase", &phase), ("index", &(index + 1)), ("stack", &stack_name), ("describe", &hook.describe())]));
+            let result = match hook.run.is_some() {
+                true => self.run_command_hook(stack_name, phase, hook, stack_dir, secrets_env_vars).await,
+                false => self.run_job_hook(stack_name, &format!("{}-{}", phase, index + 1), hook, credentials, deploy).await,
+            };
+            if let Err(e) = result {
+                return Err(anyhow::anyhow!(i18n::tr("hook-failed", &[("phase", &phase), ("index", &(index + 1)), ("stack", &stack_name), ("describe", &hook.describe()), ("error", &e)])));
+            }
+        }
+        if !hooks.is_empty() {
+            println!("    {}", i18n::tr("hooks-succeeded", &[("phase", &phase), ("stack", &stack_name)]));
+        }
+        Ok(())
+    }
+
+    /// Run a shell hook in the stack directory, with the Docker endpoint and
+    /// the secrets of the stack in its environment
+    async fn run_command_hook(&self, stack_name: &str, phase: &str, hook: &Hook, stack_dir: &Path, secrets_env_vars: &[(String, String)]) -> Result<()> {
+        let timeout = hook.timeout()?;
+        let mut command = tokio::process::Command::new("sh");
+        command.args(["-c", hook.run.as_deref().unwrap_or_default()])
+            .current_dir(stack_dir)
+            .env("DOCKEROPS_STACK", stack_name)
+            .env("DOCKEROPS_HOOK", phase)
+            .envs(secrets_env_vars.iter().map(|(name, value)| (name, value)))
+            .envs(&hook.env)
+            .kill_on_drop(true);
+        if let Some((variable, value)) = self.config.docker_env() {
+            command.env(variable, value);
+        }
+        
+        let output = tokio::time::timeout(timeout, command.output()).await
+            .map_err(|_| anyhow::anyhow!(i18n::tr("timed-out", &[("timeout", &timeout.as_secs())])))??;
+        print_hook_output(&String::from_utf8_lossy(&output.stdout));
+        print_hook_output(&String::from_utf8_lossy(&output.stderr));
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("{}: {}", output.status, last_lines(&String::from_utf8_lossy(&output.stderr), 5)));
+        }
+        Ok(())
+    }
+
+    /// Run an image hook as a one-off job attached to networks of the stack,
+    /// removed once it finished
+    async fn run_job_hook(&self, stack_name: &str, name: &str, hook: &Hook, credentials: &RegistryCredentials, deploy: &DeploySettings) -> Result<()> {
+        let timeout = hook.timeout()?;
+        let image = hook.image.as_deref().unwrap_or_default();
+        let job = format!("dockerops-hook-{}-{}", stack_name, name);
+        // A job left over by an interrupted run would be reported instead of this one
+        let _ = self.docker().args(["service", "rm", &job]).output();
+        
+        let mut command = self.docker();
+        command.args(["service", "create", "--detach", "--name", &job, "--mode", "replicated-job", "--restart-condition", "none",
+            "--label", &format!("com.dockerops.hook={}", stack_name)]);
+        for network in &hook.networks {
+            command.args(["--network", &format!("{}_{}", stack_name, network)]);
+        }
+        for (name, value) in &hook.env {
+            command.args(["--env", &format!("{}={}", name, value)]);
+        }
+        let _login_config = self.with_registry_auth(&mut command, &[image.to_string()], credentials, deploy)?;
+        let output = command.arg(image).args(&hook.command).output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("job-start-failed", &[("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        
+        let deadline = tokio::time::Instant::now() + timeout;
+        let result = loop {
+            let output = self.docker().args(["service", "ps", &job, "--no-trunc", "--format", "{{.CurrentState}}\t{{.Error}}"]).output()?;
+            let tasks = String::from_utf8_lossy(&output.stdout).to_string();
+            let (state, error) = tasks.lines().next().unwrap_or_default().split_once('\t').unwrap_or_default();
+            if state.starts_with("Complete") {
+                break Ok(());
+            }
+            if ["Failed", "Rejected", "Shutdown"].iter().any(|end| state.starts_with(end)) {
+                break Err(anyhow::anyhow!("{}{}", state.split_whitespace().next().unwrap_or_default(),
+                    Some(error.trim()).filter(|error| !error.is_empty()).map(|error| format!(": {}", error)).unwrap_or_default()));
+            }
+            if tokio::time::Instant::now() >= deadline {
+                break Err(anyhow::anyhow!(i18n::tr("timed-out", &[("timeout", &timeout.as_secs())])));
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        };
+        
+        if let Ok(output) = self.docker().args(["service", "logs", "--raw", &job]).output() {
+            print_hook_output(&String::from_utf8_lossy(&output.stdout));
+            print_hook_output(&String::from_utf8_lossy(&output.stderr));
+        }
+        let _ = self.docker().args(["service", "rm", &job]).output();
+        result
+    }
+
+    /// Verify the host prerequisites of a stack on every active node before
+    /// deploying it, failing with the unmet ones of each node
+    async fn check_prerequisites(&self, stack_name: &str, prerequisites: Option<&HostPrerequisites>) -> Result<()> {
+        let Some(prerequisites) = prerequisites else {
+            return Ok(());
+        };
+        println!("    {}", i18n::tr("prerequisites-checking", &[("stack", &stack_name)]));
+        let output = self.docker()
+            .args(["node", "ls", "--format", "{{.ID}}"])
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("failed-to-list-the-swarm-nodes", &[("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        let ids: Vec<String> = String::from_utf8_lossy(&output.stdout).lines().map(|line| line.trim().to_string()).collect();
+        let output = self.docker()
+            .args(["node", "inspect", "--format", "{{.Description.Hostname}}\t{{.Description.Engine.EngineVersion}}\t{{.Status.State}}\t{{.Spec.Availability}}"])
+            .args(&ids)
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("failed-to-inspect-the-swarm-nodes", &[("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        // Drained and down nodes run no task, of this stack or of the probe
+        let nodes: Vec<(String, String)> = String::from_utf8_lossy(&output.stdout).lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+                (fields.len() == 4 && fields[2] == "ready" && fields[3] == "active").then(|| (fields[0].to_string(), fields[1].to_string()))
+            })
+            .collect();
+        
+        let probes = match prerequisites.needs_probe() {
+            true => self.probe_nodes(stack_name, prerequisites).await?,
+            false => Default::default(),
+        };
+        let report: Vec<String> = nodes.iter()
+            .filter_map(|(hostname, version)| {
+                let unmet = prerequisites.unmet(hostname, version, &probes);
+                (!unmet.is_empty()).then(|| format!("{}: {}", hostname, unmet.join(", ")))
+            })
+            .collect();
+        if !report.is_empty() {
+            return Err(anyhow::anyhow!(i18n::tr("prerequisites-not-met", &[("stack", &stack_name), ("failed", &report.len()), ("nodes", &nodes.len()), ("details", &report.join("\n  "))])));
+        }
+        println!("    {}", i18n::tr("prerequisites-met", &[("count", &nodes.len())]));
+        Ok(())
+    }
+
+    /// Run the checks of the prerequisites on every node with a one-off
+    /// global job, returning what each node reported
+    async fn probe_nodes(&self, stack_name: &str, prerequisites: &HostPrerequisites) -> Result<prerequisites::ProbeResults> {
+        let job = format!("dockerops-prerequisites-{}", stack_name);
+        // A job left over by an interrupted run would keep its old results
+        let _ = self.docker().args(["service", "rm", &job]).output();
+        
+        let mut command = self.docker();
+        command.args(["service", "create", "--detach", "--name", &job, "--mode", "global-job", "--restart-condition", "none",
+            "--hostname", "{{.Node.Hostname}}", "--network", "host", "--label", "com.dockerops.probe=true"]);
+        if prerequisites.needs_host_root() {
+            command.args(["--mount", &format!("type=bind,source=/,target={},readonly", prerequisites::HOST_ROOT)]);
+        }
+        let output = command.args([prerequisites::PROBE_IMAGE, "sh", "-c", &prerequisites.probe_script()]).output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(i18n::tr("prerequisites-probe-failed", &[("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+        }
+        
+        let deadline = tokio::time::Instant::now() + PROBE_TIMEOUT;
+        loop {
+            let output = self.docker().args(["service", "ps", &job, "--format", "{{.CurrentState}}"]).output()?;
+            let states = String::from_utf8_lossy(&output.stdout).to_string();
+            let finished = !states.trim().is_empty() && states.lines()
+                .all(|state| ["Complete", "Failed", "Rejected", "Shutdown"].iter().any(|end| state.starts_with(end)));
+            if finished || tokio::time::Instant::now() >= deadline {
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+        
+        let output = self.docker().args(["service", "logs", "--raw", &job]).output();
+        let _ = self.docker().args(["service", "rm", &job]).output();
+        Ok(prerequisites::parse_probe_output(&String::from_utf8_lossy(&output?.stdout)))
+    }
+
+    /// Wait for a stack that was just deployed to run: the result of the
+    /// deployment once every service converged, failed when no replica of
+    /// the stack runs after `convergence_timeout`, and whether the stack is
+    /// degraded, running only part of its replicas
+    async fn converge(&self, stack_name: &str, deployed: Result<()>, deploy: &DeploySettings) -> (Result<()>, bool) {
+        if deployed.is_err() {
+            return (deployed, false);
+        }
+        let timeout = match deploy.convergence_timeout() {
+            Ok(timeout) if timeout.is_zero() => return (deployed, false),
+            Ok(timeout) => timeout,
+            Err(e) => return (Err(e), false),
+        };
+        
+        println!("    {}", i18n::tr("convergence-waiting", &[("timeout", &timeout.as_secs()), ("stack", &stack_name)]));
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let services = match self.service_statuses(stack_name).await {
+                Ok(services) => services,
+                Err(e) => return (Err(e), false),
+            };
+            // A rollback finished by Swarm is reported by the canary analysis
+            let pending: Vec<&ServiceStatus> = services.iter()
+                .filter(|service| !matches!(service.state().as_str(), "converged" | "rolled back"))
+                .collect();
+            if pending.is_empty() {
+                println!("    {}", i18n::tr("convergence-done", &[("stack", &stack_name)]));
+                return (Ok(()), false);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                let summary: Vec<String> = pending.iter()
+                    .map(|service| format!("{} {}/{} {}{}", service.name, service.running, service.desired, service.state(),
+                        service.error.as_deref().map(|error| format!(" ({})", error)).unwrap_or_default()))
+                    .collect();
+                if services.iter().all(|service| service.running == 0) {
+                    return (Err(anyhow::anyhow!(i18n::tr("convergence-failed", &[("stack", &stack_name), ("timeout", &timeout.as_secs()), ("list", &summary.join(", "))]))), false);
+                }
+                println!("    {}", i18n::tr("convergence-degraded", &[("stack", &stack_name), ("timeout", &timeout.as_secs()), ("list", &summary.join(", "))]));
+                return (Ok(()), true);
+            }
+            tokio::time::sleep(CONVERGENCE_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Wait up to the stack's convergence timeout for the services of the
+    /// stacks it depends on to converge, false when some are still
+    /// missing or not converged
+    async fn dependencies_ready(&self, stack_def: &StackDefinition, deploy: &DeploySettings) -> Result<bool> {
+        if stack_def.depends_on.is_empty() {
+            return Ok(true);
+        }
+        let deadline = tokio::time::Instant::now() + deploy.convergence_timeout()?;
+        let mut is_waiting = false;
+        loop {
+            let mut pending = Vec::new();
+            for dependency in &stack_def.depends_on {
+                let services = self.service_statuses(dependency).await?;
+                if services.is_empty() {
+                    pending.push(format!("{} (not deployed)", dependency));
+                    continue;
+                }
+                let unconverged: Vec<String> = services.iter()
+                    .filter(|service| !matches!(service.state().as_str(), "converged" | "rolled back"))
+                    .map(|service| format!("{} {}/{} {}", service.name, service.running, service.desired, service.state()))
+                    .collect();
+                if !unconverged.is_empty() {
+                    pending.push(format!("{} ({})", dependency, unconverged.join(", ")));
+                }
+            }
+            if pending.is_empty() {
+                return Ok(true);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                println!("  {}", i18n::tr("dependencies-not-ready", &[("stack", &stack_def.name), ("list", &pending.join("; "))]));
+                return Ok(false);
+            }
+            if !is_waiting {
+                println!("    {}", i18n::tr("dependencies-waiting", &[("stack", &stack_def.name), ("list", &pending.join("; "))]));
+                is_waiting = true;
+            }
+            tokio::time::sleep(CONVERGENCE_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Add `--with-registry-auth` to a deploy or update command when enabled:
+    /// worker nodes pull the images themselves, with the logins the manager
+    /// sends along with the services. The returned configuration holding the
+    /// logins of registries.yaml must live until the command has run.
+    fn with_registry_auth(&self, command: &mut Command, images: &[String], credentials: &RegistryCredentials, deploy: &DeploySettings) -> Result<Option<DockerLoginConfig>> {
+        let mut logins: Vec<(&str, &RegistryLogin)> = Vec::new();
+        let references: Vec<ImageReference> = images.iter().map(|image| ImageReference::parse(image)).collect();
+        for reference in &references {
+            if let Some(login) = credentials.get(&reference.registry) {
+                if !logins.iter().any(|(registry, _)| *registry == reference.registry) {
+                    logins.push((&reference.registry, login));
+                }
+            }
+        }
+        let with_registry_auth = deploy.with_registry_auth.unwrap_or(!logins.is_empty());
+        if !with_registry_auth {
+            if !logins.is_empty() {
+                println!("    {}", i18n::tr("registry-auth-disabled", &[]));
+            }
+            return Ok(None);
+        }
+        
+        command.arg("--with-registry-auth");
+        if logins.is_empty() {
+            println!("    {}", i18n::tr("registry-auth-docker-cli", &[]));
+            return Ok(None);
+        }
+        let login_config = DockerLoginConfig::new(&logins)?;
+        let registries: Vec<&str> = logins.iter().map(|(registry, _)| *registry).collect();
+        println!("    {}", i18n::tr("registry-auth-sending", &[("list", &registries.join(", "))]));
+        command.env("DOCKER_CONFIG", &login_config.dir);
+        Ok(Some(login_config))
+    }
+
+    /// Apply the image and replica changes of a stack with `docker service
+    /// update` on the changed services only, refreshing the DockerOps labels
+    /// of the others, instead of redeploying the whole stack
+    async fn update_services(&self, stack_name: &str, changes: &[ServiceChange], compose_content: &str, credentials: &RegistryCredentials, deploy: &DeploySettings) -> Result<()> {
+        let compose: Value = serde_yaml::from_str(compose_content)?;
+        let Some(services) = compose["services"].as_mapping() else {
+            return Ok(());
+        };
+        
+        for (name, service) in services {
+            let Some(name) = name.as_str() else {
+                continue;
+            };
+            let mut command = self.docker();
+            command.args(["service", "update", "--detach=false"]);
+            if let Some(labels) = service["deploy"]["labels"].as_mapping() {
+                for (key, value) in labels {
+                    if let (Some(key), Some(value)) = (key.as_str().filter(|key| key.starts_with("com.dockerops.")), value.as_str()) {
+                        command.args(["--label-add", &format!("{}={}", key, value)]);
+                    }
+                }
+            }
+            
+            let mut _login_config = None;
+            match changes.iter().find(|change| change.service == name) {
+                Some(change) => {
+                    println!("    {}", i18n::tr("updating-service", &[("stack", &stack_name), ("name", &name), ("image", &change.image.as_deref().map(|image| format!(", image {}", image)).unwrap_or_default()), ("replicas", &change.replicas.as_deref().map(|replicas| format!(", {} replicas", replicas)).unwrap_or_default())]));
+                    if let Some(image) = &change.image {
+                        let pinned = self.pull_image(image, credentials).await?;
+                        command.args(["--image", pinned.as_deref().unwrap_or(image)]);
+                        _login_config = self.with_registry_auth(&mut command, std::slice::from_ref(image), credentials, deploy)?;
+                    }
+                    if let Some(replicas) = &change.replicas {
+                        command.args(["--replicas", replicas]);
+                    }
+                }
+                // Only the labels change, the tasks keep running
+                None => {
+                    command.arg("--detach");
+                }
+            }
+            command.arg(format!("{}_{}", stack_name, name));
+            
+            let output = tokio::process::Command::from(command).output().await?;
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(i18n::tr("failed-to-update-service", &[("stack", &stack_name), ("name", &name), ("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+            }
+        }
+        println!("    {}", i18n::tr("services-updated", &[("count", &changes.len()), ("stack", &stack_name)]));
+        Ok(())
+    }
+
+    /// Move a stopped stack to the trash with a copy of its directory, so that
+    /// restoring it finds the files its compose file references
+    async fn trash_stack(&self, stack: &Stack, reason: &str) -> Result<()> {
+        let stack_dir = match self.db.get_repository_from_cache(&stack.repository_url).await? {
+            Some(repo) => Path::new(&stack.compose_path).parent()
+                .map(|dir| self.working_copy_path(&repo.url).join(repo.path.as_deref().unwrap_or_default()).join(dir))
+                .filter(|dir| dir.is_dir()),
+            None => None,
+        };
+        let snapshot_dir = match stack_dir {
+            Some(stack_dir) => {
+                let snapshot_dir = self.config.trash_dir()
+                    .join(format!("{}_{}_{:x}", stack.name, chrono::Utc::now().timestamp(), md5::compute(&stack.repository_url)));
+                match copy_tree(&stack_dir, &snapshot_dir, |_| true) {
+                    Ok(_) => Some(snapshot_dir.to_string_lossy().to_string()),
+                    Err(e) => {
+                        println!("    {}", i18n::tr("trash-copy-failed", &[("stack", &stack.name), ("error", &e)]));
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        self.db.trash_stack(&stack.name, &stack.repository_url, reason, &self.config.operator(), snapshot_dir.as_deref()).await?;
+        Ok(())
+    }
+
+    async fn stop_stack(&self, stack_name: &str) -> Result<()> {
+        println!("    {}", i18n::tr("stack-stopping", &[("stack", &stack_name)]));
+        
+        let output = self.docker()
+            .args(["stack", "rm", stack_name])
+            .output()?;
+        
+        if output.status.success() {
+            println!("    {}", i18n::tr("successfully-stopped-stack", &[("stack", &stack_name)]));
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr);
+            println!("    {}", i18n::tr("stack-stop-failed", &[("stack", &stack_name), ("error", &error)]));
+            // Don't return error here as the stack might not exist
+        }
+        
+        Ok(())
+    }
+
+    async fn process_images(&self, repository_url: &str, previous_images: &[String], credentials: &RegistryCredentials, unchanged: &BTreeSet<String>) -> Result<()> {
+        let images = self.db.get_repository_images(repository_url).await?;
+        println!("  {}", i18n::tr("images-used-by-repository", &[("count", &images.len())]));
+        
+        // Images only the stacks left out by --changed-only use are not checked
+        let mut checked = images.clone();
+        if !unchanged.is_empty() {
+            let mut used = BTreeSet::new();
+            for stack in self.db.get_all_stacks().await?.iter().filter(|stack| stack.repository_url == repository_url && !unchanged.contains(&stack.name)) {
+                used.extend(self.db.get_stack_images(&stack.name, repository_url).await?);
+            }
+            checked.retain(|image| used.contains(image));
+            println!("  {}", i18n::tr("images-checking-changed", &[("count", &checked.len())]));
+        }
+        
+        for image_name in &checked {
+            // Check and update image if needed
+            println!("  {}", i18n::tr("processing-image", &[("image", &image_name)]));
+            self.check_and_update_image(image_name, credentials).await?;
+        }
+        
+        // Remove the images this repository stopped using, unless another
+        // repository still references them
+        let unused: Vec<String> = previous_images.iter()
+            .filter(|image| !images.contains(image))
+            .cloned()
+            .collect();
+        self.remove_unreferenced_images(&unused).await
+    }
+
+    /// Remove the given images from Docker and the database when no stack references them anymore
+    async fn remove_unreferenced_images(&self, candidates: &[String]) -> Result<()> {
+        if candidates.is_empty() {
+            return Ok(());
+        }
+        let users = self.unmanaged_image_users(&self.db.get_all_stacks().await?).await;
+        
+        for image_name in candidates {
+            let still_used = self.db.get_image_by_name(image_name).await?
+                .is_some_and(|image| image.reference_count > 0);
+            if still_used {
+                println!("  {}", i18n::tr("image-kept-referenced", &[("image", &image_name)]));
+                continue;
+            }
+            
+            // DockerOps stops tracking the image but leaves it to the workloads still running it
+            if let Some(users) = image_kept_for(image_name, &users) {
+                println!("  {}", i18n::tr("image-kept-in-use", &[("image", &image_name), ("users", &users)]));
+                self.db.delete_image(image_name).await?;
+                continue;
+            }
+            
+            println!("  {}", i18n::tr("removing-unused-image", &[("image", &image_name)]));
+            self.remove_image(image_name).await?;
+            self.db.delete_image(image_name).await?;
+        }
+        
+        Ok(())
+    }
+
+    /// Containers and services outside the stacks managed by DockerOps, by
+    /// the image they run (see `image_key`). `None` when Docker could not be
+    /// asked, in which case no image is removed.
+    async fn unmanaged_image_users(&self, managed: &[Stack]) -> Option<BTreeMap<String, Vec<String>>> {
+        let is_managed = |namespace: &str| managed.iter().any(|stack| stack.name == namespace);
+        let mut users: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        
+        // Stack containers carry the namespace label of their stack, swarm
+        // task containers of other services are reported with their service
+        let containers = self.docker()
+            .args(["container", "ls", "--all", "--format",
+                "{{.Names}}\t{{.Label \"com.docker.stack.namespace\"}}\t{{.Label \"com.docker.swarm.service.name\"}}\t{{.Image}}"])
+            .output();
+        match containers {
+            Ok(output) if output.status.success() => {
+                for line in String::from_utf8_lossy(&output.stdout).lines() {
+                    let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+                    if let [name, namespace, service, image] = fields[..] {
+                        if !is_managed(namespace) && service.is_empty() {
+                            users.entry(image_key(image)).or_default().push(format!("container {}", name));
+                        }
+                    }
+                }
+            }
+            Ok(output) => {
+                println!("{}", i18n::tr("image-users-unknown-containers", &[("error", &String::from_utf8_lossy(&output.stderr).trim())]));
+                return None;
+            }
+            Err(e) => {
+                println!("{}", i18n::tr("image-users-unknown-containers", &[("error", &e)]));
+                return None;
+            }
+        }
+        
+        // Services only exist on swarm managers
+        let services = self.docker().args(["service", "ls", "--quiet"]).output();
+        let ids: Vec<String> = match services {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .map(|id| id.to_string())
+                .collect(),
+            _ => Vec::new(),
+        };
+        if !ids.is_empty() {
+            let output = self.docker()
+                .args(["service", "inspect", "--format",
+                    "{{.Spec.Name}}\t{{index .Spec.Labels \"com.docker.stack.namespace\"}}\t{{.Spec.TaskTemplate.ContainerSpec.Image}}"])
+                .args(&ids)
+                .output();
+            match output {
+                Ok(output) if output.status.success() => {
+                    for line in String::from_utf8_lossy(&output.stdout).lines() {
+                        let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+                        if let [name, namespace, image] = fields[..] {
+                            if !is_managed(namespace) {
+                                users.entry(image_key(image)).or_default().push(format!("service {}", name));
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    println!("{}", i18n::tr("image-users-unknown-services", &[]));
+                    return None;
+                }
+            }
+        }
+        
+        Some(users)
+    }
+
+    async fn check_and_update_image(&self, image_name: &str, credentials: &RegistryCredentials) -> Result<()> {
+        let reference = ImageReference::parse(image_name);
+        
+        // Check if image exists locally
+        let local_sha = self.get_local_image_sha(image_name, &reference).await?;
+        
+        // Get remote SHA from registry
+        let remote_sha = RegistryClient::with_credentials(credentials.clone()).manifest_digest(&reference).await?;
+        
+        if let (Some(local), Some(remote)) = (&local_sha, &remote_sha) {
+            if local != remote {
+                println!("    {}", i18n::tr("image-digest-mismatch", &[("image", &image_name), ("local", &local), ("remote", &remote)]));
+                println!("    {}", i18n::tr("image-replacing", &[]));
+                self.remove_image(image_name).await?;
+                self.pull_image(image_name, credentials).await?;
+            } else {
+                println!("    {}", i18n::tr("image-is-up-to-date", &[("image", &image_name)]));
+            }
+        } else if local_sha.is_none() {
+            // Image doesn't exist locally, pull it
+            println!("    {}", i18n::tr("image-missing-locally", &[("image", &image_name)]));
+            self.pull_image(image_name, credentials).await?;
+        } else {
+            println!("    {}", i18n::tr("image-remote-digest-unknown", &[("image", &image_name)]));
+        }
+        
+        Ok(())
+    }
+
+    /// Registry digest of a local image, or its image ID when it was not
+    /// pulled from the reference's repository (e.g. built locally)
+    async fn get_local_image_sha(&self, image_name: &str, reference: &ImageReference) -> Result<Option<String>> {
+        let output = self.docker()
+            .args(["image", "inspect", image_name, "--format", "{{.Id}}\t{{join .RepoDigests \" \"}}"])
+            .output()?;
+        
+        if !output.status.success() {
+            return Ok(None);
+        }
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (id, repo_digests) = stdout.trim().split_once('\t').unwrap_or((stdout.trim(), ""));
+        if id.is_empty() {
+            return Ok(None);
+        }
+        
+        let digest = repo_digests.split_whitespace()
+            .map(ImageReference::parse)
+            .find(|r| r.registry == reference.registry && r.repository == reference.repository)
+            .and_then(|r| r.digest);
+        
+        Ok(Some(digest.unwrap_or_else(|| id.to_string())))
+    }
+
+    async fn remove_image(&self, image_name: &str) -> Result<()> {
+        println!("    {}", i18n::tr("removing-image-indented", &[("image", &image_name)]));
+        
+        let output = self.docker()
+            .args(["image", "rm", image_name])
+            .output()?;
+        
+        if output.status.success() {
+            println!("    {}", i18n::tr("successfully-removed-image", &[("image", &image_name)]));
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr);
+            println!("    {}", i18n::tr("image-remove-failed", &[("image", &image_name), ("error", &error)]));
+        }
+        
+        Ok(())
+    }
+
+    /// Pull an image, by the digest whose provenance was verified when a
+    /// provenance policy applies to it, returning that pinned reference
+    async fn pull_image(&self, image_name: &str, credentials: &RegistryCredentials) -> Result<Option<String>> {
+        let reference = ImageReference::parse(image_name);
+        let mut pinned = None;
+        if let Some(policy) = self.config.provenance_policy(&reference) {
+            let client = RegistryClient::with_credentials(credentials.clone());
+            let provenance = provenance::verify(&client, &reference, policy).await
+                .map_err(|e| anyhow::anyhow!(i18n::tr("provenance-unverified", &[("image", &image_name), ("error", &e)])))?;
+            let login_config = match credentials.get(&reference.registry) {
+                Some(login) => Some(DockerLoginConfig::new(&[(reference.registry.as_str(), login)])?),
+                None => None,
+            };
+            provenance::verify_signature(&provenance, login_config.as_ref().map(|config| config.dir.as_path())).await
+                .map_err(|e| anyhow::anyhow!(i18n::tr("provenance-unverified", &[("image", &image_name), ("error", &e)])))?;
+            println!("    {}", i18n::tr("provenance-verified", &[("workflow", &provenance.workflow), ("source", &provenance.source)]));
+            // The tag could move to another image between the check and the pull
+            pinned = Some(provenance.image);
+        }
+        let pull_name = pinned.as_deref().unwrap_or(image_name);
+        let pull_reference = ImageReference::parse(pull_name);
+        
+        // With a bandwidth cap the layers are fetched through the rate limited
+        // proxy first, the regular pull then only downloads the manifest
+        let proxied_reference = match self.config.pull_bandwidth_limit()? {
+            Some(limit) => self.prefetch_image(pull_name, limit, credentials).await,
+            None => None,
+        };
+        
+        let _slot = self.pull_slot(&pull_reference).await;
+        println!("    {}", i18n::tr("pulling-image", &[("image", &pull_name)]));
+        let pulled = match self.docker_pull(pull_name, credentials).await {
+            Ok(()) => Ok(()),
+            Err(e) => self.pull_from_mirrors(pull_name, &pull_reference, credentials, e).await,
+        };
+        
+        if let Some(proxied_reference) = proxied_reference {
+            // Only drop the temporary tag, the layers are shared with the real image
+            let _ = self.docker().args(["image", "rm", &proxied_reference]).output();
+        }
+        
+        match pulled {
+            Ok(()) => {
+                // Keep the tag on the verified image for the commands comparing local images
+                if pinned.is_some() {
+                    let _ = self.docker().args(["image", "tag", pull_name, image_name]).output();
+                }
+                println!("    {}", i18n::tr("successfully-pulled-image", &[("image", &pull_name)]));
+                Ok(pinned)
+            }
+            Err(e) => {
+                println!("    {}", i18n::tr("error-pulling-image", &[("image", &pull_name), ("error", &e)]));
+                Err(anyhow::anyhow!(i18n::tr("pull-failed", &[("error", &e)])))
+            }
+        }
+    }
+
+    /// Wait until fewer than `max_concurrent_pulls` pulls of the image's
+    /// registry are running, the returned permit holds the slot
+    async fn pull_slot(&self, reference: &ImageReference) -> Option<tokio::sync::OwnedSemaphorePermit> {
+        let limit = self.config.registry_settings(reference.registry_name())
+            .and_then(|settings| settings.max_concurrent_pulls)
+            .unwrap_or(DEFAULT_CONCURRENT_PULLS);
+        let semaphore = self.pull_slots.lock().unwrap()
+            .entry(reference.registry_name().to_string())
+            .or_insert_with(|| Arc::new(tokio::sync::Semaphore::new(limit.max(1))))
+            .clone();
+        semaphore.acquire_owned().await.ok()
+    }
+
+    /// `docker image pull` with the login of registries.yaml for the image's registry
+    async fn docker_pull(&self, image_name: &str, credentials: &RegistryCredentials) -> Result<()> {
+        let reference = ImageReference::parse(image_name);
+        let mut command = self.docker();
+        let login_config = match credentials.get(&reference.registry) {
+            Some(login) => Some(DockerLoginConfig::new(&[(reference.registry.as_str(), login)])?),
+            None => None,
+        };
+        if let Some(login_config) = &login_config {
+            command.env("DOCKER_CONFIG", &login_config.dir);
+        }
+        command.args(["image", "pull", image_name]);
+        
+        let output = tokio::process::Command::from(command).output().await?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(())
+    }
+
+    /// Pull an image from the mirrors of its registry, in order, once the
+    /// registry itself failed or rate limited the pull with `error`. The copy
+    /// pulled from a mirror gets the original name, for the deployment to use it.
+    async fn pull_from_mirrors(&self, image_name: &str, reference: &ImageReference, credentials: &RegistryCredentials, error: anyhow::Error) -> Result<()> {
+        let mirrors = self.config.registry_settings(reference.registry_name())
+            .map(|settings| settings.mirrors.as_slice())
+            .unwrap_or_default();
+        let mut error = error;
+        for mirror in mirrors {
+            println!("    {}", i18n::tr("pull-trying-mirror", &[("registry_name", &reference.registry_name()), ("mirror", &mirror), ("error", &last_lines(&error.to_string(), 1))]));
+            let mirrored = mirror_reference(reference, mirror);
+            if let Err(e) = self.docker_pull(&mirrored, credentials).await {
+                error = e;
+                continue;
+            }
+            // Images pinned by digest are found by their digest, whatever the name
+            if reference.digest.is_none() {
+                let output = self.docker().args(["image", "tag", &mirrored, image_name]).output()?;
+                if !output.status.success() {
+                    return Err(anyhow::anyhow!(i18n::tr("mirror-tag-failed", &[("mirrored", &mirrored), ("image", &image_name), ("error", &String::from_utf8_lossy(&output.stderr).trim())])));
+                }
+                let _ = self.docker().args(["image", "rm", &mirrored]).output();
+            }
+            println!("    {}", i18n::tr("pulled-from-mirror", &[("image", &image_name), ("mirror", &mirror)]));
+            return Ok(());
+        }
+        Err(error)
+    }
+
+    /// Download an image's layers through a local proxy capped at `limit`
+    /// bytes per second, returning the temporary reference that was pulled.
+    /// Failures are reported and leave the regular pull to do the work.
+    async fn prefetch_image(&self, image_name: &str, limit: u64, credentials: &RegistryCredentials) -> Option<String> {
+        let reference = ImageReference::parse(image_name);
+        if reference.registry.contains(':') {
+            // Registries on a custom port are local ones, and can't be nested in a repository path
+            return None;
+        }
+        if !self.config.docker_is_local() {
+            println!("    {}", i18n::tr("bandwidth-limit-ignored", &[]));
+            return None;
+        }
+        
+        let proxy = match PullProxy::start(limit, credentials.clone()).await {
+            Ok(proxy) => proxy,
+            Err(e) => {
+                println!("    ⚠️  {}", e);
+                return None;
+            }
+        };
+        let proxied_reference = proxy.proxied_reference(&reference);
+        println!("    {}", i18n::tr("bandwidth-limited-fetch", &[("image", &image_name), ("limit", &(limit / 1024))]));
+        
+        // The daemon calls back into the proxy, so the pull must not block the runtime
+        let output = tokio::process::Command::from(self.docker())
+            .args(["image", "pull", &proxied_reference])
+            .output()
+            .await;
+        
+        match output {
+            Ok(output) if output.status.success() => Some(proxied_reference),
+            Ok(output) => {
+                println!("    {}", i18n::tr("bandwidth-limited-pull-failed", &[("error", &String::from_utf8_lossy(&output.stderr).trim())]));
+                None
+            }
+            Err(e) => {
+                println!("    {}", i18n::tr("bandwidth-limited-pull-failed", &[("error", &e)]));
+                None
+            }
+        }
+    }
+
+    async fn process_compose_secrets(&self, stack_dir: &Path, repo_path: &str) -> Result<Vec<(String, String)>> {
+        println!("    {}", i18n::tr("checking-for-secrets-yaml-file", &[]));
+        
+        // Read secrets.yaml file if it exists
+        let secrets_file_path = stack_dir.join("secrets.yaml");
+        if !secrets_file_path.exists() {
+            println!("    {}", i18n::tr("secrets-file-missing", &[]));
+            return Ok(Vec::new());
+        }
+        
+        println!("    {}", i18n::tr("secrets-file-found", &[]));
+        let secrets_content = fs::read_to_string(&secrets_file_path)?;
+        let secrets_definitions: Vec<SecretDefinition> = serde_yaml::from_str(&secrets_content)?;
+        println!("    {}", i18n::tr("found-secret-definitions", &[("count", &secrets_definitions.len())]));
+        
+        // Read NFS configuration to get the secrets path
+        let nfs_config = self.read_nfs_config(repo_path).await?;
+        let secrets_base_path = Path::new(&nfs_config.path).join("secret");
+        println!("    {}", i18n::tr("using-secrets-path", &[("secrets_base_path", &secrets_base_path.display())]));
+        
+        let mut env_vars = Vec::new();
+        
+        // Process each secret definition
+        for secret_def in &secrets_definitions {
+            println!("    {}", i18n::tr("processing-secret", &[("id", &secret_def.id), ("env", &secret_def.env)]));
+            
+            // Read secret value from NFS secrets directory
+            let secret_path = secrets_base_path.join(&secret_def.id);
+            if !secret_path.exists() {
+                return Err(anyhow::anyhow!(i18n::tr("secret-file-not-found", &[("secret_path", &secret_path.display())])));
+            }
+            
+            let secret_value = fs::read_to_string(&secret_path)?;
+            let secret_value = secret_value.trim(); // Remove trailing whitespace/newlines
+            
+            println!("    {}", i18n::tr("secret-value-loaded-from", &[("secret_path", &secret_path.display())]));
+            
+            // Add to environment variables list
+            env_vars.push((secret_def.env.clone(), secret_value.to_string()));
+        }
+        
+        println!("    {}", i18n::tr("successfully-loaded-secrets", &[("count", &env_vars.len())]));
+        Ok(env_vars)
+    }
+
+    async fn read_nfs_config(&self, repo_path: &str) -> Result<NfsConfig> {
+        // Look for nfs.yaml file
+        let nfs_file_path = Path::new(repo_path).join("nfs.yaml");
+        if !nfs_file_path.exists() {
+            return Err(anyhow::anyhow!(i18n::tr("nfs-yaml-not-found-at", &[("nfs_file_path", &nfs_file_path.display())])));
+        }
+        
+        println!("  {}", i18n::tr("reading-nfs-yaml-from", &[("nfs_file_path", &nfs_file_path.display())]));
+        let nfs_content = fs::read_to_string(&nfs_file_path)?;
+        let config = serde_yaml::from_str::<NfsConfig>(&nfs_content)?;
+        println!("  {}", i18n::tr("nfs-config", &[("config", &format!("{:?}", config))]));
+        
+        Ok(config)
+    }
+
+    /// Read volumes.yaml, pointing the bindings to their copy on the NFS
+    /// share, refreshed from the repository when `copy_bindings` is set
+    /// Bindings of a repository watched in a namespace are copied to a
+    /// directory of that name on the NFS share
+    async fn process_volumes_config(&self, repo_path: &str, copy_bindings: bool, namespace: Option<&str>) -> Result<Option<Vec<VolumeDefinition>>> {
+        println!("  {}", i18n::tr("looking-for-volumes-yaml-in", &[("repo_path", &repo_path)]));
+        
+        // Look for volumes.yaml file
+        let volumes_file_path = Path::new(repo_path).join("volumes.yaml");
+        if !volumes_file_path.exists() {
+            println!("  {}", i18n::tr("volumes-file-missing", &[("volumes_file_path", &volumes_file_path.display())]));
+            return Ok(None);
+        }
+        
+        println!("  {}", i18n::tr("found-volumes-yaml-at", &[("volumes_file_path", &volumes_file_path.display())]));
+        
+        // Read and parse volumes.yaml
+        let volumes_content = fs::read_to_string(&volumes_file_path)?;
+        println!("  {}", i18n::tr("volumes-file-read", &[("count", &volumes_content.len())]));
+        
+        let volumes_definitions: Vec<VolumeDefinition> = serde_yaml::from_str(&volumes_content)?;
+        println!("  {}", i18n::tr("volumes-file-parsed", &[("count", &volumes_definitions.len())]));
+        
+        // Look for nfs.yaml file
+        let nfs_file_path = Path::new(repo_path).join("nfs.yaml");
+        let nfs_config = if nfs_file_path.exists() {
+            println!("  {}", i18n::tr("found-nfs-yaml-at", &[("nfs_file_path", &nfs_file_path.display())]));
+            let nfs_content = fs::read_to_string(&nfs_file_path)?;
+            let config = serde_yaml::from_str::<NfsConfig>(&nfs_content)?;
+            println!("  {}", i18n::tr("nfs-config", &[("config", &format!("{:?}", config))]));
+            Some(config)
+        } else {
+            println!("  {}", i18n::tr("nfs-file-missing", &[("nfs_file_path", &nfs_file_path.display())]));
+            None
+        };
+        
+        println!("  {}", i18n::tr("processing-volume-definitions", &[("count", &volumes_definitions.len())]));
+        
+        let mut volumes_definitions = volumes_definitions;
+        
+        for volume_def in &mut volumes_definitions {
+            println!("  {}", i18n::tr("processing-volume-definition", &[("volume_def", &format!("{:?}", volume_def))]));
+            
+            match volume_def.r#type {
+                VolumeType::Volume => {
+                    println!("  {}", i18n::tr("processing-volume", &[("id", &volume_def.id), ("path", &volume_def.path)]));
+                    // For Docker volumes, we just need to ensure they exist
+                    //self.ensure_docker_volume_exists(&volume_def.path).await?;
+                }
+                VolumeType::Binding => {
+                    println!("  {}", i18n::tr("processing-binding", &[("id", &volume_def.id), ("path", &volume_def.path)]));
+                    if let Some(nfs_config) = &nfs_config {
+                        self.process_binding_volume(volume_def, nfs_config, repo_path, copy_bindings, namespace).await?;
+                    } else {
+                        println!("    {}", i18n::tr("binding-skipped-no-nfs", &[]));
+                    }
+                }
+            }
+        }
+        
+        println!("  {}", i18n::tr("finished-processing-all-volume-definitions", &[]));
+        Ok(Some(volumes_definitions))
+    }
+
+    async fn process_binding_volume(&self, volume_def: &mut VolumeDefinition, nfs_config: &NfsConfig, repo_path: &str, copy: bool, namespace: Option<&str>) -> Result<()> {
+        let local_path = Path::new(repo_path).join(&volume_def.path);
+        
+        if !local_path.exists() {
+            println!("    {}", i18n::tr("binding-source-missing", &[("local_path", &local_path.display())]));
+            return Ok(());
+        }
+        
+        // Create NFS destination path
+        let nfs_dest_path = Path::new(&nfs_config.path).join(namespace.unwrap_or_default()).join(&volume_def.path);
+        if !copy {
+            volume_def.path = nfs_dest_path.to_string_lossy().to_string();
+            return Ok(());
+        }
+        
+        println!("    {}", i18n::tr("copying-to-nfs", &[("local_path", &local_path.display()), ("nfs_dest_path", &nfs_dest_path.display())]));
+        
+        // Remove existing file or directory on NFS if it exists
+        if nfs_dest_path.exists() {
+            let metadata = fs::metadata(&nfs_dest_path)?;
+            if metadata.is_dir() {
+                println!("    {}", i18n::tr("removing-existing-directory-on-nfs", &[("nfs_dest_path", &nfs_dest_path.display())]));
+                fs::remove_dir_all(&nfs_dest_path)?;
+            } else {
+                println!("    {}", i18n::tr("removing-existing-file-on-nfs", &[("nfs_dest_path", &nfs_dest_path.display())]));
+                fs::remove_file(&nfs_dest_path)?;
+            }
+        }
+        
+        // Create parent directory if it doesn't exist
+        if let Some(parent) = nfs_dest_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        // Copy recursively
+        if local_path.is_dir() {
+            self.copy_directory_recursive(&local_path, &nfs_dest_path).await?;
+        } else {
+            // For files, copy to parent directory
+            if let Some(parent) = nfs_dest_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&local_path, &nfs_dest_path)?;
+        }
+        
+        // Fix permissions for Docker compatibility
+        self.fix_permissions_recursive(&nfs_dest_path).await?;
+        
+        // Update the volume definition path to point to NFS
+        volume_def.path = nfs_dest_path.to_string_lossy().to_string();
+        
+        println!("    {}", i18n::tr("successfully-copied-to-nfs", &[("nfs_dest_path", &nfs_dest_path.display())]));
+        
+        Ok(())
+    }
+
+    async fn copy_directory_recursive(&self, src: &Path, dst: &Path) -> Result<()> {
+        if !src.is_dir() {
+            return Err(anyhow::anyhow!(i18n::tr("source-is-not-a-directory", &[("src", &src.display())])));
+        }
+        
+        fs::create_dir_all(dst)?;
+        
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let src_path = entry.path();
+            let dst_path = dst.join(entry.file_name());
+            
+            if file_type.is_dir() {
+                // Use Box::pin for recursive async call
+                Box::pin(self.copy_directory_recursive(&src_path, &dst_path)).await?;
+            } else {
+                fs::copy(&src_path, &dst_path)?;
+            }
+        }
+        
+        Ok(())
+    }
+
+    async fn fix_permissions_recursive(&self, path: &Path) -> Result<()> {
+        println!("    {}", i18n::tr("fixing-permissions-for-docker-compatibility", &[]));
+        
+        // Use chmod command to set appropriate permissions
+        let output = Command::new("chmod")
+            .args(["-R", "755", path.to_str().unwrap()])
+            .output()?;
+        
+        if output.status.success() {
+            println!("    {}", i18n::tr("permissions-directories-set", &[]));
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr);
+            println!("    {}", i18n::tr("permissions-directories-failed", &[("error", &error)]));
+        }
+        
+        // For files, set 644 permissions (readable by all, writable by owner)
+        let output = Command::new("find")
+            .args([path.to_str().unwrap(), "-type", "f", "-exec", "chmod", "644", "{}", ";"])
+            .output()?;
+        
+        if output.status.success() {
+            println!("    {}", i18n::tr("permissions-files-set", &[]));
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr);
+            println!("    {}", i18n::tr("permissions-files-failed", &[("error", &error)]));
+        }
+        
+        // Change ownership to a more Docker-friendly user/group if possible
+        // Try to use the current user or a common Docker user
+        let current_user = std::env::var("SUDO_USER").ok()
+            .or_else(|| std::env::var("USER").ok())
+            .unwrap_or_else(|| "1000".to_string());
+        
+        let output = Command::new("chown")
+            .args(["-R", &format!("{}:{}", current_user, current_user), path.to_str().unwrap()])
+            .output()?;
+        
+        if output.status.success() {
+            println!("    {}", i18n::tr("ownership-changed", &[("current_user", &current_user)]));
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr);
+            println!("    {}", i18n::tr("ownership-change-failed", &[("error", &error)]));
+        }
+        
+        Ok(())
+    }
+
+    /// Add `driver_opts: encrypted` to the overlay networks declared by a stack
+    /// (including the implicit default network) and flag external networks
+    /// that are not encrypted
+    fn enforce_network_encryption(&self, stack_name: &str, compose_content: &str) -> Result<String> {
+        let mut yaml_value: Value = serde_yaml::from_str(compose_content)?;
+        
+        // Services without a networks key are attached to the stack's default network
+        let uses_default_network = yaml_value.get("services")
+            .and_then(|services| services.as_mapping())
+            .is_some_and(|services| services.values().any(|service| service.get("networks").is_none()));
+        
+        let root = yaml_value.as_mapping_mut()
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("compose-not-a-mapping", &[])))?;
+        let networks = root.entry(Value::from("networks"))
+            .or_insert_with(|| Value::Mapping(serde_yaml::Mapping::new()));
+        if networks.is_null() {
+            *networks = Value::Mapping(serde_yaml::Mapping::new());
+        }
+        let networks = networks.as_mapping_mut()
+            .ok_or_else(|| anyhow::anyhow!(i18n::tr("compose-networks-not-a-mapping", &[("stack", &stack_name)])))?;
+        
+        if uses_default_network && !networks.contains_key("default") {
+            networks.insert(Value::from("default"), Value::Mapping(serde_yaml::Mapping::new()));
+        }
+        
+        for (name, network) in networks.iter_mut() {
+            let name = name.as_str().unwrap_or("unknown").to_string();
+            if network.is_null() {
+                *network = Value::Mapping(serde_yaml::Mapping::new());
+            }
+            let Some(network) = network.as_mapping_mut() else {
+                continue;
+            };
+            
+            // External networks are not created by the stack, only their current options can be checked
+            if let Some(external) = network.get("external").filter(|external| external.as_bool() != Some(false)) {
+                let external_name = external.get("name")
+                    .or_else(|| network.get("name"))
+                    .and_then(|n| n.as_str())
+                    .unwrap_or(&name)
+                    .to_string();
+                if !self.is_network_encrypted(&external_name)? {
+                    println!("    {}", i18n::tr("network-encryption-violation", &[("external_name", &external_name), ("stack", &stack_name)]));
+                }
+                continue;
+            }
+            
+            let driver = network.get("driver").and_then(|d| d.as_str()).unwrap_or("overlay");
+            if driver != "overlay" {
+                continue;
+            }
+            
+            let driver_opts = network.entry(Value::from("driver_opts"))
+                .or_insert_with(|| Value::Mapping(serde_yaml::Mapping::new()));
+            if let Some(driver_opts) = driver_opts.as_mapping_mut() {
+                if !driver_opts.contains_key("encrypted") {
+                    driver_opts.insert(Value::from("encrypted"), Value::from(""));
+                    println!("    {}", i18n::tr("enabled-encryption-on-network", &[("name", &name)]));
+                }
+            }
+        }
+        
+        Ok(serde_yaml::to_string(&yaml_value)?)
+    }
+
+    fn is_network_encrypted(&self, network_name: &str) -> Result<bool> {
+        let output = self.docker()
+            .args(["network", "inspect", network_name, "--format", "{{json .Options}}"])
+            .output()?;
+        
+        if !output.status.success() {
+            println!("    {}", i18n::tr("external-network-does-not-exist", &[("network_name", &network_name)]));
+            return Ok(false);
+        }
+        
+        let options: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap_or_default();
+        Ok(options.get("encrypted").is_some())
+    }
+
+    async fn process_compose_volumes(&self, compose_content: &str, volumes_definitions: &[VolumeDefinition], nfs_config: &NfsConfig) -> Result<String> {
+        println!("    {}", i18n::tr("parsing-docker-compose-content", &[]));
+        
+        // Parse the compose content to find volume references
+        let mut yaml_value: serde_yaml::Value = serde_yaml::from_str(compose_content)?;
+        println!("    {}", i18n::tr("successfully-parsed-yaml-content", &[]));
+        
+        // Process services section
+        if let Some(services) = yaml_value.get_mut("services") {
+            println!("    {}", i18n::tr("processing-services", &[("count", &services.as_mapping().map(|m| m.len()).unwrap_or(0))]));
+            
+            if let Some(services_mapping) = services.as_mapping_mut() {
+                for (service_name, service) in services_mapping {
+                    let service_name_str = service_name.as_str().unwrap_or("unknown");
+                    println!("    {}", i18n::tr("processing-service", &[("service_name_str", &service_name_str)]));
+                    
+                    if let Some(volumes) = service.get_mut("volumes") {
+                        println!("    {}", i18n::tr("found-volumes-section-in-service", &[("service_name_str", &service_name_str)]));
+                        self.process_service_volumes(volumes, volumes_definitions, nfs_config).await?;
+                    } else {
+                        println!("    {}", i18n::tr("no-volumes-section-found-in-service", &[("service_name_str", &service_name_str)]));
+                    }
+                }
+            }
+        } else {
+            println!("    {}", i18n::tr("compose-has-no-services", &[]));
+        }
+        
+        // Add volumes section to docker-compose if it doesn't exist
+        self.add_volumes_section(&mut yaml_value, volumes_definitions).await?;
+        
+        // Convert back to string
+        println!("    {}", i18n::tr("converting-modified-yaml-back-to-string", &[]));
+        let modified_content = serde_yaml::to_string(&yaml_value)?;
+        println!("    {}", i18n::tr("compose-serialized", &[("count", &modified_content.len())]));
+        
+        Ok(modified_content)
+    }
+
+    async fn process_service_volumes(&self, volumes: &mut serde_yaml::Value, volumes_definitions: &[VolumeDefinition], nfs_config: &NfsConfig) -> Result<()> {
+        println!("      {}", i18n::tr("processing-service-volumes", &[]));
+        
+        match volumes {
+            serde_yaml::Value::Sequence(seq) => {
+                println!("      {}", i18n::tr("found-volume-entries", &[("count", &seq.len())]));
+                
+                for (index, volume) in seq.iter_mut().enumerate() {
+                    println!("      {}", i18n::tr("processing-volume-entry", &[("index", &index), ("volume", &format!("{:?}", volume))]));
+                    
+                    if let Some(volume_str) = volume.as_str() {
+                        println!("      {}", i18n::tr("volume-string", &[("volume_str", &volume_str)]));
+                        
+                        // Check if this is a volume reference (format: volume_id:container_path)
+                        if volume_str.contains(':') {
+                            let parts: Vec<&str> = volume_str.split(':').collect();
+                            println!("      {}", i18n::tr("split-into-parts", &[("count", &parts.len()), ("parts", &format!("{:?}", parts))]));
+                            
+                            if parts.len() >= 2 && parts.len() <= 3 {
+                                let volume_id = parts[0];
+                                let container_path = parts[1];
+                                let options = if parts.len() == 3 { parts[2] } else { "" };
+                                
+                                println!("      {}", i18n::tr("volume-string-parts", &[("volume_id", &volume_id), ("container_path", &container_path), ("options", &options)]));
+                                
+                                // Find the volume definition
+                                if let Some(volume_def) = volumes_definitions.iter().find(|v| v.id == volume_id) {
+                                    println!("      {}", i18n::tr("found-volume-definition", &[("volume_def", &format!("{:?}", volume_def))]));
+                                    
+                                    match volume_def.r#type {
+                                        VolumeType::Volume => {
+                                            // For Docker volumes, use the path as volume name
+                                            let volume_path = if !options.is_empty() {
+                                                format!("{}:{}:{}", volume_def.path, container_path, options)
+                                            } else {
+                                                format!("{}:{}", volume_def.path, container_path)
+                                            };
+                                            println!("      {}", i18n::tr("replacing-volume", &[("volume_id", &volume_id), ("volume_path", &volume_path)]));
+                                            *volume = serde_yaml::Value::String(volume_path);
+                                        }
+                                        VolumeType::Binding => {
+                                            // For bindings, replace with NFS path
+                                            // Construct the NFS path: nfs_config.path + volume_def.path
+                                            let full_nfs_path = Path::new(&nfs_config.path).join(&volume_def.path);
+                                            println!("      {}", i18n::tr("nfs-config-path", &[("path", &nfs_config.path)]));
+                                            println!("      {}", i18n::tr("volume-path", &[("path", &volume_def.path)]));
+                                            println!("      {}", i18n::tr("full-nfs-path", &[("full_nfs_path", &full_nfs_path.display())]));
+                                            
+                                            // Create the NFS directory if it doesn't exist
+                                            if !full_nfs_path.exists() && self.dry_run {
+                                                println!("  {}", i18n::tr("dry-run-would-create-nfs-directory", &[("full_nfs_path", &full_nfs_path.display())]));
+                                            } else if !full_nfs_path.exists() {
+                                                println!("      {}", i18n::tr("creating-nfs-directory", &[("full_nfs_path", &full_nfs_path.display())]));
+                                                fs::create_dir_all(&full_nfs_path)?;
+                                                println!("      {}", i18n::tr("successfully-created-nfs-directory", &[]));
+                                            } else {
+                                                println!("      {}", i18n::tr("nfs-directory-already-exists", &[("full_nfs_path", &full_nfs_path.display())]));
+                                            }
+                                            
+                                            let nfs_path = if !options.is_empty() {
+                                                format!("{}:{}:{}", full_nfs_path.display(), container_path, options)
+                                            } else {
+                                                format!("{}:{}", full_nfs_path.display(), container_path)
+                                            };
+                                            println!("      {}", i18n::tr("replacing-binding", &[("volume_id", &volume_id), ("nfs_path", &nfs_path)]));
+                                            *volume = serde_yaml::Value::String(nfs_path);
+                                        }
+                                    }
+                                } else {
+                                    println!("      {}", i18n::tr("volume-definition-missing", &[("volume_id", &volume_id)]));
+                                    println!("      {}", i18n::tr("available-volume-definitions", &[("ids", &format!("{:?}", volumes_definitions.iter().map(|v| &v.id).collect::<Vec<_>>()))]));
+                                }
+                            } else {
+                                println!("      {}", i18n::tr("volume-string-invalid-parts", &[]));
+                            }
+                        } else {
+                            println!("      {}", i18n::tr("volume-string-without-colon", &[]));
+                        }
+                    } else {
+                        println!("      {}", i18n::tr("volume-entry-is-not-a-string", &[]));
+                    }
+                }
+            }
+            _ => {
+                println!("      {}", i18n::tr("volume-format-is-not-a-sequence", &[]));
+            }
+        }
+        
+        println!("      {}", i18n::tr("finished-processing-service-volumes", &[]));
+        Ok(())
+    }
+
+    async fn add_volumes_section(&self, yaml_value: &mut serde_yaml::Value, volumes_definitions: &[VolumeDefinition]) -> Result<()> {
+        println!("    {}", i18n::tr("adding-volumes-section", &[]));
+        
+        // Create volumes section if it doesn't exist
+        if yaml_value.get("volumes").is_none() {
+            yaml_value["volumes"] = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+            println!("    {}", i18n::tr("created-new-volumes-section", &[]));
+        }
+        
+        let volumes_section = yaml_value.get_mut("volumes").unwrap();
+        
+        // Add each volume definition to the volumes section
+        for volume_def in volumes_definitions {
+            match volume_def.r#type {
+                VolumeType::Volume => {
+                    println!("    {}", i18n::tr("adding-volume-to-volumes-section", &[("id", &volume_def.id)]));
+                    
+                    // Create volume configuration
+                    let mut volume_config = serde_yaml::Mapping::new();
+                    
+                    // For now, we'll use the default driver
+                    // In the future, we could add driver and driver_opts support
+                    volume_config.insert(
+                        serde_yaml::Value::String("driver".to_string()),
+                        serde_yaml::Value::String("local".to_string())
+                    );
+                    
+                    volumes_section[&volume_def.id] = serde_yaml::Value::Mapping(volume_config);
+                }
+                VolumeType::Binding => {
+                    // Bindings don't need to be in the volumes section
+                    // They are handled directly in the service volumes
+                    println!("    {}", i18n::tr("volumes-section-skipping-binding", &[("id", &volume_def.id)]));
+                }
+            }
+        }
+        
+        println!("    {}", i18n::tr("volumes-section-updated", &[]));
+        Ok(())
+    }
+}
+
+/// Delay after a sync during which the Docker events it caused may still arrive
+const DRIFT_GRACE_NANOS: i64 = 10_000_000_000;
+
+/// Longest wait for the prerequisites probe to finish on every node
+const PROBE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Delay between two checks of the services of a stack waiting for convergence
+const CONVERGENCE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Pulls of one registry running at the same time when its `max_concurrent_pulls` is unset
+const DEFAULT_CONCURRENT_PULLS: usize = 3;
+
+/// Days undelivered notifications stay in the outbox after being given up on
+const NOTIFICATION_RETENTION_DAYS: i64 = 7;
+
+/// Days processed webhook deliveries are kept to recognize their redeliveries
+const WEBHOOK_DELIVERY_RETENTION_DAYS: i64 = 30;
+
+/// Delay between two prunings of the stopped tasks by `daemon`
+const TASK_HISTORY_PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// ANSI colors of the services in `logs`, in turn
+const LOG_COLORS: [u8; 6] = [36, 32, 33, 35, 34, 91];
+
+/// Output of a running `docker events`, stopped once dropped
+struct DockerEvents {
+    _child: tokio::process::Child,
+    lines: tokio::io::Lines<tokio::io::BufReader<tokio::process::ChildStdout>>,
+}
+
+impl DockerEvents {
+    /// Next event, `None` once the stream ends. Never completes without a stream.
+    async fn next(events: &mut Option<Self>) -> Option<String> {
+        match events {
+            Some(events) => events.lines.next_line().await.ok().flatten(),
+            None => std::future::pending().await,
+        }
+    }
+}
+
+fn now_nanos() -> i64 {
+    chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+}
+
+/// Temporary docker CLI configuration (used through `DOCKER_CONFIG`)
+/// holding registry logins, removed once dropped
+struct DockerLoginConfig {
+    dir: std::path::PathBuf,
+}
+
+impl DockerLoginConfig {
+    fn new(logins: &[(&str, &RegistryLogin)]) -> Result<Self> {
+        use base64::Engine;
+        
+        let dir = std::env::temp_dir().join(format!("dockerops-auth-{}-{}", std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()));
+        fs::create_dir_all(&dir)?;
+        let config = Self { dir };
+        
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&config.dir, fs::Permissions::from_mode(0o700))?;
+        }
+        
+        let mut auths = serde_json::Map::new();
+        for (registry, login) in logins {
+            // The docker CLI stores Docker Hub logins under its historical index URL
+            let server = if *registry == "registry-1.docker.io" { "https://index.docker.io/v1/" } else { registry };
+            let auth = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", login.username, login.password));
+            auths.insert(server.to_string(), serde_json::json!({ "auth": auth }));
+        }
+        let content = serde_json::json!({ "auths": auths });
+        atomic::write(config.dir.join("config.json"), content.to_string())?;
+        
+        Ok(config)
+    }
+}
+
+impl Drop for DockerLoginConfig {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// What the canary analysis saw of one service
+#[derive(Debug, Default)]
+struct CanarySample {
+    failed_tasks: usize,
+    error_lines: usize,
+    /// First error lines, quoted in the summary
+    examples: Vec<String>,
+}
+
+/// Replica state of one Swarm service as reported by `docker stack services`
+#[derive(Debug, Clone)]
+struct ServiceState {
+    name: String,
+    mode: String,
+    running: u32,
+    desired: u32,
+}
+
+impl ServiceState {
+    /// Parse a `name<TAB>mode<TAB>running/desired` line; replicas may carry a
+    /// suffix such as "(max 1 per node)"
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let name = fields.next()?.trim().to_string();
+        let mode = fields.next()?.trim().to_string();
+        let replicas = fields.next()?.split_whitespace().next()?;
+        let (running, desired) = replicas.split_once('/')?;
+        Some(Self {
+            name,
+            mode,
+            running: running.parse().ok()?,
+            desired: desired.parse().ok()?,
+        })
+    }
+}
+
+/// Live state of a service shown by `status`
+struct ServiceStatus {
+    name: String,
+    mode: String,
+    running: u32,
+    desired: u32,
+    image: String,
+    /// `UpdateStatus.State` of the service, empty when it was never updated
+    update_state: String,
+    /// Error of a task that should be running and isn't
+    error: Option<String>,
+}
+
+impl ServiceStatus {
+    /// Convergence of the service towards its spec
+    fn state(&self) -> String {
+        match self.update_state.as_str() {
+            "updating" => "updating".to_string(),
+            "rollback_started" => "rolling back".to_string(),
+            "paused" => "update paused".to_string(),
+            "rollback_paused" => "rollback paused".to_string(),
+            _ if self.running < self.desired && self.error.is_some() => "failing".to_string(),
+            _ if self.running < self.desired => "converging".to_string(),
+            "rollback_completed" => "rolled back".to_string(),
+            _ => "converged".to_string(),
+        }
+    }
+}
+
+/// Image of a service, its digest shortened when it is pinned
+fn short_image(image: &str) -> String {
+    match image.split_once('@') {
+        Some((name, digest)) => format!("{}@{}", name, short_digest(digest)),
+        None => image.to_string(),
+    }
+}
+
+/// A stack has drifted when a baseline service is missing, scaled differently or not fully running
+fn stack_has_drifted(baseline: &[ServiceState], current: &[ServiceState]) -> bool {
+    baseline.iter().any(|expected| {
+        match current.iter().find(|s| s.name == expected.name) {
+            Some(actual) => actual.desired != expected.desired || actual.running < expected.desired,
+            None => true,
+        }
+    })
+}
+
+/// Reduce a repository URL to `host/owner/repo` so that https, ssh and
+/// scp-like forms of the same remote compare equal
+fn normalize_repository_url(url: &str) -> String {
+    let url = url.trim().to_lowercase();
+    let url = url.split_once("://").map(|(_, rest)| rest.to_string()).unwrap_or(url);
+    // Drop credentials and turn the scp-like `git@host:owner/repo` into `host/owner/repo`
+    let url = url.rsplit_once('@').map(|(_, rest)| rest.to_string()).unwrap_or(url);
+    let url = match url.split_once(':') {
+        Some((host, path)) if !path.starts_with(|c: char| c.is_ascii_digit()) => format!("{}/{}", host, path),
+        _ => url,
+    };
+    url.trim_end_matches('/').trim_end_matches(".git").to_string()
+}
+
+/// Delete the copy of the directory of a stack leaving the trash
+fn remove_snapshot_dir(trashed: &TrashedStack) {
+    if let Some(dir) = trashed.snapshot_dir.as_deref().filter(|dir| Path::new(dir).is_dir()) {
+        if let Err(e) = fs::remove_dir_all(dir) {
+            println!("{}", i18n::tr("trash-copy-not-removed", &[("stack", &trashed.name), ("error", &e)]));
+        }
+    }
+}
+
+/// Recursively copy a directory tree, symlinks included and the entries
+/// rejected by `keep` left out with their contents, returning the special
+/// files (sockets, FIFOs, devices) that were not copied
+fn copy_tree(src: &Path, dst: &Path, keep: impl FnMut(&walkdir::DirEntry) -> bool) -> Result<Vec<PathBuf>> {
+    let mut skipped = Vec::new();
+    for entry in walkdir::WalkDir::new(src).into_iter().filter_entry(keep) {
+        let entry = entry?;
+        let target = dst.join(entry.path().strip_prefix(src)?);
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+        } else {
+            // Sockets, FIFOs and devices: reading a FIFO would block
+            skipped.push(entry.path().to_path_buf());
+        }
+    }
+    Ok(skipped)
+}
+
+/// Append serialized YAML list items to a file, creating it if needed
+fn append_yaml_list(path: &Path, items: &str) -> Result<()> {
+    let mut content = if path.exists() { fs::read_to_string(path)? } else { String::new() };
+    // An empty list serialized as `[]` cannot be appended to
+    if content.trim() == "[]" {
+        content.clear();
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(items);
+    atomic::write(path, content)?;
+    Ok(())
+}
+
+/// Match a name against a glob where `*` is any run of characters and `?` a single one
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and of the name character it currently absorbs
+    let mut backtrack: Option<(usize, usize)> = None;
+    
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, absorbed)) => {
+                    p = star + 1;
+                    n = absorbed + 1;
+                    backtrack = Some((star, absorbed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Parse a `--tag` pattern as a semver requirement (`v1.*`, `~1.4`, `>=2.0, <3`).
+/// A leading `v` is ignored, as it is on tag names.
+fn parse_tag_pattern(pattern: &str) -> Result<semver::VersionReq> {
+    semver::VersionReq::parse(pattern.strip_prefix('v').unwrap_or(pattern))
+        .map_err(|e| anyhow::anyhow!(i18n::tr("invalid-tag-pattern", &[("pattern", &pattern), ("error", &e)])))
+}
+
+fn parse_tag_version(tag: &str) -> Option<semver::Version> {
+    semver::Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+}
+
+/// Check out the highest semver tag of a clone matching the pattern
+fn checkout_newest_tag(repo_path: &str, pattern: &str, sparse: Option<&str>) -> Result<(semver::Version, String)> {
+    let requirement = parse_tag_pattern(pattern)?;
+    let repo = git2::Repository::open(repo_path)?;
+    let tag_names = repo.tag_names(None)?;
+    
+    let (version, tag) = tag_names.iter()
+        .flatten()
+        .filter_map(|tag| parse_tag_version(tag).map(|version| (version, tag.to_string())))
+        .filter(|(version, _)| requirement.matches(version))
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .ok_or_else(|| anyhow::anyhow!(i18n::tr("no-tag-matches", &[("pattern", &pattern)])))?;
+    
+    println!("{}", i18n::tr("newest-tag-matching", &[("pattern", &pattern), ("tag", &tag)]));
+    checkout_revision(repo_path, &format!("refs/tags/{}", tag), sparse)?;
+    Ok((version, tag))
+}
+
+/// Check out a tag, branch or (possibly abbreviated) commit SHA in a clone,
+/// returning the full SHA of the checked out commit
+fn checkout_revision(repo_path: &str, revision: &str, sparse: Option<&str>) -> Result<String> {
+    let repo = git2::Repository::open(repo_path)?;
+    let commit = repo.revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| anyhow::anyhow!(i18n::tr("revision-not-found", &[("revision", &revision), ("error", &e)])))?;
+    
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout_options(&repo, &commit, sparse)?))?;
+    repo.set_head_detached(commit.id())?;
+    println!("{}", i18n::tr("checked-out-commit", &[("id", &commit.id())]));
+    Ok(commit.id().to_string())
+}
+
+/// Forced checkout, limited with `sparse_checkout` to the root files, the
+/// files next to stacks.yaml when it lives in the `sparse` subdirectory and
+/// the directories of the stacks it declares
+fn checkout_options(repo: &git2::Repository, commit: &git2::Commit, sparse: Option<&str>) -> Result<git2::build::CheckoutBuilder<'static>> {
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.force();
+    let Some(prefix) = sparse else {
+        return Ok(checkout);
+    };
+    let in_prefix = |name: &str| if prefix.is_empty() { name.to_string() } else { format!("{}/{}", prefix, name) };
+    
+    let root = commit.tree()?;
+    let stacks_tree = match prefix {
+        "" => root.clone(),
+        prefix => root.get_path(Path::new(prefix))
+            .and_then(|entry| entry.to_object(repo))
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|_| anyhow::anyhow!(i18n::tr("directory-not-found-in-commit", &[("prefix", &prefix), ("id", &commit.id())])))?,
+    };
+    
+    let is_file = |entry: &git2::TreeEntry| entry.kind() == Some(git2::ObjectType::Blob);
+    for name in root.iter().filter(is_file).filter_map(|entry| entry.name().map(|name| name.to_string())) {
+        checkout.path(name);
+    }
+    for name in stacks_tree.iter().filter(is_file).filter_map(|entry| entry.name().map(|name| name.to_string())) {
+        checkout.path(in_prefix(&name));
+    }
+    if let Some(entry) = stacks_tree.get_name("stacks.yaml") {
+        let stacks: Vec<StackDefinition> = serde_yaml::from_slice(repo.find_blob(entry.id())?.content())
+            .map_err(|e| anyhow::anyhow!(i18n::tr("invalid-stacks-yaml-in-commit", &[("id", &commit.id()), ("error", &e)])))?;
+        for stack in stacks {
+            checkout.path(format!("{}/", in_prefix(&stack.name)));
+        }
+    }
+    
+    Ok(checkout)
+}
+
+/// `registry/repository:tag` of an image, the same for all the ways of
+/// writing it (`nginx`, `docker.io/library/nginx:latest`, `nginx@sha256:...`)
+fn image_key(image: &str) -> String {
+    let reference = ImageReference::parse(image);
+    format!("{}/{}:{}", reference.registry, reference.repository, reference.tag)
+}
+
+/// Who, outside DockerOps, keeps the image from being removed
+fn image_kept_for(image_name: &str, users: &Option<BTreeMap<String, Vec<String>>>) -> Option<String> {
+    match users {
+        Some(users) => users.get(&image_key(image_name)).map(|users| users.join(", ")),
+        None => Some("workloads Docker could not list".to_string()),
+    }
+}
+
+/// `ref` fields of the stacks.yaml in `stacks_path`, none when it can't be read
+fn declared_refs(stacks_path: &Path) -> Vec<String> {
+    fs::read_to_string(stacks_path.join("stacks.yaml")).ok()
+        .and_then(|content| serde_yaml::from_str::<Vec<StackDefinition>>(&content).ok())
+        .map(|stacks| stacks.into_iter().filter_map(|stack| stack.git_ref).collect())
+        .unwrap_or_default()
+}
+
+/// Commit of a branch of origin, a tag, a local branch or any revision
+fn resolve_ref<'r>(repo: &'r git2::Repository, git_ref: &str) -> Result<git2::Commit<'r>, git2::Error> {
+    let candidates = [
+        format!("refs/remotes/origin/{}", git_ref),
+        format!("refs/tags/{}", git_ref),
+        format!("refs/heads/{}", git_ref),
+    ];
+    for candidate in &candidates {
+        if let Ok(reference) = repo.find_reference(candidate) {
+            return reference.peel_to_commit();
+        }
+    }
+    repo.revparse_single(git_ref)?.peel_to_commit()
+}
+
+/// Validate a `--path` subdirectory, `None` standing for the repository root
+fn normalize_subpath(path: Option<&str>) -> Result<Option<String>> {
+    let Some(path) = path.map(|path| path.trim().trim_matches('/')).filter(|path| !path.is_empty() && *path != ".") else {
+        return Ok(None);
+    };
+    if Path::new(path).components().any(|component| !matches!(component, std::path::Component::Normal(_))) {
+        return Err(anyhow::anyhow!(i18n::tr("path-outside-repository", &[("path", &path)])));
+    }
+    Ok(Some(path.to_string()))
+}
+
+/// Directory of the working copy holding stacks.yaml
+fn stacks_root(repo_path: &str, path: Option<&str>) -> Result<String> {
+    let Some(path) = path else {
+        return Ok(repo_path.to_string());
+    };
+    let root = Path::new(repo_path).join(path);
+    if !root.is_dir() {
+        return Err(anyhow::anyhow!(i18n::tr("directory-not-found-in-repository", &[("path", &path)])));
+    }
+    Ok(root.to_string_lossy().to_string())
+}
+
+/// Patch of a stack directory between a commit and HEAD, cut to a readable length
+fn stack_diff(repo_path: &str, from: &str, stack_dir: &str) -> Result<String> {
+    const MAX_LINES: usize = 150;
+    
+    let repo = git2::Repository::open(repo_path)?;
+    let old_tree = repo.revparse_single(from)?.peel_to_tree()?;
+    let new_tree = repo.head()?.peel_to_tree()?;
+    let mut options = git2::DiffOptions::new();
+    options.pathspec(format!("{}/", stack_dir));
+    let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut options))?;
+    
+    let mut lines = Vec::new();
+    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+        let prefix = match line.origin() {
+            origin @ ('+' | '-' | ' ') => origin.to_string(),
+            _ => String::new(),
+        };
+        lines.push(format!("{}{}", prefix, String::from_utf8_lossy(line.content()).trim_end_matches('\n')));
+        true
+    })?;
+    
+    if lines.len() > MAX_LINES {
+        let omitted = lines.len() - MAX_LINES;
+        lines.truncate(MAX_LINES);
+        lines.push(format!("... {} more lines", omitted));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Last `count` lines of a message
+fn print_effective_settings(settings: &[EffectiveSetting]) {
+    let width = settings.iter().map(|setting| setting.key.len()).max().unwrap_or_default();
+    for setting in settings {
+        println!("  {:width$}  {}  ({})", setting.key, setting.value, setting.source, width = width);
+    }
+}
+
+fn last_lines(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+/// Full SHA of the commit checked out in a working copy, `None` for the
+/// copy of a local repository's working tree
+fn head_commit(repo_path: &str) -> Result<Option<String>> {
+    if !Path::new(repo_path).join(".git").exists() {
+        return Ok(None);
+    }
+    let repo = git2::Repository::open(repo_path)?;
+    let commit = repo.head()?.peel_to_commit()?;
+    Ok(Some(commit.id().to_string()))
+}
+
+/// Commit the directory of a stack was checked out from, its `ref` or the
+/// watched revision
+fn stack_commit(repo_path: &str, definition: &StackDefinition) -> Option<String> {
+    let repo = git2::Repository::discover(repo_path).ok()?;
+    let commit = match &definition.git_ref {
+        Some(git_ref) => resolve_ref(&repo, git_ref).ok()?,
+        None => repo.head().ok()?.peel_to_commit().ok()?,
+    };
+    Some(commit.id().to_string())
+}
+
+fn short_digest(digest: &str) -> &str {
+    let hex = digest.strip_prefix("sha256:").unwrap_or(digest);
+    &hex[..hex.len().min(12)]
+}
+
+/// Render freshness rows in the Prometheus text exposition format
+fn freshness_metrics(rows: &[ImageFreshness]) -> String {
+    let now = chrono::Utc::now();
+    let mut stale = String::new();
+    let mut stale_seconds = String::new();
+    let mut newer_tags = String::new();
+    
+    for row in rows {
+        let labels = format!("stack=\"{}\",repository=\"{}\",service=\"{}\",image=\"{}\"",
+            label_value(&row.stack_name), label_value(&row.repository_url), label_value(&row.service), label_value(&row.image));
+        let seconds = row.stale_since.as_deref()
+            .and_then(|since| chrono::DateTime::parse_from_rfc3339(since).ok())
+            .map(|since| (now - since.with_timezone(&chrono::Utc)).num_seconds().max(0))
+            .unwrap_or(0);
+        
+        stale.push_str(&format!("dockerops_image_digest_stale{{{}}} {}\n", labels, row.is_digest_stale() as u8));
+        stale_seconds.push_str(&format!("dockerops_image_stale_seconds{{{}}} {}\n", labels, seconds));
+        newer_tags.push_str(&format!("dockerops_image_newer_tags{{{}}} {}\n", labels, row.newer_tags));
+    }
+    
+    format!(
+        "# HELP dockerops_image_digest_stale Whether the tag was republished with another digest since deployment\n\
+         # TYPE dockerops_image_digest_stale gauge\n{}\
+         # HELP dockerops_image_stale_seconds Time since the deployed digest was first found outdated\n\
+         # TYPE dockerops_image_stale_seconds gauge\n{}\
+         # HELP dockerops_image_newer_tags Number of newer version tags published in the registry\n\
+         # TYPE dockerops_image_newer_tags gauge\n{}",
+        stale, stale_seconds, newer_tags)
+}
+
+/// Escape a Prometheus label value (backslash, double quote and line feed)
+fn label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Contexts known to the docker CLI with their endpoint
+fn docker_contexts() -> Result<Vec<(String, String)>> {
+    let output = Command::new("docker")
+        .args(["context", "ls", "--format", "{{.Name}}\t{{.DockerEndpoint}}"])
+        .output()
+        .map_err(|e| anyhow::anyhow!(i18n::tr("docker-could-not-run", &[("error", &e)])))?;
+    
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(i18n::tr("failed-to-list-docker-contexts", &[("error", &String::from_utf8_lossy(&output.stderr))])));
+    }
+    
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(name, endpoint)| (name.trim().trim_end_matches(" *").to_string(), endpoint.trim().to_string()))
+        .collect())
+}
+
+/// Read a flat `KEY: value` values.yaml, missing files yield no values
+fn read_values_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    
+    let content = fs::read_to_string(path)?;
+    let mapping: BTreeMap<String, Value> = serde_yaml::from_str::<Option<BTreeMap<String, Value>>>(&content)
+        .map_err(|e| anyhow::anyhow!(i18n::tr("invalid-values-file", &[("path", &path.display()), ("error", &e)])))?
+        .unwrap_or_default();
+    
+    mapping.into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(value) => value,
+                Value::Number(value) => value.to_string(),
+                Value::Bool(value) => value.to_string(),
+                _ => return Err(anyhow::anyhow!(i18n::tr("invalid-value-type", &[("key", &key), ("path", &path.display())]))),
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// Keys of a `.env.example` file (`KEY=example` lines, `export` allowed)
+fn read_env_example_keys(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)?;
+    Ok(content.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.strip_prefix("export ").unwrap_or(line))
+        .map(|line| line.split('=').next().unwrap_or_default().trim().to_string())
+        .filter(|key| !key.is_empty())
+        .collect())
+}
+
+/// Substitute `${KEY}`, `${KEY:-default}` and `${KEY-default}` for the keys
+/// defined in `values`, leaving every other variable (secrets, host
+/// environment) to `docker stack deploy`
+fn render_values(content: &str, values: &BTreeMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(content.len());
+    let mut rest = content;
+    
+    while let Some(start) = rest.find("${") {
+        // `$${` is an escaped dollar sign in compose files
+        if rest[..start].ends_with('$') {
+            rendered.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        }
+        
+        rendered.push_str(&rest[..start]);
+        let expression = &rest[start + 2..];
+        let Some(end) = expression.find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        
+        let key = expression[..end].split([':', '-', '?']).next().unwrap_or_default();
+        match values.get(key) {
+            Some(value) => rendered.push_str(value),
+            None => rendered.push_str(&rest[start..start + 2 + end + 1]),
+        }
+        rest = &expression[end + 1..];
+    }
+    
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Hash of the files the bindings of volumes.yaml copy to the NFS share,
+/// which the compose files only know by path, None when one of them can't
+/// be read
+fn bindings_fingerprint(repo_path: &str) -> Option<String> {
+    let Ok(content) = fs::read_to_string(Path::new(repo_path).join("volumes.yaml")) else {
+        return Some(String::new());
+    };
+    let definitions: Vec<VolumeDefinition> = serde_yaml::from_str(&content).ok()?;
+    let mut context = md5::Context::new();
+    for definition in definitions.iter().filter(|definition| matches!(definition.r#type, VolumeType::Binding)) {
+        let entries = walkdir::WalkDir::new(Path::new(repo_path).join(&definition.path)).sort_by_file_name();
+        for entry in entries.into_iter().filter_map(|entry| entry.ok()).filter(|entry| entry.file_type().is_file()) {
+            context.consume(entry.path().strip_prefix(repo_path).unwrap_or(entry.path()).to_string_lossy().as_bytes());
+            context.consume(fs::read(entry.path()).ok()?);
+        }
+    }
+    Some(format!("{:x}", context.compute()))
+}
+
+fn find_compose_file(stack_dir: &Path) -> Option<PathBuf> {
+    ["docker-compose.yml", "docker-compose.yaml", "compose.yml", "compose.yaml"]
+        .iter()
+        .map(|name| stack_dir.join(name))
+        .find(|path| path.exists())
+}
+
+/// Stack and output named by a `{{ output "stack" "name" }}` expression,
+/// given the text following `{{`, with the length up to the closing `}}`
+fn parse_output_reference(expression: &str) -> Option<(String, String, usize)> {
+    let end = expression.find("}}")?;
+    let arguments = expression[..end].trim().strip_prefix("output")?;
+    
+    // ` "db" "network"` splits into [" ", "db", " ", "network", ""]
+    let parts: Vec<&str> = arguments.split('"').collect();
+    match parts.as_slice() {
+        [before, stack, between, output, after]
+            if before.starts_with(char::is_whitespace) && before.trim().is_empty()
+                && !between.is_empty() && between.trim().is_empty()
+                && after.trim().is_empty()
+                && !stack.is_empty() && !output.is_empty() => {
+            Some((stack.to_string(), output.to_string(), end + 2))
+        }
+        _ => None,
+    }
+}
+
+/// Outputs referenced by a compose file, other `{{ }}` expressions (swarm
+/// service templates such as `{{.Node.Hostname}}`) are ignored
+fn output_references(content: &str) -> Vec<(String, String)> {
+    let mut references = Vec::new();
+    let mut rest = content;
+    
+    while let Some(start) = rest.find("{{") {
+        rest = &rest[start + 2..];
+        if let Some((stack, output, length)) = parse_output_reference(rest) {
+            references.push((stack, output));
+            rest = &rest[length..];
+        }
+    }
+    
+    references
+}
+
+/// Substitute the `{{ output "stack" "name" }}` expressions found in `outputs`
+fn render_outputs(content: &str, outputs: &BTreeMap<(String, String), String>) -> String {
+    let mut rendered = String::with_capacity(content.len());
+    let mut rest = content;
+    
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let expression = &rest[start + 2..];
+        match parse_output_reference(expression).and_then(|(stack, output, length)| outputs.get(&(stack, output)).map(|value| (value, length))) {
+            Some((value, length)) => {
+                rendered.push_str(value);
+                rest = &expression[length..];
+            }
+            None => {
+                rendered.push_str("{{");
+                rest = expression;
+            }
+        }
+    }
+    
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Stacks each stack has to be deployed after: the ones of its `depends_on`
+/// and the stacks of the repository whose outputs its compose file references
+fn stack_dependencies<'a>(repo_path: &Path, definitions: &'a [StackDefinition]) -> Result<BTreeMap<&'a str, Vec<String>>> {
+    let mut dependencies: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for definition in definitions {
+        if let Some(dependency) = definition.depends_on.iter().find(|stack| !definitions.iter().any(|d| &&d.name == stack)) {
+            return Err(anyhow::anyhow!(i18n::tr("dependency-not-declared", &[("stack", &definition.name), ("dependency", &dependency)])));
+        }
+        if definition.depends_on.contains(&definition.name) {
+            return Err(anyhow::anyhow!(i18n::tr("stack-depends-on-itself", &[("stack", &definition.name)])));
+        }
+        let compose_content = match find_compose_file(&repo_path.join(definition.directory())) {
+            Some(path) => fs::read_to_string(path)?,
+            None => String::new(),
+        };
+        let mut referenced: Vec<String> = output_references(&compose_content).into_iter()
+            .map(|(stack, _)| stack)
+            .filter(|stack| stack != &definition.name && definitions.iter().any(|d| &d.name == stack))
+            .collect();
+        referenced.extend(definition.depends_on.iter().cloned());
+        dependencies.insert(&definition.name, referenced);
+    }
+    Ok(dependencies)
+}
+
+/// Stack definitions ordered so that every stack comes after its
+/// `dependencies`, then by decreasing priority, keeping the stacks.yaml
+/// order otherwise
+fn deployment_order<'a>(definitions: &'a [StackDefinition], dependencies: &BTreeMap<&str, Vec<String>>, last: Option<&str>) -> Result<Vec<&'a StackDefinition>> {
+    let mut ordered: Vec<&StackDefinition> = Vec::with_capacity(definitions.len());
+    while ordered.len() < definitions.len() {
+        let is_ready = |definition: &&StackDefinition| {
+            !ordered.iter().any(|o| o.name == definition.name)
+                && dependencies[definition.name.as_str()].iter().all(|stack| ordered.iter().any(|o| &o.name == stack))
+        };
+        // min_by_key keeps the first of equal priorities, in stacks.yaml order
+        let next = definitions.iter().filter(is_ready).filter(|definition| Some(definition.name.as_str()) != last)
+            .min_by_key(|definition| std::cmp::Reverse(definition.priority.unwrap_or(0)))
+            .or_else(|| definitions.iter().find(is_ready));
+        match next {
+            Some(definition) => ordered.push(definition),
+            None => {
+                let remaining: Vec<&str> = definitions.iter()
+                    .filter(|definition| !ordered.iter().any(|o| o.name == definition.name))
+                    .map(|definition| definition.name.as_str())
+                    .collect();
+                return Err(anyhow::anyhow!(i18n::tr("dependency-cycle", &[("list", &remaining.join(", "))])));
+            }
+        }
+    }
+    
+    Ok(ordered)
+}
+
+/// Fields DockerOps compares with the running services, by compose service
+/// name: `replicas` (replicated services only) and `image` without digest
+fn compose_fields(content: &str) -> Result<BTreeMap<(String, String), String>> {
+    let compose: Value = serde_yaml::from_str(content)?;
+    let mut fields = BTreeMap::new();
+    
+    let Some(services) = compose["services"].as_mapping() else {
+        return Ok(fields);
+    };
+    for (name, service) in services {
+        let Some(name) = name.as_str() else {
+            continue;
+        };
+        if service["deploy"]["mode"].as_str() != Some("global") {
+            let replicas = match &service["deploy"]["replicas"] {
+                Value::Number(replicas) => replicas.to_string(),
+                Value::String(replicas) => replicas.clone(),
+                _ => "1".to_string(),
+            };
+            fields.insert((name.to_string(), "replicas".to_string()), replicas);
+        }
+        if let Some(image) = service["image"].as_str() {
+            fields.insert((name.to_string(), "image".to_string()), image.split('@').next().unwrap_or_default().to_string());
+        }
+    }
+    
+    Ok(fields)
+}
+
+/// Name of an image on a mirror of its registry: same repository, tag and digest
+fn mirror_reference(reference: &ImageReference, mirror: &str) -> String {
+    let version = match &reference.digest {
+        Some(digest) => format!("@{}", digest),
+        None => format!(":{}", reference.tag),
+    };
+    format!("{}/{}{}", mirror.trim_end_matches('/'), reference.repository, version)
+}
+
+/// What the garbage collection removed, or could remove, of one kind of resource
+struct Reclaimed {
+    resource: &'static str,
+    /// Unknown for the build cache
+    removed: Option<usize>,
+    bytes: u64,
+}
+
+fn print_gc_report(report: &[Reclaimed], dry_run: bool) {
+    let header = if dry_run { ["RESOURCE", "REMOVABLE", "RECLAIMABLE"] } else { ["RESOURCE", "REMOVED", "RECLAIMED"] };
+    let rows: Vec<[String; 3]> = report.iter()
+        .map(|reclaimed| [reclaimed.resource.to_string(),
+            reclaimed.removed.map(|removed| removed.to_string()).unwrap_or_else(|| "-".to_string()),
+            format_size(reclaimed.bytes)])
+        .collect();
+    print_table(header, &rows);
+    let total = format_size(report.iter().map(|reclaimed| reclaimed.bytes).sum());
+    match dry_run {
+        true => println!("{}", i18n::tr("gc-reclaimable", &[("total", &total)])),
+        false => println!("{}", i18n::tr("gc-reclaimed", &[("total", &total)])),
+    }
+}
+
+/// `resource<TAB>removed<TAB>bytes` lines stored in `gc_runs.report`
+fn gc_report_lines(report: &[Reclaimed]) -> String {
+    report.iter()
+        .map(|reclaimed| format!("{}\t{}\t{}", reclaimed.resource,
+            reclaimed.removed.map(|removed| removed.to_string()).unwrap_or_default(), reclaimed.bytes))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// One-line summary of a stored report: "dangling images: 3 (1.2 GB), build cache (3.4 GB)"
+fn gc_report_summary(report: &str) -> String {
+    report.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let (resource, removed, bytes) = (fields.next()?, fields.next()?, fields.next()?.parse::<u64>().ok()?);
+            Some(match removed {
+                "" => format!("{} ({})", resource, format_size(bytes)),
+                removed => format!("{}: {} ({})", resource, removed, format_size(bytes)),
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Bytes in decimal multiples as the docker CLI prints them
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", size, UNITS[unit]),
+    }
+}
+
+/// Size printed by the docker CLI ("0B", "12.3kB", "1.2GB (virtual 187MB)"), 0 when unreadable
+fn docker_size(text: &str) -> u64 {
+    let size = text.split_whitespace().next().unwrap_or_default();
+    let (number, unit) = size.split_at(size.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(size.len()));
+    let multiplier = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return 0,
+    };
+    number.parse::<f64>().map(|number| (number * multiplier) as u64).unwrap_or(0)
+}
+
+/// Space freed according to a `docker ... prune` output ("Total reclaimed space: 1.2GB", "Total: 1.2GB")
+fn reclaimed_space(output: &str) -> u64 {
+    output.lines().rev()
+        .filter_map(|line| line.trim().strip_prefix("Total"))
+        .find_map(|total| total.split_once(':'))
+        .map(|(_, size)| docker_size(size.trim()))
+        .unwrap_or(0)
+}
+
+/// Creation time printed by the docker CLI ("2024-05-02 14:30:00 +0200 CEST")
+fn docker_time(text: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let fields: Vec<&str> = text.split_whitespace().take(3).collect();
+    chrono::DateTime::parse_from_str(&fields.join(" "), "%Y-%m-%d %H:%M:%S %z").ok()
+        .map(|time| time.with_timezone(&chrono::Utc))
+}
+
+/// Output of a hook, indented under its log line
+fn print_hook_output(output: &str) {
+    for line in output.trim_end().lines() {
+        println!("      | {}", line);
+    }
+}
+
+/// Print rows under a header, in columns as wide as their longest cell
+fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let header = header.map(str::to_string);
+    let widths: Vec<usize> = (0..N)
+        .map(|column| rows.iter().chain([&header]).map(|row| row[column].chars().count()).max().unwrap_or(0))
+        .collect();
+    for row in [&header].into_iter().chain(rows) {
+        let line: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+        println!("  {}", line.join("  ").trim_end());
+    }
+}
+
+/// Image or replica count of a service to change with `docker service update`
+#[derive(Debug)]
+struct ServiceChange {
+    service: String,
+    image: Option<String>,
+    replicas: Option<String>,
+}
+
+/// Changes between two compose files of a stack when they are limited to
+/// the images and replica counts of existing services, `None` when anything
+/// else changed and the stack has to be deployed again
+fn selective_changes(previous: &str, next: &str) -> Option<Vec<ServiceChange>> {
+    let (Ok(mut previous), Ok(mut next)) = (serde_yaml::from_str::<Value>(previous), serde_yaml::from_str::<Value>(next)) else {
+        return None;
+    };
+    let previous_fields = updatable_fields(&mut previous)?;
+    let next_fields = updatable_fields(&mut next)?;
+    // Everything but the images, the replicas and DockerOps' own labels must be identical
+    if previous != next {
+        return None;
+    }
+    
+    Some(next_fields.into_iter()
+        .filter_map(|(service, (image, replicas))| {
+            let (previous_image, previous_replicas) = previous_fields.get(&service)?.clone();
+            let change = ServiceChange {
+                service,
+                image: image.filter(|image| Some(image) != previous_image.as_ref()),
+                replicas: replicas.filter(|replicas| Some(replicas) != previous_replicas.as_ref()),
+            };
+            (change.image.is_some() || change.replicas.is_some()).then_some(change)
+        })
+        .collect())
+}
+
+/// Image and replica count of a service, as written in its compose file
+type UpdatableFields = (Option<String>, Option<String>);
+
+/// Remove the image, replicas and `com.dockerops.*` labels of every service,
+/// returning the images and replicas
+fn updatable_fields(compose: &mut Value) -> Option<BTreeMap<String, UpdatableFields>> {
+    let services = compose.get_mut("services")?.as_mapping_mut()?;
+    let mut fields = BTreeMap::new();
+    for (name, service) in services.iter_mut() {
+        let service = service.as_mapping_mut()?;
+        let image = service.remove("image").and_then(|image| image.as_str().map(str::to_string));
+        let mut replicas = None;
+        if let Some(deploy) = service.get_mut("deploy").and_then(|deploy| deploy.as_mapping_mut()) {
+            replicas = deploy.remove("replicas").map(|replicas| match replicas {
+                Value::Number(number) => number.to_string(),
+                other => other.as_str().unwrap_or_default().to_string(),
+            });
+            if let Some(labels) = deploy.get_mut("labels").and_then(|labels| labels.as_mapping_mut()) {
+                labels.retain(|key, _| !key.as_str().is_some_and(|key| key.starts_with("com.dockerops.")));
+            }
+        }
+        fields.insert(name.as_str()?.to_string(), (image, replicas));
+    }
+    Some(fields)
+}
+
+/// Images are compared as references, so `nginx` and `docker.io/library/nginx:latest` are equal
+fn same_field_value(field: &str, a: &str, b: &str) -> bool {
+    if field == "image" {
+        return ImageReference::parse(a) == ImageReference::parse(b);
+    }
+    a == b
+}
+
+/// Read an answer from the terminal, `default` being used for an empty line
+fn ask(question: &str, default: Option<&str>) -> Result<String> {
+    match default.filter(|default| !default.is_empty()) {
+        Some(default) => print!("{} [{}]: ", question, default),
+        None => print!("{}: ", question),
+    }
+    std::io::Write::flush(&mut std::io::stdout())?;
+    let mut line = String::new();
+    if std::io::stdin().read_line(&mut line)? == 0 {
+        return Err(anyhow::anyhow!(i18n::tr("setup-aborted", &[])));
+    }
+    let answer = line.trim();
+    Ok(if answer.is_empty() { default.unwrap_or_default().to_string() } else { answer.to_string() })
+}
+
+fn ask_yes_no(question: &str, default: bool) -> Result<bool> {
+    loop {
+        let answer = ask(&format!("{} (y/n)", question), Some(if default { "y" } else { "n" }))?;
+        match answer.to_lowercase().as_str() {
+            "y" | "yes" | "o" | "oui" => return Ok(true),
+            "n" | "no" | "non" => return Ok(false),
+            _ => println!("  {}", i18n::tr("setup-answer-y-or-n", &[])),
+        }
+    }
+}
+
+/// Pin the images of a compose file to the digests of an exported stack
+fn pin_images(content: &str, images: &[PinnedImage]) -> Result<String> {
+    if images.is_empty() {
+        return Ok(content.to_string());
+    }
+    let mut compose: Value = serde_yaml::from_str(content)?;
+    for pinned in images {
+        let Some(service) = compose["services"].get_mut(pinned.service.as_str()) else {
+            continue;
+        };
+        let declared = service["image"].as_str().unwrap_or_default();
+        if same_field_value("image", declared.split('@').next().unwrap_or_default(), &pinned.image) {
+            service["image"] = Value::from(format!("{}@{}", pinned.image, pinned.digest));
+        }
+    }
+    Ok(serde_yaml::to_string(&compose)?)
+}
+
+/// Where `mirror` keeps the standby copy of the database
+enum MirrorTarget {
+    /// Database file, on a local disk or a share mounted by the standby node
+    File(PathBuf),
+    /// Database file on the standby host itself, copied with `scp`
+    Ssh { destination: String, port: Option<u16>, path: String },
+}
+
+impl MirrorTarget {
+    /// A file path or `sqlite:` URL, or `ssh://[user@]host[:port]/path`
+    fn parse(target: &str) -> Result<Self> {
+        if let Some(path) = target.strip_prefix("sqlite:") {
+            return Ok(Self::File(PathBuf::from(path)));
+        }
+        if target.starts_with("ssh://") {
+            let url = reqwest::Url::parse(target)
+                .map_err(|e| anyhow::anyhow!(i18n::tr("invalid-mirror-target", &[("target", &target), ("error", &e)])))?;
+            let host = url.host_str().filter(|_| url.path().len() > 1)
+                .ok_or_else(|| anyhow::anyhow!(i18n::tr("invalid-mirror-target", &[("target", &target), ("error", &"expected ssh://[user@]host[:port]/path")])))?;
+            let destination = match url.username() {
+                "" => host.to_string(),
+                user => format!("{}@{}", user, host),
+            };
+            return Ok(Self::Ssh { destination, port: url.port(), path: url.path().to_string() });
+        }
+        if let Some((scheme, _)) = target.split_once("://") {
+            return Err(anyhow::anyhow!(i18n::tr("unsupported-mirror-target", &[("scheme", &scheme)])));
+        }
+        Ok(Self::File(PathBuf::from(target)))
+    }
+}
+
+impl std::fmt::Display for MirrorTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Ssh { destination, port: Some(port), path } => write!(f, "ssh://{}:{}{}", destination, port, path),
+            Self::Ssh { destination, port: None, path } => write!(f, "ssh://{}{}", destination, path),
+        }
+    }
+}
+
+/// Absolute path of a file that may not exist yet, through its parent
+/// directory, so that two spellings of the same file compare equal
+fn canonical_target(path: &Path) -> Result<PathBuf> {
+    if path.exists() {
+        return Ok(path.canonicalize()?);
+    }
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let file_name = path.file_name().ok_or_else(|| anyhow::anyhow!(i18n::tr("invalid-mirror-target", &[("target", &path.display()), ("error", &"not a file path")])))?;
+    match parent.canonicalize() {
+        Ok(parent) => Ok(parent.join(file_name)),
+        // The directory is created on the first copy, so it can't hold the database
+        Err(_) => Ok(std::path::absolute(path)?),
+    }
+}
+
+/// Copy a file to a standby host with `scp`, under a temporary name renamed
+/// over `path` once complete
+async fn copy_over_ssh(file: &Path, destination: &str, port: Option<u16>, path: &str) -> Result<()> {
+    let partial = format!("{}.partial", path);
+    let port = port.map(|port| port.to_string());
+    
+    let mut scp = tokio::process::Command::new("scp");
+    scp.args(["-q", "-o", "BatchMode=yes"]);
+    if let Some(port) = &port {
+        scp.args(["-P", port]);
+    }
+    let output = scp.arg(file).arg(format!("{}:{}", destination, partial)).output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("scp: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    
+    let mut ssh = tokio::process::Command::new("ssh");
+    ssh.args(["-o", "BatchMode=yes"]);
+    if let Some(port) = &port {
+        ssh.args(["-p", port]);
+    }
+    let output = ssh.args([destination, "mv", "-f", &shell_quote(&partial), &shell_quote(path)]).output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("ssh: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+/// Quote an argument for the remote shell `ssh` runs commands with
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Replace the images of a compose file by the digest their provenance was verified on
+fn pin_verified_images(content: &str, pinned: &[(&str, String)]) -> Result<String> {
+    let mut compose: Value = serde_yaml::from_str(content)?;
+    let Some(services) = compose.get_mut("services").and_then(Value::as_mapping_mut) else {
+        return Ok(content.to_string());
+    };
+    for service in services.values_mut() {
+        let declared = service["image"].as_str().unwrap_or_default();
+        if let Some((_, digest_reference)) = pinned.iter().find(|(image, _)| *image == declared) {
+            service["image"] = Value::from(digest_reference.as_str());
+        }
+    }
+    Ok(serde_yaml::to_string(&compose)?)
+}
+
+/// Manual changes a resolution keeps: all of them for `keep-override`,
+/// those git doesn't change for `merge`, none for `take-git`
+fn kept_overrides(resolution: &str, overrides: &[ServiceOverride]) -> Vec<ServiceOverride> {
+    overrides.iter()
+        .filter(|o| match resolution {
+            "keep-override" => true,
+            "merge" => !o.conflicting,
+            _ => false,
+        })
+        .map(|o| ServiceOverride { conflicting: false, ..o.clone() })
+        .collect()
+}
+
+/// Write the running values of kept manual changes into a compose file
+fn apply_overrides(content: &str, overrides: &[ServiceOverride]) -> Result<String> {
+    if overrides.is_empty() {
+        return Ok(content.to_string());
+    }
+    
+    let mut compose: Value = serde_yaml::from_str(content)?;
+    for o in overrides {
+        let Some(service) = compose.get_mut("services").and_then(|services| services.get_mut(o.service.as_str())) else {
+            continue;
+        };
+        let Some(service) = service.as_mapping_mut() else {
+            continue;
+        };
+        match o.field.as_str() {
+            "replicas" => {
+                let deploy = service.entry(Value::from("deploy")).or_insert_with(|| Value::Mapping(Default::default()));
+                if let (Some(deploy), Ok(replicas)) = (deploy.as_mapping_mut(), o.live.parse::<u64>()) {
+                    deploy.insert(Value::from("replicas"), Value::from(replicas));
+                }
+            }
+            "image" => {
+                service.insert(Value::from("image"), Value::from(o.live.clone()));
+            }
+            _ => {}
+        }
+    }
+    
+    Ok(serde_yaml::to_string(&compose)?)
+}
@@ -0,0 +1,262 @@
+use anyhow::Result;
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+
+use crate::commands::glob_match;
+use crate::config::HashExclusions;
+
+// Compose file format written in place of versions docker stack deploy rejects
+const SWARM_VERSION: &str = "3.8";
+
+/// Resource keys of version 2 files and where Swarm expects them
+const RESOURCE_KEYS: [(&str, &str, &str); 3] = [
+    ("mem_limit", "limits", "memory"),
+    ("cpus", "limits", "cpus"),
+    ("mem_reservation", "reservations", "memory"),
+];
+
+/// Version 2 tuning knobs with no Swarm equivalent, rejected by the version 3 schema
+const UNSUPPORTED_KEYS: [&str; 5] = ["cpu_shares", "cpu_quota", "cpu_period", "memswap_limit", "mem_swappiness"];
+
+/// Legacy syntax found in a compose file
+#[derive(Debug)]
+pub struct Deprecation {
+    /// Location in the file, such as `services.web.links`
+    pub location: String,
+    /// What is deprecated and how to migrate it
+    pub guidance: String,
+    /// Whether the deployed file was rewritten, the source file is never touched
+    pub fixed: bool,
+}
+
+impl fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}{}", self.location, self.guidance, if self.fixed { " (fixed for this deployment)" } else { "" })
+    }
+}
+
+/// Rewrite the legacy syntax of a compose file that docker stack deploy
+/// would reject or silently ignore, listing every deprecated construct. The
+/// content is returned untouched when nothing had to be rewritten.
+pub fn normalize(content: &str) -> Result<(String, Vec<Deprecation>)> {
+    let mut yaml: Value = serde_yaml::from_str(content)?;
+    let Some(root) = yaml.as_mapping_mut() else {
+        return Ok((content.to_string(), Vec::new()));
+    };
+    let mut deprecations = Vec::new();
+
+    // Version 1 files list the services at the root of the file
+    if !root.contains_key("services") && !root.contains_key("version") && !root.is_empty()
+        && root.values().all(|service| service.get("image").is_some() || service.get("build").is_some()) {
+        let services = std::mem::take(root);
+        root.insert(Value::from("services"), Value::Mapping(services));
+        deprecations.push(fixed("(root)", "version 1 files list services at the root, moved under `services:`".to_string()));
+    }
+
+    let version = root.get("version").map(|version| match version {
+        Value::String(version) => version.clone(),
+        other => serde_yaml::to_string(other).unwrap_or_default().trim().to_string(),
+    });
+    let legacy_version = version.as_deref().filter(|version| version.starts_with('1') || version.starts_with('2'));
+    if let Some(legacy) = legacy_version {
+        deprecations.push(fixed("version", format!("version {} files are not supported by docker stack deploy, deployed as version {}", legacy, SWARM_VERSION)));
+        root.insert(Value::from("version"), Value::from(SWARM_VERSION));
+    }
+
+    if let Some(services) = root.get_mut("services").and_then(|services| services.as_mapping_mut()) {
+        for (name, service) in services.iter_mut() {
+            let name = name.as_str().unwrap_or_default().to_string();
+            if let Some(service) = service.as_mapping_mut() {
+                normalize_service(&name, service, &mut deprecations);
+            }
+        }
+    }
+
+    // Still honored by Swarm, only reported
+    for section in ["networks", "volumes", "secrets", "configs"] {
+        let Some(objects) = root.get(section).and_then(|objects| objects.as_mapping()) else {
+            continue;
+        };
+        for (name, object) in objects {
+            if object.get("external").and_then(|external| external.get("name")).is_some() {
+                deprecations.push(Deprecation {
+                    location: format!("{}.{}.external.name", section, name.as_str().unwrap_or_default()),
+                    guidance: "`external: { name: ... }` is deprecated, use `name: ...` with `external: true` (version 3.5 or later)".to_string(),
+                    fixed: false,
+                });
+            }
+        }
+    }
+
+    if !deprecations.iter().any(|deprecation| deprecation.fixed) {
+        return Ok((content.to_string(), deprecations));
+    }
+    Ok((serde_yaml::to_string(&yaml)?, deprecations))
+}
+
+/// Content a compose file is hashed from: the file itself without the
+/// excluded lines and keys. Without exclusions it is returned unchanged,
+/// keeping the hashes of earlier deployments.
+pub fn hashed_content(content: &str, exclusions: &HashExclusions) -> String {
+    if exclusions.is_empty() {
+        return content.to_string();
+    }
+    let kept: Vec<&str> = content.lines()
+        .filter(|line| !exclusions.lines.iter().any(|pattern| glob_match(pattern, line.trim())))
+        .collect();
+    let content = kept.join("\n");
+    if exclusions.keys.is_empty() {
+        return content;
+    }
+
+    // Invalid YAML is hashed as is, docker reports it when deploying
+    let Ok(mut yaml) = serde_yaml::from_str::<Value>(&content) else {
+        return content;
+    };
+    for key in &exclusions.keys {
+        let path: Vec<&str> = key.split('.').collect();
+        remove_key(&mut yaml, &path);
+    }
+    serde_yaml::to_string(&yaml).unwrap_or(content)
+}
+
+/// Remove the keys matching a path of globs
+fn remove_key(value: &mut Value, path: &[&str]) {
+    let (Some(pattern), Some(mapping)) = (path.first(), value.as_mapping_mut()) else {
+        return;
+    };
+    if path.len() == 1 {
+        mapping.retain(|key, _| !key.as_str().is_some_and(|key| glob_match(pattern, key)));
+        return;
+    }
+    for (key, child) in mapping.iter_mut() {
+        if key.as_str().is_some_and(|key| glob_match(pattern, key)) {
+            remove_key(child, &path[1..]);
+        }
+    }
+}
+
+/// Set `deploy.labels` on every service, which Swarm puts on the service
+/// itself, overriding labels of the same name the file declares
+pub fn label_services(content: &str, labels: &[(&str, String)]) -> Result<String> {
+    let mut yaml: Value = serde_yaml::from_str(content)?;
+    let Some(services) = yaml.get_mut("services").and_then(|services| services.as_mapping_mut()) else {
+        return Ok(content.to_string());
+    };
+    for service in services.values_mut() {
+        let Some(service) = service.as_mapping_mut() else {
+            continue;
+        };
+        // The list form ("key=value") is turned into the mapping form
+        if let Some(Value::Sequence(entries)) = service.get("deploy").and_then(|deploy| deploy.get("labels")).cloned() {
+            let mapping: Mapping = entries.iter()
+                .filter_map(|entry| entry.as_str())
+                .map(|entry| match entry.split_once('=') {
+                    Some((key, value)) => (Value::from(key), Value::from(value)),
+                    None => (Value::from(entry), Value::from("")),
+                })
+                .collect();
+            deploy_section(service, "labels").extend(mapping);
+        }
+        let section = deploy_section(service, "labels");
+        for (key, value) in labels {
+            section.insert(Value::from(*key), Value::from(value.as_str()));
+        }
+    }
+    Ok(serde_yaml::to_string(&yaml)?)
+}
+
+fn normalize_service(name: &str, service: &mut Mapping, deprecations: &mut Vec<Deprecation>) {
+    let location = |key: &str| format!("services.{}.{}", name, key);
+
+    if service.remove("container_name").is_some() {
+        deprecations.push(fixed(&location("container_name"), "ignored by Swarm, which names containers after their service, removed".to_string()));
+    }
+    for key in ["links", "external_links"] {
+        if service.remove(key).is_some() {
+            deprecations.push(fixed(&location(key), "ignored by Swarm, services reach each other by name on a shared network, removed".to_string()));
+        }
+    }
+
+    // Start order conditions of version 2.1+ files, Swarm starts every service at once
+    if let Some(Value::Mapping(depends_on)) = service.get("depends_on") {
+        let names: Vec<Value> = depends_on.keys().cloned().collect();
+        service.insert(Value::from("depends_on"), Value::Sequence(names));
+        deprecations.push(fixed(&location("depends_on"), "start conditions are not supported by Swarm, kept as a plain list of services".to_string()));
+    }
+
+    if let Some(restart) = service.remove("restart") {
+        let policy = restart.as_str().unwrap_or_default();
+        let condition = match policy.split(':').next().unwrap_or_default() {
+            "no" => "none",
+            "on-failure" => "on-failure",
+            _ => "any",
+        };
+        let restart_policy = deploy_section(service, "restart_policy");
+        if !restart_policy.contains_key("condition") {
+            restart_policy.insert(Value::from("condition"), Value::from(condition));
+        }
+        deprecations.push(fixed(&location("restart"), format!("ignored by Swarm, moved to deploy.restart_policy.condition: {}", condition)));
+    }
+
+    for (key, section, field) in RESOURCE_KEYS {
+        let Some(value) = service.remove(key) else {
+            continue;
+        };
+        // Swarm reads CPU limits as strings
+        let value = match value {
+            Value::Number(number) if field == "cpus" => Value::from(number.to_string()),
+            other => other,
+        };
+        let resources = deploy_section(service, "resources");
+        let target = resources.entry(Value::from(section))
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+        if let Some(target) = target.as_mapping_mut() {
+            if !target.contains_key(field) {
+                target.insert(Value::from(field), value);
+            }
+        }
+        deprecations.push(fixed(&location(key), format!("version 2 syntax, moved to deploy.resources.{}.{}", section, field)));
+    }
+
+    for key in UNSUPPORTED_KEYS {
+        if service.remove(key).is_some() {
+            deprecations.push(fixed(&location(key), "not supported by Swarm, removed".to_string()));
+        }
+    }
+
+    if service.contains_key("volumes_from") {
+        deprecations.push(Deprecation {
+            location: location("volumes_from"),
+            guidance: "not supported by docker stack deploy, share a named volume between the services instead".to_string(),
+            fixed: false,
+        });
+    }
+    if service.contains_key("extends") {
+        deprecations.push(Deprecation {
+            location: location("extends"),
+            guidance: "not supported by docker stack deploy, copy the extended service's keys or use YAML anchors".to_string(),
+            fixed: false,
+        });
+    }
+}
+
+fn fixed(location: &str, guidance: String) -> Deprecation {
+    Deprecation { location: location.to_string(), guidance, fixed: true }
+}
+
+/// `deploy.<section>` of a service, created when missing
+fn deploy_section<'a>(service: &'a mut Mapping, section: &str) -> &'a mut Mapping {
+    let deploy = service.entry(Value::from("deploy"))
+        .or_insert_with(|| Value::Mapping(Mapping::new()));
+    if !deploy.is_mapping() {
+        *deploy = Value::Mapping(Mapping::new());
+    }
+    let section = deploy.as_mapping_mut().unwrap()
+        .entry(Value::from(section))
+        .or_insert_with(|| Value::Mapping(Mapping::new()));
+    if !section.is_mapping() {
+        *section = Value::Mapping(Mapping::new());
+    }
+    section.as_mapping_mut().unwrap()
+}
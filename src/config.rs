@@ -0,0 +1,1250 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::atomic;
+use crate::i18n;
+use crate::models::StackDefinition;
+use crate::provenance::ProvenancePolicy;
+use crate::signing::CommitSigners;
+use crate::registry::ImageReference;
+
+/// Global DockerOps configuration, read from `~/.dockerops/config.yaml`
+/// (or `~/.dockerops/profiles/<name>/config.yaml` when a profile is selected).
+///
+/// Every field is optional so that an absent or empty file behaves exactly
+/// like the historical environment-variable-only setup.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    /// IANA timezone (e.g. "Europe/Paris") used to render timestamps and to
+    /// evaluate schedules. Timestamps are always stored in UTC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    /// Language of the messages ("en" or "fr"), taken from `LANG` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+
+    /// SQLite database file, defaults to `dockerops.db` in the configuration directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub db_path: Option<String>,
+
+    /// Docker daemon endpoint (exported as `DOCKER_HOST` to docker commands)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docker_host: Option<String>,
+
+    /// Use the repository's GitHub Actions variables as values for compose files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_variables: Option<bool>,
+
+    /// Commits of history cloned per branch and tag, all of them when unset.
+    /// Pinned commits always get the full history.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clone_depth: Option<u32>,
+
+    /// Only check out the root files and the directories of the stacks
+    /// declared in stacks.yaml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sparse_checkout: Option<bool>,
+
+    /// Download rate cap for image pulls made by DockerOps (e.g. "5MB/s")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_bandwidth_limit: Option<String>,
+
+    /// Named Docker endpoints (name -> DOCKER_HOST) selectable with `use-context`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contexts: Option<BTreeMap<String, String>>,
+
+    /// Docker endpoints of the Swarm nodes by hostname (e.g. "ssh://root@node-2"),
+    /// used by `exec` for the tasks running on other nodes than the manager
+    /// and to prune the stopped tasks there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_endpoints: Option<BTreeMap<String, String>>,
+
+    /// GitHub token used when `GITHUB_TOKEN` is not set in the environment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_token: Option<String>,
+
+    /// GitHub App authenticating github.com clones and API calls with
+    /// installation tokens instead of `github_token`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_app_id: Option<u64>,
+
+    /// PEM private key generated for the GitHub App
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_app_private_key: Option<String>,
+
+    /// Installation used for every repository, looked up per repository owner when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_app_installation_id: Option<u64>,
+
+    /// GitLab token used when `GITLAB_TOKEN` is not set in the environment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gitlab_token: Option<String>,
+
+    /// Hosts of self-managed GitLab instances (gitlab.com is always recognized)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gitlab_hosts: Option<Vec<String>>,
+
+    /// Gitea / Forgejo token used when `GITEA_TOKEN` is not set in the environment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gitea_token: Option<String>,
+
+    /// Hosts of Gitea or Forgejo instances (codeberg.org is always recognized)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gitea_hosts: Option<Vec<String>>,
+
+    /// Bitbucket user owning `bitbucket_app_password` (`BITBUCKET_USERNAME`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitbucket_username: Option<String>,
+
+    /// Bitbucket Cloud app password or Bitbucket Server HTTP access token
+    /// used when `BITBUCKET_APP_PASSWORD` is not set in the environment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitbucket_app_password: Option<String>,
+
+    /// Hosts of Bitbucket Server / Data Center instances (bitbucket.org is always recognized)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitbucket_hosts: Option<Vec<String>>,
+
+    /// Private key used for SSH remotes, the ssh-agent is used when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<String>,
+
+    /// Passphrase of `ssh_key`, if it is encrypted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key_passphrase: Option<String>,
+
+    /// Consecutive deploy failures after which a stack is no longer retried
+    /// until its compose file changes (default 3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_threshold: Option<u32>,
+
+    /// Open a GitHub issue in the watched repository when a stack reaches
+    /// `failure_threshold`, closed once it deploys again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_issues: Option<bool>,
+
+    /// Webhooks (Slack incoming webhooks or any endpoint accepting JSON)
+    /// notified when a stack reaches `failure_threshold` and when it recovers.
+    /// Notifications are kept in an outbox and retried until delivered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_webhooks: Option<Vec<String>>,
+
+    /// `docker stack deploy` flags of every stack, overridden by `deploy` of
+    /// dockerops.yaml and of stacks.yaml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploy: Option<DeploySettings>,
+
+    /// Canary analysis of every stack update, overridden by `updates.canary`
+    /// of dockerops.yaml and of stacks.yaml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary: Option<CanarySettings>,
+
+    /// Roll updates that never become healthy back to the previous revision,
+    /// overridden by `updates.auto_rollback` of dockerops.yaml and of stacks.yaml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_rollback: Option<bool>,
+
+    /// Parts of the compose files left out of their hash, overridden by
+    /// `updates.hash_exclude` of dockerops.yaml and of stacks.yaml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_exclude: Option<HashExclusions>,
+
+    /// Keys that must have signed a commit for it to be deployed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_signers: Option<CommitSigners>,
+
+    /// SLSA provenance required from images before they are pulled, the
+    /// first policy matching an image applies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance_policies: Option<Vec<ProvenancePolicy>>,
+
+    /// Delay automatic updates of stacks while a Prometheus load metric is high
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traffic_aware_updates: Option<TrafficAwareUpdates>,
+
+    /// Shared secret used to verify webhook signatures in `serve` mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_secret: Option<String>,
+
+    /// Bearer token required by the JSON endpoints of `serve` (`/pending`, `/apps`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_token: Option<String>,
+
+    /// Age of the last successful sync after which `healthz` reports
+    /// DockerOps unhealthy, unchecked when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_max_age: Option<String>,
+
+    /// How long removed stacks stay restorable in the trash (default 7d)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash_retention: Option<String>,
+
+    /// Remove the stacks no longer declared in stacks.yaml, moving them to
+    /// the trash (default false), overridden by dockerops.yaml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prune_stacks: Option<bool>,
+
+    /// Stacks of a repository deployed at the same time, the ones depending
+    /// on each other still one after the other (default 1, overridden by
+    /// `--max-parallel`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_parallel_deploys: Option<usize>,
+
+    /// Deployed compose files kept per stack for `rollback` (default 10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revisions_kept: Option<u32>,
+
+    /// Registries (as written in image names, e.g. "docker.io", "ghcr.io")
+    /// queried for image freshness. Unset checks every registry, an empty
+    /// list disables the checks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freshness_registries: Option<Vec<String>>,
+
+    /// Pull settings by registry, as written in image names ("docker.io", "ghcr.io")
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub registries: BTreeMap<String, RegistrySettings>,
+
+    /// Stacks whose overlay networks must be encrypted ("*" for every stack)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_networks: Option<Vec<String>>,
+
+    /// Cleanup of the Docker resources left on the manager beyond the images
+    /// DockerOps pulled, disabled when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gc: Option<GcSettings>,
+
+    /// Stopped tasks kept by every stack, overridden by `task_history` of
+    /// dockerops.yaml and of stacks.yaml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_history: Option<TaskHistorySettings>,
+
+    /// Swarm's `task-history-limit` (tasks kept per replica slot, running
+    /// one included), set on the cluster by `daemon`, left as is when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swarm_task_history_limit: Option<u32>,
+
+    #[serde(skip)]
+    tz: Option<Tz>,
+
+    #[serde(skip)]
+    dir: PathBuf,
+
+    #[serde(skip)]
+    profile: Option<String>,
+
+    /// Context selected with `use-context`, either a key of `contexts` or a docker CLI context
+    #[serde(skip)]
+    context: Option<String>,
+
+    /// Name given with `--as`, or the daemon / server role
+    #[serde(skip)]
+    operator: Option<String>,
+}
+
+impl Config {
+    pub fn load(profile: Option<&str>) -> Result<Self> {
+        let dir = Self::dir_for_profile(profile)?;
+        let config_path = Self::file_in(&dir);
+
+        if profile.is_some() && !dir.exists() {
+            return Err(anyhow::anyhow!("Profile '{}' not found (expected directory {})",
+                profile.unwrap_or_default(), dir.display()));
+        }
+        // The variables would point a profile at the files of another one
+        if let Some(profile) = profile {
+            if let Some(variable) = ["DOCKEROPS_CONFIG", "DOCKEROPS_DB_PATH"].into_iter().find(|variable| std::env::var_os(variable).is_some()) {
+                return Err(anyhow::anyhow!("{} is set while profile '{}' is selected, unset it or set db_path in {}",
+                    variable, profile, dir.join("config.yaml").display()));
+            }
+        }
+
+        let content = if config_path.exists() {
+            fs::read_to_string(&config_path)?
+        } else {
+            String::new()
+        };
+
+        let mut config = if !content.trim().is_empty() {
+            serde_yaml::from_str::<Config>(&content)
+                .map_err(|e| anyhow::anyhow!("Invalid configuration file {}: {}", config_path.display(), e))?
+        } else {
+            Config::default()
+        };
+        config.dir = dir;
+        config.profile = profile.map(|p| p.to_string());
+
+        // The environment variable takes precedence over the configuration file
+        if let Ok(timezone) = std::env::var("DOCKEROPS_TIMEZONE") {
+            config.timezone = Some(timezone);
+        }
+
+        config.context = std::env::var("DOCKEROPS_CONTEXT").ok()
+            .or_else(|| fs::read_to_string(config.context_file()).ok())
+            .map(|context| context.trim().to_string())
+            .filter(|context| !context.is_empty());
+
+        if let Ok(locale) = std::env::var("DOCKEROPS_LOCALE") {
+            config.locale = Some(locale);
+        }
+        if let Some(locale) = &config.locale {
+            if i18n::language_of(locale).is_none() {
+                return Err(anyhow::anyhow!("Unknown locale '{}' (available: {})", locale, i18n::available().join(", ")));
+            }
+        }
+
+        if let Some(timezone) = &config.timezone {
+            config.tz = Some(timezone.parse::<Tz>()
+                .map_err(|_| anyhow::anyhow!("Unknown timezone '{}' (expected an IANA name such as 'Europe/Paris')", timezone))?);
+        }
+
+        Ok(config)
+    }
+
+    /// Directory holding the database and configuration (`~/.dockerops`)
+    pub fn base_dir() -> PathBuf {
+        let home_dir = std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .unwrap_or_else(|_| ".".to_string());
+        Path::new(&home_dir).join(".dockerops")
+    }
+
+    fn dir_for_profile(profile: Option<&str>) -> Result<PathBuf> {
+        match profile {
+            Some(name) => {
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                    return Err(anyhow::anyhow!("Invalid profile name '{}' (use letters, digits, '-' or '_')", name));
+                }
+                Ok(Self::base_dir().join("profiles").join(name))
+            }
+            None => Ok(Self::base_dir()),
+        }
+    }
+
+    /// Configuration file: `DOCKEROPS_CONFIG` (without a profile), then `config.yaml` in the profile directory
+    pub fn file_path(&self) -> PathBuf {
+        Self::file_in(&self.dir)
+    }
+
+    fn file_in(dir: &Path) -> PathBuf {
+        std::env::var("DOCKEROPS_CONFIG")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| dir.join("config.yaml"))
+    }
+
+    /// Write the settings to the configuration file, unset ones being left out
+    pub fn save(&self) -> Result<()> {
+        let path = self.file_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        atomic::write(&path, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Directory holding the compose file generated by `self-deploy`
+    pub fn self_deploy_dir(&self) -> PathBuf {
+        self.state_dir().join("self")
+    }
+
+    /// Stack running this DockerOps instance, set by the `self-deploy` compose file
+    pub fn self_stack(&self) -> Option<String> {
+        std::env::var("DOCKEROPS_SELF_STACK").ok().filter(|stack| !stack.is_empty())
+    }
+
+    /// Directory holding the persistent working copies of watched repositories
+    pub fn repos_dir(&self) -> PathBuf {
+        self.state_dir().join("repos")
+    }
+
+    /// Copies of the directories of the trashed stacks
+    pub fn trash_dir(&self) -> PathBuf {
+        self.state_dir().join("trash")
+    }
+
+    /// Directory holding the state of the selected context (database, working
+    /// copies, trash), so that each cluster is managed from its own state:
+    /// `contexts/<name>` in the profile directory, or the profile directory itself
+    pub fn state_dir(&self) -> PathBuf {
+        self.state_dir_of(self.context.as_deref())
+    }
+
+    pub fn state_dir_of(&self, context: Option<&str>) -> PathBuf {
+        match context {
+            Some(context) => self.dir.join("contexts").join(context.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_', "_")),
+            None => self.dir.clone(),
+        }
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// File persisting the context selected with `use-context`
+    fn context_file(&self) -> PathBuf {
+        self.dir.join("context")
+    }
+
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+
+    /// Persist the default context, `None` going back to `docker_host` / the environment
+    pub fn set_context(&self, context: Option<&str>) -> Result<()> {
+        let path = self.context_file();
+        match context {
+            Some(context) => {
+                fs::create_dir_all(&self.dir)?;
+                atomic::write(&path, format!("{}\n", context))?;
+            }
+            None if path.exists() => fs::remove_file(&path)?,
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Environment variable pointing docker commands at the managed cluster:
+    /// the selected context first, then `docker_host`
+    pub fn docker_env(&self) -> Option<(&'static str, String)> {
+        match &self.context {
+            Some(context) => match self.contexts.as_ref().and_then(|contexts| contexts.get(context)) {
+                Some(host) => Some(("DOCKER_HOST", host.clone())),
+                None => Some(("DOCKER_CONTEXT", context.clone())),
+            },
+            None => self.docker_host.clone().map(|host| ("DOCKER_HOST", host)),
+        }
+    }
+
+    /// Endpoint of a Swarm node declared in `node_endpoints`, or in
+    /// `contexts` under its hostname
+    pub fn node_endpoint(&self, hostname: &str) -> Option<String> {
+        [&self.node_endpoints, &self.contexts].into_iter()
+            .find_map(|endpoints| endpoints.as_ref().and_then(|endpoints| endpoints.get(hostname)))
+            .cloned()
+    }
+
+    /// Who runs this process, recorded with the changes it makes: the name
+    /// given with `--as`, then the user behind sudo, then the system user
+    pub fn operator(&self) -> String {
+        self.operator.clone()
+            .or_else(|| ["SUDO_USER", "USER", "LOGNAME"].iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|user| !user.is_empty()))
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    pub fn set_operator(&mut self, operator: Option<String>) {
+        self.operator = operator.filter(|operator| !operator.trim().is_empty());
+    }
+
+    /// Language of the messages: `locale`, then the system locale, then English
+    pub fn language(&self) -> &'static str {
+        let system = ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|locale| !locale.is_empty());
+        self.locale.as_deref().or(system.as_deref())
+            .and_then(i18n::language_of)
+            .unwrap_or("en")
+    }
+
+    /// Database location: `DOCKEROPS_DB_PATH` (without a profile), then `db_path`, then the context's state directory
+    pub fn database_path(&self) -> PathBuf {
+        self.explicit_database_path()
+            .unwrap_or_else(|| self.state_dir().join("dockerops.db"))
+    }
+
+    /// Database given with `DOCKEROPS_DB_PATH` or `db_path`, shared by all the contexts
+    pub fn explicit_database_path(&self) -> Option<PathBuf> {
+        std::env::var("DOCKEROPS_DB_PATH")
+            .ok()
+            .or_else(|| self.db_path.clone())
+            .map(PathBuf::from)
+    }
+
+    /// GitHub token from the environment, falling back to the configuration
+    pub fn github_token(&self) -> Option<String> {
+        std::env::var("GITHUB_TOKEN").ok().or_else(|| self.github_token.clone())
+    }
+
+    /// GitLab token from the environment, falling back to the configuration
+    pub fn gitlab_token(&self) -> Option<String> {
+        std::env::var("GITLAB_TOKEN").ok().or_else(|| self.gitlab_token.clone())
+    }
+
+    /// Gitea token from the environment, falling back to the configuration
+    pub fn gitea_token(&self) -> Option<String> {
+        std::env::var("GITEA_TOKEN").ok().or_else(|| self.gitea_token.clone())
+    }
+
+    pub fn bitbucket_username(&self) -> Option<String> {
+        std::env::var("BITBUCKET_USERNAME").ok().or_else(|| self.bitbucket_username.clone())
+    }
+
+    pub fn bitbucket_app_password(&self) -> Option<String> {
+        std::env::var("BITBUCKET_APP_PASSWORD").ok().or_else(|| self.bitbucket_app_password.clone())
+    }
+
+    /// SSH key from `DOCKEROPS_SSH_KEY`, falling back to the configuration (`~/` is expanded)
+    pub fn ssh_key(&self) -> Option<PathBuf> {
+        let key = std::env::var("DOCKEROPS_SSH_KEY").ok().or_else(|| self.ssh_key.clone())?;
+        Some(Self::expand_home(&key))
+    }
+
+    /// GitHub App id and private key from `GITHUB_APP_ID` and
+    /// `GITHUB_APP_PRIVATE_KEY`, falling back to the configuration
+    pub fn github_app(&self) -> Result<Option<(u64, PathBuf)>> {
+        let app_id = match std::env::var("GITHUB_APP_ID") {
+            Ok(app_id) => Some(app_id.trim().parse::<u64>()
+                .map_err(|_| anyhow::anyhow!("Invalid GITHUB_APP_ID '{}'", app_id))?),
+            Err(_) => self.github_app_id,
+        };
+        let key = std::env::var("GITHUB_APP_PRIVATE_KEY").ok().or_else(|| self.github_app_private_key.clone());
+        match (app_id, key) {
+            (Some(app_id), Some(key)) => Ok(Some((app_id, Self::expand_home(&key)))),
+            (None, None) => Ok(None),
+            _ => Err(anyhow::anyhow!("A GitHub App needs both github_app_id and github_app_private_key")),
+        }
+    }
+
+    /// Allowed commit signers, with `~/` expanded in key paths
+    pub fn commit_signers(&self) -> Option<CommitSigners> {
+        let mut signers = self.commit_signers.clone()?;
+        for key in &mut signers.gpg_keys {
+            *key = Self::expand_home(key).to_string_lossy().to_string();
+        }
+        Some(signers)
+    }
+
+    fn expand_home(path: &str) -> PathBuf {
+        match path.strip_prefix("~/") {
+            // base_dir() is ~/.dockerops
+            Some(relative) => Self::base_dir().parent().unwrap_or(Path::new(".")).join(relative),
+            None => PathBuf::from(path),
+        }
+    }
+
+    /// Webhook secret from `DOCKEROPS_WEBHOOK_SECRET`, falling back to the configuration
+    pub fn webhook_secret(&self) -> Option<String> {
+        std::env::var("DOCKEROPS_WEBHOOK_SECRET").ok().or_else(|| self.webhook_secret.clone())
+    }
+
+    /// API token from `DOCKEROPS_API_TOKEN`, falling back to the configuration
+    pub fn api_token(&self) -> Option<String> {
+        std::env::var("DOCKEROPS_API_TOKEN").ok().or_else(|| self.api_token.clone())
+            .filter(|token| !token.is_empty())
+    }
+
+    pub fn health_max_age(&self) -> Result<Option<Duration>> {
+        self.health_max_age.as_deref().map(parse_duration).transpose()
+    }
+
+    pub fn trash_retention(&self) -> Result<Duration> {
+        parse_duration(self.trash_retention.as_deref().unwrap_or("7d"))
+    }
+
+    pub fn max_parallel_deploys(&self) -> usize {
+        self.max_parallel_deploys.unwrap_or(1).max(1)
+    }
+
+    pub fn revisions_kept(&self) -> i64 {
+        self.revisions_kept.unwrap_or(10).max(1) as i64
+    }
+
+    pub fn freshness_allowed(&self, registry: &str) -> bool {
+        self.freshness_registries.as_ref()
+            .is_none_or(|registries| registries.iter().any(|r| r == registry))
+    }
+
+    pub fn provenance_policy(&self, image: &ImageReference) -> Option<&ProvenancePolicy> {
+        self.provenance_policies.as_ref()?.iter().find(|policy| policy.applies_to(image))
+    }
+
+    pub fn registry_settings(&self, registry: &str) -> Option<&RegistrySettings> {
+        self.registries.get(registry)
+    }
+
+    pub fn requires_encrypted_networks(&self, stack_name: &str) -> bool {
+        self.encrypted_networks.as_ref()
+            .is_some_and(|stacks| stacks.iter().any(|s| s == "*" || s == stack_name))
+    }
+
+    /// Pull rate cap in bytes per second
+    pub fn pull_bandwidth_limit(&self) -> Result<Option<u64>> {
+        self.pull_bandwidth_limit.as_deref().map(parse_bandwidth).transpose()
+    }
+
+    /// Whether docker commands reach a daemon on this host, which is required
+    /// for it to pull through a local proxy
+    pub fn docker_is_local(&self) -> bool {
+        let host = match self.docker_env() {
+            Some(("DOCKER_HOST", host)) => Some(host),
+            Some((_, context)) => return context == "default",
+            None => std::env::var("DOCKER_HOST").ok(),
+        };
+        host.is_none_or(|host| host.starts_with("unix://") || host.starts_with("npipe://"))
+    }
+
+    /// Time of day of a timestamp in the configured timezone
+    pub fn local_time(&self, time: &DateTime<Utc>) -> NaiveTime {
+        time.with_timezone(&self.tz.unwrap_or(Tz::UTC)).time()
+    }
+
+    pub fn notification_webhooks(&self) -> &[String] {
+        self.notification_webhooks.as_deref().unwrap_or_default()
+    }
+
+    /// Render a timestamp in the configured timezone
+    pub fn format_time(&self, time: &DateTime<Utc>) -> String {
+        time.with_timezone(&self.tz.unwrap_or(Tz::UTC)).format("%Y-%m-%d %H:%M:%S %Z").to_string()
+    }
+
+    /// Read a timestamp given on the command line: RFC 3339, or a date with an
+    /// optional time ("2024-05-02", "2024-05-02 14:30") in the configured timezone
+    pub fn parse_timestamp(&self, value: &str) -> Result<DateTime<Utc>> {
+        if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+            return Ok(time.with_timezone(&Utc));
+        }
+        let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"].iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+            .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))
+            .ok_or_else(|| anyhow::anyhow!("Invalid timestamp '{}', expected RFC 3339 or YYYY-MM-DD [HH:MM[:SS]]", value))?;
+        self.tz.unwrap_or(Tz::UTC).from_local_datetime(&naive).earliest()
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or_else(|| anyhow::anyhow!("Timestamp '{}' does not exist in timezone {}", value, self.tz.unwrap_or(Tz::UTC)))
+    }
+
+    /// Render a stored RFC 3339 timestamp in the configured timezone.
+    /// Values that cannot be parsed are returned unchanged.
+    pub fn format_timestamp(&self, timestamp: &str) -> String {
+        let tz = self.tz.unwrap_or(Tz::UTC);
+        match DateTime::parse_from_rfc3339(timestamp) {
+            Ok(parsed) => parsed.with_timezone(&tz).format("%Y-%m-%d %H:%M:%S %Z").to_string(),
+            Err(_) => timestamp.to_string(),
+        }
+    }
+}
+
+/// How images are pulled from one registry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegistrySettings {
+    /// Pulls from this registry running at the same time (default 3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_pulls: Option<usize>,
+    /// Registries serving the same repositories, tried in order when a pull
+    /// from this one fails or is rate limited
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+}
+
+/// Resources removed by the garbage collection, and when it runs on its own
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GcSettings {
+    /// Untagged images no container uses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dangling_images: Option<bool>,
+    /// Stopped containers of the managed stacks (old tasks kept by Swarm)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopped_containers: Option<bool>,
+    /// Build cache no image uses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_cache: Option<bool>,
+    /// Only remove resources created (last used for the build cache) at
+    /// least this long ago (default 24h)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub older_than: Option<String>,
+    /// Scheduled runs are skipped while less space than this can be
+    /// reclaimed (e.g. "1GB", default 0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_reclaimable: Option<String>,
+    /// Shortest delay between two scheduled runs (default 24h), "0" disables them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+    /// Hours of the day scheduled runs are allowed in, in the configured
+    /// timezone ("02:00-05:00"), any time when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours: Option<String>,
+}
+
+impl GcSettings {
+    pub fn is_enabled(&self) -> bool {
+        self.dangling_images.unwrap_or(false) || self.stopped_containers.unwrap_or(false) || self.build_cache.unwrap_or(false)
+    }
+
+    pub fn older_than(&self) -> Result<Duration> {
+        parse_duration(self.older_than.as_deref().unwrap_or("24h"))
+    }
+
+    /// Threshold in bytes
+    pub fn min_reclaimable(&self) -> Result<u64> {
+        self.min_reclaimable.as_deref().map(parse_size).transpose().map(Option::unwrap_or_default)
+    }
+
+    /// `None` when only explicit runs are wanted
+    pub fn interval(&self) -> Result<Option<Duration>> {
+        let interval = parse_duration(self.interval.as_deref().unwrap_or("24h"))?;
+        Ok(Some(interval).filter(|interval| !interval.is_zero()))
+    }
+
+    /// Whether a scheduled run may start at this time of day. A window
+    /// ending before it starts spans midnight.
+    pub fn in_hours(&self, time: NaiveTime) -> Result<bool> {
+        let Some(hours) = &self.hours else {
+            return Ok(true);
+        };
+        let invalid = || anyhow::anyhow!("Invalid gc.hours '{}' (expected e.g. 02:00-05:00)", hours);
+        let (start, end) = hours.split_once('-').ok_or_else(invalid)?;
+        let parse = |value: &str| NaiveTime::parse_from_str(value.trim(), "%H:%M").map_err(|_| invalid());
+        let (start, end) = (parse(start)?, parse(end)?);
+        Ok(if start <= end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        })
+    }
+
+    /// Check the durations, sizes and hours before anything runs
+    pub fn validate(&self) -> Result<()> {
+        self.older_than()?;
+        self.min_reclaimable()?;
+        self.interval()?;
+        self.in_hours(NaiveTime::MIN)?;
+        Ok(())
+    }
+}
+
+/// Updates made by `daemon` and `serve` wait while the stack's load is
+/// above its threshold, for at most `max_delay`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficAwareUpdates {
+    pub prometheus_url: String,
+    /// PromQL query whose `$stack` is replaced by the stack name
+    pub query: String,
+    pub threshold: f64,
+    /// Per-stack thresholds overriding `threshold`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub thresholds: BTreeMap<String, f64>,
+    /// Longest delay before an update is deployed regardless of the load (default 6h)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_delay: Option<String>,
+}
+
+impl TrafficAwareUpdates {
+    pub fn threshold(&self, stack_name: &str) -> f64 {
+        self.thresholds.get(stack_name).copied().unwrap_or(self.threshold)
+    }
+
+    pub fn max_delay(&self) -> Result<Duration> {
+        parse_duration(self.max_delay.as_deref().unwrap_or("6h"))
+    }
+}
+
+/// Defaults a repository declares for its own stacks in a `dockerops.yaml`
+/// next to its stacks.yaml, taking precedence over `config.yaml`. The same
+/// sections in a stacks.yaml entry take precedence over both for that stack.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepositorySettings {
+    /// Prepended to the names of stacks.yaml to form the Swarm stack names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack_prefix: Option<String>,
+    /// Overrides `prune_stacks`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prune_stacks: Option<bool>,
+    #[serde(default)]
+    pub deploy: DeploySettings,
+    #[serde(default)]
+    pub updates: UpdateSettings,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    #[serde(default)]
+    pub task_history: TaskHistorySettings,
+}
+
+/// Flags passed to `docker stack deploy`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeploySettings {
+    /// Remove the services no longer defined in the compose file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prune: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolve_image: Option<ResolveImage>,
+    /// Send the registry logins to the nodes pulling the images, by default
+    /// when registries.yaml has a login for one of the stack's images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub with_registry_auth: Option<bool>,
+    /// Update only the changed services with `docker service update` when
+    /// nothing but their images or replica counts changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selective: Option<bool>,
+    /// How long to wait after deploying for every service to run all its
+    /// replicas before the stack is marked degraded or failed ("0" to not wait)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub convergence_timeout: Option<String>,
+}
+
+impl DeploySettings {
+    /// These flags, the unset ones taken from `defaults`
+    fn or(&self, defaults: &DeploySettings) -> DeploySettings {
+        DeploySettings {
+            prune: self.prune.or(defaults.prune),
+            resolve_image: self.resolve_image.or(defaults.resolve_image),
+            with_registry_auth: self.with_registry_auth.or(defaults.with_registry_auth),
+            selective: self.selective.or(defaults.selective),
+            convergence_timeout: self.convergence_timeout.clone().or_else(|| defaults.convergence_timeout.clone()),
+        }
+    }
+
+    pub fn convergence_timeout(&self) -> Result<Duration> {
+        parse_duration(self.convergence_timeout.as_deref().unwrap_or("5m"))
+    }
+}
+
+/// Stopped tasks of a stack whose containers `daemon` removes from the
+/// nodes, Swarm itself forgetting them after `swarm_task_history_limit`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskHistorySettings {
+    /// Stopped tasks kept per replica slot, the most recent ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep: Option<u32>,
+    /// Stopped tasks older than this are removed even within `keep` ("7d")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<String>,
+}
+
+impl TaskHistorySettings {
+    /// These settings, the unset ones taken from `defaults`
+    fn or(&self, defaults: &TaskHistorySettings) -> TaskHistorySettings {
+        TaskHistorySettings {
+            keep: self.keep.or(defaults.keep),
+            max_age: self.max_age.clone().or_else(|| defaults.max_age.clone()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.keep.is_some() || self.max_age.is_some()
+    }
+
+    pub fn max_age(&self) -> Result<Option<Duration>> {
+        self.max_age.as_deref().map(parse_duration).transpose()
+    }
+}
+
+/// `docker stack deploy --resolve-image`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResolveImage {
+    Always,
+    Changed,
+    Never,
+}
+
+impl ResolveImage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResolveImage::Always => "always",
+            ResolveImage::Changed => "changed",
+            ResolveImage::Never => "never",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateSettings {
+    /// Overrides `failure_threshold`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_threshold: Option<u32>,
+    /// `false` deploys the updates without waiting for `traffic_aware_updates`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traffic_aware: Option<bool>,
+    /// Watch the new tasks of updated stacks and roll the update back when they misbehave
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary: Option<CanarySettings>,
+    /// Changes that don't make the compose file different for DockerOps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_exclude: Option<HashExclusions>,
+    /// Redeploy the previous revision when an update does not converge
+    /// within `deploy.convergence_timeout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_rollback: Option<bool>,
+}
+
+impl UpdateSettings {
+    fn or(&self, defaults: &UpdateSettings) -> UpdateSettings {
+        UpdateSettings {
+            failure_threshold: self.failure_threshold.or(defaults.failure_threshold),
+            traffic_aware: self.traffic_aware.or(defaults.traffic_aware),
+            canary: self.canary.clone().or_else(|| defaults.canary.clone()),
+            hash_exclude: self.hash_exclude.clone().or_else(|| defaults.hash_exclude.clone()),
+            auto_rollback: self.auto_rollback.or(defaults.auto_rollback),
+        }
+    }
+}
+
+/// Parts of a compose file left out of its hash, such as a timestamp CI
+/// writes in it, so that changes to them alone don't redeploy the stack
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HashExclusions {
+    /// Globs matched against each trimmed line, e.g. "# generated at *"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lines: Vec<String>,
+    /// Dotted key paths whose segments are globs, e.g. "services.*.labels.build-date"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+}
+
+impl HashExclusions {
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.keys.is_empty()
+    }
+}
+
+/// Thresholds of the analysis run on the tasks of an updated stack
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CanarySettings {
+    /// How long the new tasks are sampled (default 2m)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+    /// Failed or rejected new tasks allowed per service (default 0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_restarts: Option<u32>,
+    /// Error log lines allowed per service (default 10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_error_lines: Option<u32>,
+    /// Case-insensitive substrings making a log line an error (default error, fatal, panic)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error_patterns: Vec<String>,
+}
+
+impl CanarySettings {
+    pub fn duration(&self) -> Result<Duration> {
+        parse_duration(self.duration.as_deref().unwrap_or("2m"))
+    }
+
+    pub fn max_restarts(&self) -> usize {
+        self.max_restarts.unwrap_or(0) as usize
+    }
+
+    pub fn max_error_lines(&self) -> usize {
+        self.max_error_lines.unwrap_or(10) as usize
+    }
+
+    /// Whether a log line reports an error
+    pub fn is_error(&self, line: &str) -> bool {
+        let line = line.to_lowercase();
+        if self.error_patterns.is_empty() {
+            return ["error", "fatal", "panic"].iter().any(|pattern| line.contains(pattern));
+        }
+        self.error_patterns.iter().any(|pattern| line.contains(&pattern.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationSettings {
+    /// Overrides `failure_issues`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_issues: Option<bool>,
+    /// Webhooks notified in addition to the ones of the less precise layers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<String>,
+    /// `false` notifies only `webhooks`, not the ones of the less precise layers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherit_webhooks: Option<bool>,
+}
+
+impl NotificationSettings {
+    /// Webhooks of this layer followed by the ones of `defaults` unless
+    /// `inherit_webhooks` is false, each one once
+    fn merged_webhooks(&self, defaults: &[String]) -> Vec<String> {
+        let mut webhooks = self.webhooks.clone();
+        if self.inherit_webhooks.unwrap_or(true) {
+            webhooks.extend(defaults.iter().cloned());
+        }
+        let mut seen = std::collections::HashSet::new();
+        webhooks.retain(|url| seen.insert(url.clone()));
+        webhooks
+    }
+}
+
+/// Value of one layered setting and the file it comes from
+#[derive(Debug)]
+pub struct EffectiveSetting {
+    pub key: &'static str,
+    pub value: String,
+    /// `stacks.yaml`, `dockerops.yaml`, `config.yaml` or `default`
+    pub source: &'static str,
+}
+
+/// A layered setting: its name, how a layer sets it and its value when no layer does
+type LayeredSetting = (&'static str, fn(&RepositorySettings) -> Option<String>, &'static str);
+
+fn layered_settings() -> [LayeredSetting; 14] {
+    [
+        ("prune_stacks", |s| s.prune_stacks.map(|prune| prune.to_string()), "false"),
+        ("deploy.prune", |s| s.deploy.prune.map(|prune| prune.to_string()), "false"),
+        ("deploy.resolve_image", |s| s.deploy.resolve_image.map(|resolve| resolve.as_str().to_string()), "always (docker default)"),
+        ("deploy.with_registry_auth", |s| s.deploy.with_registry_auth.map(|auth| auth.to_string()),
+            "when registries.yaml has a login for an image of the stack"),
+        ("deploy.selective", |s| s.deploy.selective.map(|selective| selective.to_string()), "false"),
+        ("deploy.convergence_timeout", |s| s.deploy.convergence_timeout.clone(), "5m"),
+        ("updates.failure_threshold", |s| s.updates.failure_threshold.map(|threshold| threshold.max(1).to_string()), "3"),
+        ("updates.traffic_aware", |s| s.updates.traffic_aware.map(|aware| aware.to_string()), "true"),
+        ("updates.canary", |s| s.updates.canary.as_ref().map(|canary| format!("duration {}, max_restarts {}, max_error_lines {}",
+            canary.duration.as_deref().unwrap_or("2m"), canary.max_restarts(), canary.max_error_lines())), "disabled"),
+        ("updates.hash_exclude", |s| s.updates.hash_exclude.as_ref().map(|exclude| format!("lines [{}], keys [{}]",
+            exclude.lines.join(", "), exclude.keys.join(", "))), "none"),
+        ("updates.auto_rollback", |s| s.updates.auto_rollback.map(|rollback| rollback.to_string()), "false"),
+        ("notifications.failure_issues", |s| s.notifications.failure_issues.map(|issues| issues.to_string()), "false"),
+        ("task_history.keep", |s| s.task_history.keep.map(|keep| keep.to_string()), "all the tasks Swarm keeps"),
+        ("task_history.max_age", |s| s.task_history.max_age.clone(), "none"),
+    ]
+}
+
+impl RepositorySettings {
+    /// Settings of the repository whose stacks.yaml is in `dir`, the defaults without a `dockerops.yaml`
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join("dockerops.yaml");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let settings: Self = serde_yaml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| anyhow::anyhow!("Invalid dockerops.yaml: {}", e))?;
+
+        let prefix = settings.stack_prefix.as_deref().unwrap_or_default();
+        if !prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(anyhow::anyhow!("Invalid stack_prefix '{}' in dockerops.yaml (letters, digits, '-' and '_' only)", prefix));
+        }
+        settings.validate().map_err(|e| anyhow::anyhow!("Invalid dockerops.yaml: {}", e))?;
+        Ok(settings)
+    }
+
+    /// Layer of the settings given by a stacks.yaml entry
+    fn of_stack(stack: &StackDefinition) -> Self {
+        let mut deploy = stack.deploy.clone().unwrap_or_default();
+        deploy.with_registry_auth = deploy.with_registry_auth.or(stack.with_registry_auth);
+        Self {
+            stack_prefix: None,
+            prune_stacks: None,
+            deploy,
+            updates: stack.updates.clone().unwrap_or_default(),
+            notifications: stack.notifications.clone().unwrap_or_default(),
+            task_history: stack.task_history.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Layer of the settings given by config.yaml
+    fn of_config(config: &Config) -> Self {
+        Self {
+            stack_prefix: None,
+            prune_stacks: config.prune_stacks,
+            deploy: config.deploy.clone().unwrap_or_default(),
+            updates: UpdateSettings {
+                failure_threshold: config.failure_threshold,
+                traffic_aware: None,
+                canary: config.canary.clone(),
+                hash_exclude: config.hash_exclude.clone(),
+                auto_rollback: config.auto_rollback,
+            },
+            notifications: NotificationSettings {
+                failure_issues: config.failure_issues,
+                webhooks: config.notification_webhooks().to_vec(),
+                inherit_webhooks: None,
+            },
+            task_history: config.task_history.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Settings applying to a stack: its stacks.yaml entry, then the
+    /// repository's dockerops.yaml, then config.yaml
+    pub fn for_stack(&self, config: &Config, stack: &StackDefinition) -> Result<Self> {
+        let stack_layer = Self::of_stack(stack);
+        let global = Self::of_config(config);
+        let settings = Self {
+            stack_prefix: self.stack_prefix.clone(),
+            prune_stacks: self.prune_stacks.or(global.prune_stacks),
+            deploy: stack_layer.deploy.or(&self.deploy.or(&global.deploy)),
+            updates: stack_layer.updates.or(&self.updates.or(&global.updates)),
+            notifications: NotificationSettings {
+                failure_issues: stack_layer.notifications.failure_issues.or(self.notifications.failure_issues).or(global.notifications.failure_issues),
+                webhooks: stack_layer.notifications.merged_webhooks(&self.notifications.merged_webhooks(&global.notifications.webhooks)),
+                inherit_webhooks: Some(false),
+            },
+            task_history: stack_layer.task_history.or(&self.task_history.or(&global.task_history)),
+        };
+        settings.validate().map_err(|e| anyhow::anyhow!("Invalid settings for stack '{}': {}", stack.name, e))?;
+        Ok(settings)
+    }
+
+    /// Every layered setting of a stack with the layer it comes from
+    pub fn explain(&self, config: &Config, stack: &StackDefinition) -> Vec<EffectiveSetting> {
+        let layers = [
+            ("stacks.yaml", Self::of_stack(stack)),
+            ("dockerops.yaml", self.clone()),
+            ("config.yaml", Self::of_config(config)),
+        ];
+        let mut settings: Vec<EffectiveSetting> = layered_settings().into_iter()
+            .map(|(key, value_in, default)| {
+                let found = layers.iter().find_map(|(source, layer)| value_in(layer).map(|value| (value, *source)));
+                let (value, source) = found.unwrap_or_else(|| (default.to_string(), "default"));
+                EffectiveSetting { key, value, source }
+            })
+            .collect();
+
+        // Webhooks add up across the layers instead of overriding each other
+        let webhooks = layers[0].1.notifications.merged_webhooks(&layers[1].1.notifications.merged_webhooks(&layers[2].1.notifications.webhooks));
+        let sources: Vec<&'static str> = layers.iter()
+            .filter(|(_, layer)| layer.notifications.webhooks.iter().any(|url| webhooks.contains(url)))
+            .map(|(source, _)| *source)
+            .collect();
+        settings.push(EffectiveSetting {
+            key: "notifications.webhooks",
+            value: match webhooks.is_empty() {
+                true => "none".to_string(),
+                false => webhooks.iter().map(|url| crate::notify::display_url(url)).collect::<Vec<_>>().join(", "),
+            },
+            source: match sources.as_slice() {
+                [] => "default",
+                [source] => source,
+                _ => "merged",
+            },
+        });
+        settings
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.deploy.convergence_timeout().map_err(|e| anyhow::anyhow!("deploy.convergence_timeout: {}", e))?;
+        if let Some(canary) = &self.updates.canary {
+            canary.duration().map_err(|e| anyhow::anyhow!("updates.canary.duration: {}", e))?;
+        }
+        self.task_history.max_age().map_err(|e| anyhow::anyhow!("task_history.max_age: {}", e))?;
+        Ok(())
+    }
+
+    /// Swarm stack name of a stack declared in stacks.yaml
+    pub fn stack_name(&self, name: &str) -> String {
+        format!("{}{}", self.stack_prefix.as_deref().unwrap_or_default(), name)
+    }
+
+    /// Settings of a repository watched in a namespace, whose stack names
+    /// start with `<namespace>_` before the `stack_prefix`
+    pub fn in_namespace(mut self, namespace: Option<&str>) -> Self {
+        if let Some(namespace) = namespace {
+            self.stack_prefix = Some(format!("{}_{}", namespace, self.stack_prefix.unwrap_or_default()));
+        }
+        self
+    }
+
+    /// Name in stacks.yaml (and directory) of a Swarm stack of the repository
+    pub fn declared_name<'a>(&self, stack_name: &'a str) -> &'a str {
+        stack_name.strip_prefix(self.stack_prefix.as_deref().unwrap_or_default()).unwrap_or(stack_name)
+    }
+
+    /// Consecutive failures after which the stack is no longer retried, once merged with `for_stack`
+    pub fn failure_threshold(&self) -> i64 {
+        self.updates.failure_threshold.unwrap_or(3).max(1) as i64
+    }
+
+    pub fn failure_issues(&self) -> bool {
+        self.notifications.failure_issues.unwrap_or(false)
+    }
+
+    /// Webhooks notified of the stack's failures, once merged with `for_stack`
+    pub fn notification_webhooks(&self) -> &[String] {
+        &self.notifications.webhooks
+    }
+
+    /// Whether the stacks removed from stacks.yaml are removed from Swarm, once merged with `for_stack`
+    pub fn prune_stacks(&self) -> bool {
+        self.prune_stacks.unwrap_or(false)
+    }
+
+    pub fn traffic_aware(&self) -> bool {
+        self.updates.traffic_aware.unwrap_or(true)
+    }
+
+    pub fn auto_rollback(&self) -> bool {
+        self.updates.auto_rollback.unwrap_or(false)
+    }
+
+    pub fn hash_exclusions(&self) -> HashExclusions {
+        self.updates.hash_exclude.clone().unwrap_or_default()
+    }
+}
+
+/// Parse a human friendly duration such as `90`, `60s`, `5m`, `2h` or `1d`.
+/// A bare number is interpreted as seconds.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "s"),
+    };
+
+    let amount: u64 = number.parse()
+        .map_err(|_| anyhow::anyhow!("Invalid duration '{}' (expected e.g. 30s, 5m, 1h)", value))?;
+
+    let multiplier: u64 = match unit.trim() {
+        "s" | "sec" | "secs" => 1,
+        "m" | "min" | "mins" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        other => return Err(anyhow::anyhow!("Invalid duration unit '{}' in '{}' (expected s, m, h or d)", other, value)),
+    };
+    let seconds = amount.checked_mul(multiplier)
+        .ok_or_else(|| anyhow::anyhow!("Duration '{}' is too long", value))?;
+
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Parse the period of a recurring task, which can't be zero
+pub fn parse_interval(value: &str) -> Result<Duration> {
+    let interval = parse_duration(value)?;
+    if interval.is_zero() {
+        return Err(anyhow::anyhow!("Invalid interval '{}' (must be longer than 0s)", value.trim()));
+    }
+    Ok(interval)
+}
+
+/// Parse a rate such as `500KB/s`, `5MB/s` or `1G` (binary multiples, `/s` optional)
+pub fn parse_bandwidth(value: &str) -> Result<u64> {
+    let trimmed = value.trim();
+    parse_bytes(trimmed.strip_suffix("/s").unwrap_or(trimmed), "bandwidth", "500KB/s, 5MB/s")
+}
+
+/// Parse a size such as `500MB` or `2GB` (binary multiples)
+pub fn parse_size(value: &str) -> Result<u64> {
+    parse_bytes(value.trim(), "size", "500MB, 2GB")
+}
+
+fn parse_bytes(value: &str, what: &str, examples: &str) -> Result<u64> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "B"),
+    };
+
+    let amount: u64 = number.parse()
+        .map_err(|_| anyhow::anyhow!("Invalid {} '{}' (expected e.g. {})", what, value, examples))?;
+
+    let multiplier = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        other => return Err(anyhow::anyhow!("Invalid {} unit '{}' in '{}' (expected B, KB, MB or GB)", what, other, value)),
+    };
+
+    Ok(amount * multiplier)
+}
@@ -1,5 +1,7 @@
 use sqlx::sqlite::SqlitePool;
-use crate::models::{Image, Stack, RepositoryCache};
+use sqlx::Row;
+use crate::models::{Deployment, DriftEvent, GcRun, Image, ImageFreshness, OutboxNotification, OverrideConflict, Stack, RenderCacheEntry, StackFailure, StackOutput, StackRevision, RepositoryCache, TrashedStack, WebhookDelivery};
+use std::collections::BTreeMap;
 
 pub struct Database {
     pool: SqlitePool,
@@ -64,134 +66,1299 @@ impl Database {
         .execute(pool)
         .await?;
 
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS stack_images (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                stack_name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                image TEXT NOT NULL,
+                UNIQUE(stack_name, repository_url, image)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS stack_trash (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                compose_path TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                compose_content TEXT,
+                reason TEXT NOT NULL,
+                deleted_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS image_freshness (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                stack_name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                service TEXT NOT NULL,
+                image TEXT NOT NULL,
+                deployed_digest TEXT,
+                latest_digest TEXT,
+                newer_tags INTEGER NOT NULL DEFAULT 0,
+                latest_tag TEXT,
+                stale_since TEXT,
+                checked_at TEXT NOT NULL,
+                UNIQUE(stack_name, repository_url, service)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS stack_failures (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                stack_name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                failures INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT NOT NULL,
+                issue_number INTEGER,
+                failed_at TEXT NOT NULL,
+                UNIQUE(stack_name, repository_url)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS stack_outputs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                stack_name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                name TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                UNIQUE(stack_name, repository_url, name)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS override_conflicts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                stack_name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                overrides TEXT NOT NULL,
+                resolution TEXT,
+                detected_at TEXT NOT NULL,
+                UNIQUE(stack_name, repository_url)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS drift_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                stack_name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                object_type TEXT NOT NULL,
+                object_name TEXT NOT NULL,
+                action TEXT NOT NULL,
+                detected_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS deployments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                stack_name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                commit_hash TEXT,
+                kind TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                result TEXT NOT NULL,
+                error TEXT
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS stack_revisions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                stack_name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                revision INTEGER NOT NULL,
+                hash TEXT NOT NULL,
+                compose_content TEXT NOT NULL,
+                deployed_at TEXT NOT NULL,
+                rolled_back_from TEXT,
+                UNIQUE(stack_name, repository_url, revision)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS canary_analyses (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                stack_name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                finished_at TEXT NOT NULL,
+                passed INTEGER NOT NULL,
+                summary TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS notification_outbox (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                url TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TEXT NOT NULL,
+                last_error TEXT,
+                status TEXT NOT NULL DEFAULT 'pending',
+                created_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS gc_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                started_at TEXT NOT NULL,
+                trigger TEXT NOT NULL,
+                reclaimed_bytes INTEGER NOT NULL,
+                report TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS render_cache (
+                stack_name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                input_hash TEXT NOT NULL,
+                content TEXT NOT NULL,
+                outputs TEXT NOT NULL,
+                rendered_at TEXT NOT NULL,
+                PRIMARY KEY(stack_name, repository_url)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                delivery_id TEXT PRIMARY KEY,
+                repository_url TEXT NOT NULL,
+                git_ref TEXT NOT NULL,
+                commit_hash TEXT,
+                sender TEXT NOT NULL,
+                received_at TEXT NOT NULL,
+                processed_at TEXT,
+                result TEXT
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Columns added after the initial schema
+        Self::add_column_if_missing(pool, "repository_cache", "branch", "TEXT").await?;
+        Self::add_column_if_missing(pool, "stacks", "compose_content", "TEXT").await?;
+        Self::add_column_if_missing(pool, "repository_cache", "tag_pattern", "TEXT").await?;
+        Self::add_column_if_missing(pool, "repository_cache", "deployed_tag", "TEXT").await?;
+        Self::add_column_if_missing(pool, "repository_cache", "pinned_commit", "TEXT").await?;
+        Self::add_column_if_missing(pool, "repository_cache", "last_commit", "TEXT").await?;
+        Self::add_column_if_missing(pool, "stacks", "deferred_since", "TEXT").await?;
+        Self::add_column_if_missing(pool, "repository_cache", "last_attempt", "TEXT").await?;
+        Self::add_column_if_missing(pool, "repository_cache", "last_success", "TEXT").await?;
+        Self::add_column_if_missing(pool, "repository_cache", "last_error", "TEXT").await?;
+        Self::add_column_if_missing(pool, "repository_cache", "path", "TEXT").await?;
+        Self::add_column_if_missing(pool, "stacks", "app", "TEXT").await?;
+        Self::add_column_if_missing(pool, "deployments", "images", "TEXT").await?;
+        Self::add_column_if_missing(pool, "deployments", "operator", "TEXT").await?;
+        Self::add_column_if_missing(pool, "stack_trash", "deleted_by", "TEXT").await?;
+        Self::add_column_if_missing(pool, "stack_trash", "snapshot_dir", "TEXT").await?;
+        Self::add_column_if_missing(pool, "override_conflicts", "resolved_by", "TEXT").await?;
+        Self::add_column_if_missing(pool, "gc_runs", "operator", "TEXT").await?;
+        Self::add_column_if_missing(pool, "repository_cache", "namespace", "TEXT").await?;
+
+        // last_watch used to be updated by every successful sync
+        sqlx::query("UPDATE repository_cache SET last_success = last_watch WHERE last_success IS NULL AND last_attempt IS NULL")
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Add a column to an existing table so databases created by older versions keep working
+    async fn add_column_if_missing(pool: &SqlitePool, table: &str, column: &str, definition: &str) -> Result<(), sqlx::Error> {
+        let columns = sqlx::query("SELECT name FROM pragma_table_info(?)")
+            .bind(table)
+            .fetch_all(pool)
+            .await?;
+
+        if !columns.iter().any(|row| row.get::<String, _>("name") == column) {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // Image operations
+    pub async fn get_image_by_name(&self, name: &str) -> Result<Option<Image>, sqlx::Error> {
+        let row = sqlx::query_as::<_, Image>(
+            "SELECT id, name, reference_count FROM images WHERE name = ?"
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn delete_image(&self, name: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM images WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Replace the images used by a stack
+    pub async fn set_stack_images(&self, stack_name: &str, repository_url: &str, images: &[String]) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM stack_images WHERE stack_name = ? AND repository_url = ?")
+            .bind(stack_name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        for image in images {
+            sqlx::query("INSERT OR IGNORE INTO stack_images (stack_name, repository_url, image) VALUES (?, ?, ?)")
+                .bind(stack_name)
+                .bind(repository_url)
+                .bind(image)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Distinct images used by the stacks of one repository
+    pub async fn get_stack_images(&self, stack_name: &str, repository_url: &str) -> Result<Vec<String>, sqlx::Error> {
+        let images = sqlx::query_scalar::<_, String>(
+            "SELECT image FROM stack_images WHERE stack_name = ? AND repository_url = ? ORDER BY image"
+        )
+        .bind(stack_name)
+        .bind(repository_url)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(images)
+    }
+
+    pub async fn get_repository_images(&self, repository_url: &str) -> Result<Vec<String>, sqlx::Error> {
+        let images = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT image FROM stack_images WHERE repository_url = ? ORDER BY image"
+        )
+        .bind(repository_url)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(images)
+    }
+
+
+
+
+
+    // Stack operations
+    pub async fn create_stack(&self, stack: &Stack) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query(
+            "INSERT OR REPLACE INTO stacks (name, repository_url, compose_path, hash, status, app) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&stack.name)
+        .bind(&stack.repository_url)
+        .bind(&stack.compose_path)
+        .bind(&stack.hash)
+        .bind(&stack.status)
+        .bind(&stack.app)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    pub async fn get_stack_by_name(&self, name: &str, repository_url: &str) -> Result<Option<Stack>, sqlx::Error> {
+        let row = sqlx::query_as::<_, Stack>(
+            "SELECT id, name, repository_url, compose_path, hash, status, deferred_since, app FROM stacks WHERE name = ? AND repository_url = ?"
+        )
+        .bind(name)
+        .bind(repository_url)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn get_stacks_named(&self, name: &str) -> Result<Vec<Stack>, sqlx::Error> {
+        let stacks = sqlx::query_as::<_, Stack>(
+            "SELECT id, name, repository_url, compose_path, hash, status, deferred_since, app FROM stacks WHERE name = ? ORDER BY repository_url"
+        )
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(stacks)
+    }
+
+    pub async fn get_all_stacks(&self) -> Result<Vec<Stack>, sqlx::Error> {
+        let stacks = sqlx::query_as::<_, Stack>(
+            "SELECT id, name, repository_url, compose_path, hash, status, deferred_since, app FROM stacks ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(stacks)
+    }
+
+    pub async fn update_stack_status(&self, name: &str, repository_url: &str, status: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE stacks SET status = ? WHERE name = ? AND repository_url = ?")
+            .bind(status)
+            .bind(name)
+            .bind(repository_url)
+            .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_stack_hash(&self, name: &str, repository_url: &str, hash: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE stacks SET hash = ? WHERE name = ? AND repository_url = ?")
+            .bind(hash)
+            .bind(name)
+            .bind(repository_url)
+            .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Keep the rendered compose file that was last deployed for a stack
+    pub async fn update_stack_deferred_since(&self, name: &str, repository_url: &str, deferred_since: Option<&str>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE stacks SET deferred_since = ? WHERE name = ? AND repository_url = ?")
+            .bind(deferred_since)
+            .bind(name)
+            .bind(repository_url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_stack_app(&self, name: &str, repository_url: &str, app: Option<&str>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE stacks SET app = ? WHERE name = ? AND repository_url = ?")
+            .bind(app)
+            .bind(name)
+            .bind(repository_url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_stack_compose_content(&self, name: &str, repository_url: &str) -> Result<Option<String>, sqlx::Error> {
+        let content = sqlx::query_scalar::<_, Option<String>>("SELECT compose_content FROM stacks WHERE name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(content.flatten())
+    }
+
+    pub async fn update_stack_compose_content(&self, name: &str, repository_url: &str, compose_content: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE stacks SET compose_content = ? WHERE name = ? AND repository_url = ?")
+            .bind(compose_content)
+            .bind(name)
+            .bind(repository_url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_all_stacks(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM stacks")
+            .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Trash operations
+    /// Move a stack and its last deployed compose file to the trash
+    pub async fn trash_stack(&self, name: &str, repository_url: &str, reason: &str, deleted_by: &str, snapshot_dir: Option<&str>) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO stack_trash (name, repository_url, compose_path, hash, compose_content, reason, deleted_at, deleted_by, snapshot_dir) \
+             SELECT name, repository_url, compose_path, hash, compose_content, ?, ?, ?, ? FROM stacks WHERE name = ? AND repository_url = ?"
+        )
+        .bind(reason)
+        .bind(&now)
+        .bind(deleted_by)
+        .bind(snapshot_dir)
+        .bind(name)
+        .bind(repository_url)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM stacks WHERE name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM image_freshness WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM stack_images WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM stack_failures WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM stack_outputs WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM override_conflicts WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM drift_events WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM canary_analyses WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM stack_revisions WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM render_cache WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn get_trashed_stacks(&self) -> Result<Vec<TrashedStack>, sqlx::Error> {
+        let stacks = sqlx::query_as::<_, TrashedStack>(
+            "SELECT id, name, repository_url, compose_path, hash, compose_content, reason, deleted_at, deleted_by, snapshot_dir FROM stack_trash ORDER BY deleted_at DESC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(stacks)
+    }
+
+    pub async fn delete_trashed_stack(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM stack_trash WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Permanently delete trash entries older than the given RFC 3339 timestamp
+    pub async fn purge_trash_before(&self, cutoff: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM stack_trash WHERE deleted_at < ?")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Stack failure operations
+    /// Count one more consecutive deploy failure of the same compose hash,
+    /// returning the new count. A failure of another revision starts over at 1.
+    pub async fn record_stack_failure(&self, name: &str, repository_url: &str, hash: &str, error: &str) -> Result<i64, sqlx::Error> {
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query(
+            r#"
+            INSERT INTO stack_failures (stack_name, repository_url, hash, failures, last_error, failed_at)
+            VALUES (?, ?, ?, 1, ?, ?)
+            ON CONFLICT(stack_name, repository_url) DO UPDATE SET
+                failures = CASE WHEN stack_failures.hash = excluded.hash THEN stack_failures.failures + 1 ELSE 1 END,
+                hash = excluded.hash,
+                last_error = excluded.last_error,
+                failed_at = excluded.failed_at
+            "#,
+        )
+        .bind(name)
+        .bind(repository_url)
+        .bind(hash)
+        .bind(error)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        let failures = sqlx::query_scalar("SELECT failures FROM stack_failures WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(failures)
+    }
+
+    pub async fn get_stack_failure(&self, name: &str, repository_url: &str) -> Result<Option<StackFailure>, sqlx::Error> {
+        let failure = sqlx::query_as::<_, StackFailure>(
+            "SELECT id, stack_name, repository_url, hash, failures, last_error, issue_number, failed_at FROM stack_failures WHERE stack_name = ? AND repository_url = ?"
+        )
+        .bind(name)
+        .bind(repository_url)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(failure)
+    }
+
+    pub async fn set_stack_failure_issue(&self, name: &str, repository_url: &str, issue_number: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE stack_failures SET issue_number = ? WHERE stack_name = ? AND repository_url = ?")
+            .bind(issue_number)
+            .bind(name)
+            .bind(repository_url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_stack_failure(&self, name: &str, repository_url: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM stack_failures WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Notification outbox operations
+    pub async fn enqueue_notification(&self, url: &str, payload: &str) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query("INSERT INTO notification_outbox (url, payload, attempts, next_attempt_at, status, created_at) VALUES (?, ?, 0, ?, 'pending', ?)")
+            .bind(url)
+            .bind(payload)
+            .bind(&now)
+            .bind(&now)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Pending notifications whose next attempt is due at `now` (RFC 3339), oldest first
+    pub async fn get_due_notifications(&self, now: &str) -> Result<Vec<OutboxNotification>, sqlx::Error> {
+        let notifications = sqlx::query_as::<_, OutboxNotification>(
+            "SELECT id, url, payload, attempts, next_attempt_at, last_error, status, created_at FROM notification_outbox \
+             WHERE status = 'pending' AND next_attempt_at <= ? ORDER BY id"
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(notifications)
+    }
+
+    /// Notifications not delivered yet, whether they are still retried or not
+    pub async fn get_undelivered_notifications(&self) -> Result<Vec<OutboxNotification>, sqlx::Error> {
+        let notifications = sqlx::query_as::<_, OutboxNotification>(
+            "SELECT id, url, payload, attempts, next_attempt_at, last_error, status, created_at FROM notification_outbox ORDER BY id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(notifications)
+    }
+
+    /// Record a failed delivery attempt, the notification being retried at
+    /// `next_attempt_at` or given up on with the `dead` status
+    pub async fn record_notification_failure(&self, id: i64, next_attempt_at: &str, error: &str, status: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE notification_outbox SET attempts = attempts + 1, next_attempt_at = ?, last_error = ?, status = ? WHERE id = ?")
+            .bind(next_attempt_at)
+            .bind(error)
+            .bind(status)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_notification(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM notification_outbox WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Forget undelivered notifications created before the given RFC 3339 timestamp
+    pub async fn purge_dead_notifications_before(&self, cutoff: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM notification_outbox WHERE status = 'dead' AND created_at < ?")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Garbage collection operations
+    pub async fn record_gc_run(&self, started_at: &str, trigger: &str, reclaimed_bytes: i64, report: &str, operator: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO gc_runs (started_at, trigger, reclaimed_bytes, report, operator) VALUES (?, ?, ?, ?, ?)")
+            .bind(started_at)
+            .bind(trigger)
+            .bind(reclaimed_bytes)
+            .bind(report)
+            .bind(operator)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Most recent runs first
+    pub async fn get_gc_runs(&self, limit: i64) -> Result<Vec<GcRun>, sqlx::Error> {
+        let runs = sqlx::query_as::<_, GcRun>(
+            "SELECT id, started_at, trigger, reclaimed_bytes, report, operator FROM gc_runs ORDER BY started_at DESC, id DESC LIMIT ?"
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(runs)
+    }
+
+    // Override conflict operations
+    /// Record a conflict for a git state, a previous resolution only applies to the state it was chosen for
+    pub async fn record_override_conflict(&self, name: &str, repository_url: &str, hash: &str, overrides: &str) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query(
+            r#"
+            INSERT INTO override_conflicts (stack_name, repository_url, hash, overrides, resolution, detected_at)
+            VALUES (?, ?, ?, ?, NULL, ?)
+            ON CONFLICT(stack_name, repository_url) DO UPDATE SET
+                hash = excluded.hash,
+                overrides = excluded.overrides,
+                resolution = CASE WHEN override_conflicts.hash = excluded.hash THEN override_conflicts.resolution END,
+                resolved_by = CASE WHEN override_conflicts.hash = excluded.hash THEN override_conflicts.resolved_by END,
+                detected_at = CASE WHEN override_conflicts.hash = excluded.hash THEN override_conflicts.detected_at ELSE excluded.detected_at END
+            "#,
+        )
+        .bind(name)
+        .bind(repository_url)
+        .bind(hash)
+        .bind(overrides)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_override_conflict(&self, name: &str, repository_url: &str) -> Result<Option<OverrideConflict>, sqlx::Error> {
+        let conflict = sqlx::query_as::<_, OverrideConflict>(
+            "SELECT id, stack_name, repository_url, hash, overrides, resolution, detected_at, resolved_by FROM override_conflicts WHERE stack_name = ? AND repository_url = ?"
+        )
+        .bind(name)
+        .bind(repository_url)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(conflict)
+    }
+
+    pub async fn get_override_conflicts(&self) -> Result<Vec<OverrideConflict>, sqlx::Error> {
+        let conflicts = sqlx::query_as::<_, OverrideConflict>(
+            "SELECT id, stack_name, repository_url, hash, overrides, resolution, detected_at, resolved_by FROM override_conflicts ORDER BY detected_at"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(conflicts)
+    }
+
+    pub async fn set_override_resolution(&self, name: &str, repository_url: &str, resolution: &str, resolved_by: Option<&str>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE override_conflicts SET resolution = ?, resolved_by = ? WHERE stack_name = ? AND repository_url = ?")
+            .bind(resolution)
+            .bind(resolved_by)
+            .bind(name)
+            .bind(repository_url)
+            .execute(&self.pool)
+            .await?;
+
         Ok(())
     }
 
-    // Image operations
-    pub async fn create_image(&self, image: &Image) -> Result<i64, sqlx::Error> {
-        let id = sqlx::query(
-            "INSERT OR IGNORE INTO images (name, reference_count) VALUES (?, ?)"
+    pub async fn delete_override_conflict(&self, name: &str, repository_url: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM override_conflicts WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Drift operations
+    /// Record a change made to a Docker object of the stack outside DockerOps
+    pub async fn record_drift_event(&self, name: &str, repository_url: &str, object_type: &str, object_name: &str, action: &str) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO drift_events (stack_name, repository_url, object_type, object_name, action, detected_at) VALUES (?, ?, ?, ?, ?, ?)"
         )
-        .bind(&image.name)
-        .bind(image.reference_count)
+        .bind(name)
+        .bind(repository_url)
+        .bind(object_type)
+        .bind(object_name)
+        .bind(action)
+        .bind(&now)
         .execute(&self.pool)
-        .await?
-        .last_insert_rowid();
+        .await?;
 
-        Ok(id)
+        Ok(())
     }
 
-    pub async fn get_image_by_name(&self, name: &str) -> Result<Option<Image>, sqlx::Error> {
-        let row = sqlx::query_as::<_, Image>(
-            "SELECT id, name, reference_count FROM images WHERE name = ?"
+    pub async fn record_deployment(&self, deployment: &Deployment) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO deployments (stack_name, repository_url, hash, commit_hash, kind, started_at, duration_ms, result, error, images, operator) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
-        .bind(name)
-        .fetch_optional(&self.pool)
+        .bind(&deployment.stack_name)
+        .bind(&deployment.repository_url)
+        .bind(&deployment.hash)
+        .bind(&deployment.commit_hash)
+        .bind(&deployment.kind)
+        .bind(&deployment.started_at)
+        .bind(deployment.duration_ms)
+        .bind(&deployment.result)
+        .bind(&deployment.error)
+        .bind(&deployment.images)
+        .bind(&deployment.operator)
+        .execute(&self.pool)
         .await?;
 
-        Ok(row)
+        Ok(())
     }
 
-    pub async fn update_image_reference_count(&self, name: &str, count: i32) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE images SET reference_count = ? WHERE name = ?")
-            .bind(count)
-            .bind(name)
+    /// Latest deployments, of one stack when a name is given, newest first
+    pub async fn get_deployments(&self, stack_name: Option<&str>, repository_url: Option<&str>, limit: i64) -> Result<Vec<Deployment>, sqlx::Error> {
+        let deployments = sqlx::query_as::<_, Deployment>(
+            "SELECT d.id, d.stack_name, d.repository_url, d.hash, d.commit_hash, d.kind, d.started_at, d.duration_ms, d.result, d.error, d.images, d.operator, \
+             (SELECT c.passed FROM canary_analyses c WHERE c.stack_name = d.stack_name AND c.repository_url = d.repository_url \
+              AND c.hash = d.hash AND c.started_at = d.started_at) AS canary_passed \
+             FROM deployments d WHERE (?1 IS NULL OR d.stack_name = ?1) AND (?2 IS NULL OR d.repository_url = ?2) \
+             ORDER BY d.started_at DESC, d.id DESC LIMIT ?3"
+        )
+        .bind(stack_name)
+        .bind(repository_url)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(deployments)
+    }
+
+    /// Last successful deployment of every stack at `at` (RFC 3339), leaving
+    /// out the stacks removed between that deployment and `at`
+    pub async fn get_deployments_at(&self, at: &str, repository_url: Option<&str>) -> Result<Vec<Deployment>, sqlx::Error> {
+        let deployments = sqlx::query_as::<_, Deployment>(
+            "SELECT d.id, d.stack_name, d.repository_url, d.hash, d.commit_hash, d.kind, d.started_at, d.duration_ms, d.result, d.error, d.images, d.operator, \
+             NULL AS canary_passed FROM deployments d \
+             WHERE d.id = (SELECT l.id FROM deployments l WHERE l.stack_name = d.stack_name AND l.repository_url = d.repository_url \
+              AND l.result = 'success' AND l.started_at <= ?1 ORDER BY l.started_at DESC, l.id DESC LIMIT 1) \
+             AND (?2 IS NULL OR d.repository_url = ?2) \
+             AND NOT EXISTS (SELECT 1 FROM stack_trash t WHERE t.name = d.stack_name AND t.repository_url = d.repository_url \
+              AND t.deleted_at > d.started_at AND t.deleted_at <= ?1) \
+             ORDER BY d.stack_name, d.repository_url"
+        )
+        .bind(at)
+        .bind(repository_url)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(deployments)
+    }
+
+    /// Keep a deployed compose file as the next revision of the stack, unless
+    /// it is already the latest one, and forget the revisions beyond `kept`
+    pub async fn record_stack_revision(&self, stack_name: &str, repository_url: &str, hash: &str, compose_content: &str, kept: i64) -> Result<(), sqlx::Error> {
+        let latest = sqlx::query("SELECT revision, hash FROM stack_revisions WHERE stack_name = ? AND repository_url = ? ORDER BY revision DESC LIMIT 1")
+            .bind(stack_name)
+            .bind(repository_url)
+            .fetch_optional(&self.pool)
+            .await?;
+        let revision = match latest {
+            Some(row) if row.get::<String, _>("hash") == hash => return Ok(()),
+            Some(row) => row.get::<i64, _>("revision") + 1,
+            None => 1,
+        };
+
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO stack_revisions (stack_name, repository_url, revision, hash, compose_content, deployed_at) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(stack_name)
+        .bind(repository_url)
+        .bind(revision)
+        .bind(hash)
+        .bind(compose_content)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query("DELETE FROM stack_revisions WHERE stack_name = ? AND repository_url = ? AND revision <= ?")
+            .bind(stack_name)
+            .bind(repository_url)
+            .bind(revision - kept)
             .execute(&self.pool)
             .await?;
 
         Ok(())
     }
 
+    /// Revisions of a stack, newest first
+    pub async fn get_stack_revisions(&self, stack_name: &str, repository_url: &str) -> Result<Vec<StackRevision>, sqlx::Error> {
+        let revisions = sqlx::query_as::<_, StackRevision>(
+            "SELECT id, stack_name, repository_url, revision, hash, compose_content, deployed_at, rolled_back_from FROM stack_revisions \
+             WHERE stack_name = ? AND repository_url = ? ORDER BY revision DESC"
+        )
+        .bind(stack_name)
+        .bind(repository_url)
+        .fetch_all(&self.pool)
+        .await?;
 
+        Ok(revisions)
+    }
 
+    /// Remember that a revision was redeployed in place of the compose file with hash `from`
+    pub async fn set_stack_revision_rolled_back_from(&self, id: i64, from: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE stack_revisions SET rolled_back_from = ? WHERE id = ?")
+            .bind(from)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
 
+        Ok(())
+    }
 
-    // Stack operations
-    pub async fn create_stack(&self, stack: &Stack) -> Result<i64, sqlx::Error> {
-        let id = sqlx::query(
-            "INSERT OR REPLACE INTO stacks (name, repository_url, compose_path, hash, status) VALUES (?, ?, ?, ?, ?)"
+    /// Keep the outcome of the canary analysis of a stack update
+    pub async fn record_canary_analysis(&self, stack_name: &str, repository_url: &str, hash: &str, started_at: &str, passed: bool, summary: &str) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO canary_analyses (stack_name, repository_url, hash, started_at, finished_at, passed, summary) VALUES (?, ?, ?, ?, ?, ?, ?)"
         )
-        .bind(&stack.name)
-        .bind(&stack.repository_url)
-        .bind(&stack.compose_path)
-        .bind(&stack.hash)
-        .bind(&stack.status)
+        .bind(stack_name)
+        .bind(repository_url)
+        .bind(hash)
+        .bind(started_at)
+        .bind(&now)
+        .bind(passed)
+        .bind(summary)
         .execute(&self.pool)
-        .await?
-        .last_insert_rowid();
+        .await?;
 
-        Ok(id)
+        Ok(())
     }
 
-    pub async fn get_stack_by_name(&self, name: &str, repository_url: &str) -> Result<Option<Stack>, sqlx::Error> {
-        let row = sqlx::query_as::<_, Stack>(
-            "SELECT id, name, repository_url, compose_path, hash, status FROM stacks WHERE name = ? AND repository_url = ?"
+    pub async fn get_drift_events(&self) -> Result<Vec<DriftEvent>, sqlx::Error> {
+        let events = sqlx::query_as::<_, DriftEvent>(
+            "SELECT id, stack_name, repository_url, object_type, object_name, action, detected_at FROM drift_events ORDER BY detected_at"
         )
-        .bind(name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Forget the drift of a stack once DockerOps deployed it again
+    pub async fn delete_drift_events(&self, name: &str, repository_url: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM drift_events WHERE stack_name = ? AND repository_url = ?")
+            .bind(name)
+            .bind(repository_url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Stack output operations
+    /// Replace the outputs published by a stack
+    pub async fn set_stack_outputs(&self, stack_name: &str, repository_url: &str, outputs: &BTreeMap<String, String>) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM stack_outputs WHERE stack_name = ? AND repository_url = ?")
+            .bind(stack_name)
+            .bind(repository_url)
+            .execute(&mut *tx)
+            .await?;
+
+        for (name, value) in outputs {
+            sqlx::query("INSERT INTO stack_outputs (stack_name, repository_url, name, value, updated_at) VALUES (?, ?, ?, ?, ?)")
+                .bind(stack_name)
+                .bind(repository_url)
+                .bind(name)
+                .bind(value)
+                .bind(&now)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Outputs of the stack with this name, whichever repository deploys it
+    pub async fn get_stack_outputs(&self, stack_name: &str) -> Result<Vec<StackOutput>, sqlx::Error> {
+        let outputs = sqlx::query_as::<_, StackOutput>(
+            "SELECT id, stack_name, repository_url, name, value, updated_at FROM stack_outputs WHERE stack_name = ? ORDER BY name"
+        )
+        .bind(stack_name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(outputs)
+    }
+
+    // Render cache operations
+    pub async fn get_render_cache(&self, stack_name: &str, repository_url: &str) -> Result<Option<RenderCacheEntry>, sqlx::Error> {
+        let entry = sqlx::query_as::<_, RenderCacheEntry>(
+            "SELECT stack_name, repository_url, input_hash, content, outputs, rendered_at FROM render_cache WHERE stack_name = ? AND repository_url = ?"
+        )
+        .bind(stack_name)
         .bind(repository_url)
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row)
+        Ok(entry)
     }
 
-    pub async fn get_all_stacks(&self) -> Result<Vec<Stack>, sqlx::Error> {
-        let stacks = sqlx::query_as::<_, Stack>(
-            "SELECT id, name, repository_url, compose_path, hash, status FROM stacks ORDER BY name"
+    pub async fn set_render_cache(&self, stack_name: &str, repository_url: &str, input_hash: &str, content: &str, outputs: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO render_cache (stack_name, repository_url, input_hash, content, outputs, rendered_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(stack_name, repository_url) DO UPDATE SET
+                input_hash = excluded.input_hash,
+                content = excluded.content,
+                outputs = excluded.outputs,
+                rendered_at = excluded.rendered_at
+            "#,
+        )
+        .bind(stack_name)
+        .bind(repository_url)
+        .bind(input_hash)
+        .bind(content)
+        .bind(outputs)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Webhook delivery operations
+    /// Record a delivery, false when one with the same id was already recorded
+    pub async fn record_webhook_delivery(&self, delivery: &WebhookDelivery) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO webhook_deliveries (delivery_id, repository_url, git_ref, commit_hash, sender, received_at) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&delivery.delivery_id)
+        .bind(&delivery.repository_url)
+        .bind(&delivery.git_ref)
+        .bind(&delivery.commit_hash)
+        .bind(&delivery.sender)
+        .bind(&delivery.received_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deliveries whose deployment did not end, oldest first
+    pub async fn get_pending_webhook_deliveries(&self) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+            "SELECT delivery_id, repository_url, git_ref, commit_hash, sender, received_at, processed_at, result FROM webhook_deliveries \
+             WHERE processed_at IS NULL ORDER BY received_at"
         )
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(stacks)
+        Ok(deliveries)
     }
 
-    pub async fn update_stack_status(&self, name: &str, repository_url: &str, status: &str) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE stacks SET status = ? WHERE name = ? AND repository_url = ?")
-            .bind(status)
-            .bind(name)
-            .bind(repository_url)
-            .execute(&self.pool)
+    /// Mark the pending deliveries of a repository received up to `received_until`
+    /// (RFC 3339) as processed, a deployment having started after them
+    pub async fn finish_webhook_deliveries(&self, repository_url: &str, received_until: &str, result: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE webhook_deliveries SET processed_at = ?, result = ? WHERE repository_url = ? AND processed_at IS NULL AND received_at <= ?"
+        )
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(result)
+        .bind(repository_url)
+        .bind(received_until)
+        .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    pub async fn update_stack_hash(&self, name: &str, repository_url: &str, hash: &str) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE stacks SET hash = ? WHERE name = ? AND repository_url = ?")
-            .bind(hash)
-            .bind(name)
-            .bind(repository_url)
+    /// Forget the processed deliveries received before the given RFC 3339 timestamp
+    pub async fn purge_webhook_deliveries_before(&self, cutoff: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM webhook_deliveries WHERE processed_at IS NOT NULL AND received_at < ?")
+            .bind(cutoff)
             .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Image freshness operations
+    /// Record a freshness check, keeping the original `stale_since` while the
+    /// service stays behind its registry
+    pub async fn upsert_image_freshness(&self, freshness: &ImageFreshness) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO image_freshness (stack_name, repository_url, service, image, deployed_digest, latest_digest, newer_tags, latest_tag, stale_since, checked_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(stack_name, repository_url, service) DO UPDATE SET
+                image = excluded.image,
+                deployed_digest = excluded.deployed_digest,
+                latest_digest = excluded.latest_digest,
+                newer_tags = excluded.newer_tags,
+                latest_tag = excluded.latest_tag,
+                stale_since = CASE WHEN excluded.stale_since IS NULL THEN NULL
+                                   ELSE COALESCE(image_freshness.stale_since, excluded.stale_since) END,
+                checked_at = excluded.checked_at
+            "#,
+        )
+        .bind(&freshness.stack_name)
+        .bind(&freshness.repository_url)
+        .bind(&freshness.service)
+        .bind(&freshness.image)
+        .bind(&freshness.deployed_digest)
+        .bind(&freshness.latest_digest)
+        .bind(freshness.newer_tags)
+        .bind(&freshness.latest_tag)
+        .bind(&freshness.stale_since)
+        .bind(&freshness.checked_at)
+        .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    pub async fn delete_all_stacks(&self) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM stacks")
+    /// Forget services of a stack that were not seen by the check made at `checked_at`
+    pub async fn delete_image_freshness_not_checked_at(&self, stack_name: &str, repository_url: &str, checked_at: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM image_freshness WHERE stack_name = ? AND repository_url = ? AND checked_at <> ?")
+            .bind(stack_name)
+            .bind(repository_url)
+            .bind(checked_at)
             .execute(&self.pool)
-        .await?;
+            .await?;
 
         Ok(())
     }
 
+    pub async fn get_all_image_freshness(&self) -> Result<Vec<ImageFreshness>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, ImageFreshness>(
+            "SELECT id, stack_name, repository_url, service, image, deployed_digest, latest_digest, newer_tags, latest_tag, stale_since, checked_at \
+             FROM image_freshness ORDER BY stack_name, service"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
     // Repository cache operations
-    pub async fn add_repository_to_cache(&self, url: &str) -> Result<(), sqlx::Error> {
-        let now = chrono::Utc::now().to_rfc3339();
+    /// Record a repository whose first sync (done by `watch`) succeeded
+    pub async fn add_repository_to_cache(&self, url: &str, branch: Option<&str>, tag_pattern: Option<&str>, deployed_tag: Option<&str>, path: Option<&str>) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now();
         sqlx::query(
-            "INSERT OR REPLACE INTO repository_cache (url, last_watch) VALUES (?, ?)"
+            "INSERT OR REPLACE INTO repository_cache (url, last_watch, last_attempt, last_success, branch, tag_pattern, deployed_tag, path) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(url)
-        .bind(&now)
+        .bind(now)
+        .bind(now)
+        .bind(now)
+        .bind(branch)
+        .bind(tag_pattern)
+        .bind(deployed_tag)
+        .bind(path)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
+    pub async fn record_repository_attempt(&self, url: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE repository_cache SET last_attempt = ? WHERE url = ?")
+            .bind(chrono::Utc::now())
+            .bind(url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_repository_success(&self, url: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE repository_cache SET last_success = ?, last_error = NULL WHERE url = ?")
+            .bind(chrono::Utc::now())
+            .bind(url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_repository_error(&self, url: &str, error: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE repository_cache SET last_error = ? WHERE url = ?")
+            .bind(error)
+            .bind(url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn pin_repository_commit(&self, url: &str, commit: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE repository_cache SET pinned_commit = ? WHERE url = ?")
+            .bind(commit)
+            .bind(url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_repository_namespace(&self, url: &str, namespace: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE repository_cache SET namespace = ? WHERE url = ?")
+            .bind(namespace)
+            .bind(url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_repository_deployed_tag(&self, url: &str, tag: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE repository_cache SET deployed_tag = ? WHERE url = ?")
+            .bind(tag)
+            .bind(url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_repository_last_commit(&self, url: &str, commit: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE repository_cache SET last_commit = ? WHERE url = ?")
+            .bind(commit)
+            .bind(url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn get_repository_from_cache(&self, url: &str) -> Result<Option<RepositoryCache>, sqlx::Error> {
         let row = sqlx::query_as::<_, RepositoryCache>(
-            "SELECT id, url, last_watch FROM repository_cache WHERE url = ?"
+            "SELECT id, url, last_watch, last_attempt, last_success, last_error, branch, tag_pattern, deployed_tag, pinned_commit, last_commit, path, namespace FROM repository_cache WHERE url = ?"
         )
         .bind(url)
         .fetch_optional(&self.pool)
@@ -202,7 +1369,7 @@ impl Database {
 
     pub async fn get_all_repositories(&self) -> Result<Vec<RepositoryCache>, sqlx::Error> {
         let repositories = sqlx::query_as::<_, RepositoryCache>(
-            "SELECT id, url, last_watch FROM repository_cache ORDER BY last_watch DESC"
+            "SELECT id, url, last_watch, last_attempt, last_success, last_error, branch, tag_pattern, deployed_tag, pinned_commit, last_commit, path, namespace FROM repository_cache ORDER BY last_watch DESC"
         )
         .fetch_all(&self.pool)
         .await?;
@@ -210,6 +1377,15 @@ impl Database {
         Ok(repositories)
     }
 
+    pub async fn remove_repository_from_cache(&self, url: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM repository_cache WHERE url = ?")
+            .bind(url)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn clear_repository_cache(&self) -> Result<(), sqlx::Error> {
         // Use a transaction to ensure the deletion is committed
         let mut tx = self.pool.begin().await?;
@@ -225,10 +1401,19 @@ impl Database {
     }
 
     // Image management operations
-    pub async fn reset_image_reference_counts(&self) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE images SET reference_count = 0")
-            .execute(&self.pool)
-        .await?;
+    /// Derive every image's reference count from the stacks of all repositories
+    pub async fn refresh_image_reference_counts(&self) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("INSERT OR IGNORE INTO images (name, reference_count) SELECT DISTINCT image, 0 FROM stack_images")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE images SET reference_count = (SELECT COUNT(*) FROM stack_images WHERE stack_images.image = images.name)")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
 
         Ok(())
     }
@@ -250,4 +1435,48 @@ impl Database {
 
         Ok(images)
     }
-}
\ No newline at end of file
+
+    /// Round trip to the database, for health checks
+    pub async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Write a consistent copy of the whole database to a new file
+    pub async fn snapshot_to(&self, path: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh database in a temporary file, removed by the caller
+    async fn test_database(name: &str) -> (Database, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("dockerops-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        (Database::new(&format!("sqlite:{}", path.display())).await.unwrap(), path)
+    }
+
+    #[tokio::test]
+    async fn stack_failures_start_over_when_the_hash_changes() {
+        let (db, path) = test_database("stack-failures").await;
+        let url = "https://github.com/acme/stacks";
+
+        assert_eq!(db.record_stack_failure("api", url, "hash-1", "boom").await.unwrap(), 1);
+        assert_eq!(db.record_stack_failure("api", url, "hash-1", "boom").await.unwrap(), 2);
+        assert_eq!(db.record_stack_failure("api", url, "hash-2", "other").await.unwrap(), 1);
+        assert_eq!(db.record_stack_failure("api", url, "hash-2", "other").await.unwrap(), 2);
+
+        let failure = db.get_stack_failure("api", url).await.unwrap().unwrap();
+        assert_eq!(failure.hash, "hash-2");
+        assert_eq!(failure.last_error, "other");
+        let _ = std::fs::remove_file(path);
+    }
+}
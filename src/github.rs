@@ -0,0 +1,324 @@
+use anyhow::Result;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::provider;
+
+const API_URL: &str = "https://api.github.com";
+
+// Requests are spread out to stay far below the 5000 requests per hour of
+// a token and clear of GitHub's secondary limits: bursts of 10, then one
+// request per second
+const BUCKET_CAPACITY: f64 = 10.0;
+const REQUESTS_PER_SECOND: f64 = 1.0;
+
+// Longest wait for the quota to come back before a request gives up, the
+// primary quota may only be reset an hour later
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+const MAX_RATE_LIMIT_RETRIES: usize = 3;
+
+/// HTTP client shared by every GitHub API call of the process. It answers
+/// repeated GETs from their ETag (304 responses don't count against the
+/// quota), spaces requests out and waits for the quota to come back when
+/// GitHub reports it exhausted or asks to retry later.
+pub struct GithubApi {
+    http: reqwest::Client,
+    etags: Mutex<HashMap<String, (String, serde_json::Value)>>,
+    bucket: Mutex<TokenBucket>,
+    exhausted_until: Mutex<Option<DateTime<Utc>>>,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Default for GithubApi {
+    fn default() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            etags: Mutex::new(HashMap::new()),
+            bucket: Mutex::new(TokenBucket { tokens: BUCKET_CAPACITY, updated: Instant::now() }),
+            exhausted_until: Mutex::new(None),
+        }
+    }
+}
+
+impl GithubApi {
+    /// Send an API request authenticated with the bearer token, returning its JSON body
+    pub async fn request(&self, method: reqwest::Method, path: &str, token: Option<&str>, body: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let cached = if method == reqwest::Method::GET { self.etags.lock().unwrap().get(path).cloned() } else { None };
+
+        let mut retries = 0;
+        loop {
+            self.wait_for_quota(path).await?;
+
+            let mut request = self.http.request(method.clone(), format!("{}{}", API_URL, path))
+                .header("Accept", "application/vnd.github+json")
+                .header("User-Agent", concat!("dockerops/", env!("CARGO_PKG_VERSION")));
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            if let Some(body) = &body {
+                request = request.json(body);
+            }
+            if let Some((etag, _)) = &cached {
+                request = request.header("If-None-Match", etag);
+            }
+
+            let response = request.send().await?;
+            self.record_quota(&response);
+            let status = response.status();
+            if status == StatusCode::NOT_MODIFIED {
+                if let Some((_, body)) = cached {
+                    return Ok(body);
+                }
+            }
+            if status == StatusCode::NO_CONTENT {
+                return Ok(serde_json::Value::Null);
+            }
+            if status.is_success() {
+                let etag = response.headers().get("ETag").and_then(|etag| etag.to_str().ok()).map(|etag| etag.to_string());
+                let body: serde_json::Value = response.json().await?;
+                if let (Some(etag), true) = (etag, method == reqwest::Method::GET) {
+                    self.etags.lock().unwrap().insert(path.to_string(), (etag, body.clone()));
+                }
+                return Ok(body);
+            }
+
+            if let Some(delay) = retry_delay(&response).filter(|delay| *delay <= MAX_RATE_LIMIT_WAIT && retries < MAX_RATE_LIMIT_RETRIES) {
+                println!("⚠️  GitHub API rate limit reached, retrying {} in {}s", path, delay.as_secs());
+                tokio::time::sleep(delay).await;
+                retries += 1;
+                continue;
+            }
+
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("GitHub API request {} failed with {}: {}", path, status, body.trim()));
+        }
+    }
+
+    /// Take a token from the bucket, after the quota reset when GitHub reported it exhausted
+    async fn wait_for_quota(&self, path: &str) -> Result<()> {
+        let exhausted_until = *self.exhausted_until.lock().unwrap();
+        if let Some(reset) = exhausted_until.filter(|reset| *reset > Utc::now()) {
+            let wait = (reset - Utc::now()).to_std().unwrap_or_default();
+            if wait > MAX_RATE_LIMIT_WAIT {
+                return Err(anyhow::anyhow!("GitHub API rate limit exhausted until {}, not sending {}", reset.to_rfc3339(), path));
+            }
+            tokio::time::sleep(wait).await;
+        }
+
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let now = Instant::now();
+                bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * REQUESTS_PER_SECOND).min(BUCKET_CAPACITY);
+                bucket.updated = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return Ok(());
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / REQUESTS_PER_SECOND)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Remember when the quota resets once GitHub reports none left
+    fn record_quota(&self, response: &reqwest::Response) {
+        if header(response, "x-ratelimit-remaining").as_deref() == Some("0") {
+            let reset = header(response, "x-ratelimit-reset")
+                .and_then(|reset| reset.parse::<i64>().ok())
+                .and_then(|reset| DateTime::from_timestamp(reset, 0));
+            *self.exhausted_until.lock().unwrap() = reset;
+        }
+    }
+}
+
+fn header(response: &reqwest::Response, name: &str) -> Option<String> {
+    response.headers().get(name).and_then(|value| value.to_str().ok()).map(|value| value.to_string())
+}
+
+/// Delay GitHub asks for before retrying a rate limited request: `Retry-After`
+/// for secondary limits, the quota reset when the primary limit is reached
+fn retry_delay(response: &reqwest::Response) -> Option<Duration> {
+    if response.status() != StatusCode::FORBIDDEN && response.status() != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    if let Some(seconds) = header(response, "retry-after").and_then(|seconds| seconds.parse::<u64>().ok()) {
+        return Some(Duration::from_secs(seconds));
+    }
+    if header(response, "x-ratelimit-remaining").as_deref() != Some("0") {
+        return None;
+    }
+    let reset = header(response, "x-ratelimit-reset")?.parse::<i64>().ok()?;
+    Some(Duration::from_secs((reset - Utc::now().timestamp()).max(1) as u64))
+}
+
+/// Minimal GitHub REST API client
+pub struct GithubClient {
+    api: Arc<GithubApi>,
+    token: Option<String>,
+}
+
+impl GithubClient {
+    pub fn new(api: Arc<GithubApi>, token: Option<String>) -> Self {
+        Self { api, token }
+    }
+
+    async fn get(&self, path: &str) -> Result<serde_json::Value> {
+        self.request(reqwest::Method::GET, path, None).await
+    }
+
+    async fn request(&self, method: reqwest::Method, path: &str, body: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        self.api.request(method, path, self.token.as_deref(), body).await
+    }
+
+    /// GitHub Actions variables of a repository (secrets can't be read back through the API)
+    pub async fn repository_variables(&self, owner: &str, repo: &str) -> Result<BTreeMap<String, String>> {
+        let mut variables = BTreeMap::new();
+
+        // The API returns at most 30 variables per page
+        for page in 1.. {
+            let body = self.get(&format!("/repos/{}/{}/actions/variables?per_page=30&page={}", owner, repo, page)).await?;
+            let entries = body["variables"].as_array().cloned().unwrap_or_default();
+
+            for entry in &entries {
+                if let (Some(name), Some(value)) = (entry["name"].as_str(), entry["value"].as_str()) {
+                    variables.insert(name.to_string(), value.to_string());
+                }
+            }
+
+            let total = body["total_count"].as_u64().unwrap_or(0) as usize;
+            if entries.is_empty() || variables.len() >= total {
+                break;
+            }
+        }
+
+        Ok(variables)
+    }
+
+    /// Login of the account the token belongs to
+    pub async fn authenticated_user(&self) -> Result<String> {
+        let user = self.get("/user").await?;
+        user["login"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("GitHub did not return the login of the token's account"))
+    }
+
+    /// Open an issue, returning its number
+    pub async fn create_issue(&self, owner: &str, repo: &str, title: &str, body: &str) -> Result<i64> {
+        let issue = self.request(reqwest::Method::POST, &format!("/repos/{}/{}/issues", owner, repo),
+            Some(serde_json::json!({ "title": title, "body": body }))).await?;
+
+        issue["number"].as_i64()
+            .ok_or_else(|| anyhow::anyhow!("GitHub did not return the number of the created issue"))
+    }
+
+    pub async fn comment_issue(&self, owner: &str, repo: &str, number: i64, body: &str) -> Result<()> {
+        self.request(reqwest::Method::POST, &format!("/repos/{}/{}/issues/{}/comments", owner, repo, number),
+            Some(serde_json::json!({ "body": body }))).await?;
+        Ok(())
+    }
+
+    pub async fn close_issue(&self, owner: &str, repo: &str, number: i64) -> Result<()> {
+        self.request(reqwest::Method::PATCH, &format!("/repos/{}/{}/issues/{}", owner, repo, number),
+            Some(serde_json::json!({ "state": "closed" }))).await?;
+        Ok(())
+    }
+}
+
+/// GitHub App credentials, exchanged for short-lived installation tokens
+/// (valid one hour) that are cached per repository owner and renewed
+/// shortly before they expire
+pub struct GithubApp {
+    api: Arc<GithubApi>,
+    app_id: u64,
+    private_key: PathBuf,
+    installation_id: Option<u64>,
+    tokens: Mutex<HashMap<String, (String, DateTime<Utc>)>>,
+}
+
+impl GithubApp {
+    pub fn new(api: Arc<GithubApi>, app_id: u64, private_key: PathBuf, installation_id: Option<u64>) -> Self {
+        Self { api, app_id, private_key, installation_id, tokens: Mutex::new(HashMap::new()) }
+    }
+
+    /// Installation token giving access to the repositories of `owner`
+    pub async fn installation_token(&self, owner: &str, repo: &str) -> Result<String> {
+        let renew_after = Utc::now() + chrono::Duration::minutes(5);
+        if let Some((token, expires_at)) = self.tokens.lock().unwrap().get(owner) {
+            if *expires_at > renew_after {
+                return Ok(token.clone());
+            }
+        }
+
+        let installation_id = match self.installation_id {
+            Some(id) => id,
+            None => {
+                let installation = self.app_request(reqwest::Method::GET, &format!("/repos/{}/{}/installation", owner, repo)).await?;
+                installation["id"].as_u64()
+                    .ok_or_else(|| anyhow::anyhow!("GitHub did not return the installation of the app on {}/{}", owner, repo))?
+            }
+        };
+
+        let body = self.app_request(reqwest::Method::POST, &format!("/app/installations/{}/access_tokens", installation_id)).await?;
+        let token = body["token"].as_str()
+            .ok_or_else(|| anyhow::anyhow!("GitHub did not return an installation token"))?
+            .to_string();
+        let expires_at = body["expires_at"].as_str()
+            .and_then(|expires_at| DateTime::parse_from_rfc3339(expires_at).ok())
+            .map(|expires_at| expires_at.with_timezone(&Utc))
+            .unwrap_or_else(|| Utc::now() + chrono::Duration::minutes(55));
+        println!("Obtained GitHub App installation token for {} (expires {})", owner, expires_at.to_rfc3339());
+
+        self.tokens.lock().unwrap().insert(owner.to_string(), (token.clone(), expires_at));
+        Ok(token)
+    }
+
+    /// Request authenticated as the app itself
+    async fn app_request(&self, method: reqwest::Method, path: &str) -> Result<serde_json::Value> {
+        self.api.request(method, path, Some(&self.jwt()?), None).await
+    }
+
+    /// RS256 JSON Web Token identifying the app, valid a few minutes
+    fn jwt(&self) -> Result<String> {
+        let pem = std::fs::read(&self.private_key)
+            .map_err(|e| anyhow::anyhow!("Could not read GitHub App private key {}: {}", self.private_key.display(), e))?;
+        let key = openssl::pkey::PKey::private_key_from_pem(&pem)
+            .map_err(|e| anyhow::anyhow!("Invalid GitHub App private key {}: {}", self.private_key.display(), e))?;
+
+        // Issued a minute in the past to tolerate clock drift, GitHub accepts at most 10 minutes
+        let now = Utc::now().timestamp();
+        let encode = |value: serde_json::Value| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(value.to_string());
+        let header = encode(serde_json::json!({ "alg": "RS256", "typ": "JWT" }));
+        let claims = encode(serde_json::json!({ "iat": now - 60, "exp": now + 540, "iss": self.app_id.to_string() }));
+        let message = format!("{}.{}", header, claims);
+
+        let mut signer = openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), &key)?;
+        let signature = signer.sign_oneshot_to_vec(message.as_bytes())?;
+        Ok(format!("{}.{}", message, base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(signature)))
+    }
+}
+
+/// Owner and name of a github.com repository URL (https, ssh or scp-like),
+/// `None` for repositories hosted elsewhere
+pub fn parse_repository_url(url: &str) -> Option<(String, String)> {
+    if provider::host(url).as_deref() != Some("github.com") {
+        return None;
+    }
+    let path = url.split_once("github.com")?.1.trim_start_matches([':', '/']);
+    let mut parts = path.trim_end_matches('/').split('/');
+    let owner = parts.next().filter(|owner| !owner.is_empty())?;
+    let repo = parts.next()?.trim_end_matches(".git");
+    if repo.is_empty() {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string()))
+}
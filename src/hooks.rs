@@ -0,0 +1,99 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::config::parse_duration;
+
+/// Commands a stack declares in stacks.yaml around its deployments
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StackHooks {
+    /// Run before the stack is deployed, a failure leaves it untouched
+    #[serde(default, deserialize_with = "hook_list", skip_serializing_if = "Vec::is_empty")]
+    pub pre: Vec<Hook>,
+    /// Run once the stack converged, a failure rolls it back to its previous revision
+    #[serde(default, deserialize_with = "hook_list", skip_serializing_if = "Vec::is_empty")]
+    pub post: Vec<Hook>,
+}
+
+/// A shell command run by DockerOps in the stack directory (`run`), or a
+/// one-off Swarm job running an image on the stack networks (`image`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Command of the job, the image's default command when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+    /// Networks of the stack the job is attached to, as named in the compose file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<String>,
+    /// Environment of the command or the job
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Longest run of the hook (default 10m)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+}
+
+/// A hook as written in stacks.yaml, a plain string being a `run` hook
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HookEntry {
+    Run(String),
+    Detailed(Hook),
+}
+
+fn hook_list<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<Hook>, D::Error> {
+    let entries = Vec::<HookEntry>::deserialize(deserializer)?;
+    Ok(entries.into_iter()
+        .map(|entry| match entry {
+            HookEntry::Run(run) => Hook { run: Some(run), ..Default::default() },
+            HookEntry::Detailed(hook) => hook,
+        })
+        .collect())
+}
+
+impl StackHooks {
+    pub fn validate(&self) -> Result<()> {
+        for (phase, hooks) in [("pre", &self.pre), ("post", &self.post)] {
+            for (index, hook) in hooks.iter().enumerate() {
+                hook.validate().map_err(|e| anyhow::anyhow!("{} hook {}: {}", phase, index + 1, e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Hook {
+    fn validate(&self) -> Result<()> {
+        match (&self.run, &self.image) {
+            (Some(_), Some(_)) => return Err(anyhow::anyhow!("'run' and 'image' are exclusive")),
+            (None, None) => return Err(anyhow::anyhow!("either 'run' or 'image' is required")),
+            (Some(_), None) if !self.command.is_empty() || !self.networks.is_empty() => {
+                return Err(anyhow::anyhow!("'command' and 'networks' only apply to 'image' hooks"));
+            }
+            _ => {}
+        }
+        self.timeout()?;
+        Ok(())
+    }
+
+    pub fn timeout(&self) -> Result<Duration> {
+        parse_duration(self.timeout.as_deref().unwrap_or("10m"))
+    }
+
+    /// Short description for the logs and errors
+    pub fn describe(&self) -> String {
+        match (&self.run, &self.image) {
+            (Some(run), _) => format!("`{}`", run.lines().next().unwrap_or_default()),
+            (None, Some(image)) if self.command.is_empty() => format!("job {}", image),
+            (None, Some(image)) => format!("job {} {}", image, self.command.join(" ")),
+            (None, None) => String::new(),
+        }
+    }
+}
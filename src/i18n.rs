@@ -0,0 +1,139 @@
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Message catalogs in Fluent syntax, English being the reference every
+/// other catalog falls back to
+const CATALOGS: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+const FALLBACK: &str = "en";
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Messages of one language, then the English ones it lacks
+struct Catalog {
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+/// Languages DockerOps has messages for
+pub fn available() -> Vec<&'static str> {
+    CATALOGS.iter().map(|(language, _)| *language).collect()
+}
+
+/// Language of a locale such as "fr_FR.UTF-8" or "fr-CA", when a catalog exists for it
+pub fn language_of(locale: &str) -> Option<&'static str> {
+    let language = locale.split(['_', '-', '.', '@']).next().unwrap_or_default().to_ascii_lowercase();
+    CATALOGS.iter().map(|(name, _)| *name).find(|name| *name == language)
+}
+
+/// Select the language of the messages for the rest of the process
+pub fn init(language: &str) {
+    let _ = CATALOG.set(Catalog::load(language));
+}
+
+/// Message `id` formatted by Fluent with `args`, the variants of its
+/// selectors picked by the plural rules of the language. The English message
+/// is used when the selected catalog lacks it, the id itself when no catalog
+/// has it.
+pub fn tr(id: &str, args: &[(&str, &(dyn Display + Sync))]) -> String {
+    CATALOG.get_or_init(|| Catalog::load(FALLBACK)).format(id, args)
+}
+
+impl Catalog {
+    fn load(language: &str) -> Self {
+        let language = CATALOGS.iter().map(|(name, _)| *name).find(|name| *name == language).unwrap_or(FALLBACK);
+        let mut languages = vec![language];
+        if language != FALLBACK {
+            languages.push(FALLBACK);
+        }
+        Self { bundles: languages.into_iter().map(bundle).collect() }
+    }
+
+    fn format(&self, id: &str, args: &[(&str, &(dyn Display + Sync))]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, argument(value.to_string()));
+        }
+        self.bundles.iter()
+            .find_map(|bundle| {
+                let pattern = bundle.get_message(id)?.value()?;
+                // Missing arguments are written as { $name } in the message
+                let mut errors = Vec::new();
+                Some(bundle.format_pattern(pattern, Some(&fluent_args), &mut errors).into_owned())
+            })
+            .unwrap_or_else(|| id.to_string())
+    }
+}
+
+/// Catalog of a language, its messages printed without the Unicode isolation
+/// marks Fluent puts around placeables by default
+fn bundle(language: &'static str) -> FluentBundle<FluentResource> {
+    let source = CATALOGS.iter().find(|(name, _)| *name == language).map(|(_, source)| *source).unwrap_or_default();
+    // Entries Fluent can't parse are left out, the other ones still load
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, _)| resource);
+    let mut bundle = FluentBundle::new_concurrent(vec![language.parse().unwrap_or_default()]);
+    bundle.set_use_isolating(false);
+    let _ = bundle.add_resource(resource);
+    bundle
+}
+
+/// Value of an argument, a number when it is written as one so that
+/// selectors can match its plural category
+fn argument(value: String) -> FluentValue<'static> {
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() && number.to_string() == value => FluentValue::from(number),
+        _ => FluentValue::from(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalogs_parse_and_declare_the_same_messages() {
+        let ids = |source: &'static str| -> Vec<&'static str> {
+            source.lines()
+                .filter(|line| line.starts_with(|c: char| c.is_ascii_lowercase()))
+                .filter_map(|line| line.split_once(" =").map(|(id, _)| id))
+                .collect()
+        };
+        for (language, source) in CATALOGS {
+            if let Err((_, errors)) = FluentResource::try_new(source.to_string()) {
+                panic!("invalid {} catalog: {:?}", language, errors);
+            }
+            let bundle = bundle(language);
+            let missing: Vec<_> = ids(CATALOGS[0].1).into_iter().filter(|id| !bundle.has_message(id)).collect();
+            assert!(missing.is_empty(), "messages missing from the {} catalog: {:?}", language, missing);
+        }
+        assert_eq!(ids(CATALOGS[0].1).len(), ids(CATALOGS[1].1).len());
+    }
+
+    #[test]
+    fn english_plural_selection() {
+        let catalog = Catalog::load("en");
+        assert_eq!(catalog.format("found-stacks-to-remove", &[("count", &0)]), "Found 0 stacks to remove");
+        assert_eq!(catalog.format("found-stacks-to-remove", &[("count", &1)]), "Found 1 stack to remove");
+        assert_eq!(catalog.format("found-stacks-to-remove", &[("count", &2)]), "Found 2 stacks to remove");
+    }
+
+    #[test]
+    fn french_plural_selection() {
+        let catalog = Catalog::load("fr");
+        // 0 takes the singular in French
+        assert_eq!(catalog.format("found-stacks-to-remove", &[("count", &0)]), "0 stack à supprimer");
+        assert_eq!(catalog.format("found-stacks-to-remove", &[("count", &1)]), "1 stack à supprimer");
+        assert_eq!(catalog.format("found-stacks-to-remove", &[("count", &2)]), "2 stacks à supprimer");
+    }
+
+    #[test]
+    fn arguments_are_kept_as_written() {
+        let catalog = Catalog::load("en");
+        assert_eq!(catalog.format("checked-out-commit", &[("id", &"0123456")]), "Checked out commit 0123456");
+        assert_eq!(catalog.format("unknown-message-id", &[]), "unknown-message-id");
+    }
+}
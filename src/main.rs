@@ -1,99 +1,730 @@
 mod models;
 mod database;
 mod commands;
+mod config;
+mod server;
+mod registry;
+mod github;
+mod provider;
+mod pull_proxy;
+mod provenance;
+mod prometheus;
+mod signing;
+mod compose;
+mod atomic;
+mod archive;
+mod i18n;
+mod notify;
+mod prerequisites;
+mod hooks;
+mod schema;
+mod plan;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use anyhow::Result;
 
 #[derive(Parser)]
 #[command(name = "dockerops")]
 #[command(about = "A Docker Compose file watcher and manager")]
 struct Cli {
+    /// Configuration profile stored under ~/.dockerops/profiles/<name> (or DOCKEROPS_PROFILE)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Name recorded as the operator of the changes made by this command,
+    /// instead of the system user
+    #[arg(long = "as", global = true, value_name = "NAME")]
+    operator: Option<String>,
+
+    /// Stacks of a repository deployed at the same time (overrides max_parallel_deploys)
+    #[arg(long, global = true, value_name = "N")]
+    max_parallel: Option<usize>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Watch a GitHub repository for file changes
+    /// Watch a git repository for file changes
     Watch {
-        /// GitHub repository URL to watch (e.g., https://github.com/user/repo)
+        /// Git remote to watch: https, ssh, git:// or scp-like (e.g., https://github.com/user/repo or git@host:user/repo.git)
+        #[arg(required_unless_present = "from_file")]
+        url: Option<String>,
+        /// Branch to deploy instead of the remote default branch
+        #[arg(long, conflicts_with = "tag")]
+        branch: Option<String>,
+        /// Deploy the newest tag matching this semver pattern (e.g. "v1.*") instead of HEAD
+        #[arg(long)]
+        tag: Option<String>,
+        /// Deploy this exact commit and keep the repository pinned to it
+        #[arg(long, conflicts_with = "tag")]
+        commit: Option<String>,
+        /// Directory of the repository holding stacks.yaml and the stack directories (e.g. deploy/swarm)
+        #[arg(long)]
+        path: Option<String>,
+        /// Prefix of the stack names (`<namespace>_<stack>`) and of the NFS directory of the bindings
+        #[arg(long)]
+        namespace: Option<String>,
+        /// Watch every repository listed in this YAML file
+        #[arg(long, conflicts_with_all = ["url", "branch", "tag", "commit", "path", "namespace"])]
+        from_file: Option<String>,
+        /// Repositories bootstrapped at the same time with --from-file
+        #[arg(long, default_value_t = 4, requires = "from_file")]
+        parallel: usize,
+        /// Redeploy the stacks even when their compose hash is unchanged, a
+        /// repository already watched being synced again (e.g. after services were changed by hand)
+        #[arg(long)]
+        force: bool,
+        /// Print what would be deployed, pulled and removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Remove the stacks no longer declared in stacks.yaml, moving them to the trash, even when prune_stacks is disabled
+        #[arg(long)]
+        prune: bool,
+        #[command(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Stop watching a repository and remove only its stacks
+    Unwatch {
+        /// URL of the watched repository
         url: String,
     },
     /// Reconcile the database and show current state
     Reconcile {
-        /// Force reconciliation even if no changes detected
+        /// Only re-evaluate and redeploy this stack
+        #[arg(conflicts_with_all = ["only", "exclude"])]
+        stack: Option<String>,
+        /// Redeploy the stacks even when their compose hash is unchanged (e.g. after services were changed by hand)
         #[arg(long)]
         force: bool,
+        /// Only reconcile this repository
+        #[arg(long)]
+        repo: Option<String>,
+        /// Print what would be redeployed, pulled and removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Remove the stacks no longer declared in stacks.yaml, moving them to the trash, even when prune_stacks is disabled
+        #[arg(long)]
+        prune: bool,
+        #[command(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Save what a sync would change to a file, for review before `apply`
+    Plan {
+        /// Only plan the changes of this repository
+        #[arg(long)]
+        repo: Option<String>,
+        /// File the plan is written to
+        #[arg(long, short, default_value = "dockerops-plan.yaml")]
+        out: String,
+        #[command(flatten)]
+        scope: ScopeArgs,
+    },
+    /// Carry out a plan saved by `plan`, exactly as reviewed
+    Apply {
+        /// File written by `plan`
+        plan: String,
+    },
+    /// Deploy a stack from its repository now, even when its compose file is unchanged
+    Deploy {
+        /// Name of the stack
+        stack: String,
+        /// Repository of the stack when the name exists in several repositories, or of a stack not deployed yet
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// Start managing a stack already running in Swarm without redeploying it
+    Adopt {
+        /// Name of the running stack, as declared in stacks.yaml
+        stack: String,
+        /// Watched repository declaring the stack
+        repo: String,
+    },
+    /// Show the global settings, or the ones a stack is deployed with after
+    /// merging stacks.yaml, dockerops.yaml and config.yaml
+    ConfigShow {
+        /// Stack whose merged settings are shown, with the file each one comes from
+        #[arg(long)]
+        effective: Option<String>,
+        /// Repository of the stack when the name exists in several repositories, or of a stack not deployed yet
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// Redeploy an earlier compose file of a stack, by default the one deployed before the current one
+    Rollback {
+        /// Name of the stack
+        stack: String,
+        /// Revision to redeploy, see the stack_revisions table
+        #[arg(long)]
+        to: Option<i64>,
+        /// Repository of the stack when the name exists in several repositories
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// Show the latest deploy attempts and their outcome, of every stack or of one
+    History {
+        /// Name of the stack
+        stack: Option<String>,
+        /// Only the stacks of this repository
+        #[arg(long)]
+        repo: Option<String>,
+        /// Number of deployments shown
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+    /// Report the stacks removed or changed in Swarm outside DockerOps since their last deployment
+    Drift {
+        /// Only the stacks of this repository
+        #[arg(long)]
+        repo: Option<String>,
+        /// Deploy the drifted stacks again from their last deployed compose file
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Write a stack's deployed compose file, image digests and volumes to a portable archive
+    ExportStack {
+        /// Name of the stack
+        stack: String,
+        /// Repository of the stack when the name exists in several repositories
+        #[arg(long)]
+        repo: Option<String>,
+        /// Archive written (default: <stack>-<hash>.tar.gz)
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+    /// Deploy a stack archive written by export-stack and manage the stack
+    ImportStack {
+        /// Archive to import
+        archive: String,
+        /// Repository the stack is attached to (default: the one it was exported from)
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// Keep running and re-sync every watched repository on an interval
+    Daemon {
+        /// Polling interval (e.g. 30s, 5m, 1h)
+        #[arg(long, default_value = "60s")]
+        interval: String,
+        /// Skip the stacks rendering to their deployed compose file, with their volume syncing and image checks
+        #[arg(long)]
+        changed_only: bool,
+        /// Remove the stacks no longer declared in stacks.yaml, moving them to the trash, even when prune_stacks is disabled
+        #[arg(long)]
+        prune: bool,
+    },
+    /// Run an HTTP server receiving push webhooks and deploying the pushed repository
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "0.0.0.0:8080")]
+        listen: String,
+        /// Accept unsigned webhook payloads when no webhook secret is configured
+        #[arg(long)]
+        insecure: bool,
+        /// Skip the stacks rendering to their deployed compose file, with their volume syncing and image checks
+        #[arg(long)]
+        changed_only: bool,
+        /// Remove the stacks no longer declared in stacks.yaml, moving them to the trash, even when prune_stacks is disabled
+        #[arg(long)]
+        prune: bool,
+    },
+    /// Import an existing docker-compose project into a DockerOps repository
+    ImportProject {
+        /// docker-compose file of the project to import
+        compose_file: String,
+        /// Path of the local DockerOps repository to generate files in
+        #[arg(long)]
+        repo: String,
+        /// Stack name (defaults to the compose project directory name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Stop a stack's services and measure how fast DockerOps detects and repairs it
+    Drill {
+        /// Name of the managed stack to disrupt
+        stack: String,
+        /// Repository of the stack when the name exists in several repositories
+        #[arg(long)]
+        repo: Option<String>,
+        /// Maximum time allowed for detection and recovery
+        #[arg(long, default_value = "300s")]
+        timeout: String,
+    },
+    /// Show how far the deployed images lag behind their registries
+    ImageReport {
+        /// Only report this stack
+        stack: Option<String>,
+        /// Print Prometheus metrics instead of a report
+        #[arg(long)]
+        prometheus: bool,
+    },
+    /// List the Docker endpoints and contexts DockerOps can target
+    Contexts,
+    /// Select the context targeted by the following commands
+    UseContext {
+        /// Name of a context from config.yaml or of a docker CLI context ("default" clears the selection)
+        name: String,
+    },
+    /// Continuously copy the DockerOps database to a standby location
+    Mirror {
+        /// Database file (or sqlite: URL) to keep up to date, e.g. on a share mounted by the standby node, or ssh://[user@]host[:port]/path on the standby node itself
+        #[arg(long)]
+        to: String,
+        /// Copy interval (e.g. 30s, 5m, 1h)
+        #[arg(long, default_value = "60s")]
+        interval: String,
+        /// Copy once and exit
+        #[arg(long)]
+        once: bool,
+    },
+    /// Deploy DockerOps itself as a Swarm stack running `serve`
+    SelfDeploy {
+        /// DockerOps image to run (built from the repository's Dockerfile)
+        #[arg(long)]
+        image: String,
+        /// Name of the stack
+        #[arg(long, default_value = "dockerops")]
+        stack: String,
+        /// Published port of the webhook server
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        /// Only print the compose file
+        #[arg(long)]
+        print: bool,
+    },
+    /// Check the database, the docker daemon and the last successful sync, exiting non-zero when unhealthy
+    Healthz {
+        /// Maximum age of the last successful sync (e.g. 10m), overrides health_max_age
+        #[arg(long)]
+        max_age: Option<String>,
+    },
+    /// Show the live Swarm state of the managed stacks: replicas, convergence and images
+    Status {
+        /// Only show this stack
+        stack: Option<String>,
+        /// Only the stacks of this repository
+        #[arg(long)]
+        repo: Option<String>,
+        /// Show what was deployed at this time instead (RFC 3339 or YYYY-MM-DD [HH:MM[:SS]])
+        #[arg(long)]
+        at: Option<String>,
+    },
+    /// List the tasks of a managed stack: node, state and error
+    Ps {
+        /// Stack to list the tasks of
+        stack: String,
+        /// Repository of the stack when the name exists in several repositories
+        #[arg(long)]
+        repo: Option<String>,
+        /// Include the stopped tasks of previous versions and restarts
+        #[arg(long)]
+        all: bool,
+    },
+    /// Rolling restart of the services of a managed stack, without changing its compose file
+    Restart {
+        /// Stack to restart
+        stack: String,
+        /// Only this service, by its name in the compose file
+        service: Option<String>,
+        /// Repository of the stack when the name exists in several repositories
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// Stream the logs of the services of a managed stack, interleaved with a colored prefix per service
+    Logs {
+        /// Stack to show the logs of
+        stack: String,
+        /// Only this service, by its name in the compose file
+        service: Option<String>,
+        /// Repository of the stack when the name exists in several repositories
+        #[arg(long)]
+        repo: Option<String>,
+        /// Print the existing logs and exit instead of following new ones
+        #[arg(long)]
+        no_follow: bool,
+        /// Number of existing lines to show per service, or "all"
+        #[arg(long)]
+        tail: Option<String>,
+    },
+    /// Run a command in a running container of a service of a stack, on whichever node runs it
+    Exec {
+        /// Stack of the service
+        stack: String,
+        /// Service, by its name in the compose file
+        service: String,
+        /// Command to run after `--`, a shell when omitted
+        #[arg(last = true)]
+        command: Vec<String>,
+        /// Repository of the stack when the name exists in several repositories
+        #[arg(long)]
+        repo: Option<String>,
+        /// Replica to run the command in, the lowest running one by default
+        #[arg(long)]
+        slot: Option<u64>,
+    },
+    /// Show the combined status of the applications grouping stacks (`app:` in stacks.yaml)
+    Apps {
+        /// Only show this application
+        app: Option<String>,
+    },
+    /// List stacks whose manual changes conflict with git
+    Pending,
+    /// Settle a conflict between manual changes and git, then redeploy the stack
+    #[command(group(clap::ArgGroup::new("resolution").required(true).args(["keep_override", "take_git", "merge"])))]
+    Resolve {
+        /// Name of the stack
+        stack: String,
+        /// Keep every manual change, ignoring git for those fields
+        #[arg(long)]
+        keep_override: bool,
+        /// Deploy git as is, reverting the manual changes
+        #[arg(long)]
+        take_git: bool,
+        /// Keep the manual changes git doesn't touch, take git for the others
+        #[arg(long)]
+        merge: bool,
+        /// Repository of the stack when the name exists in several repositories
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// Remove one stack from Swarm and from the database, keeping it in the trash
+    Rm {
+        /// Name of the stack
+        stack: String,
+        /// Repository of the stack when the name exists in several repositories
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// List stacks removed from their repository and still restorable
+    Trash,
+    /// Remove the dangling images, stopped containers and build cache enabled in the gc settings
+    Gc {
+        /// Only report what could be reclaimed
+        #[arg(long)]
+        dry_run: bool,
+        /// List the last collections instead
+        #[arg(long, conflicts_with = "dry_run")]
+        history: bool,
+    },
+    /// Redeploy a stack from the trash using its last deployed compose file
+    RestoreStack {
+        /// Name of the trashed stack
+        stack: String,
+        /// Repository of the stack when the name exists in several repositories
+        #[arg(long)]
+        repo: Option<String>,
     },
     /// Stop the application
-    Stop,
+    Stop {
+        /// Only remove the stacks and images of this repository, leaving the others running
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// Create the configuration file interactively and watch a first repository
+    Setup,
+    /// Print the JSON schema of a repository file, for editors and CI
+    Schema {
+        /// File described: stacks, dockerops, volumes or nfs (lists them when omitted)
+        file: Option<String>,
+    },
     /// Show version information
     Version,
     /// Debug repository cache
     DebugCache,
 }
 
+/// Stacks touched by a run, by name
+#[derive(Args)]
+struct ScopeArgs {
+    /// Only process stacks matching this glob (e.g. "web-*"), can be repeated
+    #[arg(long)]
+    only: Vec<String>,
+    /// Leave stacks matching this glob untouched (e.g. "*-db"), can be repeated
+    #[arg(long)]
+    exclude: Vec<String>,
+}
+
+impl ScopeArgs {
+    fn filter(&self) -> commands::StackFilter {
+        commands::StackFilter::new(self.only.clone(), self.exclude.clone())
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    // Schemas describe files, they need neither root nor a configuration
+    if let Commands::Schema { file } = &cli.command {
+        return commands::Commands::print_schema(file.as_deref());
+    }
+
     // Check if running as root
     if std::env::var("USER").unwrap_or_default() != "root" {
         eprintln!("❌ Error: DockerOps must be run with root privileges (use sudo)");
-        eprintln!("");
+        eprintln!();
         eprintln!("This is required because DockerOps needs to:");
         eprintln!("  • Execute Docker commands");
         eprintln!("  • Manage Docker Swarm stacks");
         eprintln!("  • Pull and remove Docker images");
         eprintln!("  • Access Docker daemon");
-        eprintln!("");
+        eprintln!();
         eprintln!("Please run: sudo dockerops <command>");
         std::process::exit(1);
     }
 
-    let cli = Cli::parse();
+    let profile = cli.profile.clone().or_else(|| std::env::var("DOCKEROPS_PROFILE").ok());
+    let mut config = config::Config::load(profile.as_deref())?;
+    // Long running processes act on their own unless given a name
+    let role = match &cli.command {
+        Commands::Daemon { .. } => Some("daemon".to_string()),
+        Commands::Serve { .. } => Some("serve".to_string()),
+        _ => None,
+    };
+    config.set_operator(cli.operator.clone().or(role));
+    if cli.max_parallel.is_some() {
+        config.max_parallel_deploys = cli.max_parallel;
+    }
+    i18n::init(config.language());
+    if let Some(profile) = config.profile() {
+        eprintln!("{}", i18n::tr("using-profile", &[("profile", &profile)]));
+    }
+    if let Some(context) = config.context() {
+        eprintln!("{}", i18n::tr("using-context", &[("context", &context)]));
+    }
 
-    // Get database path from environment or use default
-    let db_path = std::env::var("DOCKEROPS_DB_PATH")
-        .unwrap_or_else(|_| {
-            let home_dir = std::env::var("HOME")
-                .or_else(|_| std::env::var("USERPROFILE"))
-                .unwrap_or_else(|_| ".".to_string());
-            format!("{}/.dockerops/dockerops.db", home_dir)
-        });
+    // Get database path from environment, configuration or use default
+    let db_path = config.database_path();
 
     // Create .dockerops directory if it doesn't exist
-    if let Some(parent) = std::path::Path::new(&db_path).parent() {
+    if let Some(parent) = db_path.parent() {
         if !parent.exists() {
             std::fs::create_dir_all(parent)?;
         }
     }
 
-    let database_url = format!("sqlite:{}", db_path);
+    let database_url = format!("sqlite:{}", db_path.display());
 
     // Only initialize database for commands that need it
     match &cli.command {
-        Commands::Watch { url } => {
+        Commands::Watch { url, branch, tag, commit, path, namespace, from_file, parallel, force, dry_run, prune, scope } => {
+            let db = database::Database::new(&database_url).await?;
+            let mut commands = commands::Commands::new(db, config);
+            commands.set_dry_run(*dry_run);
+            commands.set_force(*force);
+            commands.set_prune(*prune);
+            match (from_file, url) {
+                (Some(path), _) => std::sync::Arc::new(commands).watch_from_file(path, *parallel, &scope.filter()).await?,
+                (None, Some(url)) => {
+                    let entry = models::WatchEntry { url: url.clone(), branch: branch.clone(), tag: tag.clone(), commit: commit.clone(), path: path.clone(), namespace: namespace.clone(), ..Default::default() };
+                    commands.watch(&entry, &scope.filter()).await?
+                }
+                (None, None) => return Err(anyhow::anyhow!("A repository URL or --from-file is required")),
+            }
+        }
+        Commands::Unwatch { url } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.unwatch(url).await?;
+        }
+        Commands::Reconcile { stack, force, repo, dry_run, prune, scope } => {
+            let db = database::Database::new(&database_url).await?;
+            let mut commands = commands::Commands::new(db, config);
+            commands.set_dry_run(*dry_run);
+            commands.set_prune(*prune);
+            match stack {
+                Some(stack) => commands.reconcile_stack(stack, repo.as_deref(), *force).await?,
+                None => commands.reconcile(*force, repo.as_deref(), &scope.filter()).await?,
+            }
+        }
+        Commands::Plan { repo, out, scope } => {
+            let db = database::Database::new(&database_url).await?;
+            let mut commands = commands::Commands::new(db, config);
+            commands.plan(repo.as_deref(), &scope.filter(), out).await?;
+        }
+        Commands::Apply { plan } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.apply(plan).await?;
+        }
+        Commands::Deploy { stack, repo } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.deploy(stack, repo.as_deref()).await?;
+        }
+        Commands::Adopt { stack, repo } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.adopt(stack, repo).await?;
+        }
+        Commands::ConfigShow { effective, repo } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.config_show(effective.as_deref(), repo.as_deref()).await?;
+        }
+        Commands::Rollback { stack, to, repo } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.rollback(stack, repo.as_deref(), *to).await?;
+        }
+        Commands::History { stack, repo, limit } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.history(stack.as_deref(), repo.as_deref(), *limit).await?;
+        }
+        Commands::Drift { repo, fix } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.drift(repo.as_deref(), *fix).await?;
+        }
+        Commands::ExportStack { stack, repo, output } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.export_stack(stack, repo.as_deref(), output.as_deref()).await?;
+        }
+        Commands::ImportStack { archive, repo } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.import_stack(archive, repo.as_deref()).await?;
+        }
+        Commands::Daemon { interval, changed_only, prune } => {
+            let interval = config::parse_interval(interval)?;
             let db = database::Database::new(&database_url).await?;
-            let commands = commands::Commands::new(db);
-            commands.watch(url).await?;
+            let mut commands = commands::Commands::new(db, config);
+            commands.set_changed_only(*changed_only);
+            commands.set_prune(*prune);
+            commands.daemon(interval).await?;
+        }
+        Commands::Serve { listen, insecure, changed_only, prune } => {
+            let db = database::Database::new(&database_url).await?;
+            let mut commands = commands::Commands::new(db, config);
+            commands.set_changed_only(*changed_only);
+            commands.set_prune(*prune);
+            server::serve(commands, listen, *insecure).await?;
+        }
+        Commands::ImportProject { compose_file, repo, name } => {
+            // Import only generates files and doesn't need database
+            commands::Commands::import_project(compose_file, repo, name.as_deref())?;
         }
-        Commands::Reconcile { force } => {
+        Commands::Drill { stack, repo, timeout } => {
+            let timeout = config::parse_duration(timeout)?;
             let db = database::Database::new(&database_url).await?;
-            let commands = commands::Commands::new(db);
-            commands.reconcile(*force).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.drill(stack, repo.as_deref(), timeout).await?;
         }
-        Commands::Stop => {
+        Commands::ImageReport { stack, prometheus } => {
             let db = database::Database::new(&database_url).await?;
-            let commands = commands::Commands::new(db);
-            commands.stop().await?;
+            let commands = commands::Commands::new(db, config);
+            commands.image_report(stack.as_deref(), *prometheus).await?;
+        }
+        Commands::Contexts => {
+            // Context commands only read and write the configuration
+            commands::Commands::contexts(&config)?;
+        }
+        Commands::UseContext { name } => {
+            commands::Commands::use_context(&config, name).await?;
+        }
+        Commands::Mirror { to, interval, once } => {
+            let interval = config::parse_interval(interval)?;
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.mirror(to, interval, *once).await?;
+        }
+        Commands::SelfDeploy { image, stack, port, print } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.self_deploy(image, stack, *port, *print).await?;
+        }
+        Commands::Healthz { max_age } => {
+            let max_age = match max_age {
+                Some(max_age) => Some(config::parse_duration(max_age)?),
+                None => config.health_max_age()?,
+            };
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.healthz(max_age).await?;
+        }
+        Commands::Status { stack, repo, at } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.status(stack.as_deref(), repo.as_deref(), at.as_deref()).await?;
+        }
+        Commands::Ps { stack, repo, all } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.ps(stack, repo.as_deref(), *all).await?;
+        }
+        Commands::Restart { stack, service, repo } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.restart(stack, service.as_deref(), repo.as_deref()).await?;
+        }
+        Commands::Logs { stack, service, repo, no_follow, tail } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.logs(stack, service.as_deref(), repo.as_deref(), !*no_follow, tail.as_deref()).await?;
+        }
+        Commands::Exec { stack, service, command, repo, slot } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            let code = commands.exec(stack, service, repo.as_deref(), *slot, command).await?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        Commands::Apps { app } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.apps(app.as_deref()).await?;
+        }
+        Commands::Pending => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.pending().await?;
+        }
+        Commands::Resolve { stack, keep_override, take_git, merge: _, repo } => {
+            let resolution = if *keep_override { "keep-override" } else if *take_git { "take-git" } else { "merge" };
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.resolve(stack, repo.as_deref(), resolution).await?;
+        }
+        Commands::Rm { stack, repo } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.remove_stack(stack, repo.as_deref()).await?;
+        }
+        Commands::Trash => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.trash().await?;
+        }
+        Commands::Gc { dry_run, history } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.gc(*dry_run, *history).await?;
+        }
+        Commands::RestoreStack { stack, repo } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.restore_stack(stack, repo.as_deref()).await?;
+        }
+        Commands::Stop { repo } => {
+            let db = database::Database::new(&database_url).await?;
+            let commands = commands::Commands::new(db, config);
+            commands.stop(repo.as_deref()).await?;
+        }
+        Commands::Setup => {
+            // The wizard chooses the database location itself
+            commands::Commands::setup(config).await?;
         }
+        Commands::Schema { .. } => unreachable!("schemas are printed before the configuration is loaded"),
         Commands::Version => {
             // Version command doesn't need database
             commands::Commands::show_version();
         }
         Commands::DebugCache => {
             let db = database::Database::new(&database_url).await?;
-            let commands = commands::Commands::new(db);
+            let commands = commands::Commands::new(db, config);
             commands.debug_cache().await?;
         }
     }
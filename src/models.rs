@@ -1,5 +1,10 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::BTreeMap;
+use crate::config::{DeploySettings, NotificationSettings, TaskHistorySettings, UpdateSettings};
+use crate::hooks::StackHooks;
+use crate::prerequisites::HostPrerequisites;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Image {
@@ -18,18 +23,330 @@ pub struct Stack {
     pub compose_path: String,
     pub hash: String,
     pub status: String, // "deployed", "stopped", "error"
+    pub deferred_since: Option<String>, // ISO timestamp of the first automatic update delayed by load
+    pub app: Option<String>, // Application grouping the stack, from stacks.yaml
 }
 
+/// A removed stack kept with its last deployed compose file so it can be restored
 #[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct TrashedStack {
+    pub id: i64,
+    pub name: String,
+    pub repository_url: String,
+    pub compose_path: String,
+    pub hash: String,
+    pub compose_content: Option<String>,
+    pub reason: String,
+    pub deleted_at: String, // ISO timestamp
+    pub deleted_by: Option<String>, // Operator who removed the stack, NULL for older entries
+    pub snapshot_dir: Option<String>, // Copy of the stack directory, NULL when the working copy had none
+}
+
+/// Notification waiting in the outbox to be delivered to a webhook
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct OutboxNotification {
+    pub id: i64,
+    pub url: String,
+    pub payload: String, // JSON body posted to the webhook
+    pub attempts: i64,
+    pub next_attempt_at: String, // ISO timestamp
+    pub last_error: Option<String>,
+    pub status: String, // pending, or dead once every attempt failed
+    pub created_at: String, // ISO timestamp
+}
+
+/// One garbage collection of the Docker resources on the manager
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct GcRun {
+    pub id: i64,
+    pub started_at: String, // ISO timestamp
+    pub trigger: String, // "manual" or "scheduled"
+    pub reclaimed_bytes: i64,
+    pub report: String, // One `resource<TAB>removed<TAB>bytes` line per resource
+    pub operator: Option<String>,
+}
+
+/// Consecutive deploy failures of a stack, cleared by the next successful deploy
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct StackFailure {
+    pub id: i64,
+    pub stack_name: String,
+    pub repository_url: String,
+    pub hash: String, // Compose hash of the last failed attempt
+    pub failures: i64,
+    pub last_error: String,
+    pub issue_number: Option<i64>, // GitHub issue opened for the failures
+    pub failed_at: String, // ISO timestamp
+}
+
+/// One attempt at deploying a stack, successful or not
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Deployment {
+    pub id: i64,
+    pub stack_name: String,
+    pub repository_url: String,
+    pub hash: String, // Compose hash deployed
+    pub commit_hash: Option<String>, // Commit the compose file comes from, NULL for local directories and snapshots
+    pub kind: String, // new, update, restore, rollback, reapply, import, adopt or restart
+    pub started_at: String, // ISO timestamp
+    pub duration_ms: i64,
+    pub result: String, // success or failure
+    pub error: Option<String>,
+    pub canary_passed: Option<bool>, // Outcome of the canary analysis of the update, read from canary_analyses
+    pub images: Option<String>, // "service image" lines with the digests Swarm resolved, NULL for failures and older deployments
+    pub operator: Option<String>, // Who triggered it: `--as` name or system user, daemon, serve or webhook:<sender>
+}
+
+impl Deployment {
+    pub fn new(stack_name: &str, repository_url: &str, hash: &str, kind: &str, started_at: DateTime<Utc>) -> Self {
+        Self {
+            id: 0, // Will be set by database
+            stack_name: stack_name.to_string(),
+            repository_url: repository_url.to_string(),
+            hash: hash.to_string(),
+            commit_hash: None,
+            kind: kind.to_string(),
+            started_at: started_at.to_rfc3339(),
+            duration_ms: 0,
+            result: "success".to_string(),
+            error: None,
+            canary_passed: None,
+            images: None,
+            operator: None,
+        }
+    }
+}
+
+/// Compose file of a stack as it was successfully deployed, kept for `rollback`
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct StackRevision {
+    pub id: i64,
+    pub stack_name: String,
+    pub repository_url: String,
+    pub revision: i64, // Numbered per stack, from 1
+    pub hash: String,
+    pub compose_content: String,
+    pub deployed_at: String, // ISO timestamp
+    pub rolled_back_from: Option<String>, // Hash of the compose file replaced by this revision with `rollback`
+}
+
+/// Manual changes to a running stack (`docker service scale`, `docker
+/// service update --image`) that a new git state also changes, waiting
+/// for an operator to choose which side wins
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct OverrideConflict {
+    pub id: i64,
+    pub stack_name: String,
+    pub repository_url: String,
+    pub hash: String, // Compose hash of the git state in conflict
+    pub overrides: String, // JSON list of ServiceOverride
+    pub resolution: Option<String>, // "keep-override", "take-git" or "merge" once chosen
+    pub detected_at: String, // ISO timestamp
+    pub resolved_by: Option<String>, // Operator who chose the resolution
+}
+
+/// A Docker object of a managed stack changed outside of DockerOps
+/// (`docker service update`, `docker network rm`...), seen in the Docker
+/// events by the daemon
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct DriftEvent {
+    pub id: i64,
+    pub stack_name: String,
+    pub repository_url: String,
+    pub object_type: String, // "service", "network", "secret" or "config"
+    pub object_name: String,
+    pub action: String, // Docker event action: "update", "remove"...
+    pub detected_at: String, // ISO timestamp
+}
+
+/// One field of a service changed outside of git since the last deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceOverride {
+    pub service: String,
+    pub field: String, // "replicas" or "image"
+    pub deployed: String,
+    pub live: String,
+    pub git: String,
+    /// Git changed the field too
+    pub conflicting: bool,
+}
+
+impl OverrideConflict {
+    pub fn service_overrides(&self) -> Vec<ServiceOverride> {
+        serde_json::from_str(&self.overrides).unwrap_or_default()
+    }
+}
+
+/// A value published by a stack, referenced as `{{ output "stack" "name" }}`
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct StackOutput {
+    pub id: i64,
+    pub stack_name: String,
+    pub repository_url: String,
+    pub name: String,
+    pub value: String,
+    pub updated_at: String, // ISO timestamp
+}
+
+/// Compose file of a stack as last rendered, reused while the inputs of the
+/// rendering and the outputs it references stay the same
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct RenderCacheEntry {
+    pub stack_name: String,
+    pub repository_url: String,
+    /// MD5 of the compose file, values, volumes and settings it was rendered from
+    pub input_hash: String,
+    pub content: String,
+    /// JSON array of the `[stack, output, value]` substituted in the file
+    pub outputs: String,
+    pub rendered_at: String, // ISO timestamp
+}
+
+/// Push received by `serve`, kept to ignore redeliveries and to replay the
+/// ones whose deployment a restart interrupted
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct WebhookDelivery {
+    pub delivery_id: String, // Delivery header of the forge, MD5 of the payload when missing
+    pub repository_url: String,
+    pub git_ref: String,
+    pub commit_hash: Option<String>, // Commit the push moved the ref to
+    pub sender: String, // Operator of the deployment: webhook:<login> or webhook
+    pub received_at: String, // ISO timestamp
+    pub processed_at: Option<String>, // ISO timestamp, NULL until a deployment of the repository started after it ended
+    pub result: Option<String>, // success, failure or unwatched
+}
+
+/// Last freshness check of the image running in one service of a stack
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ImageFreshness {
+    pub id: i64,
+    pub stack_name: String,
+    pub repository_url: String,
+    pub service: String,
+    pub image: String, // Image reference without digest
+    pub deployed_digest: Option<String>,
+    pub latest_digest: Option<String>, // None when the registry was not or could not be checked
+    pub newer_tags: i64,
+    pub latest_tag: Option<String>,
+    pub stale_since: Option<String>, // ISO timestamp of the first check that found a newer digest
+    pub checked_at: String, // ISO timestamp
+}
+
+impl ImageFreshness {
+    /// The tag was republished with another digest since it was deployed
+    pub fn is_digest_stale(&self) -> bool {
+        matches!((&self.deployed_digest, &self.latest_digest), (Some(deployed), Some(latest)) if deployed != latest)
+    }
+
+    pub fn is_outdated(&self) -> bool {
+        self.is_digest_stale() || self.newer_tags > 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct RepositoryCache {
     pub id: i64,
     pub url: String,
-    pub last_watch: String, // ISO timestamp
+    pub last_watch: DateTime<Utc>, // When the repository was watched
+    pub last_attempt: Option<DateTime<Utc>>, // Start of the last sync
+    pub last_success: Option<DateTime<Utc>>, // End of the last successful sync
+    pub last_error: Option<String>, // Error of the last sync, cleared when one succeeds
+    pub branch: Option<String>, // None means the remote default branch
+    pub tag_pattern: Option<String>, // Semver requirement selecting release tags instead of HEAD
+    pub deployed_tag: Option<String>, // Tag currently deployed when tag_pattern is set
+    pub pinned_commit: Option<String>, // Full SHA deployed instead of HEAD
+    pub last_commit: Option<String>, // SHA of the last successfully processed checkout
+    pub path: Option<String>, // Directory holding stacks.yaml, None for the repository root
+    pub namespace: Option<String>, // Prefix of the stack names and of the NFS directory of the bindings
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// One repository of a `watch --from-file` list, or given to `watch`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchEntry {
+    pub url: String,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub only: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Prepended with `_` to the stack names, so that several repositories
+    /// can declare the same stacks, and to the NFS directory of their bindings
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct StackDefinition {
     pub name: String,
+    /// Branch, tag or commit the stack directory is deployed from instead of the watched one
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+    /// Values published to other stacks' compose files, `${KEY}` values allowed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub outputs: BTreeMap<String, String>,
+    /// Application the stack belongs to, its status combines the ones of all its stacks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    /// Stacks with a higher priority are deployed first (default 0), the
+    /// order of stacks.yaml breaking ties
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// Stacks of stacks.yaml deployed before this one, whose services must
+    /// have converged when it deploys
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Shorthand for `deploy.with_registry_auth`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub with_registry_auth: Option<bool>,
+    /// `false` leaves the stack out of the syncs, running as last deployed
+    /// unless `teardown` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Remove the stack from Swarm while it is disabled, moving it to the trash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub teardown: Option<bool>,
+    /// Settings of this stack overriding dockerops.yaml and config.yaml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploy: Option<DeploySettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updates: Option<UpdateSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<NotificationSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_history: Option<TaskHistorySettings>,
+    /// Requirements on the Swarm nodes verified before every deployment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prerequisites: Option<HostPrerequisites>,
+    /// Commands run before and after every deployment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<StackHooks>,
+    /// Name declared in stacks.yaml when `name` carries the `stack_prefix` of dockerops.yaml
+    #[serde(skip)]
+    pub declared_name: Option<String>,
+}
+
+impl StackDefinition {
+    /// Directory of the stack in the repository
+    pub fn directory(&self) -> &str {
+        self.declared_name.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// Whether the stack is disabled and has to be removed from Swarm
+    pub fn is_torn_down(&self) -> bool {
+        !self.is_enabled() && self.teardown.unwrap_or(false)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -57,18 +374,20 @@ pub struct SecretDefinition {
     pub env: String,
 }
 
-impl Image {
-    pub fn new(name: String, reference_count: i32) -> Self {
-        Self {
-            id: 0, // Will be set by database
-            name,
-            reference_count,
+impl RepositoryCache {
+    /// Human readable description of what is deployed from the repository
+    pub fn tracking(&self) -> String {
+        if let Some(commit) = &self.pinned_commit {
+            return format!("commit {}", &commit[..commit.len().min(12)]);
+        }
+        match (&self.tag_pattern, &self.deployed_tag) {
+            (Some(pattern), Some(tag)) => format!("tags {} @ {}", pattern, tag),
+            (Some(pattern), None) => format!("tags {}", pattern),
+            _ => self.branch.clone().unwrap_or_else(|| "default branch".to_string()),
         }
     }
 }
 
-
-
 impl Stack {
     pub fn new(name: String, repository_url: String, compose_path: String, hash: String) -> Self {
         Self {
@@ -78,6 +397,8 @@ impl Stack {
             compose_path,
             hash,
             status: "stopped".to_string(),
+            deferred_since: None,
+            app: None,
         }
     }
 } 
\ No newline at end of file
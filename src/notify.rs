@@ -0,0 +1,39 @@
+use anyhow::Result;
+use std::time::Duration;
+
+use crate::i18n;
+
+/// Failed attempts after which a notification is kept as undelivered
+pub const MAX_ATTEMPTS: i64 = 10;
+
+/// Post a notification to a webhook. The payload has a `text` field, which
+/// is what Slack and compatible incoming webhooks display.
+pub async fn post(url: &str, payload: &serde_json::Value) -> Result<()> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(payload)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(i18n::tr("webhook-answered", &[("status", &status), ("body", &body.trim())])));
+    }
+    Ok(())
+}
+
+/// Delay before the next attempt after `attempts` failed ones: 30 seconds,
+/// doubled each time up to an hour
+pub fn backoff(attempts: i64) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 7) as u32;
+    Duration::from_secs(30 * 2u64.pow(exponent)).min(Duration::from_secs(3600))
+}
+
+/// Webhook URL without its path, which usually holds the secret token
+pub fn display_url(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, rest)) => format!("{}://{}/…", scheme, rest.split('/').next().unwrap_or_default()),
+        None => url.to_string(),
+    }
+}
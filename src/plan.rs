@@ -0,0 +1,116 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::atomic;
+
+const FORMAT: u32 = 1;
+
+/// Changes `plan` found in the watched repositories, saved for `apply` to
+/// carry out exactly as reviewed
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Plan {
+    pub format: u32,
+    pub created_at: String,
+    pub created_by: String,
+    pub dockerops_version: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repositories: Vec<RepositoryPlan>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepositoryPlan {
+    pub url: String,
+    /// Commit the stacks were rendered from, checked out again by `apply`,
+    /// None for local directories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<StackChange>,
+    /// Images no stack of the repository would use any more, removed unless
+    /// something else still uses them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_images: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StackChange {
+    pub stack: String,
+    pub action: ChangeAction,
+    /// Compose hash of the stack in the database when the plan was made,
+    /// None for a new stack
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_hash: Option<String>,
+    /// Compose hash of the rendered file, None for a removal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Images of the rendered file, pulled on the nodes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    /// New stack deployed
+    Create,
+    /// Stack deployed with a different compose file
+    Update,
+    /// Stack deployed again with the same compose file, its last deployment failed
+    Redeploy,
+    /// Stack no longer declared, moved to the trash
+    Remove,
+}
+
+impl ChangeAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeAction::Create => "create",
+            ChangeAction::Update => "update",
+            ChangeAction::Redeploy => "redeploy",
+            ChangeAction::Remove => "remove",
+        }
+    }
+}
+
+impl Plan {
+    pub fn new(created_by: &str, repositories: Vec<RepositoryPlan>) -> Self {
+        Self {
+            format: FORMAT,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            created_by: created_by.to_string(),
+            dockerops_version: env!("CARGO_PKG_VERSION").to_string(),
+            repositories,
+        }
+    }
+
+    /// Read a plan file, refusing the ones another version of DockerOps made
+    /// since the compose hashes depend on how it renders the files
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Could not read plan {}: {}", path.display(), e))?;
+        let plan: Plan = serde_yaml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid plan {}: {}", path.display(), e))?;
+        if plan.format != FORMAT {
+            return Err(anyhow::anyhow!("Plan format {} is not supported (expected {})", plan.format, FORMAT));
+        }
+        if plan.dockerops_version != env!("CARGO_PKG_VERSION") {
+            return Err(anyhow::anyhow!("Plan made by DockerOps {}, run plan again with {}",
+                plan.dockerops_version, env!("CARGO_PKG_VERSION")));
+        }
+        Ok(plan)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        atomic::write(path, &serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.repositories.iter().all(|repository| repository.changes.is_empty())
+    }
+}
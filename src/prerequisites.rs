@@ -0,0 +1,156 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Image of the job probing the nodes, only `sh`, `grep` and `cat` are needed
+pub const PROBE_IMAGE: &str = "busybox:stable";
+
+/// Where the probe job mounts the root filesystem of the node
+pub const HOST_ROOT: &str = "/host";
+
+/// Host requirements a stack declares in stacks.yaml, checked on every
+/// active Swarm node before the stack is deployed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostPrerequisites {
+    /// Minimum Docker Engine version of the nodes ("24.0")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docker_version: Option<String>,
+    /// Kernel modules loaded or built into the kernel
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kernel_modules: Vec<String>,
+    /// Kernel parameters, with the exact value expected or a bound on a
+    /// number (">=262144", "<=10")
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sysctls: BTreeMap<String, String>,
+    /// Absolute paths that must exist on the nodes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+}
+
+/// What the probe job found on the nodes: value by (hostname, kind, name)
+pub type ProbeResults = BTreeMap<(String, String, String), String>;
+
+impl HostPrerequisites {
+    /// Reject names that can't be checked safely from a shell script
+    pub fn validate(&self) -> Result<()> {
+        let is_name = |name: &str, extra: &[char]| !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || extra.contains(&c));
+        if let Some(module) = self.kernel_modules.iter().find(|module| !is_name(module, &[])) {
+            return Err(anyhow::anyhow!("Invalid kernel module name '{}'", module));
+        }
+        if let Some(key) = self.sysctls.keys().find(|key| !is_name(key, &['.']) || key.contains("..")) {
+            return Err(anyhow::anyhow!("Invalid sysctl '{}'", key));
+        }
+        if let Some(path) = self.paths.iter().find(|path| !path.starts_with('/') || path.contains('\'') || path.split('/').any(|part| part == "..")) {
+            return Err(anyhow::anyhow!("Invalid path '{}' (absolute paths without quotes or '..' only)", path));
+        }
+        if let Some(version) = &self.docker_version {
+            if version_parts(version).is_empty() {
+                return Err(anyhow::anyhow!("Invalid docker_version '{}'", version));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether checks have to run on the nodes themselves, the Docker
+    /// version being known to the manager
+    pub fn needs_probe(&self) -> bool {
+        !self.kernel_modules.is_empty() || !self.sysctls.is_empty() || !self.paths.is_empty()
+    }
+
+    /// Whether the probe job needs the root filesystem of the nodes
+    pub fn needs_host_root(&self) -> bool {
+        !self.paths.is_empty()
+    }
+
+    /// Shell script run on every node, printing a `hostname<TAB>kind<TAB>name<TAB>value`
+    /// line per check
+    pub fn probe_script(&self) -> String {
+        let mut script = String::from("h=$(hostname)\n");
+        for module in &self.kernel_modules {
+            script.push_str(&format!(
+                "if [ -d /sys/module/{0} ] || grep -q '^{0} ' /proc/modules; then v=loaded; else v=missing; fi; printf '%s\\tmodule\\t{0}\\t%s\\n' \"$h\" \"$v\"\n",
+                module));
+        }
+        for key in self.sysctls.keys() {
+            script.push_str(&format!(
+                "v=$(cat /proc/sys/{} 2>/dev/null | tr '\\t' ' ' || true); printf '%s\\tsysctl\\t{}\\t%s\\n' \"$h\" \"${{v:-missing}}\"\n",
+                key.replace('.', "/"), key));
+        }
+        for path in &self.paths {
+            script.push_str(&format!(
+                "if [ -e '{}{}' ]; then v=present; else v=missing; fi; printf '%s\\tpath\\t{}\\t%s\\n' \"$h\" \"$v\"\n",
+                HOST_ROOT, path, path));
+        }
+        script
+    }
+
+    /// Unmet prerequisites of a node, from its Docker Engine version and what
+    /// the probe job found there
+    pub fn unmet(&self, hostname: &str, engine_version: &str, probes: &ProbeResults) -> Vec<String> {
+        let mut unmet = Vec::new();
+        if let Some(minimum) = &self.docker_version {
+            if version_parts(engine_version) < version_parts(minimum) {
+                unmet.push(format!("Docker {} < {}", engine_version, minimum));
+            }
+        }
+        if !self.needs_probe() {
+            return unmet;
+        }
+        if !probes.keys().any(|(host, _, _)| host == hostname) {
+            unmet.push("no result from the probe job".to_string());
+            return unmet;
+        }
+
+        let probe = |kind: &str, name: &str| probes.get(&(hostname.to_string(), kind.to_string(), name.to_string())).map(String::as_str);
+        for module in &self.kernel_modules {
+            if probe("module", module) != Some("loaded") {
+                unmet.push(format!("kernel module {} not loaded", module));
+            }
+        }
+        for (key, expected) in &self.sysctls {
+            match probe("sysctl", key) {
+                None | Some("missing") => unmet.push(format!("sysctl {} missing", key)),
+                Some(value) if !sysctl_matches(value, expected) => unmet.push(format!("sysctl {} = {} (expected {})", key, value, expected)),
+                Some(_) => {}
+            }
+        }
+        for path in &self.paths {
+            if probe("path", path) != Some("present") {
+                unmet.push(format!("path {} missing", path));
+            }
+        }
+        unmet
+    }
+}
+
+/// Read the output of the probe job
+pub fn parse_probe_output(output: &str) -> ProbeResults {
+    output.lines()
+        .filter_map(|line| {
+            let mut fields = line.trim_end().splitn(4, '\t');
+            Some(((fields.next()?.to_string(), fields.next()?.to_string(), fields.next()?.to_string()), fields.next()?.trim().to_string()))
+        })
+        .collect()
+}
+
+/// A sysctl value against its exact expected value (whitespace collapsed) or a numeric bound
+fn sysctl_matches(value: &str, expected: &str) -> bool {
+    let number = |text: &str| text.trim().parse::<i64>().ok();
+    if let Some(minimum) = expected.strip_prefix(">=") {
+        return number(value).zip(number(minimum)).is_some_and(|(value, minimum)| value >= minimum);
+    }
+    if let Some(maximum) = expected.strip_prefix("<=") {
+        return number(value).zip(number(maximum)).is_some_and(|(value, maximum)| value <= maximum);
+    }
+    value.split_whitespace().eq(expected.split_whitespace())
+}
+
+/// Numeric components of a version such as "24.0.7" or "20.10.17+dfsg1"
+fn version_parts(version: &str) -> Vec<u64> {
+    version.trim().trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse::<u64>().ok())
+        .collect()
+}
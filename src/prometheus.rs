@@ -0,0 +1,33 @@
+use anyhow::Result;
+
+/// Evaluate an instant PromQL query, summing the samples of a vector result.
+/// A query without samples (no traffic recorded) yields 0.
+pub async fn query_value(base_url: &str, query: &str) -> Result<f64> {
+    let url = format!("{}/api/v1/query", base_url.trim_end_matches('/'));
+    let response = reqwest::Client::new()
+        .get(&url)
+        .query(&[("query", query)])
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!("Prometheus query failed with {}: {}", status, body.trim()));
+    }
+
+    let body: serde_json::Value = response.json().await?;
+    let data = &body["data"];
+    let samples: Vec<&serde_json::Value> = match data["resultType"].as_str() {
+        Some("vector") => data["result"].as_array().map(|result| result.iter().map(|sample| &sample["value"]).collect()).unwrap_or_default(),
+        Some("scalar") => vec![&data["result"]],
+        other => return Err(anyhow::anyhow!("Unsupported Prometheus result type {:?}", other)),
+    };
+
+    // Values are [timestamp, "value"] pairs
+    samples.iter()
+        .map(|value| value[1].as_str()
+            .and_then(|value| value.parse::<f64>().ok())
+            .ok_or_else(|| anyhow::anyhow!("Invalid Prometheus sample {}", value)))
+        .sum()
+}
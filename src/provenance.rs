@@ -0,0 +1,223 @@
+use anyhow::Result;
+use base64::Engine;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::commands::glob_match;
+use crate::registry::{ImageReference, RegistryClient};
+
+const OCI_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+
+/// Builds allowed for the images matching `images`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvenancePolicy {
+    /// Glob on the image name without tag (`ghcr.io/acme/*`, `docker.io/library/nginx`)
+    pub images: String,
+    /// Globs on the source repository (`github.com/acme/*`)
+    pub source_repositories: Vec<String>,
+    /// Globs on the CI workflow path (`.github/workflows/release.yml`), any workflow when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workflows: Vec<String>,
+}
+
+impl ProvenancePolicy {
+    pub fn applies_to(&self, image: &ImageReference) -> bool {
+        glob_match(&self.images, &format!("{}/{}", image.registry_name(), image.repository))
+    }
+}
+
+/// OIDC issuers of the CI systems whose keyless certificates are accepted
+const CERTIFICATE_ISSUERS: &str = r"^https://(token\.actions\.githubusercontent\.com|gitlab\.com)$";
+
+/// Origin of an image, as stated by its SLSA provenance
+#[derive(Debug)]
+pub struct Provenance {
+    pub source: String,
+    pub workflow: String,
+    /// Image the attestation is about, pinned by digest
+    pub image: String,
+    pub predicate_type: String,
+}
+
+/// Check that the image carries a signed SLSA provenance attestation (a
+/// Sigstore bundle attached with the OCI referrers API) for a build allowed
+/// by the policy.
+///
+/// The DSSE signature is checked against the bundle's certificate, whose
+/// identity must belong to the source repository. The certificate chain up
+/// to the Fulcio root and the Rekor transparency log are then checked by
+/// `cosign` (see [`verify_signature`]).
+pub async fn verify(client: &RegistryClient, image: &ImageReference, policy: &ProvenancePolicy) -> Result<Provenance> {
+    let digest = match &image.digest {
+        Some(digest) => digest.clone(),
+        None => client.manifest_digest(image).await?
+            .ok_or_else(|| anyhow::anyhow!("the registry did not return the digest of {}:{}", image.repository, image.tag))?,
+    };
+
+    let base = format!("https://{}/v2/{}", image.registry, image.repository);
+    let response = client.send(Method::GET, &format!("{}/referrers/{}", base, digest), OCI_INDEX).await?;
+    let index: Value = if response.status().is_success() {
+        response.json().await?
+    } else {
+        // Registries without the referrers API (GHCR...) publish them under the sha256-<hex> tag
+        let response = client.send(Method::GET, &format!("{}/manifests/{}", base, digest.replace(':', "-")), OCI_INDEX).await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("no attestation is attached to {}", digest));
+        }
+        response.json().await?
+    };
+
+    let mut problems = Vec::new();
+    let bundles = index["manifests"].as_array().cloned().unwrap_or_default();
+    for manifest in bundles.iter().filter(|m| m["artifactType"].as_str().is_some_and(|t| t.contains("sigstore.bundle"))) {
+        let Some(manifest_digest) = manifest["digest"].as_str() else {
+            continue;
+        };
+        let result = async {
+            let manifest: Value = client.send(Method::GET, &format!("{}/manifests/{}", base, manifest_digest), OCI_MANIFEST).await?
+                .json().await?;
+            let layer = manifest["layers"][0]["digest"].as_str()
+                .ok_or_else(|| anyhow::anyhow!("attestation {} has no layer", manifest_digest))?;
+            let bundle: Value = client.send(Method::GET, &format!("{}/blobs/{}", base, layer), "*/*").await?
+                .json().await?;
+            check_bundle(&bundle, &digest, policy)
+        }.await;
+        let result = result.map(|provenance| Provenance {
+            image: format!("{}/{}@{}", image.registry_name(), image.repository, digest),
+            ..provenance
+        });
+
+        match result {
+            Ok(provenance) => return Ok(provenance),
+            Err(e) => problems.push(e.to_string()),
+        }
+    }
+
+    if problems.is_empty() {
+        return Err(anyhow::anyhow!("no Sigstore attestation is attached to {}", digest));
+    }
+    Err(anyhow::anyhow!("no attestation satisfies the policy: {}", problems.join("; ")))
+}
+
+fn check_bundle(bundle: &Value, digest: &str, policy: &ProvenancePolicy) -> Result<Provenance> {
+    let base64 = &base64::engine::general_purpose::STANDARD;
+    let envelope = &bundle["dsseEnvelope"];
+    let payload_type = envelope["payloadType"].as_str().unwrap_or_default();
+    let payload = base64.decode(envelope["payload"].as_str().unwrap_or_default())?;
+    let signature = base64.decode(envelope["signatures"][0]["sig"].as_str().unwrap_or_default())?;
+
+    // v0.3 bundles hold the leaf certificate, older ones a chain starting with it
+    let material = &bundle["verificationMaterial"];
+    let certificate = material["certificate"]["rawBytes"].as_str()
+        .or_else(|| material["x509CertificateChain"]["certificates"][0]["rawBytes"].as_str())
+        .ok_or_else(|| anyhow::anyhow!("the attestation has no signing certificate"))?;
+    let certificate = openssl::x509::X509::from_der(&base64.decode(certificate)?)?;
+
+    // DSSE signs the pre-authentication encoding of the payload
+    let mut signed = format!("DSSEv1 {} {} {} ", payload_type.len(), payload_type, payload.len()).into_bytes();
+    signed.extend_from_slice(&payload);
+    let key = certificate.public_key()?;
+    let mut verifier = openssl::sign::Verifier::new(openssl::hash::MessageDigest::sha256(), &key)?;
+    if !verifier.verify_oneshot(&signature, &signed)? {
+        return Err(anyhow::anyhow!("the attestation signature does not match its certificate"));
+    }
+
+    let statement: Value = serde_json::from_slice(&payload)?;
+    let predicate_type = statement["predicateType"].as_str().unwrap_or_default();
+    if !predicate_type.starts_with("https://slsa.dev/provenance/") {
+        return Err(anyhow::anyhow!("attestation of type {} is not a SLSA provenance", predicate_type));
+    }
+    let hex = digest.strip_prefix("sha256:").unwrap_or(digest);
+    let subjects = statement["subject"].as_array().cloned().unwrap_or_default();
+    if !subjects.iter().any(|subject| subject["digest"]["sha256"].as_str() == Some(hex)) {
+        return Err(anyhow::anyhow!("the provenance is about another image"));
+    }
+
+    // SLSA v1 (GitHub, GitLab) and v0.2 layouts
+    let predicate = &statement["predicate"];
+    let workflow = &predicate["buildDefinition"]["externalParameters"]["workflow"];
+    let (source, path) = match workflow["repository"].as_str() {
+        Some(repository) => (repository, workflow["path"].as_str().unwrap_or_default()),
+        None => (
+            predicate["invocation"]["configSource"]["uri"].as_str().unwrap_or_default(),
+            predicate["invocation"]["configSource"]["entryPoint"].as_str().unwrap_or_default(),
+        ),
+    };
+    let source = normalize_source(source);
+    if source.is_empty() {
+        return Err(anyhow::anyhow!("the provenance does not name its source repository"));
+    }
+
+    if !policy.source_repositories.iter().any(|pattern| glob_match(pattern, &source)) {
+        return Err(anyhow::anyhow!("built from {}, which is not an allowed source repository", source));
+    }
+    if !policy.workflows.is_empty() && !policy.workflows.iter().any(|pattern| glob_match(pattern, path)) {
+        return Err(anyhow::anyhow!("built by workflow {}, which is not allowed", path));
+    }
+
+    // Keyless certificates are issued to the workflow that signed them
+    let identities: Vec<String> = certificate.subject_alt_names()
+        .map(|names| names.iter().filter_map(|name| name.uri().map(normalize_source)).collect())
+        .unwrap_or_default();
+    if identities.is_empty() {
+        return Err(anyhow::anyhow!("the signing certificate names no workflow identity"));
+    }
+    if !identities.iter().any(|identity| identity.starts_with(&format!("{}/", source))) {
+        return Err(anyhow::anyhow!("signed by {}, not by a workflow of {}", identities.join(", "), source));
+    }
+
+    Ok(Provenance {
+        source,
+        workflow: path.to_string(),
+        image: String::new(),
+        predicate_type: predicate_type.to_string(),
+    })
+}
+
+/// Verify the attestation with `cosign verify-attestation`: its certificate
+/// must chain up to the Fulcio root, be logged in Rekor and be issued to a
+/// workflow of the source repository. `docker_config` holds the registry
+/// logins, like for `docker pull`.
+pub async fn verify_signature(provenance: &Provenance, docker_config: Option<&std::path::Path>) -> Result<()> {
+    let identity = format!("^https://{}/", escape_regex(&provenance.source));
+    let mut command = tokio::process::Command::new("cosign");
+    command.args(["verify-attestation", "--new-bundle-format", "--output", "text"])
+        .args(["--type", &provenance.predicate_type])
+        .args(["--certificate-identity-regexp", &identity])
+        .args(["--certificate-oidc-issuer-regexp", CERTIFICATE_ISSUERS])
+        .arg(&provenance.image);
+    if let Some(docker_config) = docker_config {
+        command.env("DOCKER_CONFIG", docker_config);
+    }
+
+    let output = command.output().await
+        .map_err(|e| anyhow::anyhow!("cosign is required to verify the attestation signature: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default();
+        return Err(anyhow::anyhow!("cosign rejected the attestation: {}", reason.trim()));
+    }
+    Ok(())
+}
+
+/// Escape the characters `cosign` reads as regular expression syntax
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// `git+https://github.com/acme/app@refs/heads/main` -> `github.com/acme/app`
+fn normalize_source(uri: &str) -> String {
+    let uri = uri.strip_prefix("git+").unwrap_or(uri);
+    let uri = uri.split_once("://").map_or(uri, |(_, rest)| rest);
+    let uri = uri.split_once('@').map_or(uri, |(path, _)| path);
+    uri.trim_end_matches('/').trim_end_matches(".git").to_lowercase()
+}
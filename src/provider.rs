@@ -0,0 +1,169 @@
+use std::path::PathBuf;
+
+use crate::config::Config;
+
+/// Git hosting service of a watched repository, deciding which token is
+/// used for HTTPS remotes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Provider {
+    GitHub,
+    GitLab,
+    Gitea,
+    Bitbucket,
+}
+
+impl Provider {
+    /// Provider hosting the URL, `None` for other git servers
+    pub fn detect(url: &str, config: &Config) -> Option<Self> {
+        let host = host(url)?;
+        if host == "github.com" {
+            return Some(Provider::GitHub);
+        }
+        if host == "gitlab.com" || listed(&config.gitlab_hosts, &host) {
+            return Some(Provider::GitLab);
+        }
+        // Codeberg runs Forgejo, a Gitea fork with the same API
+        if host == "codeberg.org" || listed(&config.gitea_hosts, &host) {
+            return Some(Provider::Gitea);
+        }
+        if host == "bitbucket.org" || listed(&config.bitbucket_hosts, &host) {
+            return Some(Provider::Bitbucket);
+        }
+        None
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Provider::GitHub => "GitHub",
+            Provider::GitLab => "GitLab",
+            Provider::Gitea => "Gitea",
+            Provider::Bitbucket => "Bitbucket",
+        }
+    }
+
+    /// Environment variable holding the provider's token
+    pub fn token_variable(&self) -> &'static str {
+        match self {
+            Provider::GitHub => "GITHUB_TOKEN",
+            Provider::GitLab => "GITLAB_TOKEN",
+            Provider::Gitea => "GITEA_TOKEN",
+            Provider::Bitbucket => "BITBUCKET_APP_PASSWORD",
+        }
+    }
+
+    /// Username and password used for HTTPS remotes, `None` as username
+    /// keeping the one from the URL
+    pub fn credentials(&self, config: &Config) -> Option<(Option<String>, String)> {
+        match self {
+            Provider::GitHub => config.github_token().map(|token| (None, token)),
+            // Personal and project access tokens accept any username, CI job
+            // tokens only work with gitlab-ci-token
+            Provider::GitLab => match config.gitlab_token() {
+                Some(token) => Some((Some("oauth2".to_string()), token)),
+                None => std::env::var("CI_JOB_TOKEN").ok().map(|token| (Some("gitlab-ci-token".to_string()), token)),
+            },
+            // Gitea authenticates a token given as the username
+            Provider::Gitea => config.gitea_token().map(|token| (Some(token), "x-oauth-basic".to_string())),
+            // App passwords (Cloud) and HTTP access tokens (Server) belong to
+            // a user, given in the configuration or in the URL
+            Provider::Bitbucket => config.bitbucket_app_password().map(|password| (config.bitbucket_username(), password)),
+        }
+    }
+}
+
+fn listed(hosts: &Option<Vec<String>>, host: &str) -> bool {
+    hosts.as_ref().is_some_and(|hosts| hosts.iter().any(|h| h.eq_ignore_ascii_case(host)))
+}
+
+/// Lowercase host of an https, ssh, scp-like (`git@host:owner/repo`) or
+/// scheme-less (`host/owner/repo`) URL
+pub fn host(url: &str) -> Option<String> {
+    let url = url.trim();
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split('/').next()?;
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = authority.split(':').next()?;
+    if host.is_empty() {
+        return None;
+    }
+    Some(host.to_lowercase())
+}
+
+/// URL handed to git: scheme-less `host/owner/repo` URLs are cloned over
+/// HTTPS and the web pages of Bitbucket hosts are turned into their
+/// repository's URL, other servers' paths being kept as given
+pub fn clone_url(url: &str, config: &Config) -> String {
+    // URLs with a scheme and scp-like ones contain a ':'
+    let first_segment = url.split('/').next().unwrap_or_default();
+    let url = if !url.contains(':') && first_segment.contains('.') && !first_segment.starts_with('.') {
+        format!("https://{}", url)
+    } else {
+        url.to_string()
+    };
+
+    if Provider::detect(&url, config) != Some(Provider::Bitbucket) {
+        return url;
+    }
+    bitbucket_clone_url(&url).unwrap_or(url)
+}
+
+/// Clone URL of a Bitbucket Server `/projects/KEY/repos/name/browse` page or
+/// of a Bitbucket Cloud `/workspace/repo/src/<branch>` page
+fn bitbucket_clone_url(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://").filter(|(scheme, _)| scheme.starts_with("http"))?;
+    let (authority, path) = rest.split_once('/')?;
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+    // A context path may come before /projects (https://host/bitbucket/projects/...)
+    if let Some(index) = segments.iter().position(|segment| *segment == "projects") {
+        if let (Some(project), Some(&"repos"), Some(name)) = (segments.get(index + 1), segments.get(index + 2), segments.get(index + 3)) {
+            let context: String = segments[..index].iter().map(|segment| format!("{}/", segment)).collect();
+            return Some(format!("{}://{}/{}scm/{}/{}.git", scheme, authority, context, project.to_lowercase(), name));
+        }
+    }
+
+    match segments.as_slice() {
+        [workspace, repo, "src", ..] if host(url).as_deref() == Some("bitbucket.org") => {
+            Some(format!("{}://{}/{}/{}.git", scheme, authority, workspace, repo.trim_end_matches(".git")))
+        }
+        _ => None,
+    }
+}
+
+/// Whether git reaches the URL over SSH
+pub fn is_ssh(url: &str) -> bool {
+    match url.split_once("://") {
+        Some((scheme, _)) => scheme == "ssh" || scheme == "git+ssh",
+        None => url.split_once(':').is_some_and(|(host, _)| !host.contains('/')),
+    }
+}
+
+/// Directory of a local repository given as a path or a `file://` URL
+pub fn local_path(url: &str) -> Option<PathBuf> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return Some(PathBuf::from(path));
+    }
+    if url.starts_with('/') || url.starts_with("./") || url.starts_with("../") || url == "." {
+        return Some(PathBuf::from(url));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitbucket_pages_are_rewritten_on_bitbucket_hosts_only() {
+        let mut config = Config::default();
+        config.bitbucket_hosts = Some(vec!["git.example.com".to_string()]);
+        assert_eq!(clone_url("https://git.example.com/bitbucket/projects/OPS/repos/stacks/browse", &config),
+            "https://git.example.com/bitbucket/scm/ops/stacks.git");
+        assert_eq!(clone_url("https://bitbucket.org/team/stacks/src/main/", &config), "https://bitbucket.org/team/stacks.git");
+        // A group or repository named "projects" elsewhere is left alone
+        assert_eq!(clone_url("https://gitlab.com/acme/projects/ops/repos/stacks", &config),
+            "https://gitlab.com/acme/projects/ops/repos/stacks");
+        assert_eq!(clone_url("git.internal.net/projects/OPS/repos/stacks", &config),
+            "https://git.internal.net/projects/OPS/repos/stacks");
+    }
+}
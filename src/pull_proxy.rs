@@ -0,0 +1,178 @@
+use anyhow::Result;
+use futures_util::StreamExt;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::Instant;
+
+use crate::registry::{ImageReference, RegistryClient, RegistryCredentials};
+
+// Upstream headers the Docker daemon relies on when pulling
+const FORWARDED_HEADERS: &[&str] = &[
+    "Content-Type",
+    "Content-Length",
+    "Docker-Content-Digest",
+    "Docker-Distribution-Api-Version",
+    "Etag",
+];
+
+/// Local registry endpoint relaying image downloads from upstream
+/// registries at a capped rate.
+///
+/// The Docker daemon pulls `127.0.0.1:<port>/<token>/<registry>/<repository>:<tag>`
+/// (loopback registries are allowed over plain HTTP by default) and the
+/// proxy fetches the matching manifests and blobs upstream with the
+/// repository's registry credentials, like the freshness checks do. The
+/// random token of each proxy keeps other local processes from pulling
+/// private images with those credentials.
+pub struct PullProxy {
+    addr: SocketAddr,
+    token: String,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+struct ProxyState {
+    client: RegistryClient,
+    limiter: RateLimiter,
+    token: String,
+}
+
+impl PullProxy {
+    pub async fn start(bytes_per_second: u64, credentials: RegistryCredentials) -> Result<Self> {
+        let mut token = [0u8; 16];
+        openssl::rand::rand_bytes(&mut token)?;
+        let token = hex::encode(token);
+        let state = Arc::new(ProxyState {
+            client: RegistryClient::with_credentials(credentials),
+            limiter: RateLimiter::new(bytes_per_second),
+            token: token.clone(),
+        });
+
+        let make_service = make_service_fn(move |_connection| {
+            let state = state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| handle_request(state.clone(), request)))
+            }
+        });
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .map_err(|e| anyhow::anyhow!("Could not start the pull proxy: {}", e))?
+            .serve(make_service);
+        let addr = server.local_addr();
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let _ = server.with_graceful_shutdown(async {
+                let _ = stopped.await;
+            }).await;
+        });
+
+        Ok(Self { addr, token, shutdown: Some(shutdown) })
+    }
+
+    /// Reference making the Docker daemon pull an image through the proxy
+    pub fn proxied_reference(&self, image: &ImageReference) -> String {
+        match &image.digest {
+            Some(digest) => format!("{}/{}/{}/{}@{}", self.addr, self.token, image.registry, image.repository, digest),
+            None => format!("{}/{}/{}/{}:{}", self.addr, self.token, image.registry, image.repository, image.tag),
+        }
+    }
+}
+
+impl Drop for PullProxy {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn handle_request(state: Arc<ProxyState>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    Ok(relay(state, request).await.unwrap_or_else(|e| {
+        println!("    ⚠️  Pull proxy request failed: {}", e);
+        Response::builder()
+            .status(StatusCode::BAD_GATEWAY)
+            .body(Body::from(e.to_string()))
+            .unwrap_or_default()
+    }))
+}
+
+async fn relay(state: Arc<ProxyState>, request: Request<Body>) -> Result<Response<Body>> {
+    let path = request.uri().path();
+
+    // API version check made by the daemon before pulling
+    if path == "/v2/" || path == "/v2" {
+        return Ok(Response::new(Body::from("{}")));
+    }
+
+    // /v2/<token>/<registry>/<repository>/(manifests|blobs)/<reference>
+    let Some((token, rest)) = path.strip_prefix("/v2/").and_then(|rest| rest.split_once('/')) else {
+        return Ok(Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty())?);
+    };
+    if token.len() != state.token.len() || !openssl::memcmp::eq(token.as_bytes(), state.token.as_bytes()) {
+        return Ok(Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty())?);
+    }
+    let Some((registry, upstream_path)) = rest.split_once('/') else {
+        return Ok(Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty())?);
+    };
+    let mut url = format!("https://{}/v2/{}", registry, upstream_path);
+    if let Some(query) = request.uri().query() {
+        url = format!("{}?{}", url, query);
+    }
+
+    let accept = request.headers()
+        .get("Accept")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("*/*")
+        .to_string();
+
+    let upstream = state.client.send(request.method().clone(), &url, &accept).await?;
+
+    let mut response = Response::builder().status(upstream.status());
+    for name in FORWARDED_HEADERS {
+        if let Some(value) = upstream.headers().get(*name) {
+            response = response.header(*name, value);
+        }
+    }
+
+    // Chunks are only read from upstream once the limiter lets them through,
+    // so TCP backpressure slows the download itself down
+    let body = upstream.bytes_stream().then(move |chunk| {
+        let state = state.clone();
+        async move {
+            if let Ok(bytes) = &chunk {
+                state.limiter.acquire(bytes.len()).await;
+            }
+            chunk
+        }
+    });
+
+    Ok(response.body(Body::wrap_stream(body))?)
+}
+
+/// Paces relayed bytes so that they never exceed the configured rate
+struct RateLimiter {
+    bytes_per_second: u64,
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    fn new(bytes_per_second: u64) -> Self {
+        Self { bytes_per_second: bytes_per_second.max(1), next_slot: Mutex::new(None) }
+    }
+
+    async fn acquire(&self, bytes: usize) {
+        let start = {
+            let mut next_slot = self.next_slot.lock().await;
+            let now = Instant::now();
+            let start = next_slot.map_or(now, |slot| slot.max(now));
+            *next_slot = Some(start + Duration::from_secs_f64(bytes as f64 / self.bytes_per_second as f64));
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+}
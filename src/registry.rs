@@ -0,0 +1,299 @@
+use anyhow::Result;
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+const DOCKER_HUB: &str = "registry-1.docker.io";
+
+// Manifest lists and OCI indexes are accepted so the digest matches the one
+// Docker records when pulling a multi-architecture image by tag
+const MANIFEST_TYPES: &str = "application/vnd.oci.image.index.v1+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.docker.distribution.manifest.v2+json, \
+    application/vnd.oci.image.manifest.v1+json";
+
+// Upper bound on paginated tag listings, large repositories have thousands of tags
+const MAX_TAG_PAGES: usize = 10;
+
+/// An image reference split into the parts used by the registry API
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageReference {
+    pub registry: String,
+    pub repository: String,
+    pub tag: String,
+    pub digest: Option<String>,
+}
+
+impl ImageReference {
+    pub fn parse(image: &str) -> Self {
+        let (name, digest) = match image.split_once('@') {
+            Some((name, digest)) => (name, Some(digest.to_string())),
+            None => (image, None),
+        };
+
+        // Only a ':' after the last '/' separates the tag, an earlier one is a registry port
+        let (name, tag) = match name.rfind(':') {
+            Some(index) if !name[index..].contains('/') => (&name[..index], name[index + 1..].to_string()),
+            _ => (name, "latest".to_string()),
+        };
+
+        let (registry, repository) = match name.split_once('/') {
+            Some(("docker.io" | "index.docker.io", rest)) => (DOCKER_HUB.to_string(), rest.to_string()),
+            Some((first, rest)) if first.contains('.') || first.contains(':') || first == "localhost" => {
+                (first.to_string(), rest.to_string())
+            }
+            _ => (DOCKER_HUB.to_string(), name.to_string()),
+        };
+
+        // Official Docker Hub images live under library/
+        let repository = if registry == DOCKER_HUB && !repository.contains('/') {
+            format!("library/{}", repository)
+        } else {
+            repository
+        };
+
+        Self { registry, repository, tag, digest }
+    }
+
+    /// Registry host as users write it in compose files
+    pub fn registry_name(&self) -> &str {
+        if self.registry == DOCKER_HUB { "docker.io" } else { &self.registry }
+    }
+}
+
+/// Username and password (or token) for one registry
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegistryLogin {
+    pub username: String,
+    pub password: String,
+}
+
+/// Logins by registry host, as declared in a repository's `registries.yaml`
+#[derive(Debug, Clone, Default)]
+pub struct RegistryCredentials {
+    logins: HashMap<String, RegistryLogin>,
+}
+
+impl RegistryCredentials {
+    /// Index logins declared with user-facing names (`docker.io`, `ghcr.io`) by API host
+    pub fn new(logins: BTreeMap<String, RegistryLogin>) -> Self {
+        let logins = logins.into_iter()
+            .map(|(name, login)| (ImageReference::parse(&format!("{}/image", name)).registry, login))
+            .collect();
+        Self { logins }
+    }
+
+    /// Login for an API host such as `registry-1.docker.io`
+    pub fn get(&self, registry: &str) -> Option<&RegistryLogin> {
+        self.logins.get(registry)
+    }
+}
+
+/// Minimal client for the registry HTTP API v2, using bearer tokens when the
+/// registry asks for them (Docker Hub, GHCR, Quay...), anonymous unless
+/// credentials are given for the registry
+#[derive(Default)]
+pub struct RegistryClient {
+    http: reqwest::Client,
+    credentials: RegistryCredentials,
+}
+
+impl RegistryClient {
+    pub fn with_credentials(credentials: RegistryCredentials) -> Self {
+        Self { http: reqwest::Client::new(), credentials }
+    }
+
+    /// Digest currently published for the reference's tag
+    pub async fn manifest_digest(&self, image: &ImageReference) -> Result<Option<String>> {
+        let url = format!("https://{}/v2/{}/manifests/{}", image.registry, image.repository, image.tag);
+        let response = self.send(Method::HEAD, &url, MANIFEST_TYPES).await?;
+
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+
+        Ok(response.headers()
+            .get("Docker-Content-Digest")
+            .and_then(|digest| digest.to_str().ok())
+            .map(|digest| digest.to_string()))
+    }
+
+    /// All tags of the reference's repository
+    pub async fn list_tags(&self, image: &ImageReference) -> Result<Vec<String>> {
+        let mut tags = Vec::new();
+        let mut url = format!("https://{}/v2/{}/tags/list?n=1000", image.registry, image.repository);
+
+        for _ in 0..MAX_TAG_PAGES {
+            let response = self.send(Method::GET, &url, "application/json").await?;
+            if !response.status().is_success() {
+                return Err(anyhow::anyhow!("Could not list tags of {}: HTTP {}", image.repository, response.status()));
+            }
+
+            let next = response.headers()
+                .get("Link")
+                .and_then(|link| link.to_str().ok())
+                .and_then(next_page);
+
+            let body: serde_json::Value = response.json().await?;
+            if let Some(page) = body["tags"].as_array() {
+                tags.extend(page.iter().filter_map(|tag| tag.as_str().map(|tag| tag.to_string())));
+            }
+
+            match next {
+                // The Link header is relative to the registry root
+                Some(path) if path.starts_with('/') => url = format!("https://{}{}", image.registry, path),
+                Some(path) => url = path,
+                None => break,
+            }
+        }
+
+        Ok(tags)
+    }
+
+    /// Send a request, retrying with a bearer token (or basic credentials) when challenged
+    pub async fn send(&self, method: Method, url: &str, accept: &str) -> Result<reqwest::Response> {
+        let response = self.http.request(method.clone(), url)
+            .header("Accept", accept)
+            .send()
+            .await?;
+
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        let challenge = response.headers()
+            .get("WWW-Authenticate")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+
+        let login = url.split_once("://")
+            .and_then(|(_, rest)| rest.split('/').next())
+            .and_then(|registry| self.credentials.get(registry));
+        
+        if let (Some(login), true) = (login, challenge.starts_with("Basic")) {
+            return Ok(self.http.request(method, url)
+                .header("Accept", accept)
+                .basic_auth(&login.username, Some(&login.password))
+                .send()
+                .await?);
+        }
+
+        match self.bearer_token(&challenge, login).await? {
+            Some(token) => Ok(self.http.request(method, url)
+                .header("Accept", accept)
+                .bearer_auth(token)
+                .send()
+                .await?),
+            None => Ok(response),
+        }
+    }
+
+    async fn bearer_token(&self, challenge: &str, login: Option<&RegistryLogin>) -> Result<Option<String>> {
+        let Some(params) = challenge.strip_prefix("Bearer ") else {
+            return Ok(None);
+        };
+        let params = parse_challenge(params);
+        let Some(realm) = params.get("realm") else {
+            return Ok(None);
+        };
+
+        let query: Vec<(&str, &str)> = ["service", "scope"]
+            .iter()
+            .filter_map(|key| params.get(*key).map(|value| (*key, value.as_str())))
+            .collect();
+
+        let mut request = self.http.get(realm).query(&query);
+        if let Some(login) = login {
+            request = request.basic_auth(&login.username, Some(&login.password));
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        Ok(body["token"].as_str()
+            .or_else(|| body["access_token"].as_str())
+            .map(|token| token.to_string()))
+    }
+}
+
+/// Parse `realm="...",service="...",scope="..."`, where quoted values may contain commas
+fn parse_challenge(params: &str) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    let mut rest = params.trim();
+
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let (value, remaining) = match after.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((value, remaining)) => (value.to_string(), remaining),
+                None => (quoted.to_string(), ""),
+            },
+            None => match after.split_once(',') {
+                Some((value, remaining)) => (value.trim().to_string(), remaining),
+                None => (after.trim().to_string(), ""),
+            },
+        };
+        values.insert(key, value);
+        rest = remaining.trim_start_matches(',').trim();
+    }
+
+    values
+}
+
+/// Extract the target of `Link: </v2/...>; rel="next"`
+fn next_page(link: &str) -> Option<String> {
+    link.split(',')
+        .find(|part| part.contains("rel=\"next\""))
+        .and_then(|part| part.split_once('<'))
+        .and_then(|(_, rest)| rest.split_once('>'))
+        .map(|(target, _)| target.to_string())
+}
+
+/// A version-like tag such as `1.25`, `v2.1.3` or `16-alpine`
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct TagVersion {
+    numbers: Vec<u64>,
+}
+
+fn parse_tag(tag: &str) -> Option<(TagVersion, &str)> {
+    let tag = tag.strip_prefix('v').unwrap_or(tag);
+    let (version, suffix) = match tag.find('-') {
+        Some(index) => tag.split_at(index),
+        None => (tag, ""),
+    };
+
+    let numbers = version.split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+
+    if numbers.is_empty() || numbers.len() > 4 {
+        return None;
+    }
+
+    Some((TagVersion { numbers }, suffix))
+}
+
+/// Tags newer than `current` with the same shape (component count and
+/// variant suffix, e.g. `-alpine`), newest first. Empty when `current` is
+/// not version-like (`latest`, `stable`...).
+pub fn newer_tags(current: &str, tags: &[String]) -> Vec<String> {
+    let Some((current_version, current_suffix)) = parse_tag(current) else {
+        return Vec::new();
+    };
+
+    let mut newer: Vec<(TagVersion, &String)> = tags.iter()
+        .filter_map(|tag| parse_tag(tag).map(|(version, suffix)| (version, suffix, tag)))
+        .filter(|(version, suffix, _)| {
+            *suffix == current_suffix
+                && version.numbers.len() == current_version.numbers.len()
+                && *version > current_version
+        })
+        .map(|(version, _, tag)| (version, tag))
+        .collect();
+
+    newer.sort_by(|a, b| b.0.cmp(&a.0));
+    newer.into_iter().map(|(_, tag)| tag.clone()).collect()
+}
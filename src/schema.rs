@@ -0,0 +1,456 @@
+use serde_json::{json, Value};
+
+/// Files of a repository DockerOps reads, with the schema `schema` prints for each
+pub const FILES: [(&str, &str); 4] = [
+    ("stacks", "stacks.yaml"),
+    ("dockerops", "dockerops.yaml"),
+    ("volumes", "volumes.yaml"),
+    ("nfs", "nfs.yaml"),
+];
+
+/// JSON Schema (draft 2020-12) of a file of `FILES`, None for an unknown one
+pub fn for_file(file: &str) -> Option<Value> {
+    let (schema, title) = match file {
+        "stacks" => (stacks(), "stacks.yaml"),
+        "dockerops" => (dockerops(), "dockerops.yaml"),
+        "volumes" => (volumes(), "volumes.yaml"),
+        "nfs" => (nfs(), "nfs.yaml"),
+        _ => return None,
+    };
+    let mut schema = schema;
+    let object = schema.as_object_mut()?;
+    object.insert("$schema".to_string(), json!("https://json-schema.org/draft/2020-12/schema"));
+    object.insert("title".to_string(), json!(format!("DockerOps {}", title)));
+    Some(schema)
+}
+
+/// A stacks.yaml: the stacks of the repository, deployed in this order unless
+/// `priority` or `depends_on` say otherwise
+fn stacks() -> Value {
+    json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": { "type": "string", "description": "Directory of the stack holding its docker-compose.yml, and its Swarm name after stack_prefix" },
+                "ref": { "type": "string", "description": "Branch, tag or commit the stack is deployed from instead of the watched one" },
+                "outputs": string_map("Values published to other stacks' compose files, ${KEY} values allowed"),
+                "app": { "type": "string", "description": "Application the stack belongs to" },
+                "priority": { "type": "integer", "description": "Stacks with a higher priority are deployed first (default 0)" },
+                "depends_on": string_list("Stacks of stacks.yaml deployed before this one, whose services must have converged when it deploys"),
+                "with_registry_auth": { "type": "boolean", "description": "Shorthand for deploy.with_registry_auth" },
+                "enabled": { "type": "boolean", "description": "false leaves the stack out of the syncs, running as last deployed unless teardown is set" },
+                "teardown": { "type": "boolean", "description": "Remove the stack from Swarm while it is disabled, moving it to the trash" },
+                "deploy": deploy(),
+                "updates": updates(),
+                "notifications": notifications(),
+                "task_history": task_history(),
+                "prerequisites": prerequisites(),
+                "hooks": hooks(),
+            },
+        },
+    })
+}
+
+/// A dockerops.yaml: defaults of the repository for its own stacks
+fn dockerops() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "stack_prefix": {
+                "type": "string",
+                "pattern": "^[A-Za-z0-9_-]*$",
+                "description": "Prepended to the names of stacks.yaml to form the Swarm stack names",
+            },
+            "prune_stacks": { "type": "boolean", "description": "Overrides prune_stacks of config.yaml" },
+            "deploy": deploy(),
+            "updates": updates(),
+            "notifications": notifications(),
+            "task_history": task_history(),
+        },
+    })
+}
+
+/// A volumes.yaml: volumes and bind mounts created before the stacks deploy
+fn volumes() -> Value {
+    json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "required": ["id", "type", "path"],
+            "properties": {
+                "id": { "type": "string" },
+                "type": { "enum": ["volume", "binding"] },
+                "path": { "type": "string" },
+            },
+        },
+    })
+}
+
+/// A nfs.yaml: NFS share the volumes are created in
+fn nfs() -> Value {
+    json!({
+        "type": "object",
+        "required": ["path"],
+        "properties": {
+            "path": { "type": "string", "description": "Mount point of the NFS share on the nodes" },
+        },
+    })
+}
+
+fn deploy() -> Value {
+    json!({
+        "type": "object",
+        "description": "Flags passed to docker stack deploy",
+        "additionalProperties": false,
+        "properties": {
+            "prune": { "type": "boolean", "description": "Remove the services no longer defined in the compose file" },
+            "resolve_image": { "enum": ["always", "changed", "never"] },
+            "with_registry_auth": { "type": "boolean", "description": "Send the registry logins to the nodes pulling the images" },
+            "selective": { "type": "boolean", "description": "Update only the changed services when nothing but their images or replica counts changed" },
+            "convergence_timeout": duration("How long to wait for every service to run all its replicas (default 5m, \"0\" to not wait)"),
+        },
+    })
+}
+
+fn updates() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "failure_threshold": { "type": "integer", "minimum": 0, "description": "Overrides failure_threshold of config.yaml" },
+            "traffic_aware": { "type": "boolean", "description": "false deploys the updates without waiting for traffic_aware_updates" },
+            "canary": {
+                "type": "object",
+                "description": "Watch the new tasks of updated stacks and roll the update back when they misbehave",
+                "additionalProperties": false,
+                "properties": {
+                    "duration": duration("How long the new tasks are sampled (default 2m)"),
+                    "max_restarts": { "type": "integer", "minimum": 0, "description": "Failed or rejected new tasks allowed per service (default 0)" },
+                    "max_error_lines": { "type": "integer", "minimum": 0, "description": "Error log lines allowed per service (default 10)" },
+                    "error_patterns": string_list("Case-insensitive substrings making a log line an error (default error, fatal, panic)"),
+                },
+            },
+            "hash_exclude": {
+                "type": "object",
+                "description": "Changes that don't make the compose file different for DockerOps",
+                "additionalProperties": false,
+                "properties": {
+                    "lines": string_list("Globs matched against each trimmed line"),
+                    "keys": string_list("Dotted key paths whose segments are globs"),
+                },
+            },
+            "auto_rollback": { "type": "boolean", "description": "Redeploy the previous revision when an update does not converge" },
+        },
+    })
+}
+
+fn notifications() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "failure_issues": { "type": "boolean", "description": "Overrides failure_issues of config.yaml" },
+            "webhooks": string_list("Webhooks notified in addition to the ones of the less precise layers"),
+            "inherit_webhooks": { "type": "boolean", "description": "false notifies only webhooks, not the ones of the less precise layers" },
+        },
+    })
+}
+
+fn task_history() -> Value {
+    json!({
+        "type": "object",
+        "description": "Stopped tasks whose containers the daemon removes from the nodes",
+        "additionalProperties": false,
+        "properties": {
+            "keep": { "type": "integer", "minimum": 0, "description": "Stopped tasks kept per replica slot, the most recent ones" },
+            "max_age": duration("Stopped tasks older than this are removed even within keep"),
+        },
+    })
+}
+
+fn prerequisites() -> Value {
+    json!({
+        "type": "object",
+        "description": "Requirements on the Swarm nodes verified before every deployment",
+        "additionalProperties": false,
+        "properties": {
+            "docker_version": { "type": "string", "description": "Minimum Docker Engine version of the nodes" },
+            "kernel_modules": string_list("Kernel modules loaded or built into the kernel"),
+            "sysctls": string_map("Kernel parameters, with the exact value expected or a bound such as \">=262144\""),
+            "paths": string_list("Absolute paths that must exist on the nodes"),
+        },
+    })
+}
+
+fn hooks() -> Value {
+    let hook = json!({
+        "oneOf": [
+            { "type": "string", "description": "Shell command run in the stack directory" },
+            {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "run": { "type": "string", "description": "Shell command run in the stack directory" },
+                    "image": { "type": "string", "description": "Image of a one-off Swarm job on the stack networks" },
+                    "command": string_list("Command of the job, the image's default command when empty"),
+                    "networks": string_list("Networks of the stack the job is attached to"),
+                    "env": string_map("Environment of the command or the job"),
+                    "timeout": duration("Longest run of the hook (default 10m)"),
+                },
+                "oneOf": [
+                    { "required": ["run"], "not": { "anyOf": [{ "required": ["image"] }, { "required": ["command"] }, { "required": ["networks"] }] } },
+                    { "required": ["image"], "not": { "required": ["run"] } },
+                ],
+            },
+        ],
+    });
+    json!({
+        "type": "object",
+        "description": "Commands run before and after every deployment",
+        "additionalProperties": false,
+        "properties": {
+            "pre": { "type": "array", "items": hook, "description": "Run before the stack is deployed" },
+            "post": { "type": "array", "items": hook, "description": "Run once the stack converged" },
+        },
+    })
+}
+
+fn string_list(description: &str) -> Value {
+    json!({ "type": "array", "items": { "type": "string" }, "description": description })
+}
+
+fn string_map(description: &str) -> Value {
+    json!({ "type": "object", "additionalProperties": { "type": "string" }, "description": description })
+}
+
+fn duration(description: &str) -> Value {
+    json!({ "type": "string", "pattern": "^\\s*[0-9]+\\s*(s|sec|secs|m|min|mins|h|d)?\\s*$", "description": description })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CanarySettings, DeploySettings, HashExclusions, NotificationSettings, RepositorySettings,
+        ResolveImage, TaskHistorySettings, UpdateSettings};
+    use crate::hooks::{Hook, StackHooks};
+    use crate::models::{NfsConfig, StackDefinition, VolumeDefinition, VolumeType};
+    use crate::prerequisites::HostPrerequisites;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    /// Alternatives of a schema that is one of several types, not the
+    /// constraints on the properties of an object
+    fn alternatives(schema: &Value) -> Option<&Vec<Value>> {
+        schema["oneOf"].as_array().filter(|_| schema["type"].is_null())
+    }
+
+    /// Paths of `value` the schema doesn't describe, or describes with another type
+    fn undescribed(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+        if let Some(alternatives) = alternatives(schema) {
+            match alternatives.iter().find(|alternative| has_type(alternative, value)) {
+                Some(alternative) => undescribed(alternative, value, path, errors),
+                None => errors.push(format!("{}: no alternative of the schema matches", path)),
+            }
+            return;
+        }
+        if !has_type(schema, value) {
+            errors.push(format!("{}: {} does not match the schema type {}", path, value, schema["type"]));
+            return;
+        }
+        if let Some(values) = schema["enum"].as_array() {
+            if !values.contains(value) {
+                errors.push(format!("{}: {} is not one of {:?}", path, value, values));
+            }
+        }
+        match value {
+            Value::Object(fields) => {
+                for (key, field) in fields {
+                    let path = format!("{}.{}", path, key);
+                    match schema["properties"].get(key) {
+                        Some(property) => undescribed(property, field, &path, errors),
+                        None if schema["additionalProperties"].is_object() => {
+                            undescribed(&schema["additionalProperties"], field, &path, errors)
+                        }
+                        None => errors.push(format!("{}: not a property of the schema", path)),
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    undescribed(&schema["items"], item, &format!("{}[{}]", path, index), errors);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn has_type(schema: &Value, value: &Value) -> bool {
+        match schema["type"].as_str() {
+            Some("string") => value.is_string(),
+            Some("integer") => value.is_i64() || value.is_u64(),
+            Some("boolean") => value.is_boolean(),
+            Some("array") => value.is_array(),
+            Some("object") => value.is_object(),
+            _ => true,
+        }
+    }
+
+    /// A document setting every property of the schema, objects being
+    /// preferred among the alternatives of a oneOf
+    fn sample(schema: &Value) -> Value {
+        if let Some(alternatives) = alternatives(schema) {
+            let object = alternatives.iter().find(|alternative| alternative["type"] == "object");
+            return sample(object.unwrap_or(&alternatives[0]));
+        }
+        if let Some(values) = schema["enum"].as_array() {
+            return values[0].clone();
+        }
+        match schema["type"].as_str() {
+            Some("string") if schema["pattern"].is_string() => json!("1m"),
+            Some("string") => json!("value"),
+            Some("integer") => json!(1),
+            Some("boolean") => json!(true),
+            Some("array") => json!([sample(&schema["items"])]),
+            Some("object") => match schema["properties"].as_object() {
+                Some(properties) => Value::Object(properties.iter()
+                    .map(|(key, property)| (key.clone(), sample(property)))
+                    .collect()),
+                None => json!({ "key": sample(&schema["additionalProperties"]) }),
+            },
+            other => panic!("no sample for the schema type {:?}", other),
+        }
+    }
+
+    fn assert_described<T: Serialize>(file: &str, value: &T) {
+        let schema = for_file(file).unwrap();
+        let mut errors = Vec::new();
+        undescribed(&schema, &serde_json::to_value(value).unwrap(), file, &mut errors);
+        assert!(errors.is_empty(), "fields missing from the {} schema:\n{}", file, errors.join("\n"));
+    }
+
+    /// Every property of the schema is read into a field of `T` and written back unchanged
+    fn assert_round_trips<T: Serialize + DeserializeOwned>(file: &str) {
+        let document = sample(&for_file(file).unwrap());
+        let parsed: T = serde_json::from_value(document.clone())
+            .unwrap_or_else(|e| panic!("sample of the {} schema does not parse: {}", file, e));
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), document, "properties of the {} schema lost", file);
+    }
+
+    // The values below list every field without `..Default::default()`, so
+    // that a field added to a model has to be added here, and to the schema
+
+    fn strings() -> Vec<String> {
+        vec!["value".to_string()]
+    }
+
+    fn string_map() -> BTreeMap<String, String> {
+        BTreeMap::from([("key".to_string(), "value".to_string())])
+    }
+
+    fn deploy() -> DeploySettings {
+        DeploySettings {
+            prune: Some(true),
+            resolve_image: Some(ResolveImage::Changed),
+            with_registry_auth: Some(true),
+            selective: Some(true),
+            convergence_timeout: Some("5m".to_string()),
+        }
+    }
+
+    fn updates() -> UpdateSettings {
+        UpdateSettings {
+            failure_threshold: Some(3),
+            traffic_aware: Some(false),
+            canary: Some(CanarySettings {
+                duration: Some("2m".to_string()),
+                max_restarts: Some(1),
+                max_error_lines: Some(10),
+                error_patterns: strings(),
+            }),
+            hash_exclude: Some(HashExclusions { lines: strings(), keys: strings() }),
+            auto_rollback: Some(true),
+        }
+    }
+
+    fn notifications() -> NotificationSettings {
+        NotificationSettings { failure_issues: Some(true), webhooks: strings(), inherit_webhooks: Some(false) }
+    }
+
+    fn task_history() -> TaskHistorySettings {
+        TaskHistorySettings { keep: Some(2), max_age: Some("7d".to_string()) }
+    }
+
+    fn hook() -> Hook {
+        Hook {
+            run: None,
+            image: Some("alpine".to_string()),
+            command: strings(),
+            networks: strings(),
+            env: string_map(),
+            timeout: Some("10m".to_string()),
+        }
+    }
+
+    #[test]
+    fn stacks_schema_describes_every_field() {
+        let stack = StackDefinition {
+            name: "web".to_string(),
+            git_ref: Some("main".to_string()),
+            outputs: string_map(),
+            app: Some("shop".to_string()),
+            priority: Some(1),
+            depends_on: strings(),
+            with_registry_auth: Some(true),
+            enabled: Some(true),
+            teardown: Some(false),
+            deploy: Some(deploy()),
+            updates: Some(updates()),
+            notifications: Some(notifications()),
+            task_history: Some(task_history()),
+            prerequisites: Some(HostPrerequisites {
+                docker_version: Some("24.0".to_string()),
+                kernel_modules: strings(),
+                sysctls: string_map(),
+                paths: strings(),
+            }),
+            hooks: Some(StackHooks { pre: vec![hook()], post: vec![hook()] }),
+            declared_name: None,
+        };
+        assert_described("stacks", &vec![stack]);
+        assert_round_trips::<Vec<StackDefinition>>("stacks");
+    }
+
+    #[test]
+    fn dockerops_schema_describes_every_field() {
+        let settings = RepositorySettings {
+            stack_prefix: Some("team_".to_string()),
+            prune_stacks: Some(true),
+            deploy: deploy(),
+            updates: updates(),
+            notifications: notifications(),
+            task_history: task_history(),
+        };
+        assert_described("dockerops", &settings);
+        assert_round_trips::<RepositorySettings>("dockerops");
+    }
+
+    #[test]
+    fn volumes_and_nfs_schemas_describe_every_field() {
+        let volume = VolumeDefinition { id: "data".to_string(), r#type: VolumeType::Binding, path: "data".to_string() };
+        assert_described("volumes", &vec![volume]);
+        assert_round_trips::<Vec<VolumeDefinition>>("volumes");
+        assert_described("nfs", &NfsConfig { path: "/mnt/nfs".to_string() });
+        assert_round_trips::<NfsConfig>("nfs");
+    }
+
+    #[test]
+    fn a_hook_can_be_a_string() {
+        let mut errors = Vec::new();
+        undescribed(&for_file("stacks").unwrap(), &json!([{ "name": "web", "hooks": { "pre": ["make migrate"] } }]), "stacks", &mut errors);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+}
@@ -0,0 +1,304 @@
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::commands::Commands;
+use crate::models::WebhookDelivery;
+
+struct ServerState {
+    commands: Commands,
+    webhook_secret: Option<String>,
+    api_token: Option<String>,
+    // Without an API token, the JSON endpoints are only open with --insecure
+    insecure: bool,
+    health_max_age: Option<std::time::Duration>,
+    // Deployments triggered by webhooks are serialized so two pushes never
+    // run process_and_deploy_stacks concurrently
+    deploy_lock: Mutex<()>,
+}
+
+pub async fn serve(commands: Commands, listen: &str, insecure: bool) -> Result<()> {
+    let addr: SocketAddr = listen.parse()
+        .map_err(|e| anyhow::anyhow!("Invalid listen address '{}': {}", listen, e))?;
+
+    // Without a secret, anyone reaching the port could trigger deployments
+    let webhook_secret = commands.webhook_secret();
+    if webhook_secret.is_none() {
+        if !insecure {
+            return Err(anyhow::anyhow!("No webhook secret configured, set DOCKEROPS_WEBHOOK_SECRET or 'webhook_secret' in config.yaml (or pass --insecure to accept unsigned payloads)"));
+        }
+        println!("⚠️  No webhook secret configured, payload signatures will not be verified (--insecure)");
+    }
+
+    let api_token = commands.api_token();
+    if api_token.is_none() && !insecure {
+        println!("⚠️  No API token configured, /pending and /apps are disabled (set DOCKEROPS_API_TOKEN or 'api_token' in config.yaml)");
+    }
+
+    let health_max_age = commands.health_max_age()?;
+    let state = Arc::new(ServerState {
+        commands,
+        webhook_secret,
+        api_token,
+        insecure,
+        health_max_age,
+        deploy_lock: Mutex::new(()),
+    });
+
+    // Pushes whose deployment a restart interrupted go first
+    let replay_state = state.clone();
+    tokio::spawn(async move {
+        let _guard = replay_state.deploy_lock.lock().await;
+        replay_state.commands.replay_webhook_deliveries().await;
+    });
+
+    let make_service = make_service_fn(move |_connection| {
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| handle_request(state.clone(), request)))
+        }
+    });
+
+    println!("Listening for webhooks on http://{}/webhook", addr);
+    Server::try_bind(&addr)
+        .map_err(|e| anyhow::anyhow!("Could not listen on {}: {}", addr, e))?
+        .serve(make_service)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+            println!("Received interrupt signal, stopping server");
+        })
+        .await?;
+
+    Ok(())
+}
+
+async fn handle_request(state: Arc<ServerState>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::POST, "/webhook") => handle_webhook(state, request).await,
+        (&Method::GET, "/pending") => handle_pending(state, request).await,
+        (&Method::GET, "/apps") => handle_apps(state, request).await,
+        (&Method::GET, "/healthz") => handle_healthz(state).await,
+        _ => Ok(respond(StatusCode::NOT_FOUND, "not found")),
+    };
+
+    Ok(response.unwrap_or_else(|e| {
+        println!("❌ Webhook handling failed: {}", e);
+        respond(StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+    }))
+}
+
+async fn handle_webhook(state: Arc<ServerState>, request: Request<Body>) -> Result<Response<Body>> {
+    let (event, signature) = webhook_headers(&request);
+    let delivery_id = delivery_header(&request);
+    let body = hyper::body::to_bytes(request.into_body()).await?;
+
+    if let Some(secret) = &state.webhook_secret {
+        if !verify_signature(secret, &body, signature.as_deref()) {
+            println!("❌ Rejected webhook with invalid signature");
+            return Ok(respond(StatusCode::UNAUTHORIZED, "invalid signature"));
+        }
+    }
+
+    match event.as_str() {
+        "ping" => return Ok(respond(StatusCode::OK, "pong")),
+        "push" => {}
+        other => return Ok(respond(StatusCode::ACCEPTED, &format!("ignored event '{}'", other))),
+    }
+
+    let payload: serde_json::Value = serde_json::from_slice(&body)
+        .map_err(|e| anyhow::anyhow!("Invalid push payload: {}", e))?;
+    let repository = &payload["repository"];
+
+    let candidates: Vec<&str> = ["clone_url", "html_url", "ssh_url", "git_url"]
+        .iter()
+        .filter_map(|key| repository[*key].as_str())
+        .collect();
+
+    let repo = match state.commands.find_watched_repository(&candidates).await? {
+        Some(repo) => repo,
+        None => {
+            println!("Received push for unwatched repository: {:?}", candidates);
+            return Ok(respond(StatusCode::ACCEPTED, "repository is not watched"));
+        }
+    };
+
+    if repo.pinned_commit.is_some() {
+        return Ok(respond(StatusCode::ACCEPTED, "repository is pinned to a commit"));
+    }
+
+    // Only pushes to the watched branch (or the default branch) are deployed,
+    // or any new tag for release based repositories, and pushes to the refs
+    // stacks are deployed from
+    let pushed_ref = payload["ref"].as_str().unwrap_or_default();
+    let watched_branch = repo.branch.as_deref()
+        .or_else(|| repository["default_branch"].as_str())
+        .unwrap_or("main");
+    let is_watched_ref = match repo.tag_pattern {
+        Some(_) => pushed_ref.starts_with("refs/tags/"),
+        None => pushed_ref == format!("refs/heads/{}", watched_branch),
+    } || state.commands.stack_refs(&repo).iter()
+        .any(|stack_ref| pushed_ref == format!("refs/heads/{}", stack_ref) || pushed_ref == format!("refs/tags/{}", stack_ref));
+    if !is_watched_ref {
+        return Ok(respond(StatusCode::ACCEPTED, &format!("ignored push to '{}'", pushed_ref)));
+    }
+
+    // Deployments are recorded for the account that pushed
+    let sender = [&payload["sender"]["login"], &payload["pusher"]["username"], &payload["pusher"]["name"]].iter()
+        .find_map(|value| value.as_str())
+        .filter(|sender| !sender.is_empty())
+        .map(|sender| format!("webhook:{}", sender))
+        .unwrap_or_else(|| "webhook".to_string());
+
+    // Forges redeliver on timeouts and on demand, a push is deployed once
+    let delivery = WebhookDelivery {
+        delivery_id: delivery_id.unwrap_or_else(|| format!("{:x}", md5::compute(&body))),
+        repository_url: repo.url.clone(),
+        git_ref: pushed_ref.to_string(),
+        commit_hash: payload["after"].as_str().map(|commit| commit.to_string()),
+        sender: sender.clone(),
+        received_at: chrono::Utc::now().to_rfc3339(),
+        processed_at: None,
+        result: None,
+    };
+    if !state.commands.record_webhook_delivery(&delivery).await? {
+        println!("Ignoring redelivery {} of a push for {}", delivery.delivery_id, repo.url);
+        return Ok(respond(StatusCode::OK, "delivery already received"));
+    }
+
+    println!("Received push for {} ({}) from {}, scheduling deployment", repo.url, pushed_ref, sender);
+    let task_state = state.clone();
+    tokio::spawn(async move {
+        let _guard = task_state.deploy_lock.lock().await;
+        let started = chrono::Utc::now().to_rfc3339();
+        let result = task_state.commands.refresh_repository(&repo, Some(sender)).await;
+        task_state.commands.finish_webhook_deliveries(&repo.url, &started, if result.is_ok() { "success" } else { "failure" }).await;
+        match result {
+            Ok(()) => println!("✅ Webhook deployment completed for {}", repo.url),
+            Err(e) => println!("❌ Webhook deployment failed for {}: {}", repo.url, e),
+        }
+    });
+
+    Ok(respond(StatusCode::ACCEPTED, "deployment scheduled"))
+}
+
+/// Health checks for container supervisors, 503 when one fails
+async fn handle_healthz(state: Arc<ServerState>) -> Result<Response<Body>> {
+    let checks = state.commands.health_checks(state.health_max_age).await;
+    let healthy = checks.iter().all(|check| check.healthy);
+    let body = serde_json::json!({ "healthy": healthy, "checks": checks });
+
+    Ok(Response::builder()
+        .status(if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE })
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap_or_default())
+}
+
+/// Conflicts between manual changes and git, for dashboards. The API token
+/// is required as a bearer token.
+async fn handle_pending(state: Arc<ServerState>, request: Request<Body>) -> Result<Response<Body>> {
+    if let Some(response) = unauthorized(&state, &request) {
+        return Ok(response);
+    }
+
+    let conflicts = state.commands.pending_json().await?;
+    Ok(json_response(&conflicts))
+}
+
+/// Combined status of the applications and their stacks, with the same
+/// authentication as `/pending`
+async fn handle_apps(state: Arc<ServerState>, request: Request<Body>) -> Result<Response<Body>> {
+    if let Some(response) = unauthorized(&state, &request) {
+        return Ok(response);
+    }
+
+    let apps = state.commands.apps_json().await?;
+    Ok(json_response(&apps))
+}
+
+/// Rejection of a request that doesn't carry the API token as a bearer
+/// token. The digests are compared in constant time so that the response
+/// time doesn't reveal how much of the token was guessed.
+fn unauthorized(state: &ServerState, request: &Request<Body>) -> Option<Response<Body>> {
+    let Some(api_token) = &state.api_token else {
+        return match state.insecure {
+            true => None,
+            false => Some(respond(StatusCode::FORBIDDEN, "no API token configured")),
+        };
+    };
+    let token = header(request, "Authorization")
+        .and_then(|value| value.strip_prefix("Bearer ").map(|token| token.to_string()))
+        .unwrap_or_default();
+    match bool::from(Sha256::digest(token.as_bytes()).ct_eq(&Sha256::digest(api_token.as_bytes()))) {
+        true => None,
+        false => Some(respond(StatusCode::UNAUTHORIZED, "invalid token")),
+    }
+}
+
+fn json_response(body: &serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap_or_default()
+}
+
+fn header(request: &Request<Body>, name: &str) -> Option<String> {
+    request.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// Event name and hex HMAC-SHA256 signature of a GitHub, Gitea or Forgejo
+/// webhook. Gitea also sends the GitHub headers, its own are preferred.
+fn webhook_headers(request: &Request<Body>) -> (String, Option<String>) {
+    for forge in ["Forgejo", "Gitea"] {
+        if let Some(event) = header(request, &format!("X-{}-Event", forge)) {
+            return (event, header(request, &format!("X-{}-Signature", forge)));
+        }
+    }
+
+    let event = header(request, "X-GitHub-Event").unwrap_or_default();
+    // GitHub prefixes the digest: `sha256=<hex hmac>`
+    let signature = header(request, "X-Hub-Signature-256")
+        .and_then(|signature| signature.strip_prefix("sha256=").map(|hex| hex.to_string()));
+    (event, signature)
+}
+
+/// Id of a webhook delivery, kept by the forge for its redeliveries
+fn delivery_header(request: &Request<Body>) -> Option<String> {
+    ["X-Forgejo-Delivery", "X-Gitea-Delivery", "X-GitHub-Delivery"].iter()
+        .find_map(|name| header(request, name))
+}
+
+/// Check a hex HMAC-SHA256 signature of the payload
+fn verify_signature(secret: &str, body: &[u8], signature: Option<&str>) -> bool {
+    let Some(expected) = signature else {
+        return false;
+    };
+    let Ok(expected) = hex::decode(expected) else {
+        return false;
+    };
+
+    let mut mac = match Hmac::<Sha256>::new_from_slice(secret.as_bytes()) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn respond(status: StatusCode, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(message.to_string()))
+        .unwrap_or_default()
+}
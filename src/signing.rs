@@ -0,0 +1,154 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Keys allowed to sign the commits DockerOps deploys
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommitSigners {
+    /// OpenPGP public key files, armored or binary
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gpg_keys: Vec<String>,
+    /// SSH public keys as found in `.pub` files (`ssh-ed25519 AAAA... alice`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ssh_keys: Vec<String>,
+}
+
+/// Verify the signature of the commit checked out in `repo_path` against
+/// the allowed keys with `gpgv` or `ssh-keygen`, returning what they
+/// reported about the signer
+pub fn verify_head(repo_path: &Path, signers: &CommitSigners) -> Result<String> {
+    let repo = git2::Repository::open(repo_path)?;
+    let commit = repo.head()?.peel_to_commit()?.id();
+    verify_commit(&repo, commit, signers)
+}
+
+/// Same as `verify_head` for any commit of the repository
+pub fn verify_commit(repo: &git2::Repository, commit: git2::Oid, signers: &CommitSigners) -> Result<String> {
+    let (signature, signed_data) = repo.extract_signature(&commit, None)
+        .map_err(|_| anyhow::anyhow!("Commit {} is not signed", commit))?;
+    let signature = signature.as_str()
+        .ok_or_else(|| anyhow::anyhow!("Commit {} has an unreadable signature", commit))?;
+
+    let workdir = TempDir::new()?;
+    let signature_path = workdir.path.join("signature");
+    let data_path = workdir.path.join("data");
+    fs::write(&signature_path, signature)?;
+    fs::write(&data_path, &*signed_data)?;
+
+    let result = if signature.starts_with("-----BEGIN SSH SIGNATURE-----") {
+        verify_ssh(&workdir.path, &signature_path, &signed_data, signers)
+    } else if signature.starts_with("-----BEGIN PGP SIGNATURE-----") {
+        verify_gpg(&workdir.path, &signature_path, &data_path, signers)
+    } else {
+        Err(anyhow::anyhow!("unsupported signature format (only GPG and SSH signatures are supported)"))
+    };
+    result.map_err(|e| anyhow::anyhow!("Signature of commit {} was not made by an allowed key: {}", commit, e))
+}
+
+fn verify_ssh(dir: &Path, signature_path: &Path, signed_data: &[u8], signers: &CommitSigners) -> Result<String> {
+    if signers.ssh_keys.is_empty() {
+        return Err(anyhow::anyhow!("no SSH key is allowed"));
+    }
+
+    // Every key is given the same principal, the identity being the key itself
+    let allowed_signers: String = signers.ssh_keys.iter()
+        .map(|key| format!("dockerops namespaces=\"git\" {}\n", key.trim()))
+        .collect();
+    let allowed_path = dir.join("allowed_signers");
+    fs::write(&allowed_path, allowed_signers)?;
+
+    let mut child = Command::new("ssh-keygen")
+        .args(["-Y", "verify", "-n", "git", "-I", "dockerops", "-f"])
+        .arg(&allowed_path)
+        .arg("-s")
+        .arg(signature_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("could not run ssh-keygen: {}", e))?;
+    child.stdin.take().ok_or_else(|| anyhow::anyhow!("ssh-keygen has no stdin"))?.write_all(signed_data)?;
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = if stderr.trim().is_empty() { String::from_utf8_lossy(&output.stdout) } else { stderr };
+        return Err(anyhow::anyhow!("{}", reason.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn verify_gpg(dir: &Path, signature_path: &Path, data_path: &Path, signers: &CommitSigners) -> Result<String> {
+    if signers.gpg_keys.is_empty() {
+        return Err(anyhow::anyhow!("no GPG key is allowed"));
+    }
+
+    // gpgv reads binary keys, armored files are converted first
+    let mut keyring = Vec::new();
+    for key in &signers.gpg_keys {
+        let content = fs::read(key).map_err(|e| anyhow::anyhow!("could not read GPG key {}: {}", key, e))?;
+        if content.starts_with(b"-----BEGIN PGP") {
+            let output = Command::new("gpg")
+                .args(["--batch", "--dearmor"])
+                .env("GNUPGHOME", dir)
+                .arg("--output").arg("-")
+                .arg(key)
+                .output()
+                .map_err(|e| anyhow::anyhow!("could not run gpg: {}", e))?;
+            if !output.status.success() {
+                return Err(anyhow::anyhow!("invalid GPG key {}: {}", key, String::from_utf8_lossy(&output.stderr).trim()));
+            }
+            keyring.extend(output.stdout);
+        } else {
+            keyring.extend(content);
+        }
+    }
+    let keyring_path = dir.join("allowed.gpg");
+    fs::write(&keyring_path, keyring)?;
+
+    let output = Command::new("gpgv")
+        .env("GNUPGHOME", dir)
+        .args(["--status-fd", "1", "--keyring"])
+        .arg(&keyring_path)
+        .arg(signature_path)
+        .arg(data_path)
+        .output()
+        .map_err(|e| anyhow::anyhow!("could not run gpgv: {}", e))?;
+
+    let status = String::from_utf8_lossy(&output.stdout);
+    let good = status.lines().find_map(|line| line.strip_prefix("[GNUPG:] GOODSIG "));
+    match good {
+        Some(signer) if output.status.success() => Ok(format!("Good GPG signature from {}", signer)),
+        _ => Err(anyhow::anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim())),
+    }
+}
+
+/// Private scratch directory removed when dropped
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    fn new() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("dockerops-signing-{}-{}", std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()));
+        fs::create_dir_all(&path)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o700))?;
+        }
+
+        Ok(Self { path })
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,55 @@
+//! End-to-end flows against a real Swarm, see tests/testkit
+
+mod testkit;
+
+use testkit::{eventually, Swarm, Workspace};
+use std::time::Duration;
+
+fn compose(version: u32) -> String {
+    format!("services:\n  app:\n    image: busybox:1.36\n    command: [\"sleep\", \"86400\"]\n    environment: [VERSION={}]\n", version)
+}
+
+#[test]
+fn watch_deploy_update_rollback() {
+    let swarm = Swarm::start();
+    let workspace = Workspace::new(&swarm);
+    let service = workspace.service("web", "app");
+    let stack = workspace.stack("web");
+
+    workspace.write_stack("web", &compose(1));
+    workspace.dockerops_ok(&["watch", &workspace.repo_url()]);
+    swarm.wait_for_env(&service, "VERSION=1");
+
+    workspace.write_stack("web", &compose(2));
+    workspace.dockerops_ok(&["deploy", &stack]);
+    swarm.wait_for_env(&service, "VERSION=2");
+
+    workspace.dockerops_ok(&["rollback", &stack]);
+    swarm.wait_for_env(&service, "VERSION=1");
+
+    // The rolled back compose file stays out of the next syncs
+    workspace.dockerops_ok(&["reconcile"]);
+    assert!(swarm.service_env(&service).iter().any(|env| env == "VERSION=1"));
+
+    let history = workspace.dockerops_ok(&["history", &stack]);
+    assert_eq!(history.matches(&format!("{} (", stack)).count(), 3, "{}", history);
+    assert!(history.contains("(rollback)"), "{}", history);
+}
+
+#[test]
+fn rm_and_restore() {
+    let swarm = Swarm::start();
+    let workspace = Workspace::new(&swarm);
+    let stack = workspace.stack("api");
+
+    workspace.write_stack("api", &compose(1));
+    workspace.dockerops_ok(&["watch", &workspace.repo_url()]);
+    swarm.wait_for_env(&workspace.service("api", "app"), "VERSION=1");
+
+    workspace.dockerops_ok(&["rm", &stack]);
+    eventually(Duration::from_secs(60), "the stack to be removed", || !swarm.stacks().contains(&stack));
+    assert!(workspace.dockerops_ok(&["trash"]).contains(&stack));
+
+    workspace.dockerops_ok(&["restore-stack", &stack]);
+    swarm.wait_for_env(&workspace.service("api", "app"), "VERSION=1");
+}
@@ -0,0 +1,204 @@
+//! Disposable Swarm and DockerOps profile for the end-to-end tests, run with
+//! `cargo test --features testkit`.
+//!
+//! Each test starts a Docker-in-Docker node initialized as a single-node
+//! Swarm, removed when the test ends. `DOCKEROPS_TEST_DOCKER_HOST` runs the
+//! tests against an existing Swarm manager instead, only touching the
+//! stacks the tests create.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const DIND_IMAGE: &str = "docker:dind";
+
+static WORKSPACES: AtomicUsize = AtomicUsize::new(0);
+
+/// Docker endpoint of a Swarm manager
+pub struct Swarm {
+    host: String,
+    /// Docker-in-Docker container started for the test
+    container: Option<String>,
+}
+
+impl Swarm {
+    /// The endpoint given in `DOCKEROPS_TEST_DOCKER_HOST`, or a new Docker-in-Docker Swarm
+    pub fn start() -> Self {
+        if let Ok(host) = std::env::var("DOCKEROPS_TEST_DOCKER_HOST") {
+            let swarm = Self { host, container: None };
+            let state = swarm.docker_output(&["info", "--format", "{{.Swarm.LocalNodeState}}"]);
+            assert_eq!(state.trim(), "active", "{} is not part of a Swarm", swarm.host);
+            return swarm;
+        }
+
+        let output = Command::new("docker")
+            .args(["run", "--detach", "--privileged", "--env", "DOCKER_TLS_CERTDIR=", "--publish", "127.0.0.1::2375", DIND_IMAGE])
+            .output()
+            .expect("could not run docker");
+        assert!(output.status.success(), "could not start {}: {}", DIND_IMAGE, String::from_utf8_lossy(&output.stderr));
+        let container = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        let port = Command::new("docker").args(["port", &container, "2375/tcp"]).output().expect("could not run docker");
+        let address = String::from_utf8_lossy(&port.stdout).lines().next().unwrap_or_default().to_string();
+        let swarm = Self { host: format!("tcp://{}", address), container: Some(container) };
+
+        eventually(Duration::from_secs(60), "the Docker-in-Docker daemon to start", || swarm.docker(&["info"]).status.success());
+        swarm.docker_output(&["swarm", "init", "--advertise-addr", "127.0.0.1"]);
+        swarm
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Run the docker CLI against the Swarm
+    pub fn docker(&self, args: &[&str]) -> Output {
+        Command::new("docker")
+            .env("DOCKER_HOST", &self.host)
+            .env_remove("DOCKER_CONTEXT")
+            .args(args)
+            .output()
+            .expect("could not run docker")
+    }
+
+    /// Standard output of a docker command that must succeed
+    pub fn docker_output(&self, args: &[&str]) -> String {
+        let output = self.docker(args);
+        assert!(output.status.success(), "docker {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    /// Environment variables of a service's spec, empty when the service doesn't exist
+    pub fn service_env(&self, service: &str) -> Vec<String> {
+        let output = self.docker(&["service", "inspect", service, "--format", "{{json .Spec.TaskTemplate.ContainerSpec.Env}}"]);
+        if !output.status.success() {
+            return Vec::new();
+        }
+        serde_json::from_slice::<Option<Vec<String>>>(&output.stdout).ok().flatten().unwrap_or_default()
+    }
+
+    /// Wait until a service's spec has an environment variable
+    pub fn wait_for_env(&self, service: &str, variable: &str) {
+        eventually(Duration::from_secs(60), &format!("{} to be set on {}", variable, service),
+            || self.service_env(service).iter().any(|env| env == variable));
+    }
+
+    pub fn stacks(&self) -> Vec<String> {
+        self.docker_output(&["stack", "ls", "--format", "{{.Name}}"]).lines().map(str::to_string).collect()
+    }
+}
+
+impl Drop for Swarm {
+    fn drop(&mut self) {
+        if let Some(container) = &self.container {
+            let _ = Command::new("docker").args(["rm", "--force", "--volumes", container]).output();
+        }
+    }
+}
+
+/// Local repository watched by DockerOps with its own profile and database.
+/// Its stacks are deployed with a prefix unique to the workspace.
+pub struct Workspace {
+    dir: PathBuf,
+    host: String,
+    prefix: String,
+}
+
+impl Workspace {
+    pub fn new(swarm: &Swarm) -> Self {
+        let number = WORKSPACES.fetch_add(1, Ordering::SeqCst);
+        let prefix = format!("e2e{}x{}-", std::process::id(), number);
+        let dir = std::env::temp_dir().join(format!("dockerops-{}", prefix.trim_end_matches('-')));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("home")).unwrap();
+        fs::create_dir_all(dir.join("repo")).unwrap();
+
+        let workspace = Self { dir, host: swarm.host().to_string(), prefix };
+        fs::write(workspace.repo().join("dockerops.yaml"), format!("stack_prefix: {}\n", workspace.prefix)).unwrap();
+        fs::write(workspace.repo().join("stacks.yaml"), "[]\n").unwrap();
+        workspace
+    }
+
+    /// Directory watched by DockerOps
+    pub fn repo(&self) -> PathBuf {
+        self.dir.join("repo")
+    }
+
+    pub fn repo_url(&self) -> String {
+        self.repo().to_string_lossy().to_string()
+    }
+
+    /// Swarm name of a stack of the repository
+    pub fn stack(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// Swarm name of a service of a stack
+    pub fn service(&self, stack: &str, service: &str) -> String {
+        format!("{}_{}", self.stack(stack), service)
+    }
+
+    /// Declare a stack in stacks.yaml, or replace its compose file
+    pub fn write_stack(&self, name: &str, compose: &str) {
+        let stack_dir = self.repo().join(name);
+        fs::create_dir_all(&stack_dir).unwrap();
+        fs::write(stack_dir.join("docker-compose.yml"), compose).unwrap();
+
+        let stacks_path = self.repo().join("stacks.yaml");
+        let mut stacks: Vec<serde_yaml::Value> = serde_yaml::from_str(&fs::read_to_string(&stacks_path).unwrap()).unwrap();
+        if !stacks.iter().any(|stack| stack["name"].as_str() == Some(name)) {
+            let mut entry = serde_yaml::Mapping::new();
+            entry.insert("name".into(), name.into());
+            stacks.push(serde_yaml::Value::Mapping(entry));
+            fs::write(&stacks_path, serde_yaml::to_string(&stacks).unwrap()).unwrap();
+        }
+    }
+
+    /// Run dockerops with the workspace's profile against the Swarm
+    pub fn dockerops(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_dockerops"))
+            .env("HOME", self.dir.join("home"))
+            // The flows tested don't touch NFS volumes, which are what needs root
+            .env("USER", "root")
+            .env("DOCKER_HOST", &self.host)
+            .env_remove("DOCKER_CONTEXT")
+            .env_remove("DOCKEROPS_CONFIG")
+            .env_remove("DOCKEROPS_DB_PATH")
+            .env_remove("DOCKEROPS_CONTEXT")
+            .args(args)
+            .output()
+            .expect("could not run dockerops")
+    }
+
+    /// Standard output of a dockerops command that must succeed
+    pub fn dockerops_ok(&self, args: &[&str]) -> String {
+        let output = self.dockerops(args);
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        assert!(output.status.success(), "dockerops {} failed:\n{}\n{}", args.join(" "), stdout, String::from_utf8_lossy(&output.stderr));
+        stdout
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        // The stacks are left behind on a shared Swarm otherwise
+        let stacks = Command::new("docker").env("DOCKER_HOST", &self.host).args(["stack", "ls", "--format", "{{.Name}}"]).output();
+        if let Ok(stacks) = stacks {
+            for stack in String::from_utf8_lossy(&stacks.stdout).lines().filter(|stack| stack.starts_with(&self.prefix)) {
+                let _ = Command::new("docker").env("DOCKER_HOST", &self.host).args(["stack", "rm", stack]).output();
+            }
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Poll a condition until it holds, failing the test after `timeout`
+pub fn eventually(timeout: Duration, what: &str, mut condition: impl FnMut() -> bool) {
+    let started = Instant::now();
+    while !condition() {
+        assert!(started.elapsed() < timeout, "timed out after {}s waiting for {}", timeout.as_secs(), what);
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}